@@ -0,0 +1,6 @@
+-- Deleted characters are archived first, and only removed for good once they've been in the trash
+-- for longer than the configured retention period. Holds when they were archived, as seconds since
+-- the unix epoch, and stays NULL for characters that haven't been
+ALTER TABLE  Characters  ADD COLUMN  archived_at  INTEGER;
+
+CREATE INDEX  IF NOT EXISTS  Characters_archived_at  ON  Characters (archived_at);
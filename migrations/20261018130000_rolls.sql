@@ -0,0 +1,23 @@
+-- Every /roll and /check, kept so GMs can look back at them. Users don't need a profile to roll,
+-- so discordId isn't a foreign key. The character's name is copied in, so rolls stay readable
+-- after the character is removed for good, at which point fk_characterId is set to NULL
+CREATE TABLE  IF NOT EXISTS    Rolls
+(
+    pk_rollId         INTEGER  PRIMARY KEY,
+    discordId         INTEGER  NOT NULL,
+    fk_characterId    INTEGER,            -- NULL when rolled without a character
+    characterName     TEXT,
+    guildId           INTEGER,            -- NULL when rolled in DMs
+    channelId         INTEGER  NOT NULL,
+    label             TEXT     NOT NULL,  -- What was rolled, like 'Roll' or 'Strength check'
+    expression        TEXT     NOT NULL,
+    total             INTEGER  NOT NULL,
+    naturalD20        INTEGER,            -- The kept d20, when exactly one counted
+    rolledAt          INTEGER  NOT NULL,  -- Seconds since the unix epoch
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId)
+);
+
+CREATE INDEX  IF NOT EXISTS  Rolls_discordId_guildId  ON  Rolls (discordId, guildId);
+CREATE INDEX  IF NOT EXISTS  Rolls_fk_characterId     ON  Rolls (fk_characterId);
@@ -0,0 +1,35 @@
+-- Fights being run in a channel. There can only be one per channel at a time, and it's removed
+-- once it ends. round is 0 until the first turn is taken
+CREATE TABLE  IF NOT EXISTS    Encounters
+(
+    pk_encounterId         INTEGER  PRIMARY KEY,
+    guildId                INTEGER,            -- NULL when run in DMs
+    channelId              INTEGER  NOT NULL  UNIQUE,
+    gameMasterId           INTEGER  NOT NULL,  -- Whoever started it
+    encounterName          TEXT     NOT NULL,
+    round                  INTEGER  NOT NULL  DEFAULT 0,
+    fk_currentCombatantId  INTEGER,            -- NULL until the first turn is taken
+    startedAt              INTEGER  NOT NULL   -- Seconds since the unix epoch
+);
+
+-- Everyone taking part in an encounter. NPCs don't have a character, and are added by the GM
+CREATE TABLE  IF NOT EXISTS    Combatants
+(
+    pk_combatantId    INTEGER  PRIMARY KEY,
+    fk_encounterId    INTEGER  NOT NULL,
+    fk_characterId    INTEGER,            -- NULL for NPCs
+    discordId         INTEGER  NOT NULL,  -- Whoever added them
+    combatantName     TEXT     NOT NULL,
+    initiative        INTEGER  NOT NULL,
+    dexterity         INTEGER  NOT NULL,  -- Breaks ties in initiative
+
+    FOREIGN KEY (fk_encounterId)
+    REFERENCES Encounters (pk_encounterId),
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId),
+
+    UNIQUE (fk_encounterId, fk_characterId)
+);
+
+CREATE INDEX  IF NOT EXISTS  Combatants_fk_characterId  ON  Combatants (fk_characterId);
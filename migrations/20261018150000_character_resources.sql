@@ -0,0 +1,12 @@
+-- How much HP and mana characters have left. Their maximums are worked out from their attributes
+-- with the formulas in the config, so only what's left is stored. Characters without a row are at
+-- full
+CREATE TABLE  IF NOT EXISTS    CharacterResources
+(
+    fk_characterId  INTEGER  PRIMARY KEY,
+    currentHp       INTEGER  NOT NULL,
+    currentMana     INTEGER  NOT NULL,
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId)
+);
@@ -0,0 +1,32 @@
+-- Every spell game masters have written up, in the catalogue of the server they wrote it in.
+-- Names are unique within a server regardless of case, so autocomplete never suggests two spells
+-- that look the same
+CREATE TABLE  IF NOT EXISTS    Spells
+(
+    pk_spellId    INTEGER  PRIMARY KEY,
+    guildId       INTEGER  NOT NULL,
+    spellName     TEXT     NOT NULL  COLLATE NOCASE,
+    school        TEXT     NOT NULL,
+    manaCost      INTEGER  NOT NULL,
+    difficulty    INTEGER  NOT NULL,  -- The Casting check needs to reach this
+    effect        TEXT     NOT NULL,
+
+    UNIQUE (guildId, spellName)
+);
+
+-- The spells each character has learnt
+CREATE TABLE  IF NOT EXISTS    KnownSpells
+(
+    fk_characterId  INTEGER  NOT NULL,
+    fk_spellId      INTEGER  NOT NULL,
+
+    PRIMARY KEY (fk_characterId, fk_spellId),
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId),
+
+    FOREIGN KEY (fk_spellId)
+    REFERENCES Spells (pk_spellId)
+);
+
+CREATE INDEX  IF NOT EXISTS  KnownSpells_fk_spellId  ON  KnownSpells (fk_spellId);
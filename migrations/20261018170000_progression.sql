@@ -0,0 +1,30 @@
+-- Experience and levels. Levels are stored rather than worked out from the experience, so that
+-- changing the level curve in the config never takes a level away from anyone. Characters without
+-- a row are at level 1 with no experience
+CREATE TABLE  IF NOT EXISTS    CharacterProgression
+(
+    fk_characterId  INTEGER  PRIMARY KEY,
+    experience      INTEGER  NOT NULL  DEFAULT 0,
+    level           INTEGER  NOT NULL  DEFAULT 1,
+    unspentPoints   INTEGER  NOT NULL  DEFAULT 0,   -- Attribute points granted by levelling up
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId)
+);
+
+-- Every level a character gained, with how they got there
+CREATE TABLE  IF NOT EXISTS    LevelUps
+(
+    pk_levelUpId    INTEGER  PRIMARY KEY  AUTOINCREMENT,
+    fk_characterId  INTEGER  NOT NULL,
+    level           INTEGER  NOT NULL,              -- The level reached
+    experience      INTEGER  NOT NULL,              -- Experience they had once it was reached
+    pointsGranted   INTEGER  NOT NULL,
+    awardedBy       INTEGER  NOT NULL,              -- Discord ID of whoever awarded the experience
+    levelledAt      INTEGER  NOT NULL,              -- Unix timestamp
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId)
+);
+
+CREATE INDEX  IF NOT EXISTS  LevelUps_fk_characterId  ON  LevelUps (fk_characterId);
@@ -0,0 +1,32 @@
+-- Every item game masters have written up. Each server keeps it's own catalogue, and names are
+-- unique within it regardless of case
+CREATE TABLE  IF NOT EXISTS    Items
+(
+    pk_itemId     INTEGER  PRIMARY KEY,
+    guildId       INTEGER  NOT NULL,
+    itemName      TEXT     NOT NULL  COLLATE NOCASE,
+    weight        INTEGER  NOT NULL,  -- In the same units as carry capacity
+    value         INTEGER  NOT NULL,
+    description   TEXT     NOT NULL,
+    tags          TEXT     NOT NULL,  -- Comma separated, like "weapon, melee"
+
+    UNIQUE (guildId, itemName)
+);
+
+-- What each character is carrying. Rows are removed once the quantity reaches 0
+CREATE TABLE  IF NOT EXISTS    InventoryItems
+(
+    fk_characterId  INTEGER  NOT NULL,
+    fk_itemId       INTEGER  NOT NULL,
+    quantity        INTEGER  NOT NULL,
+
+    PRIMARY KEY (fk_characterId, fk_itemId),
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId),
+
+    FOREIGN KEY (fk_itemId)
+    REFERENCES Items (pk_itemId)
+);
+
+CREATE INDEX  IF NOT EXISTS  InventoryItems_fk_itemId  ON  InventoryItems (fk_itemId);
@@ -0,0 +1,28 @@
+-- Items that can be worn or wielded name the slot they go into, and change the attributes of
+-- whoever has them equipped. Items without a slot stay in the bag
+ALTER TABLE  Items  ADD COLUMN  slot                  TEXT;
+ALTER TABLE  Items  ADD COLUMN  strengthModifier      INTEGER  NOT NULL  DEFAULT 0;
+ALTER TABLE  Items  ADD COLUMN  dexterityModifier     INTEGER  NOT NULL  DEFAULT 0;
+ALTER TABLE  Items  ADD COLUMN  preceptionModifier    INTEGER  NOT NULL  DEFAULT 0;
+ALTER TABLE  Items  ADD COLUMN  knowledgeModifier     INTEGER  NOT NULL  DEFAULT 0;
+ALTER TABLE  Items  ADD COLUMN  constitutionModifier  INTEGER  NOT NULL  DEFAULT 0;
+ALTER TABLE  Items  ADD COLUMN  castingModifier       INTEGER  NOT NULL  DEFAULT 0;
+
+-- What each character has equipped, at most one item per slot. Equipped items stay in the
+-- inventory, and are unequipped once the last of them leaves it
+CREATE TABLE  IF NOT EXISTS    EquippedItems
+(
+    fk_characterId  INTEGER  NOT NULL,
+    slot            TEXT     NOT NULL,
+    fk_itemId       INTEGER  NOT NULL,
+
+    PRIMARY KEY (fk_characterId, slot),
+
+    FOREIGN KEY (fk_characterId)
+    REFERENCES Characters (pk_characterId),
+
+    FOREIGN KEY (fk_itemId)
+    REFERENCES Items (pk_itemId)
+);
+
+CREATE INDEX  IF NOT EXISTS  EquippedItems_fk_itemId  ON  EquippedItems (fk_itemId);
@@ -0,0 +1,313 @@
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateEmbed,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption
+};
+
+use crate::{
+    config,
+    utils::{CharacterId, EmbedColours}
+};
+
+
+/// One of the six stats stored in the `Atributes` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    Strength,
+    Dexterity,
+    Preception,
+    Knowledge,
+    Constitution,
+    Casting
+}
+impl Attribute {
+    /// Every attribute, in the same order as the columns of the `Atributes` table
+    pub const ALL: [Attribute; 6] = [
+        Attribute::Strength,
+        Attribute::Dexterity,
+        Attribute::Preception,
+        Attribute::Knowledge,
+        Attribute::Constitution,
+        Attribute::Casting
+    ];
+
+    /// Position of the attribute inside of `ALL`, and therefore inside of an `AttributeSpread`
+    pub fn index( &self ) -> usize {
+        Self::ALL
+            .iter()
+            .position( |attribute| attribute == self )
+            .expect("Every variant is inside of ALL")
+    }
+
+    pub fn from_index( index: usize ) -> Option<Attribute> {
+        Self::ALL.get(index).copied()
+    }
+
+    /// The name of the attribute, as it is written in the database
+    pub fn name( &self ) -> &'static str {
+        match self {
+            Attribute::Strength     => "Strength",
+            Attribute::Dexterity    => "Dexterity",
+            Attribute::Preception   => "Preception",
+            Attribute::Knowledge    => "Knowledge",
+            Attribute::Constitution => "Constitution",
+            Attribute::Casting      => "Casting",
+        }
+    }
+
+    /// Inverse of `name`
+    pub fn from_name( name: &str ) -> Option<Attribute> {
+        Self::ALL
+            .into_iter()
+            .find( |attribute| attribute.name() == name )
+    }
+}
+
+
+/// The values of all six attributes of a character, indexed by `Attribute::index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeSpread( pub [i64; 6] );
+impl AttributeSpread {
+
+    pub fn get( &self, attribute: Attribute ) -> i64 {
+        self.0[ attribute.index() ]
+    }
+
+    pub fn set( &mut self, attribute: Attribute, value: i64 ) {
+        self.0[ attribute.index() ] = value;
+    }
+
+    /// Encode the spread into a short string, used to carry allocation state inside of a
+    /// component's custom id. Looks like: `1-1-4-1-2-1`
+    pub fn encode( &self ) -> String {
+        self.0
+            .iter()
+            .map( |value| value.to_string() )
+            .collect::<Vec<String>>()
+            .join("-")
+    }
+
+    /// Inverse of `encode`. Returns `None` if the string is mangled
+    pub fn decode( encoded: &str ) -> Option<AttributeSpread> {
+        let values = encoded
+            .split('-')
+            .map( |value| value.parse::<i64>().ok() )
+            .collect::<Option<Vec<i64>>>()?;
+
+        Some( AttributeSpread( values.try_into().ok()? ))
+    }
+}
+
+
+/// The limits used when a player distributes points between their attributes
+pub struct AllocationRules {
+    /// Points that can be spent on top of `floor`
+    pub budget: i64,
+    /// Lowest value of every attribute, which is also where the allocation starts from
+    pub floor:  AttributeSpread,
+    pub max:    i64
+}
+impl AllocationRules {
+    /// Rules used when building a new character, as set in the `[allocation]` table of the config
+    pub fn character_creation() -> AllocationRules {
+        let config = config::get();
+        AllocationRules {
+            budget: config.allocation_budget,
+            floor:  AttributeSpread( [config.allocation_min; 6] ),
+            max:    config.allocation_max
+        }
+    }
+
+    /// Rules used when spending the points granted by levelling up. Attributes can only be raised
+    /// from where they are now
+    pub fn level_up( current: AttributeSpread, points: i64, max: i64 ) -> AllocationRules {
+        AllocationRules {
+            budget: points,
+            floor:  current,
+            max
+        }
+    }
+
+    /// The spread every allocation starts from, every attribute at it's floor
+    pub fn starting_spread( &self ) -> AttributeSpread {
+        self.floor
+    }
+
+    /// How many points are left to spend in the given spread
+    pub fn remaining_points( &self, spread: &AttributeSpread ) -> i64 {
+        let spent: i64 = spread.0
+            .iter()
+            .zip( self.floor.0.iter() )
+            .map( |(value, floor)| value - floor )
+            .sum();
+
+        self.budget - spent
+    }
+
+    /// Whether the given spread stays between the floor and max of every attribute and doesn't
+    /// overspend
+    pub fn is_valid( &self, spread: &AttributeSpread ) -> bool {
+        spread.0
+            .iter()
+            .zip( self.floor.0.iter() )
+            .all( |(value, floor)| (*floor..=self.max).contains(value) )
+            && self.remaining_points(spread) >= 0
+    }
+
+    /// Explains the limits to the player
+    fn limits_text( &self ) -> String {
+        let lowest = self.floor.0.iter().min().copied().unwrap_or_default();
+        match self.floor.0.iter().all( |floor| *floor == lowest ) {
+            true  => format!("Every attribute must be between {lowest} and {}", self.max),
+            false => format!("Attributes can be raised up to {}, but not lowered", self.max)
+        }
+    }
+}
+
+
+/// The different things a user can do with the allocation message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationAction {
+    Select,
+    Decrease,
+    Increase,
+    Confirm
+}
+impl AllocationAction {
+    pub fn id( &self ) -> &'static str {
+        match self {
+            AllocationAction::Select   => "attr_select",
+            AllocationAction::Decrease => "attr_dec",
+            AllocationAction::Increase => "attr_inc",
+            AllocationAction::Confirm  => "attr_confirm",
+        }
+    }
+
+    pub fn from_id( id: &str ) -> Option<AllocationAction> {
+        match id {
+            "attr_select"  => Some( AllocationAction::Select ),
+            "attr_dec"     => Some( AllocationAction::Decrease ),
+            "attr_inc"     => Some( AllocationAction::Increase ),
+            "attr_confirm" => Some( AllocationAction::Confirm ),
+            _ => None
+        }
+    }
+}
+
+
+/// The state of an in-progress allocation. It is carried between interactions inside of the
+/// custom ids of the message's components, so that we don't have to hold it in memory
+///
+/// Custom ids look like: `<command_name>:<action>:<character_id>:<selected_index>:<spread>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocationState {
+    pub character_id: CharacterId,
+    pub selected:     Attribute,
+    pub spread:       AttributeSpread
+}
+impl AllocationState {
+
+    pub fn new( character_id: CharacterId, rules: &AllocationRules ) -> AllocationState {
+        AllocationState {
+            character_id,
+            selected: Attribute::Strength,
+            spread:   rules.starting_spread()
+        }
+    }
+
+    pub fn custom_id( &self, command_name: &str, action: AllocationAction ) -> String {
+        format!( "{}:{}:{}:{}:{}",
+            command_name,
+            action.id(),
+            self.character_id,
+            self.selected.index(),
+            self.spread.encode()
+        )
+    }
+
+    /// Parse a custom id created by `custom_id`. Returns `None` if it is mangled
+    pub fn parse_custom_id( custom_id: &str ) -> Option<(AllocationAction, AllocationState)> {
+        let components = custom_id.split(':').collect::<Vec<&str>>();
+        if components.len() != 5 {
+            return None
+        }
+
+        let action = AllocationAction::from_id( components[1] )?;
+        let state = AllocationState {
+            character_id: components[2].parse().ok()?,
+            selected:     Attribute::from_index( components[3].parse().ok()? )?,
+            spread:       AttributeSpread::decode( components[4] )?
+        };
+
+        Some(( action, state ))
+    }
+
+    /// Apply a `Decrease` or `Increase` to the selected attribute, as long as the rules allow it
+    pub fn adjust( &mut self, rules: &AllocationRules, delta: i64 ) {
+        let mut new_spread = self.spread;
+        new_spread.set( self.selected, self.spread.get(self.selected) + delta );
+
+        if rules.is_valid(&new_spread) {
+            self.spread = new_spread;
+        }
+    }
+
+    /// Build the embed that displays the current state of the allocation
+    pub fn embed( &self, character_name: &str, rules: &AllocationRules ) -> CreateEmbed {
+        let mut embed = CreateEmbed::new()
+            .title(format!("Allocate {character_name}'s attributes"))
+            .description(format!(
+                "Points remaining: **{}**\n{}",
+                rules.remaining_points(&self.spread), rules.limits_text()
+            ))
+            .colour(EmbedColours::info());
+
+        for attribute in Attribute::ALL {
+            let marker = if attribute == self.selected { "▶ " } else { "" };
+            embed = embed.field(
+                format!("{marker}{}", attribute.name()),
+                self.spread.get(attribute).to_string(),
+                true
+            );
+        }
+
+        embed
+    }
+
+    /// Build the select menu and buttons used to change the allocation
+    pub fn components( &self, command_name: &str, rules: &AllocationRules ) -> Vec<CreateActionRow> {
+
+        let select_options = Attribute::ALL
+            .iter()
+            .map( |attribute| {
+                CreateSelectMenuOption::new( attribute.name(), attribute.index().to_string() )
+                    .default_selection( *attribute == self.selected )
+            })
+            .collect::<Vec<CreateSelectMenuOption>>();
+
+        let select_menu = CreateSelectMenu::new(
+                self.custom_id( command_name, AllocationAction::Select ),
+                CreateSelectMenuKind::String { options: select_options }
+            )
+            .placeholder("Attribute to change");
+
+        let selected_value = self.spread.get(self.selected);
+        let buttons = vec![
+            CreateButton::new( self.custom_id(command_name, AllocationAction::Decrease) )
+                .label("-1")
+                .style(ButtonStyle::Secondary)
+                .disabled( selected_value <= rules.floor.get(self.selected) ),
+            CreateButton::new( self.custom_id(command_name, AllocationAction::Increase) )
+                .label("+1")
+                .style(ButtonStyle::Secondary)
+                .disabled( selected_value >= rules.max || rules.remaining_points(&self.spread) <= 0 ),
+            CreateButton::new( self.custom_id(command_name, AllocationAction::Confirm) )
+                .label("Confirm")
+                .style(ButtonStyle::Success)
+        ];
+
+        vec![
+            CreateActionRow::SelectMenu(select_menu),
+            CreateActionRow::Buttons(buttons)
+        ]
+    }
+}
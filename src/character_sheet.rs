@@ -0,0 +1,260 @@
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption
+};
+use sqlx::SqlitePool;
+
+use crate::{
+    attributes::Attribute,
+    db::{self, Ability, Attributes, Character, DbError, EquippedItem, Item, Progression, Resources},
+    derived_stats::{DerivedStats, Vitals},
+    equipment::{effective_spread, modifiers_text},
+    progression::progress_text,
+    utils::{paginate_fields, CharacterId, EmbedColours}
+};
+
+/// Longest value a single embed field can hold
+const MAX_FIELD_LENGTH: usize = 1024;
+
+
+/// Everything there is to know about a character, gathered from every table that refers to it
+pub struct CharacterSheet {
+    pub character:      Character,
+    /// `None` if the character's attributes haven't been allocated yet
+    pub attributes:     Option<Attributes>,
+    /// `None` if the character is at full HP and mana
+    pub resources:      Option<Resources>,
+    pub progression:    Progression,
+    /// Total weight of everything in their inventory
+    pub carried_weight: i64,
+    /// In the order of `Slot::ALL`
+    pub equipped:       Vec<EquippedItem>,
+    /// Items in their inventory that could be equipped, but aren't
+    pub equippable:     Vec<Item>,
+    pub abilities:      Vec<Ability>
+}
+impl CharacterSheet {
+
+    /// Load a character's sheet from the database. Returns `Ok(None)` if the character doesn't
+    /// exist or is in the trash
+    pub async fn load( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Option<CharacterSheet>, DbError> {
+
+        // Characters in the trash are treated as gone, so old sheets stop showing them
+        let character = match db::characters::get( database_connection, character_id ).await? {
+            Some( character ) if character.archived_at.is_none() => character,
+            _ => return Ok( None )
+        };
+
+        let equipped = db::equipment::get_equipped( database_connection, character_id ).await?;
+        let equippable = db::items::get_inventory( database_connection, character_id )
+            .await?
+            .into_iter()
+            .map( |entry| entry.item )
+            .filter( |item| item.equip_slot().is_some() )
+            .filter( |item| !equipped.iter().any( |equipped_item| equipped_item.item.item_id == item.item_id ) )
+            .collect();
+
+        Ok( Some( CharacterSheet {
+            character,
+            attributes:     db::attributes::get( database_connection, character_id ).await?,
+            resources:      db::resources::get( database_connection, character_id ).await?,
+            progression:    db::progression::get( database_connection, character_id ).await?,
+            carried_weight: db::items::carried_weight( database_connection, character_id ).await?,
+            equipped,
+            equippable,
+            abilities:      db::abilities::get_by_character( database_connection, character_id ).await?
+        }))
+    }
+
+    /// Split the sheet into pages of embed fields, as (name, value, inline). The first page always
+    /// starts with the overview of the character, the backstory and abilities follow and spill
+    /// onto further pages once a page would break Discord's embed limits
+    pub fn pages( &self ) -> Vec<Vec<(String, String, bool)>> {
+
+        let mut fields = vec![
+            ( "Species".to_owned(), self.character.species.clone(), true ),
+            ( "Owner".to_owned(), format!("<@{}>", self.character.owner_id), true ),
+            ( "Level".to_owned(), progress_text( &self.progression ), true ),
+        ];
+
+        if self.progression.unspent_points > 0 {
+            fields.push(( "Unspent points".to_owned(), format!("{}, spend them with /level_up", self.progression.unspent_points), true ));
+        }
+
+        match &self.attributes {
+            Some( character_attributes ) => {
+                // Attributes changed by equipment show their base value alongside
+                let spread = character_attributes.spread();
+                let effective = effective_spread( &spread, &self.equipped );
+                for attribute in Attribute::ALL {
+                    let value = match effective.get(attribute) == spread.get(attribute) {
+                        true  => spread.get(attribute).to_string(),
+                        false => format!("{} (base {})", effective.get(attribute), spread.get(attribute))
+                    };
+                    fields.push(( attribute.name().to_owned(), value, true ));
+                }
+
+                let stats = DerivedStats::from_spread( &spread );
+                let vitals = Vitals::new( &stats, self.resources.as_ref() );
+                fields.push(( "HP".to_owned(), format!("{}/{}", vitals.hp, vitals.max_hp), true ));
+                fields.push(( "Mana".to_owned(), format!("{}/{}", vitals.mana, vitals.max_mana), true ));
+                fields.push(( "Carrying".to_owned(), format!("{}/{}", self.carried_weight, stats.carry_capacity), true ));
+            },
+            None => fields.push(( "Attributes".to_owned(), "Not allocated yet".to_owned(), false ))
+        }
+
+        let equipment = self.equipped
+            .iter()
+            .filter_map( |equipped_item| {
+                let slot = equipped_item.item.equip_slot()?;
+                let modifiers = modifiers_text( &equipped_item.item.modifiers() );
+                Some( match modifiers.is_empty() {
+                    true  => format!("**{}:** {}", slot.name(), equipped_item.item.name),
+                    false => format!("**{}:** {} · {modifiers}", slot.name(), equipped_item.item.name)
+                })
+            })
+            .collect::<Vec<String>>();
+        fields.push(( "Equipment".to_owned(), match equipment.is_empty() {
+            true  => "Nothing equipped".to_owned(),
+            false => equipment.join("\n")
+        }, false ));
+
+        for ( index, chunk ) in split_text( &self.character.backstory, MAX_FIELD_LENGTH ).into_iter().enumerate() {
+            let name = if index == 0 { "Backstory" } else { "Backstory (continued)" };
+            fields.push(( name.to_owned(), chunk, false ));
+        }
+
+        if self.abilities.is_empty() {
+            fields.push(( "Abilities".to_owned(), "None".to_owned(), false ));
+        }
+        for ability in self.abilities.iter() {
+            for ( index, chunk ) in split_text( &ability.description, MAX_FIELD_LENGTH ).into_iter().enumerate() {
+                let name = match index {
+                    0 => format!("Ability: {}", ability.name),
+                    _ => format!("Ability: {} (continued)", ability.name)
+                };
+                fields.push(( name, chunk, false ));
+            }
+        }
+
+        paginate_fields( fields )
+    }
+
+    /// Build the embed for a single page of the sheet. Pages past the last one show the last page
+    pub fn embed( &self, page: usize ) -> CreateEmbed {
+        let pages = self.pages();
+        let page = page.min( pages.len() - 1 );
+
+        CreateEmbed::new()
+            .title( &self.character.name )
+            .fields( pages[page].clone() )
+            .footer( CreateEmbedFooter::new(format!("Page {}/{}", page + 1, pages.len())) )
+            .colour( EmbedColours::info() )
+    }
+
+    /// Build the components under the sheet: buttons to flip between pages if it doesn't fit onto
+    /// a single one, a select menu to equip items with and a button per slot to unequip them.
+    /// Their custom ids look like:
+    ///   - `<command_name>:page:<character_id>:<page>`
+    ///   - `<command_name>:equip:<character_id>:<page>`, the item's ID is the selected value
+    ///   - `<command_name>:unequip:<character_id>:<page>:<slot_id>`
+    pub fn components( &self, command_name: &str, page: usize ) -> Vec<CreateActionRow> {
+        let page_count = self.pages().len();
+        let page = page.min( page_count - 1 );
+        let character_id = self.character.character_id;
+
+        let mut components = vec![];
+        if page_count > 1 {
+            components.push( self.page_buttons(command_name, page, page_count) );
+        }
+
+        // A select menu holds at most 25 options
+        if !self.equippable.is_empty() {
+            let options = self.equippable
+                .iter()
+                .take(25)
+                .filter_map( |item| {
+                    let slot = item.equip_slot()?;
+                    let description = match modifiers_text( &item.modifiers() ) {
+                        modifiers if modifiers.is_empty() => slot.name().to_owned(),
+                        modifiers => format!("{} · {modifiers}", slot.name())
+                    };
+                    // Descriptions of options can't be longer than 100 characters
+                    let description = description.chars().take(100).collect::<String>();
+                    Some( CreateSelectMenuOption::new( &item.name, item.item_id.to_string() ).description( description ) )
+                })
+                .collect();
+
+            components.push( CreateActionRow::SelectMenu(
+                CreateSelectMenu::new( format!("{command_name}:equip:{character_id}:{page}"), CreateSelectMenuKind::String { options } )
+                    .placeholder("Equip an item")
+            ));
+        }
+
+        // There are six slots, but a row only fits five buttons
+        let unequip_buttons = self.equipped
+            .iter()
+            .filter_map( |equipped_item| equipped_item.item.equip_slot() )
+            .map( |slot| CreateButton::new( format!("{command_name}:unequip:{character_id}:{page}:{}", slot.id()) )
+                .label( format!("Unequip {}", slot.name()) )
+                .style(ButtonStyle::Secondary)
+            )
+            .collect::<Vec<CreateButton>>();
+        for buttons in unequip_buttons.chunks(5) {
+            components.push( CreateActionRow::Buttons(buttons.to_vec()) );
+        }
+
+        components
+    }
+
+    /// The buttons used to flip between pages
+    fn page_buttons( &self, command_name: &str, page: usize, page_count: usize ) -> CreateActionRow {
+        let buttons = vec![
+            CreateButton::new( format!("{command_name}:page:{}:{}", self.character.character_id, page.saturating_sub(1)) )
+                .label("Previous")
+                .style(ButtonStyle::Secondary)
+                .disabled( page == 0 ),
+            CreateButton::new( format!("{command_name}:page:{}:{}", self.character.character_id, page + 1) )
+                .label("Next")
+                .style(ButtonStyle::Secondary)
+                .disabled( page + 1 >= page_count ),
+        ];
+
+        CreateActionRow::Buttons(buttons)
+    }
+}
+
+
+/// Split text into chunks of at most `max_length` characters. Chunks end at the last whitespace
+/// that fits, unless there is none in which case the text is cut mid-word
+fn split_text( text: &str, max_length: usize ) -> Vec<String> {
+    let mut chunks = vec![];
+    let mut remaining = text.trim();
+
+    while remaining.chars().count() > max_length {
+        // Byte index of the first character that doesn't fit anymore
+        let cut_at = remaining
+            .char_indices()
+            .nth( max_length )
+            .map( |(index, _)| index )
+            .expect("Text is longer than max_length");
+
+        let split_at = match remaining[..cut_at].rfind( char::is_whitespace ) {
+            Some( index ) if index > 0 => index,
+            _ => cut_at
+        };
+
+        chunks.push( remaining[..split_at].trim_end().to_owned() );
+        remaining = remaining[split_at..].trim_start();
+    }
+
+    // Embed field values can't be empty
+    if !remaining.is_empty() || chunks.is_empty() {
+        chunks.push( match remaining.is_empty() {
+            true  => "-".to_owned(),
+            false => remaining.to_owned()
+        });
+    }
+
+    chunks
+}
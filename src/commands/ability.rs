@@ -0,0 +1,533 @@
+// Manage the abilities of a user's characters
+//
+// - `/ability add` and `/ability edit` dispatch a modal asking for the ability's name and
+//     description. Edit's modal comes prefilled with the current values
+// - `/ability remove` removes the selected ability right away
+// - `/ability list` responds with an embed of every ability the character has. Lists that don't
+//     fit into a single embed are split into pages, which can be flipped through with buttons.
+//     Their custom ids look like: `ability:page:<character_id>:<page>`
+// - Every subcommand has a character option which autocompletes over the invoking user's
+//     characters, edit and remove also have an ability option which autocompletes over the
+//     selected character's abilities
+// - The character option can be left out, in which case the user's active character is used
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, ButtonStyle, CommandInteraction, CommandOptionType, ComponentInteraction,
+    CreateActionRow, CreateAutocompleteResponse, CreateButton, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateEmbedFooter, CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage, CreateModal,
+    InputTextStyle, ModalInteraction, ResolvedOption, ResolvedValue
+};
+
+use tracing::{info, warn};
+
+use crate::{
+    commands::registry::SlashCommand,
+    db::{self, Ability},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        active_character_footer, add_active_character_footer, error_embed, get_user_character_name,
+        integer_option, modal_input_values, paginate_fields, search_user_characters,
+        selected_character_id, CharacterId, EmbedColours
+    }
+};
+
+/// Longest name an ability can have, also used as the max length of the modal's name field
+const MAX_NAME_LENGTH: u16 = 100;
+/// Longest description an ability can have. Matches the limit of an embed field's value
+const MAX_DESCRIPTION_LENGTH: u16 = 1024;
+
+
+/// Build the ability command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+
+    // Every subcommand needs to know which character we're working with, and some of them which
+    // ability. Closures keep us from repeating their definitions
+    let character_option = || CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "character",
+            "The character whose abilities to manage. Defaults to your active character"
+        )
+        .required(false)
+        .set_autocomplete(true);
+
+    let ability_option = || CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "ability",
+            "The ability to manage"
+        )
+        .required(true)
+        .set_autocomplete(true);
+
+    let subcommands = vec![
+        CreateCommandOption::new( CommandOptionType::SubCommand, "add", "Give a character a new ability" )
+            .add_sub_option( character_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "edit", "Change an ability's name or description" )
+            .add_sub_option( character_option() )
+            .add_sub_option( ability_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "remove", "Remove an ability from a character" )
+            .add_sub_option( character_option() )
+            .add_sub_option( ability_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "list", "List every ability of a character" )
+            .add_sub_option( character_option() ),
+    ];
+
+    CreateCommand::new("ability")
+        .description("Manage your characters' abilities")
+        .set_options(subcommands)
+}
+
+
+/// Build the modal used by both `add` and `edit`. `current` holds the ability's current name and
+/// description when editing
+fn ability_modal( custom_id: String, title: String, current: Option<(String, String)> ) -> CreateModal {
+    let mut name_input = CreateInputText::new( InputTextStyle::Short, "Ability Name", "name" )
+        .max_length( MAX_NAME_LENGTH );
+    let mut description_input = CreateInputText::new( InputTextStyle::Paragraph, "Ability Description", "description" )
+        .max_length( MAX_DESCRIPTION_LENGTH );
+
+    if let Some(( name, description )) = current {
+        name_input        = name_input.value( name );
+        description_input = description_input.value( description );
+    }
+
+    CreateModal::new( custom_id, title )
+        .components(vec![
+            CreateActionRow::InputText( name_input ),
+            CreateActionRow::InputText( description_input )
+        ])
+}
+
+/// Wrap an embed into a message response, with the footer showing the user's active character
+fn message_response( embed: CreateEmbed, footer: &Option<CreateEmbedFooter> ) -> CreateInteractionResponse {
+    let embed = match footer {
+        Some( footer ) => embed.footer( footer.clone() ),
+        None => embed
+    };
+
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed(embed)
+    )
+}
+
+/// Build a page of a character's abilities, along with the buttons to flip through them. Pages
+/// past the last one show the last page
+fn abilities_page( character_id: CharacterId, character_name: &str, character_abilities: Vec<Ability>, page: usize ) -> ( CreateEmbed, Vec<CreateActionRow> ) {
+    let fields = character_abilities
+        .into_iter()
+        .map( |ability| ( ability.name, ability.description, false ) )
+        .collect();
+    let pages = paginate_fields( fields );
+    let page_count = pages.len();
+    let page = page.min( page_count - 1 );
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{character_name}'s abilities"))
+        .fields( pages[page].clone() )
+        .colour(EmbedColours::info());
+
+    // The footer is taken by the active character, so the page goes into the description
+    if page_count <= 1 {
+        return ( embed, vec![] )
+    }
+    embed = embed.description(format!("Page {}/{page_count}", page + 1));
+
+    let buttons = vec![
+        CreateButton::new( format!("ability:page:{character_id}:{}", page.saturating_sub(1)) )
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled( page == 0 ),
+        CreateButton::new( format!("ability:page:{character_id}:{}", page + 1) )
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled( page + 1 >= page_count ),
+    ];
+
+    ( embed, vec![CreateActionRow::Buttons(buttons)] )
+}
+
+/// Wrap an error into a message response. Unlike `message_response`, the footer is left to the
+/// error's correlation id
+fn error_response( error: BotError, context: impl std::fmt::Display ) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( error.embed(context) )
+    )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    // Our command only consists of subcommands, so the first option will always be one
+    let options = interaction_data.data.options();
+    let ( subcommand_name, subcommand_options ) = match options.first() {
+        Some( ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. } ) => ( *name, sub_options ),
+        _ => return None
+    };
+
+    // Every embed we respond with carries the user's active character in it's footer
+    let footer = active_character_footer( ctx, &invoking_user_id ).await;
+
+    let response = 'response: {
+
+        // --== CHARACTER OWNERSHIP TEST ==-- //
+
+            // Every subcommand has a character option, if it is left out we use the active
+            // character. Either way, it must belong to the invoking user
+            let character_id = match selected_character_id( ctx, &invoking_user_id, integer_option(subcommand_options, "character") ).await {
+                Some( id ) => id,
+                None => break 'response error_response(
+                    BotError::NoActiveCharacter,
+                    format!("{invoking_user_tag} has no active character in /ability")
+                )
+            };
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'response error_response(
+                    BotError::NotOwner,
+                    format!("{invoking_user_tag} picked someone else's character in /ability")
+                )
+            };
+        // ==--
+
+        match subcommand_name {
+
+            "add" => CreateInteractionResponse::Modal(ability_modal(
+                format!("ability:add:{character_id}"),
+                format!("New ability for {character_name}"),
+                None
+            )),
+
+            "edit" => {
+                let ability_id = integer_option( subcommand_options, "ability" )?;
+
+                let query_result = db::abilities::get( &discord_bot.database_connection, character_id, ability_id ).await;
+
+                match query_result {
+                    Ok( Some(ability) ) => CreateInteractionResponse::Modal(ability_modal(
+                        format!("ability:edit:{character_id}:{ability_id}"),
+                        format!("Editing {character_name}'s ability"),
+                        Some(( ability.name, ability.description ))
+                    )),
+                    Ok( None ) => message_response(
+                        error_embed( format!("{character_name} doesn't have that ability"), "Use /ability list to see their abilities" ),
+                        &footer
+                    ),
+                    Err( why ) => error_response(
+                        BotError::from( why ),
+                        format!("Failed to fetch {invoking_user_tag}'s ability")
+                    )
+                }
+            },
+
+            "remove" => {
+                let ability_id = integer_option( subcommand_options, "ability" )?;
+
+                let query_result = db::abilities::remove( &discord_bot.database_connection, character_id, ability_id ).await;
+
+                match query_result {
+                    Ok( false ) => message_response(
+                        error_embed( format!("{character_name} doesn't have that ability"), "Use /ability list to see their abilities" ),
+                        &footer
+                    ),
+                    Ok( true ) => message_response(
+                        CreateEmbed::new()
+                            .title(format!("Removed the ability from {character_name}"))
+                            .colour(EmbedColours::good()),
+                        &footer
+                    ),
+                    Err( why ) => error_response(
+                        BotError::from( why ),
+                        format!("Failed to remove {invoking_user_tag}'s ability")
+                    )
+                }
+            },
+
+            "list" => {
+                let query_result = db::abilities::get_by_character( &discord_bot.database_connection, character_id ).await;
+
+                match query_result {
+                    Ok( character_abilities ) if character_abilities.is_empty() => message_response(
+                        CreateEmbed::new()
+                            .title(format!("{character_name} doesn't have any abilities"))
+                            .description("You can give them one with /ability add")
+                            .colour(EmbedColours::info()),
+                        &footer
+                    ),
+                    Ok( character_abilities ) => {
+                        let ( embed, components ) = abilities_page( character_id, &character_name, character_abilities, 0 );
+                        let embed = match &footer {
+                            Some( footer ) => embed.footer( footer.clone() ),
+                            None => embed
+                        };
+
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .embed(embed)
+                                .components(components)
+                        )
+                    },
+                    Err( why ) => error_response(
+                        BotError::from( why ),
+                        format!("Failed to list {invoking_user_tag}'s abilities")
+                    )
+                }
+            },
+
+            _ => return None
+        }
+    };
+
+    Some( response )
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) => option,
+            None => break 'choices vec![]
+        };
+
+        match focused_option.name {
+
+            "character" => {
+                search_user_characters( ctx, &invoking_user_id, focused_option.value )
+                    .await
+                    .into_iter()
+                    .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                    .collect()
+            },
+
+            "ability" => {
+                // To suggest abilities we need to know which character was selected, or the
+                // active one if none was. It has to belong to the user, otherwise we'd be leaking
+                // other people's abilities
+                let options = interaction_data.data.options();
+                let character_id = match options.first() {
+                    Some( ResolvedOption { value: ResolvedValue::SubCommand(sub_options), .. } ) => {
+                        selected_character_id( ctx, &invoking_user_id, integer_option(sub_options, "character") ).await
+                    },
+                    _ => None
+                };
+                let character_id = match character_id {
+                    Some( id ) => id,
+                    None => break 'choices vec![]
+                };
+
+                if get_user_character_name( ctx, &invoking_user_id, character_id ).await.is_none() {
+                    break 'choices vec![]
+                }
+
+                let query_result = db::abilities::get_by_character( &discord_bot.database_connection, character_id ).await;
+
+                // Just like in the other autocompletes, we won't log the error as there are far
+                // too many autocomplete interactions. An empty list will have to do
+                let character_abilities = match query_result {
+                    Ok( character_abilities ) => character_abilities,
+                    Err(_) => break 'choices vec![]
+                };
+
+                let query = focused_option.value.to_lowercase();
+                character_abilities
+                    .into_iter()
+                    .filter( |ability| ability.name.to_lowercase().contains(&query) )
+                    .take(25)
+                    .map( |ability| AutocompleteChoice::new(ability.name, ability.ability_id) )
+                    .collect()
+            },
+
+            _ => vec![]
+        }
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /ability")
+    }
+}
+
+
+// Both the add and edit modals land here. Their custom ids look like:
+//   - ability:add:<character_id>
+//   - ability:edit:<character_id>:<ability_id>
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create both the modal and it's id ourselves, so if anything here is missing there's
+    // nothing we can do but return
+    let character_id: i64 = match id_components.get(2).and_then( |id| id.parse().ok() ) {
+        Some( id ) => id,
+        None => return
+    };
+    let ( ability_name, ability_description ) = match modal_input_values( interaction_data ).as_deref() {
+        Some( [name, description] ) => ( name.clone(), description.clone() ),
+        _ => return
+    };
+
+    let footer = active_character_footer( ctx, &invoking_user_id ).await;
+
+    let response = 'response: {
+
+        // The character could have been deleted between opening and submitting the modal
+        let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+            Some( name ) => name,
+            None => break 'response error_response(
+                BotError::NotOwner,
+                format!("{invoking_user_tag} picked someone else's character in /ability")
+            )
+        };
+
+        let query_result = match ( id_components[1], id_components.get(3) ) {
+            ( "add", None ) => {
+                db::abilities::add( &discord_bot.database_connection, character_id, &ability_name, &ability_description )
+                    .await
+                    .map( |_| true )
+            },
+            ( "edit", Some(ability_id) ) => {
+                let ability_id: i64 = match ability_id.parse() {
+                    Ok( id ) => id,
+                    Err(_) => return
+                };
+
+                let ability = Ability {
+                    character_id,
+                    ability_id,
+                    name:        ability_name.clone(),
+                    description: ability_description.clone()
+                };
+                db::abilities::update( &discord_bot.database_connection, &ability ).await
+            },
+            _ => return
+        };
+
+        match query_result {
+            // Editing an ability that was removed after the modal got opened doesn't update
+            // anything
+            Ok( false ) => message_response(
+                error_embed( format!("{character_name} doesn't have that ability"), "Use /ability list to see their abilities" ),
+                &footer
+            ),
+            Ok( true ) => {
+                info!("{invoking_user_tag} saved the ability {ability_name} of {character_name}");
+
+                message_response(
+                    CreateEmbed::new()
+                        .title(format!("{character_name} now has {ability_name}"))
+                        .description(&ability_description)
+                        .colour(EmbedColours::good()),
+                    &footer
+                )
+            },
+            Err( why ) => error_response(
+                BotError::from( why ),
+                format!("Failed to save {invoking_user_tag}'s ability")
+            )
+        }
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to send response in /ability")
+    }
+}
+
+
+// Flip the page of an ability list. Only the character's owner can list their abilities, so only
+// they can flip through them too
+pub async fn handle_component( interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create these ids ourselves, there's nothing we can do with a mangled one
+    let ( character_id, page ): (CharacterId, usize) = match id_components.as_slice() {
+        [ _, "page", character_id, page ] => match ( character_id.parse(), page.parse() ) {
+            ( Ok(character_id), Ok(page) ) => ( character_id, page ),
+            _ => return
+        },
+        _ => return
+    };
+
+    let response = 'response: {
+
+        let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+            Some( name ) => name,
+            None => break 'response CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed( BotError::NotOwner.embed(format!("{invoking_user_tag} tried to flip through someone else's abilities")) )
+                    .ephemeral(true)
+            )
+        };
+
+        // We reload the abilities, so the page always reflects the character's current ones
+        match db::abilities::get_by_character( &discord_bot.database_connection, character_id ).await {
+            Ok( character_abilities ) => {
+                let ( embed, components ) = abilities_page( character_id, &character_name, character_abilities, page );
+
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+                        .components( components )
+                )
+            },
+            Err( why ) => {
+                warn!(error = %why, "Failed to load abilities");
+                return
+            }
+        }
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to flip the page of an ability list")
+    }
+}
+
+
+/// Routes /ability and it's interactions to the functions above
+pub struct AbilityCommand;
+#[async_trait]
+impl SlashCommand for AbilityCommand {
+    fn name( &self ) -> &'static str {
+        "ability"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+
+    async fn component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_component( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
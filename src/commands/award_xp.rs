@@ -0,0 +1,191 @@
+// Award experience to several characters at once
+//
+// - `/award_xp amount:<n> players:<mentions>` gives every mentioned player's active character the
+//     experience. Any number of players can be mentioned in the one option, as Discord has no
+//     options that take a list
+// - Only members who can manage the server see the command by default, the same as `/spell`.
+//     Discord doesn't apply that in DMs, so the bot only takes it from server managers in a server
+// - Characters who level up are told how many attribute points they've got to spend with
+//     `/level_up`. Players who aren't playing as anyone are listed as skipped
+
+use serenity::all::{
+    async_trait, CommandInteraction, CommandOptionType, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, Permissions
+};
+use tracing::{info, warn};
+
+use crate::{
+    commands::registry::SlashCommand,
+    error::BotError,
+    event_handler::DiscordBot,
+    progression::{award_experience, progress_text},
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_active_character, integer_option, manages_guild,
+        string_option, EmbedColours
+    }
+};
+
+/// Most players that can be awarded at once. Each gets a field, and an embed holds 25 of them.
+/// Two are kept for those who were skipped or failed
+const MAX_PLAYERS: usize = 23;
+
+
+/// Build the award_xp command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    CreateCommand::new("award_xp")
+        .description("Award experience to the active characters of some players")
+        .default_member_permissions( Permissions::MANAGE_GUILD )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "amount", "How much experience each character gets" )
+                .required(true)
+                .min_int_value(1)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::String, "players", "Mention every player to award it to" )
+                .required(true)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::String, "reason", "What it was awarded for" )
+                .required(false)
+                .max_length(200)
+        )
+}
+
+
+/// Pull the IDs of every user mentioned in the text, like `<@123>` or `<@!123>`, in the order
+/// they're mentioned and without repeats
+pub fn mentioned_user_ids( text: &str ) -> Vec<u64> {
+    let mut user_ids = vec![];
+
+    for mention in text.split("<@").skip(1) {
+        let mention = mention.strip_prefix('!').unwrap_or( mention );
+        let user_id = mention
+            .split_once('>')
+            .and_then( |(user_id, _)| user_id.parse::<u64>().ok() );
+
+        if let Some( user_id ) = user_id {
+            if !user_ids.contains( &user_id ) {
+                user_ids.push( user_id );
+            }
+        }
+    }
+
+    user_ids
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    // --== READ OPTIONS ==-- //
+
+        let options = interaction_data.data.options();
+
+        let amount = integer_option( &options, "amount" )?;
+        let players = mentioned_user_ids( string_option(&options, "players")? );
+        let reason = string_option( &options, "reason" );
+    // ==--
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        // --== PERMISSION TEST ==-- //
+
+            if interaction_data.guild_id.is_none() {
+                break 'return_embed error_embed( "Experience is awarded in a server", "Please use this in the server the game is played in" )
+            }
+            if !manages_guild( interaction_data ) {
+                break 'return_embed BotError::NotServerManager
+                    .embed( format!("{invoking_user_tag} tried to award experience without managing the server") )
+            }
+        // ==--
+
+        if players.is_empty() {
+            break 'return_embed error_embed( "Nobody to award it to", "Mention the players in the players option, like @Merlin" )
+        }
+        if players.len() > MAX_PLAYERS {
+            break 'return_embed error_embed(
+                "Too many players",
+                format!("Experience can be awarded to at most {MAX_PLAYERS} players at once")
+            )
+        }
+
+        let mut fields = vec![];
+        let mut skipped = vec![];
+        let mut failed = vec![];
+
+        // Every character is awarded on their own, so one failing doesn't hold back the rest
+        for player_id in players {
+            let ( character_id, character_name ) = match get_active_character( ctx, &player_id ).await {
+                Some( character ) => character,
+                None => {
+                    skipped.push( format!("<@{player_id}>") );
+                    continue
+                }
+            };
+
+            let ( before, after ) = match award_experience( &discord_bot.database_connection, character_id, amount, invoking_user_id ).await {
+                Ok( progressions ) => progressions,
+                Err( why ) => {
+                    warn!(error = %why, "Failed to award experience to {character_name}");
+                    failed.push( character_name );
+                    continue
+                }
+            };
+
+            info!("{invoking_user_tag} awarded {amount} XP to {character_name}, now level {}", after.level);
+
+            let mut value = progress_text( &after );
+            if after.level > before.level {
+                value.push_str(&format!(
+                    "\n**Reached level {}!** {} attribute points to spend with /level_up",
+                    after.level, after.unspent_points
+                ));
+            }
+            fields.push(( character_name, value, false ));
+        }
+
+        let mut embed = CreateEmbed::new()
+            .title( format!("Awarded {amount} XP") )
+            .fields( fields )
+            .colour( EmbedColours::good() );
+
+        if let Some( reason ) = reason {
+            embed = embed.description( reason );
+        }
+        if !skipped.is_empty() {
+            embed = embed.field( "Skipped, as they aren't playing as anyone", skipped.join(", "), false );
+        }
+        if !failed.is_empty() {
+            embed = embed
+                .field( "Couldn't be awarded, try them again", failed.join(", "), false )
+                .colour( EmbedColours::error() );
+        }
+
+        embed
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+    ))
+}
+
+
+/// Routes /award_xp to the functions above
+pub struct AwardXpCommand;
+#[async_trait]
+impl SlashCommand for AwardXpCommand {
+    fn name( &self ) -> &'static str {
+        "award_xp"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+}
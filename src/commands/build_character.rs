@@ -1,17 +1,29 @@
-#[allow(unused_imports)]
 use serenity::{
     all::{
-        ActionRowComponent, CreateActionRow, CreateInputText,
-        CreateModal, CreateSelectMenu, CreateSelectMenuOption, InputText, InputTextStyle, ModalInteraction}, builder::{
+        async_trait, ActionRowComponent, ComponentInteraction, ComponentInteractionDataKind, CreateActionRow, CreateInputText,
+        CreateModal, InputTextStyle, ModalInteraction}, builder::{
         CreateCommand, CreateEmbed,
         CreateInteractionResponse,
         CreateInteractionResponseMessage,
-    }, client::Context, model::application::CommandInteraction,
-    futures::StreamExt
+    }, model::application::CommandInteraction
+};
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+
+use tracing::{info, warn};
+
+use crate::{
+    attributes::{AllocationAction, AllocationRules, AllocationState, Attribute},
+    commands::registry::SlashCommand,
+    db::{self, Attributes},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, clone_user_characters,
+        CharacterId, CharacterMap, DatabaseCharactersCache, EmbedColours
+    }
 };
-use sqlx::Row;
-
-use crate::{event_handler::DiscordBot, sql_scripts::characters, utils::{self, create_log_message, DatabaseCharactersCache, EmbedColours, LogLevel}};
 
 
 
@@ -20,36 +32,55 @@ pub fn build() -> CreateCommand {
         .description("Build your character")
 }
 
-pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Option<CreateInteractionResponse> {
+/// Build the three field modal used to describe a character. `current` holds the character's
+/// current name, species and backstory when it is used for editing, which prefill the fields
+pub fn character_modal( custom_id: impl Into<String>, title: impl Into<String>, current: Option<(String, String, String)> ) -> CreateModal {
 
+    let mut name_input      = CreateInputText::new(InputTextStyle::Short, "Character Name", "name");
+    let mut species_input   = CreateInputText::new(InputTextStyle::Short, "Character Species", "species");
+    let mut backstory_input = CreateInputText::new(InputTextStyle::Paragraph, "Character Backstory", "backstory");
 
+    if let Some(( name, species, backstory )) = current {
+        name_input      = name_input.value( name );
+        species_input   = species_input.value( species );
+        backstory_input = backstory_input.value( backstory );
+    }
 
     let modal_components = vec![
-        CreateActionRow::InputText(CreateInputText::new(InputTextStyle::Short, "Character Name", "name")),
-        CreateActionRow::InputText(CreateInputText::new(InputTextStyle::Short, "Character Species", "species")),
-        CreateActionRow::InputText(CreateInputText::new(InputTextStyle::Paragraph, "Character Backstory", "backstory"))
+        CreateActionRow::InputText( name_input ),
+        CreateActionRow::InputText( species_input ),
+        CreateActionRow::InputText( backstory_input )
     ];
 
-    let new_modal = CreateModal::new("build_character", "Build a character")
-        .components( modal_components );
+    CreateModal::new( custom_id, title )
+        .components( modal_components )
+}
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext ) -> Option<CreateInteractionResponse> {
+
+    let new_modal = character_modal( "build_character", "Build a character", None );
 
     let a = CreateInteractionResponse::Modal(new_modal);
-    let b = interaction_data.create_response(&ctx.http, a);
+    let b = ctx.responses.create_response( interaction_data.id, &interaction_data.token, a );
 
     if let Err(why) = b.await {
-        println!("{}", why)
+        warn!(error = %why, "Failed to send build_character modal")
     }
 
     None
 }
 
 // After a user submits the modal, we need to parse the incoming data
-pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &Context, discord_bot: &DiscordBot ) {
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
 
     // Later on we will need to use the invokering user's id for database queries. And tag for
     // logging purposes. We'll assign them here
     let invoking_user_id  = interaction_data.user.id.get();
     let invoking_user_tag = interaction_data.user.tag();
+
+    // If the character gets created, the player is then asked to allocate their attributes. The
+    // components for that get stored here so they can be sent alongside the embed
+    let mut allocation_components = vec![];
     
     // The modal that we recieve has 3 components, each containing a InputText action row
     // component. This is certain as we created the modal in the above function. That is
@@ -74,141 +105,262 @@ pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &Context, d
         ( data[0].clone(), data[1].clone(), data[2].clone() )
     };
 
-    let embed_for_message = 'return_embed: {
-
-        // --== CHARACTER NAME UNIQUENESS TEST ==-- //
-
-            // Check to see the user already has a character of the given name.
-            // I would use a composite key in the SQL table, but we've got a foreign key in DiscordUsers
-            // and those can't reference to a part of a composite key.
-            let mut query_result = sqlx::query( characters::SELECT_BY_NAME_AND_OWNER_ID )
-                .bind(invoking_user_id as i64)    // fk_discordId
-                .bind(&character_data.0)    // Character Name
-                .fetch( &discord_bot.database_connection );
-            
-            if query_result.next().await.is_some() {
-                break 'return_embed CreateEmbed::new()
-                    .title(format!("{} Is already in the database", character_data.0))
-                    .description("If you want to remove them, use /delete_character")
-                    .colour(EmbedColours::ERROR)
-            }
-        // ==--
+    // Failures are kept as a `BotError`, so that they can be reported in a single place below
+    let embed_for_message: Result<CreateEmbed, BotError> = {
 
+        // The character goes into both the database and the characters cache
+        let characters_cache = ctx.data.read().await
+            .get::<DatabaseCharactersCache>()
+            .expect("Key 'DatabaseCharactersCache' must be in map, as it get's inserted in main.rs")
+            .clone();
 
-        let query_result = sqlx::query( characters::ADD_CHARACTER )
-        // -= Bind Values =- //
-            .bind(invoking_user_id as i64)  // fk_discordId
-            .bind(&character_data.0)        // Chracater Name
-            .bind(&character_data.1)        // Chracater Species
-            .bind(&character_data.2)        // Chracater Backstory
-        // =-
-            .execute( &discord_bot.database_connection )
-            .await;
+        let query_result = create_character(
+            &discord_bot.database_connection,
+            &characters_cache,
+            invoking_user_id,
+            &character_data.0,  // Name
+            &character_data.1,  // Species
+            &character_data.2   // Backstory
+        ).await;
 
         match query_result {
-            Ok(_) => {  // We don't need to worry about how many lines got modified
-
-                // get character's SQL given ID
-                let query_result = sqlx::query( characters::GET_NEWEST_CHARACTER_ID )
-                    .fetch_all( &discord_bot.database_connection )
-                    .await
-                    .expect("Should Work");
-
-                let character_id: u16 = query_result[0]
-                    .get(0);
-
-                // --== SYNC CACHE TO DATABASE ==-- //
-
-                // Lock Client.data's DatabaseCharactersCache and insert new character data to it
-                {  // We use brackets to keep the write lock for only as long as we need it
-
-                    let data_write = ctx.data.write().await;
-                    let character_cache_mutex = data_write.get::<DatabaseCharactersCache>()
-                        .expect("Key 'DatabaseCharactersCache' must be in map, as it get's inserted in main.rs");
-
-                    match character_cache_mutex.lock() {
-                        Err(_) => {
-                            // The only way this could error is if the mutex is poisoned. And the
-                            // only way that could happen is if a thread panicked while holding a
-                            // lock to this.
-                            //
-                            // This shouldn't ever occur as We will be designing the bot in such a
-                            // way that it cannot panic while holding a mutex lock.
-                            println!("{}", create_log_message(
-                                    "Poisoned Mutex in /build_character; Cache out of sync",
-                                    LogLevel::Error
-                            ));
-                            break 'return_embed CreateEmbed::new()
-                                .title(format!("{} successfully added, but an unexpected error occured", character_data.0))
-                                .description("Cache is out of sync due to an unexpected error. Please notify Bot Administrator")
-                                .colour(EmbedColours::ERROR)
-                        },
-                        Ok(mut map_guard) => {
-                            match map_guard.get_mut(&invoking_user_id) {
-                                None => {
-                                    map_guard.insert(
-                                        invoking_user_id,
-                                        vec![(character_id, character_data.0.clone())]
-                                    );
-                                },
-                                Some(characters) => {
-                                    characters.push(
-                                        ( character_id, character_data.0.clone() )
-                                    );
-                                }
-                            };
-                        }
-                    };
+            Ok( character_id ) => {
 
-                    
-                }
+                // --== START ATTRIBUTE ALLOCATION ==-- //
 
-                CreateEmbed::new()
-                    .title(format!("{} successfully added!", character_data.0))
-                    .colour(EmbedColours::GOOD)
-            },
-            Err(why) => {
-                // One of the errors that could occur is of code 787 which designates a FOREIGN KEY
-                // constraint failure. This occurs when the user doesn't have a profile. So we need
-                // to check for that and explain it to the user. Or if it's some other error, say
-                // to try again
-                if let sqlx::Error::Database( sqlite_error ) = &why {
-                    let error_code = match sqlite_error.code() {
-                        None => 0,  // If we can't get the code we're looking for, it might as well
-                                    // not be the one we're looking for. 0 will do for this purpose
-                        Some( code ) => code.into_owned()
-                            .parse()
-                            .unwrap_or(0)    // Same as before, if we can't parse it might as well
-                                             // be a different code.
-                    };
+                    // The character exists now, but it doesn't have any attributes yet. We'll
+                    // respond with the allocation message so they can be distributed right away
+                    info!("{invoking_user_tag} built {}", character_data.0);
 
-                    if error_code == 787 {
-                        break 'return_embed CreateEmbed::new()
-                            .title("You haven't been added to the database")
-                            .description("You can add yourself by using /register. After that you can build your character!")
-                            .colour(EmbedColours::ERROR);
-                    }
-                };
+                    let rules = AllocationRules::character_creation();
+                    let allocation_state = AllocationState::new( character_id, &rules );
 
-                CreateEmbed::new()
-                    .title("A unexpected error occured")
-                    .description("If it persists, feel free to open an issue on the bot's github page")
-                    .color(EmbedColours::ERROR)
-            }
+                    allocation_components = allocation_state.components( "build_character", &rules );
+                    Ok( allocation_state.embed( &character_data.0, &rules )
+                        .title(format!("{} successfully added! Now allocate their attributes", character_data.0)) )
+                // ==--
+            },
+            // The user needs a profile before they can own characters, or they might already have
+            // a character of that name. `BotError` explains either to them
+            Err( why ) => Err( why )
         }
     };
-    
+
+    // Like every other response, it shows which character the user is playing as. Errors show
+    // their correlation id in the footer instead
+    let embed_for_message = match embed_for_message {
+        Ok( embed ) => add_active_character_footer( ctx, &invoking_user_id, embed ).await,
+        Err( why ) => why.embed( format!("Failed to add {invoking_user_tag}'s character") )
+    };
+
     // We now load our resultant embed into a payload
-    let new_response_message = CreateInteractionResponseMessage::new().embed( embed_for_message );
+    let new_response_message = CreateInteractionResponseMessage::new()
+        .embed( embed_for_message )
+        .components( allocation_components );
     let new_response         = CreateInteractionResponse::Message( new_response_message );
-    let send_message_payload = interaction_data.create_response( &ctx.http, new_response );
+    let send_message_payload = ctx.responses.create_response( interaction_data.id, &interaction_data.token, new_response );
 
     // Send the payload, report to Stdout if an error occurs
     if let Err( why ) = send_message_payload.await {
-        println!("{}", create_log_message(
-                format!("Failed to send response in /register:\n\t{}", why ),
-                LogLevel::Warning
-        ))
+        warn!(error = %why, "Failed to send response in /build_character")
     }
 }
 
+// Every press of a button, or change of the select menu, in the allocation message lands here. The
+// state of the allocation is carried inside of the component's custom id, so we parse it, apply the
+// action, and update the message in place
+pub async fn handle_component( interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let rules = AllocationRules::character_creation();
+
+    let response = 'response: {
+
+        // --== PARSE ALLOCATION STATE ==-- //
+
+            // We create these ids ourselves, so a mangled one shouldn't occur. If it does we can't
+            // do anything with it, so we'll just log it and return
+            let ( action, mut allocation_state ) = match AllocationState::parse_custom_id( &interaction_data.data.custom_id ) {
+                Some( parsed ) => parsed,
+                None => {
+                    warn!("Recived mangled allocation id: {}", interaction_data.data.custom_id);
+                    return
+                }
+            };
+        // ==--
+
+        // --== OWNERSHIP TEST ==-- //
+
+            // The allocation message is visible to everyone in the channel, so we need to make
+            // sure that only the character's owner can press the buttons
+            let user_owned_characters = {
+                let data_read = ctx.data.read().await;
+                let character_map_mutex = data_read
+                    .get::<DatabaseCharactersCache>()
+                    .expect("Key 'DatabaseCharactersCache' must be in map, as it get's inserted in main.rs");
+
+                clone_user_characters( character_map_mutex.clone(), &invoking_user_id )
+                    .unwrap_or(vec![])
+            };
+
+            let character_name = match user_owned_characters.iter().find( |x| x.0 == allocation_state.character_id ) {
+                Some( character ) => character.1.clone(),
+                None => {
+                    // Only the owner of a character can allocate it's attributes
+                    let embed = BotError::NotOwner
+                        .embed( format!("{invoking_user_tag} tried to allocate someone else's attributes") );
+
+                    break 'response CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new().embed(embed).ephemeral(true)
+                    )
+                }
+            };
+        // ==--
+
+        // --== APPLY ACTION ==-- //
+
+            match action {
+                AllocationAction::Select => {
+                    // The value of a select menu is the index of the chosen attribute
+                    if let ComponentInteractionDataKind::StringSelect { values } = &interaction_data.data.kind {
+                        let chosen_attribute = values
+                            .first()
+                            .and_then( |value| value.parse().ok() )
+                            .and_then( Attribute::from_index );
+
+                        if let Some( attribute ) = chosen_attribute {
+                            allocation_state.selected = attribute;
+                        }
+                    }
+                },
+                AllocationAction::Decrease => allocation_state.adjust( &rules, -1 ),
+                AllocationAction::Increase => allocation_state.adjust( &rules,  1 ),
+                AllocationAction::Confirm  => {
+
+                    let query_result = save_attributes( &discord_bot.database_connection, &allocation_state, &rules ).await;
+
+                    let embed = match query_result {
+                        Ok(_) => {
+                            info!("{invoking_user_tag} allocated {character_name}'s attributes");
+
+                            let embed = allocation_state.embed( &character_name, &rules );
+                            add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                                .title(format!("{character_name}'s attributes have been saved!"))
+                                .description("They are ready for adventure")
+                                .colour(EmbedColours::good())
+                        },
+                        Err( why ) => {
+                            let embed = why
+                                .embed( format!("Failed to save {invoking_user_tag}'s attributes") );
+
+                            break 'response CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new().embed(embed).ephemeral(true)
+                            )
+                        }
+                    };
+
+                    // Once confirmed, we remove the components so the allocation can't be changed
+                    // from this message anymore
+                    break 'response CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed(embed)
+                            .components(vec![])
+                    )
+                }
+            }
+        // ==--
+
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .embed( add_active_character_footer(ctx, &invoking_user_id, allocation_state.embed(&character_name, &rules)).await )
+                .components( allocation_state.components("build_character", &rules) )
+        )
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to respond to allocation interaction")
+    }
+}
+
+
+/// Add a character to the database, and to the characters cache, returning it's newly allocated
+/// ID. Kept apart from `handle_modal` so it can be used without Discord
+pub async fn create_character(
+    database_connection: &SqlitePool,
+    characters_cache: &Mutex<CharacterMap>,
+    owner_id: u64,
+    name: &str,
+    species: &str,
+    backstory: &str
+    ) -> Result<CharacterId, BotError> {
+
+    // --== INSERT CHARACTER ==-- //
+
+        // Check to see the user already has a character of the given name, and if not add it.
+        // I would use a composite key in the SQL table, but we've got a foreign key in DiscordUsers
+        // and those can't reference to a part of a composite key, so the check is done by
+        // `db::characters::create` inside of the same transaction as the insert
+        let character_id = db::characters::create( database_connection, owner_id, name, species, backstory ).await?;
+    // ==--
+
+    // --== SYNC CACHE TO DATABASE ==-- //
+
+        // The only way locking could error is if the mutex is poisoned. And the only way that
+        // could happen is if a thread panicked while holding a lock to it. This shouldn't ever
+        // occur as we design the bot in such a way that it cannot panic while holding one
+        let mut character_map = characters_cache.lock()
+            .map_err( |_| BotError::CachePoisoned )?;
+
+        character_map
+            .entry( owner_id )
+            .or_default()
+            .push(( character_id, name.to_owned() ));
+    // ==--
+
+    Ok( character_id )
+}
+
+/// Save the attributes of a finished allocation. Someone could edit the custom id the allocation
+/// is carried in before sending it to us, so the spread is checked before it ends up in the
+/// database, and a character who already has attributes is never reset to a fresh spread
+///
+/// Fails with `AttributesAlreadySet` if the character's attributes were saved before
+pub async fn save_attributes( database_connection: &SqlitePool, allocation_state: &AllocationState, rules: &AllocationRules ) -> Result<(), BotError> {
+    if !rules.is_valid( &allocation_state.spread ) {
+        return Err( BotError::InvalidAllocation )
+    }
+
+    let character_attributes = Attributes::from_spread( allocation_state.character_id, &allocation_state.spread );
+    db::attributes::insert( database_connection, &character_attributes ).await?;
+
+    Ok(())
+}
+
+
+/// Routes /build_character and it's interactions to the functions above
+pub struct BuildCharacterCommand;
+#[async_trait]
+impl SlashCommand for BuildCharacterCommand {
+    fn name( &self ) -> &'static str {
+        "build_character"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx ).await
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+
+    async fn component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_component( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
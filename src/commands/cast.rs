@@ -0,0 +1,236 @@
+// Cast one of a character's spells
+//
+// - `/cast spell:<spell>` autocompletes over the spells the selected character knows. The
+//     character option autocompletes over your characters and defaults to your active one
+// - The spell's mana cost is paid up front, a character without enough mana left can't cast it
+//     at all. Then they roll a Casting check against the spell's difficulty
+// - On a success the spell's effect is posted, on a failure it fizzles and the mana is gone
+//     either way
+// - The check is recorded in the roll history as `Cast <spell>`
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage
+};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    attributes::Attribute,
+    commands::{
+        check::{attribute_value, meets_dc, roll_check, CheckRoll, RollMode},
+        registry::SlashCommand,
+        rolls::log_roll,
+        spell::spell_choices
+    },
+    db::{self, Spell},
+    derived_stats::{spend_mana, Vitals},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, get_user_character_name, integer_option,
+        search_user_characters, selected_character_id, CharacterId, EmbedColours
+    }
+};
+
+
+/// Build the cast command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    CreateCommand::new("cast")
+        .description("Cast one of your character's spells")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "spell", "One of the spells your character knows" )
+                .required(true)
+                .set_autocomplete(true)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "character", "The character casting it. Defaults to your active one" )
+                .required(false)
+                .set_autocomplete(true)
+        )
+}
+
+
+/// Everything that came of casting a spell
+#[derive(Debug, Clone)]
+pub struct SpellCast {
+    pub spell:   Spell,
+    pub check:   CheckRoll,
+    pub success: bool,
+    /// The caster's vitals once the mana is paid
+    pub vitals:  Vitals
+}
+
+/// Have a character cast one of the spells they know: pay it's mana cost, then roll a Casting
+/// check against it's difficulty
+///
+/// Fails with `UnknownSpell` if the character doesn't know the spell, and with `NotEnoughMana`
+/// if they can't pay for it, in which case nothing is rolled
+pub async fn cast_spell( database_connection: &SqlitePool, character_id: CharacterId, character_name: &str, spell_id: i64 ) -> Result<SpellCast, BotError> {
+
+    let spell = db::spells::get_known( database_connection, character_id )
+        .await?
+        .into_iter()
+        .find( |spell| spell.spell_id == spell_id )
+        .ok_or( BotError::UnknownSpell )?;
+
+    // Getting the attribute first means a character without attributes isn't charged for trying
+    let casting = attribute_value( database_connection, character_id, Attribute::Casting ).await?;
+    let ( _, vitals ) = spend_mana( database_connection, character_id, spell.mana_cost ).await?;
+
+    let check = roll_check( character_name, Attribute::Casting, casting, RollMode::Normal, &mut rand::thread_rng() );
+    let success = meets_dc( &check, spell.difficulty );
+
+    Ok( SpellCast { spell, check, success, vitals } )
+}
+
+/// Show how a cast went: the check against the spell's difficulty, the effect if it worked and
+/// how much mana is left
+pub fn cast_embed( cast: &SpellCast ) -> CreateEmbed {
+    let character_name = &cast.check.character_name;
+
+    let embed = CreateEmbed::new()
+        .title( format!("{character_name} casts {}", cast.spell.name) )
+        .field( format!("{character_name} (Casting)"), cast.check.breakdown(), true )
+        .field( "Difficulty", cast.spell.difficulty.to_string(), true )
+        .field( "Mana", format!("{}/{}", cast.vitals.mana, cast.vitals.max_mana), true );
+
+    let depleted_note = match cast.vitals.is_depleted() {
+        true  => format!("\n**{character_name}'s mana is depleted**"),
+        false => String::new()
+    };
+
+    match cast.success {
+        true => embed
+            .description( format!("**Success**\n{}{depleted_note}", cast.spell.effect) )
+            .colour( EmbedColours::good() ),
+        false => embed
+            .description( format!("**Failure**, the spell fizzles{depleted_note}") )
+            .colour( EmbedColours::error() )
+    }
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let options = interaction_data.data.options();
+    let spell_id = integer_option( &options, "spell" )?;
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        // --== FIND CHARACTER ==-- //
+
+            let character_id = match selected_character_id( ctx, &invoking_user_id, integer_option(&options, "character") ).await {
+                Some( id ) => id,
+                None => break 'return_embed BotError::NoActiveCharacter
+                    .embed( format!("{invoking_user_tag} has no active character in /cast") )
+            };
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'return_embed BotError::NotOwner
+                    .embed( format!("{invoking_user_tag} picked someone else's character in /cast") )
+            };
+        // ==--
+
+        let cast = match cast_spell( &discord_bot.database_connection, character_id, &character_name, spell_id ).await {
+            Ok( cast ) => cast,
+            Err( why ) => break 'return_embed why.embed( format!("{character_name} couldn't cast a spell") )
+        };
+
+        info!(
+            "{invoking_user_tag} had {character_name} cast {}: {} against DC {}",
+            cast.spell.name, cast.check.total(), cast.spell.difficulty
+        );
+
+        log_roll(
+            &discord_bot.database_connection, interaction_data, invoking_user_id,
+            Some(( character_id, &character_name )), &format!("Cast {}", cast.spell.name),
+            &cast.check.expression, &cast.check.roll
+        ).await;
+
+        add_active_character_footer( ctx, &invoking_user_id, cast_embed(&cast) ).await
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) => option,
+            None => break 'choices vec![]
+        };
+
+        match focused_option.name {
+
+            "character" => {
+                search_user_characters( ctx, &invoking_user_id, focused_option.value )
+                    .await
+                    .into_iter()
+                    .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                    .collect()
+            },
+
+            "spell" => {
+                // Only the spells of one of the user's own characters are suggested, otherwise
+                // we'd be leaking what other people's characters know
+                let character_id = match selected_character_id( ctx, &invoking_user_id, integer_option(&interaction_data.data.options(), "character") ).await {
+                    Some( id ) => id,
+                    None => break 'choices vec![]
+                };
+                if get_user_character_name( ctx, &invoking_user_id, character_id ).await.is_none() {
+                    break 'choices vec![]
+                }
+
+                // Just like in the other autocompletes, we won't log the error as there are far
+                // too many autocomplete interactions. An empty list will have to do
+                match db::spells::get_known( &discord_bot.database_connection, character_id ).await {
+                    Ok( known_spells ) => spell_choices( known_spells, focused_option.value ),
+                    Err(_) => vec![]
+                }
+            },
+
+            _ => vec![]
+        }
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /cast")
+    }
+}
+
+
+/// Routes /cast and it's autocomplete to the functions above
+pub struct CastCommand;
+#[async_trait]
+impl SlashCommand for CastCommand {
+    fn name( &self ) -> &'static str {
+        "cast"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
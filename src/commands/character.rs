@@ -0,0 +1,477 @@
+// Look at characters
+//
+// - `/character view` responds with the full character sheet of one of your own characters, or
+//     one of another user's when the user option is given. The character option autocompletes
+//     over the target user's characters and defaults to your active character
+// - Sheets that don't fit in a single embed are split into pages, which can be flipped through
+//     with buttons. Their custom ids look like: `character:page:<character_id>:<page>`
+// - Below the sheet are a select menu to equip items from the character's inventory and a button
+//     per slot to unequip them. Only the character's owner may use those
+// - `/character trash` lists your deleted characters, along with when they'll be removed for good
+// - `/character restore` takes one of them back out of the trash. The character option
+//     autocompletes over your trash, which isn't cached, so it's read from the database
+
+use std::sync::Mutex;
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, ComponentInteraction,
+    ComponentInteractionDataKind, CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, ResolvedOption, ResolvedValue
+};
+
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    commands::registry::SlashCommand,
+    character_sheet::CharacterSheet,
+    db,
+    equipment::Slot,
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    trash,
+    utils::{
+        add_active_character_footer, error_embed, filter_characters, get_active_character,
+        get_user_character_name, integer_option, search_user_characters, user_option, CharacterId,
+        CharacterMap, DatabaseCharactersCache, EmbedColours
+    }
+};
+
+
+/// Discord doesn't allow more fields than this in one embed
+const MAX_TRASH_FIELDS: usize = 25;
+
+
+/// Build the character command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    let view_options = vec![
+        CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "character",
+                "The character to view. Defaults to your active character"
+            )
+            .required(false)
+            .set_autocomplete(true),
+        CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "View one of this user's characters instead of your own"
+            )
+            .required(false),
+    ];
+
+    let restore_options = vec![
+        CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "character",
+                "The character to bring back. Must be in your trash"
+            )
+            .required(true)
+            .set_autocomplete(true),
+    ];
+
+    CreateCommand::new("character")
+        .description("Look at characters")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "view", "View a character's sheet" )
+                .set_sub_options( view_options )
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "trash", "List your deleted characters" )
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "restore", "Bring back a deleted character" )
+                .set_sub_options( restore_options )
+        )
+}
+
+
+fn error_response( embed: CreateEmbed ) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed.colour(EmbedColours::error()) )
+    )
+}
+
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let options = interaction_data.data.options();
+    let response = match options.first() {
+        Some( ResolvedOption { name: "view", value: ResolvedValue::SubCommand(sub_options), .. } ) => {
+            view( interaction_data, sub_options, ctx, discord_bot ).await
+        },
+        Some( ResolvedOption { name: "trash", .. } ) => {
+            list_trash( interaction_data, discord_bot ).await
+        },
+        Some( ResolvedOption { name: "restore", value: ResolvedValue::SubCommand(sub_options), .. } ) => {
+            restore( interaction_data, sub_options, ctx, discord_bot ).await?
+        },
+        _ => return None
+    };
+
+    Some( response )
+}
+
+
+async fn view(
+    interaction_data: &CommandInteraction,
+    view_options: &[ResolvedOption<'_>],
+    ctx: &BotContext,
+    discord_bot: &DiscordBot
+    ) -> CreateInteractionResponse {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    'response: {
+
+        // --== FIND TARGET CHARACTER ==-- //
+
+            let target_user_id = user_option( view_options, "user" ).unwrap_or( invoking_user_id );
+            let selected_id = integer_option( view_options, "character" );
+
+            // If no character was given, we fall back onto the target's active character
+            let character_id = match selected_id {
+                Some( id ) => id,
+                None => match get_active_character( ctx, &target_user_id ).await {
+                    Some(( id, _ )) => id,
+                    None => break 'response error_response( BotError::NoActiveCharacter
+                        .embed( format!("{} picked no character, and <@{target_user_id}> has no active one in /character view", interaction_data.user.tag()) ) )
+                }
+            };
+
+            // Autocomplete only offers the target's characters, but anything can be typed in
+            if get_user_character_name( ctx, &target_user_id, character_id ).await.is_none() {
+                break 'response error_response(
+                    CreateEmbed::new()
+                        .title("Couldn't find that character")
+                        .description(format!("<@{target_user_id}> doesn't own the selected character"))
+                )
+            }
+        // ==--
+
+        match CharacterSheet::load( &discord_bot.database_connection, character_id ).await {
+            Ok( Some(sheet) ) => CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed( sheet.embed(0) )
+                    .components( sheet.components("character", 0) )
+            ),
+            Ok( None ) => error_response(
+                CreateEmbed::new()
+                    .title("Couldn't find that character")
+                    .description("They may have just been deleted")
+            ),
+            Err( why ) => CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed(
+                    BotError::from( why ).embed( format!("Failed to load character sheet for {}", interaction_data.user.tag()) )
+                )
+            )
+        }
+    }
+}
+
+
+// List the user's trash. Only they need to see it
+async fn list_trash( interaction_data: &CommandInteraction, discord_bot: &DiscordBot ) -> CreateInteractionResponse {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let embed = match db::characters::get_archived_by_owner( &discord_bot.database_connection, invoking_user_id ).await {
+        Ok( archived_characters ) if archived_characters.is_empty() => CreateEmbed::new()
+            .title("Your trash is empty")
+            .description("Characters you delete end up here for a while, before being removed for good")
+            .colour(EmbedColours::info()),
+        Ok( archived_characters ) => {
+            let fields = archived_characters
+                .iter()
+                .take( MAX_TRASH_FIELDS )
+                .map( |character| {
+                    let archived_at = character.archived_at.unwrap_or_default();
+                    (
+                        character.name.clone(),
+                        format!("Deleted <t:{archived_at}:R>, removed for good <t:{}:R>", trash::removal_time(archived_at)),
+                        false
+                    )
+                });
+
+            CreateEmbed::new()
+                .title("Your trash")
+                .description("Bring a character back with /character restore")
+                .fields( fields )
+                .colour(EmbedColours::info())
+        },
+        Err( why ) => BotError::from( why ).embed( format!("Failed to list {}'s trash", interaction_data.user.tag()) )
+    };
+
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .embed( embed )
+            .ephemeral( true )
+    )
+}
+
+
+// Take a character back out of the user's trash. The response is sent here, as the footer needs
+// the cache after the character is back in it
+async fn restore(
+    interaction_data: &CommandInteraction,
+    restore_options: &[ResolvedOption<'_>],
+    ctx: &BotContext,
+    discord_bot: &DiscordBot
+    ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let character_id = integer_option( restore_options, "character" )?;
+
+    let characters_cache = {
+        let data_read = ctx.data.read().await;
+        data_read.get::<DatabaseCharactersCache>()
+            .expect("Key should be in map as it gets inserted in main.rs")
+            .clone()
+    };
+
+    let embed = match restore_character( &discord_bot.database_connection, &characters_cache, invoking_user_id, character_id ).await {
+        Ok( character_name ) => {
+            info!("{invoking_user_tag} restored {character_name}");
+
+            let embed = CreateEmbed::new()
+                .title(format!("{character_name} is back"))
+                .description("Set them as your active character with /switch_character")
+                .colour(EmbedColours::good());
+            add_active_character_footer( ctx, &invoking_user_id, embed ).await
+        },
+        Err( why ) => why.embed( format!("Failed to restore {invoking_user_tag}'s character") )
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+/// Take one of a user's characters out of the trash and put it back into the cache, returning it's
+/// name. Kept apart from `restore` so it can be used without Discord
+pub async fn restore_character(
+    database_connection: &SqlitePool,
+    characters_cache: &Mutex<CharacterMap>,
+    owner_id: u64,
+    character_id: CharacterId
+    ) -> Result<String, BotError> {
+
+    // --== GET SELECTED CHARACTER ==-- //
+
+        // Archived characters aren't cached, so we have to ask the database
+        let character = match db::characters::get( database_connection, character_id ).await? {
+            Some( character ) if character.owner_id as u64 == owner_id => character,
+            _ => return Err( BotError::NotOwner )
+        };
+
+        if character.archived_at.is_none() {
+            return Err( BotError::NotInTrash )
+        }
+    // ==--
+
+    db::characters::restore( database_connection, &character ).await?;
+
+    // --== UPDATE CACHE ==-- //
+
+        characters_cache.lock()
+            .map_err( |_| BotError::CachePoisoned )?
+            .entry( owner_id )
+            .or_default()
+            .push(( character.character_id, character.name.clone() ));
+    // ==--
+
+    Ok( character.name )
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) if option.name == "character" => option,
+            _ => break 'choices vec![]
+        };
+
+        let options = interaction_data.data.options();
+        let characters = match options.first() {
+
+            // Restoring suggests what's in the user's own trash
+            Some( ResolvedOption { name: "restore", .. } ) => {
+                let archived_characters = match db::characters::get_archived_by_owner( &discord_bot.database_connection, invoking_user_id ).await {
+                    Ok( archived_characters ) => archived_characters,
+                    Err( why ) => {
+                        warn!(error = %why, "Failed to get archived characters for autocomplete");
+                        break 'choices vec![]
+                    }
+                };
+
+                let archived_characters = archived_characters
+                    .into_iter()
+                    .map( |character| (character.character_id, character.name) )
+                    .collect();
+
+                filter_characters( archived_characters, focused_option.value )
+            },
+
+            // Otherwise, suggest the characters of whoever is being looked at
+            Some( ResolvedOption { value: ResolvedValue::SubCommand(sub_options), .. } ) => {
+                let target_user_id = user_option( sub_options, "user" ).unwrap_or( invoking_user_id );
+                search_user_characters( ctx, &target_user_id, focused_option.value ).await
+            },
+            _ => break 'choices vec![]
+        };
+
+        characters
+            .into_iter()
+            .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+            .collect()
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /character")
+    }
+}
+
+
+fn ephemeral_error( embed: CreateEmbed ) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed ).ephemeral(true)
+    )
+}
+
+// Flip the page of a character sheet, or change what the character has equipped. Character sheets
+// are public, so anyone is allowed to flip pages but only the owner may change their equipment
+pub async fn handle_component( interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create these ids ourselves, there's nothing we can do with a mangled one
+    let ( action, character_id, page ): (&str, CharacterId, usize) = match id_components.as_slice() {
+        [ _, action, character_id, page, .. ] => match ( character_id.parse(), page.parse() ) {
+            ( Ok(character_id), Ok(page) ) => ( *action, character_id, page ),
+            _ => return
+        },
+        _ => return
+    };
+
+    let response = 'response: {
+
+        // --== CHANGE EQUIPMENT ==-- //
+
+            if action == "equip" || action == "unequip" {
+                let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                    Some( name ) => name,
+                    None => break 'response ephemeral_error( BotError::NotOwner
+                        .embed( format!("{invoking_user_tag} tried to change someone else's equipment") ) )
+                };
+
+                let query_result = match ( action, id_components.get(4) ) {
+                    // The value of the select menu is the item's ID
+                    ( "equip", None ) => {
+                        let item_id = match &interaction_data.data.kind {
+                            ComponentInteractionDataKind::StringSelect { values } => values.first().and_then( |value| value.parse().ok() ),
+                            _ => None
+                        };
+                        let item_id = match item_id {
+                            Some( id ) => id,
+                            None => return
+                        };
+
+                        db::equipment::equip( database_connection, character_id, item_id ).await
+                    },
+                    ( "unequip", Some(slot_id) ) => {
+                        let slot = match Slot::from_id( slot_id ) {
+                            Some( slot ) => slot,
+                            None => return
+                        };
+
+                        db::equipment::unequip( database_connection, character_id, slot ).await
+                    },
+                    _ => return
+                };
+
+                match query_result {
+                    Ok( true ) => info!("{invoking_user_tag} had {character_name} {action} an item"),
+                    // The sheet was out of date, so there was nothing to equip or unequip
+                    Ok( false ) => break 'response ephemeral_error(
+                        error_embed( "That's no longer there", format!("{character_name}'s inventory changed since the sheet was sent, please look at it again") )
+                    ),
+                    Err( why ) => break 'response ephemeral_error(
+                        BotError::from( why ).embed( format!("Failed to change {character_name}'s equipment for {invoking_user_tag}") )
+                    )
+                }
+            }
+            else if action != "page" {
+                return
+            }
+        // ==--
+
+        // We reload the sheet, so the page always reflects the character's current state
+        match CharacterSheet::load( &discord_bot.database_connection, character_id ).await {
+            Ok( Some(sheet) ) => CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed( sheet.embed(page) )
+                    .components( sheet.components("character", page) )
+            ),
+            Ok( None ) => CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(
+                        CreateEmbed::new()
+                            .title("This character no longer exists")
+                            .colour(EmbedColours::error())
+                    )
+                    .components(vec![])
+            ),
+            Err( why ) => {
+                warn!(error = %why, "Failed to load character sheet");
+                return
+            }
+        }
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to update character sheet")
+    }
+}
+
+
+/// Routes /character and it's interactions to the functions above
+pub struct CharacterCommand;
+#[async_trait]
+impl SlashCommand for CharacterCommand {
+    fn name( &self ) -> &'static str {
+        "character"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+
+    async fn component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_component( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
@@ -0,0 +1,427 @@
+// Roll attribute checks
+//
+// - `/check attribute:<attribute>` rolls a d20 and adds the character's value of the attribute.
+//     The character option autocompletes over your characters and defaults to your active one
+// - With `dc`, the check succeeds when the total reaches the difficulty class
+// - `advantage` rolls two d20s and keeps the higher one, `disadvantage` keeps the lower one
+// - With `opponent`, the check is opposed: one of the opponent's characters rolls too, and the
+//     higher total wins. They roll the same attribute unless `opponent_attribute` is given, and
+//     use their active character unless `opponent_character` is given
+// - Both sides of a check are recorded in the roll history
+
+use std::cmp::Ordering;
+
+use rand::Rng;
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage
+};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    attributes::Attribute,
+    commands::{registry::SlashCommand, roll::format_dice, rolls::log_roll},
+    dice::{DiceTerm, Expression, Operator, Roll, Selection},
+    equipment::effective_attributes,
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_active_character, get_user_character_name,
+        integer_option, search_user_characters, string_option, user_option, CharacterId, EmbedColours
+    }
+};
+
+
+/// Whether a check is rolled with advantage, disadvantage or neither
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollMode {
+    Normal,
+    Advantage,
+    Disadvantage
+}
+impl RollMode {
+    pub fn from_name( name: &str ) -> RollMode {
+        match name {
+            "advantage"    => RollMode::Advantage,
+            "disadvantage" => RollMode::Disadvantage,
+            _ => RollMode::Normal
+        }
+    }
+}
+
+/// A character's roll for a check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckRoll {
+    pub character_name: String,
+    pub attribute:      Attribute,
+    pub expression:     Expression,
+    pub roll:           Roll
+}
+impl CheckRoll {
+    pub fn total( &self ) -> i64 {
+        self.roll.total
+    }
+
+    /// What the check is called in the roll history, like `Strength check`
+    pub fn label( &self ) -> String {
+        format!("{} check", self.attribute.name())
+    }
+
+    /// Shows the dice and how they add up, like `~~4~~, 15 + 3 = 18`
+    pub fn breakdown( &self ) -> String {
+        let dice = self.roll.groups
+            .first()
+            .map( format_dice )
+            .unwrap_or_default();
+
+        let modifier = self.total() - self.roll.groups.first().map( |group| group.total() ).unwrap_or_default();
+        let sign = if modifier < 0 { '-' } else { '+' };
+
+        format!("{dice} {sign} {} = **{}**", modifier.abs(), self.total())
+    }
+}
+
+
+/// Build the check command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+
+    // Both sides of an opposed check pick an attribute the same way
+    let attribute_option = |name: &str, description: &str| Attribute::ALL
+        .into_iter()
+        .fold(
+            CreateCommandOption::new( CommandOptionType::String, name, description ),
+            |option, attribute| option.add_string_choice( attribute.name(), attribute.name() )
+        );
+
+    CreateCommand::new("check")
+        .description("Roll a check against one of a character's attributes")
+        .add_option(
+            attribute_option( "attribute", "The attribute to roll against" )
+                .required(true)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "character", "The character rolling. Defaults to your active character" )
+                .required(false)
+                .set_autocomplete(true)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "dc", "The total needed to succeed" )
+                .required(false)
+                .min_int_value(1)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::String, "advantage", "Roll two d20s and keep the higher or lower one" )
+                .required(false)
+                .add_string_choice( "Advantage", "advantage" )
+                .add_string_choice( "Disadvantage", "disadvantage" )
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::User, "opponent", "Make this an opposed check against one of this user's characters" )
+                .required(false)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "opponent_character", "The opposing character. Defaults to the opponent's active character" )
+                .required(false)
+                .set_autocomplete(true)
+        )
+        .add_option(
+            attribute_option( "opponent_attribute", "The attribute the opponent rolls against. Defaults to the same one" )
+                .required(false)
+        )
+}
+
+
+/// What to add up for a check: a d20, or two keeping one of them, plus the attribute's value
+pub fn check_expression( attribute_value: i64, advantage: RollMode ) -> Expression {
+    let ( count, selection ) = match advantage {
+        RollMode::Normal       => ( 1, None ),
+        RollMode::Advantage    => ( 2, Some(Selection::KeepHighest(1)) ),
+        RollMode::Disadvantage => ( 2, Some(Selection::KeepLowest(1)) ),
+    };
+
+    let d20 = DiceTerm { count, sides: 20, exploding: false, selection };
+
+    Expression::Binary(
+        Box::new( Expression::Dice(d20) ),
+        Operator::Add,
+        Box::new( Expression::Number(attribute_value) )
+    )
+}
+
+/// Roll a check for a character whose attribute has the given value
+pub fn roll_check(
+    character_name: &str,
+    attribute: Attribute,
+    attribute_value: i64,
+    advantage: RollMode,
+    rng: &mut impl Rng
+    ) -> CheckRoll {
+
+    let expression = check_expression( attribute_value, advantage );
+    let roll = expression.roll( rng )
+        .expect("A d20 plus an attribute can't fail to roll");
+
+    CheckRoll {
+        character_name: character_name.to_owned(),
+        attribute,
+        expression,
+        roll
+    }
+}
+
+/// Whether a check reached the difficulty class
+pub fn meets_dc( check: &CheckRoll, dc: i64 ) -> bool {
+    check.total() >= dc
+}
+
+/// Compare the two sides of an opposed check. `Greater` means the first one won
+pub fn opposed_outcome( check: &CheckRoll, opposing_check: &CheckRoll ) -> Ordering {
+    check.total().cmp( &opposing_check.total() )
+}
+
+/// Get the effective value of one of a character's attributes, which includes the modifiers of
+/// everything they've got equipped
+pub async fn attribute_value( database_connection: &SqlitePool, character_id: CharacterId, attribute: Attribute ) -> Result<i64, BotError> {
+    Ok( effective_attributes( database_connection, character_id ).await?.get(attribute) )
+}
+
+
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    // --== READ OPTIONS ==-- //
+
+        let options = interaction_data.data.options();
+
+        let attribute = Attribute::from_name( string_option(&options, "attribute")? )?;
+        let opponent_attribute = match string_option( &options, "opponent_attribute" ) {
+            Some( name ) => Attribute::from_name( name )?,
+            None => attribute
+        };
+        let advantage = match string_option( &options, "advantage" ) {
+            Some( name ) => RollMode::from_name( name ),
+            None => RollMode::Normal
+        };
+        let dc = integer_option( &options, "dc" );
+        let character_option = integer_option( &options, "character" );
+        let opponent_character_option = integer_option( &options, "opponent_character" );
+        let opponent_id = user_option( &options, "opponent" );
+    // ==--
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        // --== FIND CHARACTERS ==-- //
+
+            // Same as everywhere else, we fall back onto the active character
+            let character_id = match character_option {
+                Some( id ) => id,
+                None => match get_active_character( ctx, &invoking_user_id ).await {
+                    Some(( id, _ )) => id,
+                    None => break 'return_embed BotError::NoActiveCharacter
+                        .embed( format!("{invoking_user_tag} has no active character in /check") )
+                }
+            };
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'return_embed BotError::NotOwner
+                    .embed( format!("{invoking_user_tag} picked someone else's character in /check") )
+            };
+
+            // An opposed check needs a character on the other side too
+            let opponent = match opponent_id {
+                None => None,
+                Some( opponent_id ) => {
+                    let opponent_character_id = match opponent_character_option {
+                        Some( id ) => id,
+                        None => match get_active_character( ctx, &opponent_id ).await {
+                            Some(( id, _ )) => id,
+                            None => break 'return_embed error_embed(
+                                "No opposing character selected",
+                                format!("<@{opponent_id}> isn't playing as anyone, pick one of their characters")
+                            )
+                        }
+                    };
+
+                    match get_user_character_name( ctx, &opponent_id, opponent_character_id ).await {
+                        Some( name ) => Some(( opponent_character_id, name )),
+                        None => break 'return_embed error_embed(
+                            "Couldn't find the opposing character",
+                            format!("<@{opponent_id}> doesn't own the selected character")
+                        )
+                    }
+                }
+            };
+        // ==--
+
+        // --== GET ATTRIBUTES ==-- //
+
+            let value = match attribute_value( &discord_bot.database_connection, character_id, attribute ).await {
+                Ok( value ) => value,
+                Err( why ) => break 'return_embed why.embed( format!("Failed to get {character_name}'s attributes") )
+            };
+
+            let opponent = match opponent {
+                None => None,
+                Some(( opponent_character_id, opponent_name )) => {
+                    match attribute_value( &discord_bot.database_connection, opponent_character_id, opponent_attribute ).await {
+                        Ok( value ) => Some(( opponent_character_id, opponent_name, value )),
+                        Err( why ) => break 'return_embed why.embed( format!("Failed to get {opponent_name}'s attributes") )
+                    }
+                }
+            };
+        // ==--
+
+        // The thread's generator can't be held across an await, so both sides roll in one go.
+        // Advantage only applies to whoever asked for the check
+        let ( check, opposing_check ) = {
+            let mut rng = rand::thread_rng();
+            (
+                roll_check( &character_name, attribute, value, advantage, &mut rng ),
+                opponent.map( |(opponent_character_id, opponent_name, opponent_value)| (
+                    opponent_character_id,
+                    roll_check( &opponent_name, opponent_attribute, opponent_value, RollMode::Normal, &mut rng )
+                ))
+            )
+        };
+
+        info!("{invoking_user_tag} rolled a {} check for {character_name}: {}", attribute.name(), check.total());
+
+        // --== RECORD ROLLS ==-- //
+
+            // Both sides of the check go into the history, each under their own user
+            log_roll(
+                &discord_bot.database_connection, interaction_data, invoking_user_id,
+                Some(( character_id, &character_name )), &check.label(), &check.expression, &check.roll
+            ).await;
+
+            if let ( Some(opponent_id), Some((opponent_character_id, opposing_check)) ) = ( opponent_id, &opposing_check ) {
+                log_roll(
+                    &discord_bot.database_connection, interaction_data, opponent_id,
+                    Some(( *opponent_character_id, &opposing_check.character_name )), &opposing_check.label(),
+                    &opposing_check.expression, &opposing_check.roll
+                ).await;
+            }
+        // ==--
+
+        let opposing_check = opposing_check.map( |(_, opposing_check)| opposing_check );
+
+        let embed = check_embed( &check, opposing_check.as_ref(), dc, advantage );
+        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+/// Show the outcome of a check, with a field for each side
+pub fn check_embed( check: &CheckRoll, opposing_check: Option<&CheckRoll>, dc: Option<i64>, advantage: RollMode ) -> CreateEmbed {
+
+    let advantage_note = match advantage {
+        RollMode::Normal       => "",
+        RollMode::Advantage    => " with advantage",
+        RollMode::Disadvantage => " with disadvantage",
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title( format!("{} rolled a {} check{advantage_note}", check.character_name, check.attribute.name()) )
+        .field( format!("{} ({})", check.character_name, check.attribute.name()), check.breakdown(), true )
+        .colour( EmbedColours::info() );
+
+    let mut outcomes = vec![];
+
+    if let Some( dc ) = dc {
+        match meets_dc( check, dc ) {
+            true => {
+                outcomes.push( format!("**Success** against DC {dc}") );
+                embed = embed.colour( EmbedColours::good() );
+            },
+            false => outcomes.push( format!("**Failure** against DC {dc}") ),
+        }
+    }
+
+    if let Some( opposing_check ) = opposing_check {
+        embed = embed.field(
+            format!("{} ({})", opposing_check.character_name, opposing_check.attribute.name()),
+            opposing_check.breakdown(),
+            true
+        );
+
+        outcomes.push( match opposed_outcome( check, opposing_check ) {
+            Ordering::Greater => format!("**{}** wins", check.character_name),
+            Ordering::Less    => format!("**{}** wins", opposing_check.character_name),
+            Ordering::Equal   => "It's a tie".to_owned(),
+        });
+    }
+
+    match outcomes.is_empty() {
+        true  => embed.description( format!("Total: **{}**", check.total()) ),
+        false => embed.description( outcomes.join("\n") )
+    }
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) => option,
+            None => break 'choices vec![]
+        };
+
+        // Each character option suggests the characters of the side it's picking for
+        let options = interaction_data.data.options();
+        let user_id = match focused_option.name {
+            "character" => invoking_user_id,
+            "opponent_character" => match user_option( &options, "opponent" ) {
+                Some( opponent_id ) => opponent_id,
+                None => break 'choices vec![]
+            },
+            _ => break 'choices vec![]
+        };
+
+        search_user_characters( ctx, &user_id, focused_option.value )
+            .await
+            .into_iter()
+            .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+            .collect()
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /check")
+    }
+}
+
+
+/// Routes /check and it's interactions to the functions above
+pub struct CheckCommand;
+#[async_trait]
+impl SlashCommand for CheckCommand {
+    fn name( &self ) -> &'static str {
+        "check"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+}
@@ -5,30 +5,32 @@
 // - If the code recives a valid character_id that belongs to the user a modal will be dispatched
 //     to the user
 // - The modal will contain one text field, the user will have to type in the character's name to
-//     confirm (The character's name will be given in the modal)
-// - Next up, a database query will remove the character, then if nothing fails the character will
-//     be removed from the character cache
-
-use std::sync::Mutex;
-
-// Temp, to catch rust analyser auto adding stuff
-#[allow(unused_imports, unused_variables)]
+//     confirm (The character's name will be given in the modal). If what they typed doesn't match,
+//     nothing gets removed. Whether the case has to match is set in the config
+// - Next up, a database query will move the character into the trash, then if nothing fails the
+//     character will be removed from the character cache. They can be brought back with
+//     `/character restore` until the trash gets emptied, see `trash.rs`
 
 use serenity::all::{
-    CommandInteraction, Context, CreateCommand, CreateCommandOption, CreateInteractionResponse, ModalInteraction,
-    AutocompleteChoice, AutocompleteOption, CreateAutocompleteResponse,
+    async_trait, CommandInteraction, CreateCommand, CreateCommandOption, CreateInteractionResponse, ModalInteraction,
+    AutocompleteChoice, CreateAutocompleteResponse,
     ResolvedValue,
-    CreateModal, CreateActionRow, ActionRow, ActionRowComponent,
-    CreateInputText, InputTextStyle
+    CreateModal, CreateActionRow,
+    CreateInputText, InputTextStyle,
+    CreateEmbed, CreateInteractionResponseMessage
 };
 
-use serenity::{all::{CreateEmbed, CreateInteractionResponseMessage}, builder::{
-//    CreateCommand, CreateCommandOption,
-}};
+use sqlx::SqlitePool;
+use std::{
+    collections::HashMap,
+    sync::Mutex
+};
+use tracing::{info, warn};
 
 use crate::{
-    event_handler::DiscordBot, sql_scripts::characters, utils::{
-        clone_user_characters, create_log_message, DatabaseCharactersCache, EmbedColours, LogLevel
+    commands::registry::SlashCommand, config, db, error::BotError, event_handler::DiscordBot, responses::BotContext, trash, utils::{
+        add_active_character_footer, clone_user_characters, modal_input_values, search_user_characters, unix_now,
+        ActiveCharactersCache, CharacterId, CharacterMap, DatabaseCharactersCache, EmbedColours
     }
 };
 
@@ -37,7 +39,7 @@ use crate::{
 pub fn build() -> CreateCommand {
     let options = vec![
         CreateCommandOption::new(
-                serenity::all::CommandOptionType::Number,
+                serenity::all::CommandOptionType::Integer,
                 "character",
                 "The character to be removed. Must belong to you"
             )
@@ -52,14 +54,15 @@ pub fn build() -> CreateCommand {
 
 
 // After receiving the event, run it
-pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Option<CreateInteractionResponse> {
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext ) -> Option<CreateInteractionResponse> {
 
-    let invoking_user_id = interaction_data.user.id.get();
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
 
-    let response_payload = 'with_response: {
+    let response_payload = {
         
-        let selected_id = match interaction_data.data.options()[0].value {
-            ResolvedValue::Number(num) => num as u16,
+        let selected_id = match interaction_data.data.options().first().map( |option| &option.value ) {
+            Some( ResolvedValue::Integer(id) ) => *id,
             _ => {
                 return None
             }
@@ -73,7 +76,7 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
                 let character_map_mutex = match data_read.get::<DatabaseCharactersCache>(){
                     Some(mutex) => mutex,
                     None => {
-                        // This branch only gets executed when our TypeMap in Context.data doesn't
+                        // This branch only gets executed when our TypeMap in BotContext.data doesn't
                         // contain the 'DatabaseCharactersCache' key. This shouldn't ever really
                         // occur due to the fact that it gets inserted in main.rs
                         return None;
@@ -90,14 +93,17 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
             let found_characters = user_owned_characters
                 .iter()
                 .filter( |x| x.0 == selected_id )
-                .collect::<Vec<&(u16, String)>>();
+                .collect::<Vec<&(CharacterId, String)>>();
 
             let found_character = found_characters.first();
 
             match found_character {
                 Some(character) => {
                     let modal_components = vec![
-                        CreateActionRow::InputText(CreateInputText::new(InputTextStyle::Short, "Please confirm character name", &character.1))
+                        CreateActionRow::InputText(
+                            CreateInputText::new(InputTextStyle::Short, "Please confirm character name", "confirm_name")
+                                .placeholder(&character.1)
+                        )
                     ];
                     let modal = CreateModal::new(
                             format!("delete_character:{}", &character.0 ),
@@ -108,10 +114,8 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
                     CreateInteractionResponse::Modal(modal)
                 },
                 None => {
-                    let embed = CreateEmbed::new()
-                        .title("Selected character doesn't belong to you")
-                        .description("We couldn't find the selected character from your owned ones")
-                        .colour(EmbedColours::ERROR);
+                    let embed = BotError::NotOwner
+                        .embed( format!("Failed to remove {invoking_user_tag}'s character") );
                     let payload = CreateInteractionResponseMessage::new().embed(embed);
 
                     CreateInteractionResponse::Message(payload)
@@ -120,10 +124,10 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
         // ==--
     };
 
-    let response = interaction_data.create_response( &ctx, response_payload );
+    let response = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response_payload );
 
     if let Err(why) = response.await {
-        println!("Failed to send modal response in /delete_character:\n\t{why}")
+        warn!(error = %why, "Failed to send modal response in /delete_character")
     }
 
     None
@@ -131,67 +135,23 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
 
 
 //
-pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &Context ) {
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
 
     let invoking_user_id = interaction_data.user.id.get();
 
-    let character_choices: Vec<(u16, String)> = 'character_data: {
+    let character_choices: Vec<(CharacterId, String)> = 'character_data: {
 
         //
-        let query = match interaction_data.data.options()[0].value {
-            ResolvedValue::Autocomplete { value, .. } => value.to_lowercase(),
-            _ => {
-                println!("{:#?}", interaction_data.data.options()[0].value);
+        let options = interaction_data.data.options();
+        let query = match options.first().map( |option| &option.value ) {
+            Some( ResolvedValue::Autocomplete { value, .. } ) => *value,
+            value => {
+                warn!(?value, "Unexpected autocomplete value");
                 break 'character_data vec![]
             }
         };
 
-        //
-        let users_characters = {
-            
-            let data_read = ctx.data.read().await;
-            let users_character_map_mutex = match data_read.get::<DatabaseCharactersCache>() {
-                Some(mutex) => mutex,
-                None => {
-                    // This shouldn't ever occur as the TypeMapKey should be inserted in main.rs
-                    break 'character_data vec![];
-                }
-            };
-
-            let user_character_map = match users_character_map_mutex.lock() {
-                Ok(map) => map,
-                Err(_) => {
-                    // This can only happen when the mutex is locked, which shouldn't ever happen
-                    break 'character_data vec![];
-                }
-            };
-
-            let user_character_data = match user_character_map.get(&invoking_user_id) {
-                Some(data) => data,
-                None => {
-                    println!("Userid is missing");
-                    &vec![]
-                }
-            };
-
-            user_character_data.clone()
-        };
-
-        let mut begins_with_choices = vec![];
-        let mut contains_choices    = vec![];
-
-        for character_data in users_characters.iter() {
-
-            let character_name = character_data.1.to_lowercase();
-
-            match (character_name.starts_with(&query), character_name.contains(&query)) {
-                (true, _) => begins_with_choices.push(character_data.clone()),
-                (false, true) => contains_choices.push(character_data.clone()),
-                _ => { /* Do Nothing */ }
-            }
-        }
-        
-        [ begins_with_choices, contains_choices ].concat()
+        search_user_characters( ctx, &invoking_user_id, query ).await
     };
 
     let autocomplete_choices = {
@@ -209,126 +169,173 @@ pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &C
 
     let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
     let response_payload = CreateInteractionResponse::Autocomplete(response);
-    let send_response = interaction_data.create_response( &ctx.http, response_payload );
+    let send_response = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response_payload );
 
     if let Err(why) = send_response.await {
-        println!("{}", create_log_message(
-                format!("Failed to send autocomple response:\n\t{why}"), 
-                LogLevel::Warning
-        ))
+        warn!(error = %why, "Failed to send autocomple response")
     }
 
 } 
 
 //
-pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &Context, discord_bot: &DiscordBot ) {
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
 
     let invoking_user_id = interaction_data.user.id.get();
     let invoking_user_tag = interaction_data.user.tag();
     
-    let target_character_id: u16 = interaction_data.data.custom_id
+    let id_components = interaction_data.data.custom_id
         .split(':')
-        .collect::<Vec<&str>>()[1]
-        .parse()
-        .expect("We only recive a number from gateway, it should have no issue parsing");
-
-    let target_character_name = {
-            
-            let user_owned_characters = {
-                let data_read = ctx.data.read().await;
-                let character_map_mutex = match data_read.get::<DatabaseCharactersCache>(){
-                    Some(mutex) => mutex,
-                    None => {
-                        // This branch only gets executed when our TypeMap in Context.data doesn't
-                        // contain the 'DatabaseCharactersCache' key. This shouldn't ever really
-                        // occur due to the fact that it gets inserted in main.rs
-                        return
-                    }
-                };
+        .collect::<Vec<&str>>();
 
-                clone_user_characters(character_map_mutex.clone(), &invoking_user_id)
-                    .unwrap_or(vec![])
-            };
-        // ==-- 
-        
-        // --== GET SELECTED CHARACTER ==-- //
-        
-            //
-            let found_characters = user_owned_characters
-                .iter()
-                .filter( |x| x.0 == target_character_id )
-                .collect::<Vec<&(u16, String)>>();
+    // We create the modal's id ourselves, there's nothing we can do with a mangled one
+    let target_character_id: CharacterId = match id_components.as_slice() {
+        [ _, character_id ] => match character_id.parse() {
+            Ok( character_id ) => character_id,
+            Err(_) => return
+        },
+        _ => return
+    };
 
-            let character_name = match found_characters.first() {
-                None => {
-                    // This shouldn't happen, so in this case i'll just return from the function
-                    return
-                },
-                Some(data) => data.1.clone()
-            };
-            character_name
-        // ==--
+    // Both caches have the character removed from them alongside the database. They're inserted
+    // in main.rs, so they should always be there
+    let ( characters_cache, active_characters_cache ) = {
+        let data_read = ctx.data.read().await;
+        match ( data_read.get::<DatabaseCharactersCache>(), data_read.get::<ActiveCharactersCache>() ) {
+            ( Some(characters_cache), Some(active_characters_cache) ) => ( characters_cache.clone(), active_characters_cache.clone() ),
+            _ => return
+        }
     };
 
-    let query_result = sqlx::query( characters::REMOVE_CHARACTER )
-        .bind( target_character_id )
-        .execute( &discord_bot.database_connection )
-        .await;
-
-    let return_response = match query_result {
-        Ok(_) => {
-
-            // --== UPDATE CACHE ==-- //
-            
-                //
-                {
-                    let data_read = ctx.data.read().await;
-                    let character_map_arc = data_read
-                        .get::<DatabaseCharactersCache>()
-                        .expect("Key should be in map as it gets inserted in main.rs");
-
-                    let mut character_map_mut = match character_map_arc.lock() {
-                        Ok(data) => data,
-                        Err(why) => return
-                    };
-
-                    let user_characters = match character_map_mut.get_mut(&invoking_user_id) {
-                        Some(vec_of_chars) => vec_of_chars,
-                        None => return
-                    };
-
-                    user_characters.retain( |character| character.0 != target_character_id );
-                    
-                }
-            // ==--
-            
-            let embed = CreateEmbed::new()
-                .title("Successfully removed {target_character_name}")
-                .description("They are now gone")
-                .colour(EmbedColours::GOOD);
+    // The modal only has the one field, the name the user typed to confirm
+    let confirmation = modal_input_values( interaction_data )
+        .and_then( |values| values.into_iter().next() )
+        .unwrap_or_default();
 
-            let response_message = CreateInteractionResponseMessage::new().embed(embed);
-            CreateInteractionResponse::Message(response_message)
+    let query_result = delete_character(
+        &discord_bot.database_connection,
+        &characters_cache,
+        &active_characters_cache,
+        invoking_user_id,
+        target_character_id,
+        &confirmation
+    ).await;
 
+    let embed_for_message = match query_result {
+        Ok(( target_character_name, archived_at )) => {
+            info!("{invoking_user_tag} moved {target_character_name} to the trash");
+
+            let embed = CreateEmbed::new()
+                .title(format!("Moved {target_character_name} to the trash"))
+                .description(format!(
+                    "They'll be removed for good <t:{}:R>. Until then, you can bring them back with /character restore",
+                    trash::removal_time( archived_at )
+                ))
+                .colour(EmbedColours::good());
+            add_active_character_footer( ctx, &invoking_user_id, embed ).await
         },
-        Err(why) => {
+        Err( why ) => why.embed( format!("Failed to remove {invoking_user_tag}'s character") )
+    };
 
-            println!("{}", create_log_message(
-                    format!("Failed to remove {}'s character:\n\t{}", invoking_user_tag, why),
-                    LogLevel::Warning
-            ));
+    let return_response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed(embed_for_message)
+    );
 
-            let embed = CreateEmbed::new()
-                .title("An unexpected Error occured")
-                .description("If this persists, feel free to open an issue on github")
-                .colour(EmbedColours::ERROR);
+    let send_response_payload = ctx.responses.create_response( interaction_data.id, &interaction_data.token, return_response );
+    if let Err( why ) = send_response_payload.await {
+        warn!(error = %why, "Failed to send response in /delete_character")
+    }
+}
 
-            let response_message = CreateInteractionResponseMessage::new().embed(embed);
-            CreateInteractionResponse::Message(response_message)
+
+/// Move one of a user's characters into the trash and remove it from both caches, returning it's
+/// name and when it was archived. The confirmation is what the user typed into the modal, which
+/// has to be the character's name. Kept apart from `handle_modal` so it can be used without Discord
+pub async fn delete_character(
+    database_connection: &SqlitePool,
+    characters_cache: &Mutex<CharacterMap>,
+    active_characters_cache: &Mutex<HashMap<u64, CharacterId>>,
+    owner_id: u64,
+    character_id: CharacterId,
+    confirmation: &str
+    ) -> Result<(String, i64), BotError> {
+
+    // --== GET SELECTED CHARACTER ==-- //
+
+        // The cache holds every character the user owns, so if it's not in there, it isn't theirs
+        let character_name = {
+            let character_map = characters_cache.lock()
+                .map_err( |_| BotError::CachePoisoned )?;
+
+            character_map
+                .get( &owner_id )
+                .and_then( |characters| characters.iter().find( |character| character.0 == character_id ) )
+                .map( |character| character.1.clone() )
+                .ok_or( BotError::NotOwner )?
+        };
+
+        if !confirmation_matches( &character_name, confirmation, config::get().confirm_name_case_sensitive ) {
+            return Err( BotError::NameMismatch )
         }
-    };
+    // ==--
+
+    // If anyone is playing as the character, that gets unset alongside archiving it
+    let archived_at = unix_now();
+    db::characters::archive( database_connection, character_id, archived_at ).await?;
 
-    let send_response_payload = interaction_data.create_response(&ctx.http, return_response);
-    let _ = send_response_payload.await;
+    // --== UPDATE CACHE ==-- //
+
+        // The character can't be active anymore
+        if let Ok( mut active_map ) = active_characters_cache.lock() {
+            active_map.retain( |_, active_character_id| *active_character_id != character_id );
+        };
+
+        let mut character_map = characters_cache.lock()
+            .map_err( |_| BotError::CachePoisoned )?;
+
+        if let Some( user_characters ) = character_map.get_mut( &owner_id ) {
+            user_characters.retain( |character| character.0 != character_id );
+        }
+    // ==--
+
+    Ok(( character_name, archived_at ))
+}
+
+
+/// Whether the name typed to confirm a deletion is the character's name. Surrounding whitespace is
+/// ignored, as it's easy to type by accident and can't be seen
+pub fn confirmation_matches( character_name: &str, confirmation: &str, case_sensitive: bool ) -> bool {
+    let confirmation = confirmation.trim();
+
+    match case_sensitive {
+        true  => confirmation == character_name,
+        false => confirmation.to_lowercase() == character_name.to_lowercase()
+    }
 }
 
+
+/// Routes /delete_character and it's interactions to the functions above
+pub struct DeleteCharacterCommand;
+#[async_trait]
+impl SlashCommand for DeleteCharacterCommand {
+    fn name( &self ) -> &'static str {
+        "delete_character"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
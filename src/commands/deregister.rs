@@ -1,169 +1,114 @@
+// Remove a user's profile
+//
+// - Without options, the profile is only removed once the user has no characters left
+// - With `purge:true`, the user is first asked to confirm with a modal. Once confirmed, their
+//     profile and every character they own are removed in one transaction, so either everything
+//     is gone or nothing is
+
+use std::{
+    collections::HashMap,
+    sync::Mutex
+};
+
 use serenity::{
+    all::{
+        CommandOptionType, CreateActionRow, CreateCommandOption, CreateInputText, CreateModal,
+        InputTextStyle, ModalInteraction, ResolvedValue
+    },
+    async_trait,
     builder::{
         CreateCommand, CreateEmbed,
-        CreateEmbedFooter,
         CreateInteractionResponse,
         CreateInteractionResponseMessage,
         EditInteractionResponse
     },
-    client::Context,
-    model::application::CommandInteraction,
-    futures::StreamExt
+    model::application::CommandInteraction
 };
-use sqlx::query;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
 use crate::{
-    event_handler,
-    sql_scripts::{
-        discord_users,
-        characters
-    },
+    commands::registry::SlashCommand,
+    db,
+    error::BotError,
+    event_handler::{self, DiscordBot},
+    responses::BotContext,
     utils::{
-        create_log_message, EmbedColours, LogLevel
+        add_active_character_footer, modal_input_values,
+        ActiveCharactersCache, CharacterId, CharacterMap, DatabaseCharactersCache, EmbedColours
     }
 };
 
-// Conveniance function so that we don't have to write the format every single time
-fn footer_test_index( test_index: u8, test_count: u8 ) -> CreateEmbedFooter {
-    CreateEmbedFooter::new(
-        format!( "Test {test_index}/{test_count}" )
-    )
-}
-
-
 
+/// What has to be typed into the purge modal to confirm it
+pub const PURGE_CONFIRMATION: &str = "delete everything";
 
 pub fn build() -> CreateCommand {
     CreateCommand::new("deregister")
         .description("Remove yourself from the database")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Boolean, "purge", "Also remove all of your characters. You'll be asked to confirm" )
+                .required(false)
+        )
 }
 
 
-
-
-pub async fn run( interaction_data: &CommandInteraction, ctx: &Context, discord_bot: &event_handler::DiscordBot ) -> Option<CreateInteractionResponse> {
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &event_handler::DiscordBot ) -> Option<CreateInteractionResponse> {
 
     // We'll be using the user's ID and Tag quite often, so lets just save it here for future use
     let invoking_user_id  = interaction_data.user.id.get();
     let invoking_user_tag = interaction_data.user.tag();
 
+    // --== PURGE CONFIRMATION ==-- //
+
+        // Purging can't be undone, so instead of removing anything right away we ask the user to
+        // confirm. The rest happens once they submit the modal
+        let purge = interaction_data.data.options()
+            .iter()
+            .any( |option| option.name == "purge" && matches!(option.value, ResolvedValue::Boolean(true)) );
+
+        if purge {
+            let confirmation_input = CreateInputText::new( InputTextStyle::Short, format!("Type '{PURGE_CONFIRMATION}' to confirm"), "confirm_purge" )
+                .placeholder( PURGE_CONFIRMATION );
+
+            let modal = CreateModal::new( "deregister:purge", "Remove your profile and characters" )
+                .components( vec![CreateActionRow::InputText( confirmation_input )] );
+
+            return Some( CreateInteractionResponse::Modal(modal) )
+        }
+    // ==--
+
     // Because we'll be doing plenty of SQLite queries, even if theoretically and practically those
     // won't take long, I personally think it's a good idea to first aknowlage the user's command 
-    let _ = interaction_data
-        .create_response(&ctx.http, CreateInteractionResponse::Defer( CreateInteractionResponseMessage::new() ))
+    let _ = ctx.responses
+        .create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Defer( CreateInteractionResponseMessage::new() ) )
         .await;
 
 
-    // There are many different messages that could get sent to the user, henceforth we shall use
-    // a code block in order to simplify the process of sending a specific option
-
-    // We will conduct a series of tests to see if we can safely remove the user's profile before
-    // doing so    
-    let embed_for_message = 'return_embed: {
-
-        // For UX, we will add a footer whenever a test fails. It will state which test of how many
-        // failed. It's so that if a user hits multiple tests, they know how many are left.
-        const TOTAL_TEST_COUNT: u8 = 1;
-
-        // --== PROFILE TEST ==-- //
-            
-            // Firstly, we need to check if the user even exists in the database or not, as
-            // we cannot remove their profile if it doesn't even exist. To do that we will
-            // preform a `SELECT` query, if it returns none, we will break early with a
-            // corresponding error embed
-            let mut rows = sqlx::query( discord_users::SELECT_BY_ID )
-                .bind( invoking_user_id as i64 )    // The sqlx::Encode trait is not implemented
-                                                    // for u64, but is for i64 hence the cast
-                .fetch( &discord_bot.database_connection );
-            
-            if rows.next().await.is_none() {
-                break 'return_embed CreateEmbed::new()
-                    .title("Can't find you")
-                    .description("Your profile is not in the database, and so it can't be removed")
-                    .footer( footer_test_index(1, TOTAL_TEST_COUNT) )
-                    .colour(EmbedColours::ERROR)
-            }
-        // ==--
-        
-        // --== CHARACTERS TEST ==-- //
-
-            // Another thing we need to make sure of, is that the user doesn't have any characters
-            // that have not yet been removed
-            let query_result = sqlx::query( characters::SELECT_BY_OWNER_ID )
-                .bind( invoking_user_id as i64 )
-                .fetch_all( &discord_bot.database_connection )
-                .await;
-
-            match query_result {
-                Ok(data) => {
-                    if !data.is_empty() {
-                        break 'return_embed CreateEmbed::new()
-                            .title("Can't remove you")
-                            .description("You have character(s) in the database. We cannot remove your profile while they're there")
-                            .colour(EmbedColours::ERROR)
-                    }
-                },
-                Err( why ) => {
-                    // For some reason, our query has failed. Lets log the error and prepare an info
-                    // error to our user
-                    println!("{}", create_log_message(
-                            format!("Failed to remove {invoking_user_tag}'s profile: \n\t{why}"),
-                            LogLevel::Error
-                    ));
-
-                    break 'return_embed CreateEmbed::new()
-                        .title("A unexpected error occured")
-                        .description("If it persists, feel free to open an issue on the bot's github page")
-                }
-            }
-        // ==--
-
-        // If we haven't broken out of this block upto this point, it means that all tests have
-        // passed. We can now move forward with removing the invoking user's database entry
-        let query_result = sqlx::query( discord_users::REMOVE_ENTRY )
-            .bind( invoking_user_id as i64 )
-            .execute( &discord_bot.database_connection )
-            .await;
-
-        
-        match query_result {
-            Ok(_) => {  // We'll ignore the count of rows detected
-                
-                // Succeeding, we notify both stdout, and the user
-                println!("{}", create_log_message(
-                        format!("Removed {invoking_user_tag}'s profile"),
-                        LogLevel::Info
-                ));
-
-                CreateEmbed::new()
-                    .title( "Your profile has been successfully removed from the database" )
-                    .description( "Aaaaand cut!" )
-                    .colour( EmbedColours::GOOD )
-            },
-            Err( why ) => {
-                // For some reason, our query has failed. Lets log the error and prepare an info
-                // error to our user
-                println!("{}", create_log_message(
-                        format!("Failed to remove {invoking_user_tag}'s profile: \n\t{why}"),
-                        LogLevel::Error
-                ));
-
-                CreateEmbed::new()
-                    .title("A unexpected error occured")
-                    .description("If it persists, feel free to open an issue on the bot's github page")
-            }
-        }
+    // Whatever happens, a single embed gets sent to the user. `remove_profile` tells us which
+    let embed_for_message = match remove_profile( &discord_bot.database_connection, invoking_user_id ).await {
+        Ok(()) => {
+
+            // Succeeding, we notify both stdout, and the user
+            info!("Removed {invoking_user_tag}'s profile");
 
+            CreateEmbed::new()
+                .title( "Your profile has been successfully removed from the database" )
+                .description( "Aaaaand cut!" )
+                .colour( EmbedColours::good() )
+        },
+        Err( why ) => why.embed( format!("Failed to remove {invoking_user_tag}'s profile") )
     };
 
     // We prepare a `EditInteractionResponse` with our embed to send and then prepare a payload
     // that we await in a further-down `if let` block to send our new embed to the end user
     let new_message = EditInteractionResponse::new().embed(embed_for_message);
-    let edit_response_payload = interaction_data.edit_response( &ctx.http, new_message );
+    let edit_response_payload = ctx.responses.edit_response( &interaction_data.token, new_message );
 
 
     // We change the earlier aknowlagement to the message we want to send
     if let Err( why ) = edit_response_payload.await {
-        println!("{}", why );
+        warn!(error = %why, "Failed to send response in /deregister")
     }
 
     // We manage sending the resulting message here in this function, no need to send a message
@@ -171,3 +116,139 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context, discord_
     None
 }
 
+
+// Once the user confirms a purge, their profile and characters get removed
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    // The modal only has the one field, what the user typed to confirm
+    let confirmation = modal_input_values( interaction_data )
+        .and_then( |values| values.into_iter().next() )
+        .unwrap_or_default();
+
+    let embed_for_message: Result<CreateEmbed, BotError> = 'return_embed: {
+
+        if !confirmation.trim().eq_ignore_ascii_case( PURGE_CONFIRMATION ) {
+            break 'return_embed Err( BotError::PurgeNotConfirmed )
+        }
+
+        let ( characters_cache, active_characters_cache ) = {
+            let data_read = ctx.data.read().await;
+            (
+                data_read.get::<DatabaseCharactersCache>()
+                    .expect("Key should be in map as it gets inserted in main.rs")
+                    .clone(),
+                data_read.get::<ActiveCharactersCache>()
+                    .expect("Key should be in map as it gets inserted in main.rs")
+                    .clone()
+            )
+        };
+
+        match purge_profile( &discord_bot.database_connection, &characters_cache, &active_characters_cache, invoking_user_id ).await {
+            Ok( removed_count ) => {
+                info!("Purged {invoking_user_tag}'s profile and {removed_count} character(s)");
+
+                Ok( CreateEmbed::new()
+                    .title( format!("Your profile and {removed_count} character(s) have been removed from the database") )
+                    .description( "Aaaaand cut!" )
+                    .colour( EmbedColours::good() ) )
+            },
+            Err( why ) => Err( why )
+        }
+    };
+
+    let embed_for_message = match embed_for_message {
+        Ok( embed ) => add_active_character_footer( ctx, &invoking_user_id, embed ).await,
+        Err( why ) => why.embed( format!("Failed to purge {invoking_user_tag}'s profile") )
+    };
+
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed_for_message )
+    );
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to send response in /deregister")
+    }
+}
+
+
+/// Remove a user's profile from the database, after a series of tests to see if it can safely be
+/// done. Kept apart from `run` so it can be used without Discord
+pub async fn remove_profile( database_connection: &SqlitePool, user_id: u64 ) -> Result<(), BotError> {
+
+    // --== PROFILE TEST ==-- //
+
+        // We cannot remove a user's profile if it doesn't even exist
+        if db::discord_users::get( database_connection, user_id ).await?.is_none() {
+            return Err( BotError::NotRegistered )
+        }
+    // ==--
+
+    // --== CHARACTERS TEST ==-- //
+
+        // Another thing we need to make sure of, is that the user doesn't have any characters
+        // that have not yet been removed
+        if !db::characters::get_by_owner( database_connection, user_id ).await?.is_empty() {
+            return Err( BotError::HasCharacters )
+        }
+    // ==--
+
+    // All tests have passed, so we can move forward with removing the user's database entry
+    db::discord_users::remove( database_connection, user_id ).await?;
+
+    Ok(())
+}
+
+
+/// Remove a user's profile along with every character they own, from both the database and the
+/// caches. Returns how many characters were removed. Kept apart from `handle_modal` so it can be
+/// used without Discord
+pub async fn purge_profile(
+    database_connection: &SqlitePool,
+    characters_cache: &Mutex<CharacterMap>,
+    active_characters_cache: &Mutex<HashMap<u64, CharacterId>>,
+    user_id: u64
+    ) -> Result<usize, BotError> {
+
+    let removed_characters = db::discord_users::purge( database_connection, user_id ).await?;
+
+    // --== UPDATE CACHE ==-- //
+
+        // None of the characters can be active anymore, and neither can the user
+        if let Ok( mut active_map ) = active_characters_cache.lock() {
+            active_map.remove( &user_id );
+            active_map.retain( |_, character_id| !removed_characters.contains(character_id) );
+        };
+
+        characters_cache.lock()
+            .map_err( |_| BotError::CachePoisoned )?
+            .remove( &user_id );
+    // ==--
+
+    Ok( removed_characters.len() )
+}
+
+
+/// Routes /deregister and it's interactions to the functions above
+pub struct DeregisterCommand;
+#[async_trait]
+impl SlashCommand for DeregisterCommand {
+    fn name( &self ) -> &'static str {
+        "deregister"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
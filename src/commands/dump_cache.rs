@@ -1,18 +1,17 @@
 use serenity::{
+    async_trait,
     builder::{
-        CreateCommand, CreateEmbed,
+        CreateCommand,
         CreateInteractionResponse,
         CreateInteractionResponseMessage
     },
-    client::Context,
     model::application::CommandInteraction
 };
 use crate::{
-    event_handler,
-    sql_scripts::{self, characters},
-    utils::{
-        self, create_log_message, EmbedColours, LogLevel
-    }
+    commands::registry::SlashCommand,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils
 };
 
 pub fn build() -> CreateCommand {
@@ -20,7 +19,7 @@ pub fn build() -> CreateCommand {
         .description("Debug command to dump cache data")
 }
 
-pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Option<CreateInteractionResponse> {
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext ) -> Option<CreateInteractionResponse> {
     
     let response = 'response_data: {
         let mut response_builder = String::new();
@@ -57,7 +56,25 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
        CreateInteractionResponseMessage::new().content(response)
     );
 
-    let _ = interaction_data.create_response( &ctx.http, response_payload ).await;
+    let _ = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response_payload ).await;
 
     None
 }
+
+
+/// Routes /dump_cache and it's interactions to the functions above
+pub struct DumpCacheCommand;
+#[async_trait]
+impl SlashCommand for DumpCacheCommand {
+    fn name( &self ) -> &'static str {
+        "dump_cache"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx ).await
+    }
+}
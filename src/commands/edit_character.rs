@@ -0,0 +1,296 @@
+// Change an existing character
+//
+// - Dispatched with a slash command, which has an autocomplete field to select one of the user's
+//     characters. If it is left out, the user's active character is used
+// - The same modal as /build_character gets dispatched, prefilled with the character's current
+//     name, species and backstory
+// - After the modal is submitted the name is checked for uniqueness just like when building, the
+//     character is updated, and on a rename the cache is updated too. Every changed field is
+//     logged and listed in the response
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, ModalInteraction, ResolvedValue
+};
+
+use tracing::{info, warn};
+
+use crate::{
+    commands::{build_character::character_modal, registry::SlashCommand},
+    db::{self, Character},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_active_character, get_user_character_name,
+        modal_input_values, search_user_characters, CharacterId, DatabaseCharactersCache, EmbedColours
+    }
+};
+
+
+/// Build the edit_character's command signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    let options = vec![
+        CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "character",
+                "The character to edit. Defaults to your active character"
+            )
+            .required(false)
+            .set_autocomplete(true)
+    ];
+
+    CreateCommand::new("edit_character")
+        .description("Change one of your characters")
+        .set_options(options)
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let selected_id = interaction_data.data.options()
+        .first()
+        .and_then( |option| match option.value {
+            ResolvedValue::Integer( id ) => Some( id ),
+            _ => None
+        });
+
+    // Failures are kept as a `BotError`, so that they can be reported in a single place below
+    let embed_for_message: Result<CreateEmbed, BotError> = 'return_embed: {
+
+        // --== FIND TARGET CHARACTER ==-- //
+
+            let character_id = match selected_id {
+                Some( id ) => id,
+                None => match get_active_character( ctx, &invoking_user_id ).await {
+                    Some(( id, _ )) => id,
+                    None => break 'return_embed Err( BotError::NoActiveCharacter )
+                }
+            };
+
+            if get_user_character_name( ctx, &invoking_user_id, character_id ).await.is_none() {
+                break 'return_embed Err( BotError::NotOwner )
+            }
+        // ==--
+
+        // --== DISPATCH PREFILLED MODAL ==-- //
+
+            let query_result = db::characters::get( &discord_bot.database_connection, character_id ).await;
+
+            match query_result {
+                Ok( Some(character) ) => {
+                    let modal = character_modal(
+                        format!("edit_character:{character_id}"),
+                        format!("Editing {}", character.name),
+                        Some(( character.name, character.species, character.backstory ))
+                    );
+
+                    return Some( CreateInteractionResponse::Modal(modal) )
+                },
+                Ok( None ) => Ok( error_embed( "Couldn't find that character", "They may have just been deleted" ) ),
+                Err( why ) => Err( BotError::from(why) )
+            }
+        // ==--
+    };
+
+    let embed_for_message = match embed_for_message {
+        Ok( embed ) => add_active_character_footer( ctx, &invoking_user_id, embed ).await,
+        Err( why ) => why.embed( format!("Failed to open {invoking_user_tag}'s character for editing") )
+    };
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed_for_message )
+    ))
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let query = match interaction_data.data.autocomplete() {
+        Some( option ) => option.value,
+        None => ""
+    };
+
+    let autocomplete_choices = search_user_characters( ctx, &invoking_user_id, query )
+        .await
+        .into_iter()
+        .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+        .collect();
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /edit_character")
+    }
+}
+
+
+// After the user submits the prefilled modal. Its custom id looks like: `edit_character:<character_id>`
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    // We create both the modal and it's id ourselves, so if anything here is missing there's
+    // nothing we can do but return
+    let character_id: CharacterId = match interaction_data.data.custom_id.split(':').nth(1).and_then( |id| id.parse().ok() ) {
+        Some( id ) => id,
+        None => return
+    };
+    //    Name      Species   Backstory
+    let ( new_name, new_species, new_backstory ) = match modal_input_values( interaction_data ).as_deref() {
+        Some( [name, species, backstory] ) => ( name.clone(), species.clone(), backstory.clone() ),
+        _ => return
+    };
+
+    // Failures are kept as a `BotError`, so that they can be reported in a single place below
+    let embed_for_message: Result<CreateEmbed, BotError> = 'return_embed: {
+
+        // --== OWNERSHIP TEST ==-- //
+
+            // The character could have been deleted between opening and submitting the modal
+            if get_user_character_name( ctx, &invoking_user_id, character_id ).await.is_none() {
+                break 'return_embed Err( BotError::NotOwner )
+            }
+        // ==--
+
+        // --== FETCH CURRENT VALUES ==-- //
+
+            // We need the current values to find out what changed
+            let query_result = db::characters::get( &discord_bot.database_connection, character_id ).await;
+
+            let current_character = match query_result {
+                Ok( Some(character) ) => character,
+                Ok( None ) => break 'return_embed Ok( error_embed( "Couldn't find that character", "They may have just been deleted" ) ),
+                Err( why ) => break 'return_embed Err( BotError::from(why) )
+            };
+            let Character { name: old_name, species: old_species, backstory: old_backstory, .. } = &current_character;
+        // ==--
+
+        // --== COLLECT CHANGES ==-- //
+
+            let changes = [
+                ( "Name",      old_name,      &new_name ),
+                ( "Species",   old_species,   &new_species ),
+                ( "Backstory", old_backstory, &new_backstory ),
+            ]
+                .into_iter()
+                .filter( |(_, old_value, new_value)| old_value != new_value )
+                .collect::<Vec<(&str, &String, &String)>>();
+
+            if changes.is_empty() {
+                break 'return_embed Ok( CreateEmbed::new()
+                    .title(format!("Nothing about {old_name} changed"))
+                    .colour(EmbedColours::info()) )
+            }
+        // ==--
+
+        // Just like in /build_character, a user can't have two characters of the same name.
+        // The character itself keeping it's name is of course fine
+        let updated_character = Character {
+            name:      new_name.clone(),
+            species:   new_species.clone(),
+            backstory: new_backstory.clone(),
+            ..current_character.clone()
+        };
+
+        if let Err( why ) = db::characters::update( &discord_bot.database_connection, &updated_character ).await {
+            break 'return_embed Err( BotError::from(why) )
+        }
+
+        // --== SYNC CACHE TO DATABASE ==-- //
+
+            // Only the name is held in the cache, so that's the only thing that needs syncing
+            if &new_name != old_name {
+                let data_read = ctx.data.read().await;
+                let character_cache_mutex = data_read
+                    .get::<DatabaseCharactersCache>()
+                    .expect("Key 'DatabaseCharactersCache' must be in map, as it get's inserted in main.rs");
+
+                match character_cache_mutex.lock() {
+                    Ok( mut map_guard ) => {
+                        let cached_character = map_guard
+                            .get_mut( &invoking_user_id )
+                            .and_then( |characters| characters.iter_mut().find( |character| character.0 == character_id ) );
+
+                        if let Some( character ) = cached_character {
+                            character.1 = new_name.clone();
+                        }
+                    },
+                    Err(_) => break 'return_embed Err( BotError::CachePoisoned )
+                };
+            }
+        // ==--
+
+        // --== RECORD CHANGES ==-- //
+
+            let changed_fields = changes
+                .iter()
+                .map( |(field, _, _)| *field )
+                .collect::<Vec<&str>>()
+                .join(", ");
+
+            info!("{invoking_user_tag} edited {old_name} (id {character_id}): {changed_fields}");
+
+            // Backstories can get long, so only short values are shown in full
+            let fields = changes
+                .iter()
+                .map( |(field, old_value, new_value)| {
+                    let value = match old_value.chars().count() + new_value.chars().count() > 900 {
+                        true  => "Changed".to_owned(),
+                        false => format!("{old_value}\n→ {new_value}")
+                    };
+                    ( *field, value, false )
+                });
+
+            Ok( CreateEmbed::new()
+                .title(format!("{new_name} successfully edited!"))
+                .fields(fields)
+                .colour(EmbedColours::good()) )
+        // ==--
+    };
+
+    let embed_for_message = match embed_for_message {
+        Ok( embed ) => add_active_character_footer( ctx, &invoking_user_id, embed ).await,
+        Err( why ) => why.embed( format!("Failed to edit {invoking_user_tag}'s character") )
+    };
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed_for_message )
+    );
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to send response in /edit_character")
+    }
+}
+
+
+/// Routes /edit_character and it's interactions to the functions above
+pub struct EditCharacterCommand;
+#[async_trait]
+impl SlashCommand for EditCharacterCommand {
+    fn name( &self ) -> &'static str {
+        "edit_character"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
@@ -0,0 +1,592 @@
+// Track turns in a fight
+//
+// - `/encounter start` begins a fight in the channel, run by whoever started it. There can only
+//     be one per channel at a time, and only in servers
+// - `/encounter join` adds a character to the fight, rolling their initiative as a Dexterity check.
+//     The character option autocompletes over your characters and defaults to your active one
+// - `/encounter npc` lets the game master add someone without a character, either rolling their
+//     initiative with a bonus or setting it outright
+// - Every response shows the turn order with Previous, Next and End buttons. The game master can
+//     press any of them, players can only press Next to end their own turn. Their custom ids look
+//     like: `encounter:<next|previous|end>:<encounter_id>`
+// - Everything lives in the Encounters and Combatants tables, so the buttons keep working after
+//     the bot restarts. Ending the fight removes it
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, ButtonStyle, CommandInteraction, CommandOptionType,
+    ComponentInteraction, CreateActionRow, CreateAutocompleteResponse, CreateButton, CreateCommand,
+    CreateCommandOption, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    ResolvedOption, ResolvedValue
+};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    attributes::Attribute,
+    commands::{
+        check::{attribute_value, roll_check, CheckRoll, RollMode},
+        registry::SlashCommand,
+        rolls::log_roll
+    },
+    db::{self, Combatant, Encounter},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_active_character, get_user_character_name,
+        integer_option, search_user_characters, string_option, unix_now, CharacterId, EmbedColours
+    }
+};
+
+
+/// What initiative rolls are called in the roll history
+const INITIATIVE_LABEL: &str = "Initiative";
+/// What an encounter is called when it isn't given a name
+const DEFAULT_ENCOUNTER_NAME: &str = "Encounter";
+/// Longest name an encounter or NPC can be given
+const MAX_NAME_LENGTH: u16 = 100;
+/// Discord doesn't allow a field's value to be longer than this
+const MAX_FIELD_LENGTH: usize = 1024;
+
+
+/// Which way to move through the turn order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnStep {
+    Next,
+    Previous
+}
+
+
+/// Build the encounter command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    CreateCommand::new("encounter")
+        .description("Track turns in a fight")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "start", "Start a fight in this channel, which you'll run" )
+                .add_sub_option(
+                    CreateCommandOption::new( CommandOptionType::String, "name", "What the fight is called" )
+                        .required(false)
+                        .max_length( MAX_NAME_LENGTH )
+                )
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "join", "Join the fight in this channel, rolling initiative" )
+                .add_sub_option(
+                    CreateCommandOption::new( CommandOptionType::Integer, "character", "The character joining. Defaults to your active character" )
+                        .required(false)
+                        .set_autocomplete(true)
+                )
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "npc", "Add someone without a character to the fight you're running" )
+                .add_sub_option(
+                    CreateCommandOption::new( CommandOptionType::String, "name", "What the NPC is called" )
+                        .required(true)
+                        .max_length( MAX_NAME_LENGTH )
+                )
+                .add_sub_option(
+                    CreateCommandOption::new( CommandOptionType::Integer, "initiative_bonus", "Added to the NPC's initiative roll" )
+                        .required(false)
+                )
+                .add_sub_option(
+                    CreateCommandOption::new( CommandOptionType::Integer, "initiative", "Set the NPC's initiative instead of rolling it" )
+                        .required(false)
+                )
+        )
+}
+
+
+/// Where an encounter ends up after a step through the turn order, as (round, current combatant)
+///
+/// The first step forward begins round 1 with whoever goes first, and stepping past the last
+/// combatant begins the next round. Stepping back from the very first turn undoes the start
+pub fn step_turn( turn_order: &[Combatant], round: i64, current_combatant_id: Option<i64>, step: TurnStep ) -> (i64, Option<i64>) {
+
+    let ( first, last ) = match ( turn_order.first(), turn_order.last() ) {
+        ( Some(first), Some(last) ) => ( first.combatant_id, last.combatant_id ),
+        _ => return ( round, current_combatant_id )
+    };
+
+    let position = current_combatant_id
+        .and_then( |current_id| turn_order.iter().position( |combatant| combatant.combatant_id == current_id ) );
+
+    match ( step, position ) {
+        ( TurnStep::Next, _ ) if round == 0 => ( 1, Some(first) ),
+        ( TurnStep::Previous, _ ) if round == 0 => ( 0, None ),
+
+        // Whoever's turn it was has left, so the round picks up again from the top
+        ( _, None ) => ( round, Some(first) ),
+
+        ( TurnStep::Next, Some(position) ) if position + 1 == turn_order.len() => ( round + 1, Some(first) ),
+        ( TurnStep::Next, Some(position) ) => ( round, Some(turn_order[position + 1].combatant_id) ),
+
+        ( TurnStep::Previous, Some(0) ) if round == 1 => ( 0, None ),
+        ( TurnStep::Previous, Some(0) ) => ( round - 1, Some(last) ),
+        ( TurnStep::Previous, Some(position) ) => ( round, Some(turn_order[position - 1].combatant_id) ),
+    }
+}
+
+
+/// Add a character to an encounter, rolling their initiative as a Dexterity check
+pub async fn join_encounter(
+    database_connection: &SqlitePool,
+    encounter: &Encounter,
+    owner_id: u64,
+    character_id: CharacterId,
+    character_name: &str
+    ) -> Result<(Combatant, CheckRoll), BotError> {
+
+    let dexterity = attribute_value( database_connection, character_id, Attribute::Dexterity ).await?;
+
+    // The thread's generator can't be held across an await, so it's dropped straight away
+    let initiative_roll = roll_check( character_name, Attribute::Dexterity, dexterity, RollMode::Normal, &mut rand::thread_rng() );
+
+    let mut combatant = Combatant {
+        combatant_id: 0,
+        encounter_id: encounter.encounter_id,
+        character_id: Some( character_id ),
+        owner_id:     owner_id as i64,
+        name:         character_name.to_owned(),
+        initiative:   initiative_roll.total(),
+        dexterity
+    };
+    combatant.combatant_id = db::encounters::add_combatant( database_connection, &combatant ).await?;
+
+    Ok(( combatant, initiative_roll ))
+}
+
+/// Add an NPC to an encounter on behalf of it's game master. Their initiative is rolled with the
+/// bonus unless it's given, and the bonus breaks ties the way Dexterity does for characters
+pub async fn add_npc(
+    database_connection: &SqlitePool,
+    encounter: &Encounter,
+    name: &str,
+    initiative_bonus: i64,
+    initiative: Option<i64>
+    ) -> Result<(Combatant, Option<CheckRoll>), BotError> {
+
+    let initiative_roll = match initiative {
+        Some(_) => None,
+        None => Some( roll_check(name, Attribute::Dexterity, initiative_bonus, RollMode::Normal, &mut rand::thread_rng()) )
+    };
+
+    let mut combatant = Combatant {
+        combatant_id: 0,
+        encounter_id: encounter.encounter_id,
+        character_id: None,
+        owner_id:     encounter.game_master_id,
+        name:         name.to_owned(),
+        initiative:   initiative.or( initiative_roll.as_ref().map(CheckRoll::total) ).unwrap_or_default(),
+        dexterity:    initiative_bonus
+    };
+    combatant.combatant_id = db::encounters::add_combatant( database_connection, &combatant ).await?;
+
+    Ok(( combatant, initiative_roll ))
+}
+
+
+/// Show an encounter's turn order, pointing out whose turn it is
+pub fn turn_order_embed( encounter: &Encounter, turn_order: &[Combatant] ) -> CreateEmbed {
+
+    let current_combatant = turn_order
+        .iter()
+        .find( |combatant| Some(combatant.combatant_id) == encounter.current_combatant_id );
+
+    let progress = match ( encounter.round, current_combatant ) {
+        ( 0, _ ) => "Waiting for everyone to join. Press Next to begin the first round".to_owned(),
+        ( round, Some(combatant) ) => format!("Round {round}, it's **{}**'s turn", combatant.name),
+        ( round, None ) => format!("Round {round}"),
+    };
+
+    let mut lines = String::new();
+    for ( index, combatant ) in turn_order.iter().enumerate() {
+        let marker = if Some(combatant) == current_combatant { "▶" } else { "▫" };
+        let npc_note = if combatant.character_id.is_none() { " (NPC)" } else { "" };
+        let line = format!("{marker} `{:>3}` **{}**{npc_note}\n", combatant.initiative, combatant.name);
+
+        // Leave room for the note saying how many got left out
+        if lines.len() + line.len() > MAX_FIELD_LENGTH - 20 {
+            lines.push_str( &format!("And {} more", turn_order.len() - index) );
+            break
+        }
+
+        lines.push_str( &line );
+    }
+
+    if turn_order.is_empty() {
+        lines = "Nobody yet, join with /encounter join".to_owned();
+    }
+
+    CreateEmbed::new()
+        .title( encounter.name.clone() )
+        .description( format!("{progress}\nRun by <@{}>", encounter.game_master_id) )
+        .field( "Turn order", lines, false )
+        .colour( EmbedColours::info() )
+}
+
+/// Buttons to step through the turn order and to end the fight
+pub fn turn_order_buttons( encounter: &Encounter, turn_order: &[Combatant] ) -> Vec<CreateActionRow> {
+    let encounter_id = encounter.encounter_id;
+
+    let buttons = vec![
+        CreateButton::new( format!("encounter:previous:{encounter_id}") )
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled( encounter.round == 0 ),
+        CreateButton::new( format!("encounter:next:{encounter_id}") )
+            .label("Next")
+            .style(ButtonStyle::Primary)
+            .disabled( turn_order.is_empty() ),
+        CreateButton::new( format!("encounter:end:{encounter_id}") )
+            .label("End")
+            .style(ButtonStyle::Danger),
+    ];
+
+    vec![ CreateActionRow::Buttons(buttons) ]
+}
+
+/// Load an encounter's turn order and show it, along with it's buttons
+async fn turn_order_message( database_connection: &SqlitePool, encounter: &Encounter ) -> Result<CreateInteractionResponseMessage, BotError> {
+    let turn_order = db::encounters::turn_order( database_connection, encounter.encounter_id ).await?;
+
+    Ok( CreateInteractionResponseMessage::new()
+        .embed( turn_order_embed(encounter, &turn_order) )
+        .components( turn_order_buttons(encounter, &turn_order) ) )
+}
+
+/// The encounter running in a channel
+async fn channel_encounter( database_connection: &SqlitePool, channel_id: u64 ) -> Result<Encounter, BotError> {
+    match db::encounters::get_by_channel( database_connection, channel_id ).await? {
+        Some( encounter ) => Ok( encounter ),
+        None => Err( BotError::NoEncounter )
+    }
+}
+
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let channel_id = interaction_data.channel_id.get();
+    let database_connection = &discord_bot.database_connection;
+
+    let options = interaction_data.data.options();
+    let ( subcommand_name, subcommand_options ) = match options.first() {
+        Some( ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. } ) => ( *name, sub_options ),
+        _ => return None
+    };
+
+    let response_message: CreateInteractionResponseMessage = 'return_message: {
+        let error_message = |embed| CreateInteractionResponseMessage::new().embed( embed ).ephemeral( true );
+
+        match subcommand_name {
+            "start" => {
+                // Anyone can start an encounter, which makes them game master over it's characters,
+                // so they're kept to servers where the players can see it
+                let guild_id = match interaction_data.guild_id {
+                    Some( guild_id ) => guild_id.get(),
+                    None => break 'return_message error_message(
+                        error_embed( "Fights happen in a server", "Please start the encounter in one of the server's channels" )
+                    )
+                };
+
+                let name = string_option( subcommand_options, "name" ).map_or( DEFAULT_ENCOUNTER_NAME, str::trim );
+                let name = if name.is_empty() { DEFAULT_ENCOUNTER_NAME } else { name };
+
+                let started = db::encounters::start( database_connection, Some(guild_id), channel_id, invoking_user_id, name, unix_now() ).await;
+
+                let encounter = match started {
+                    Ok( encounter_id ) => db::encounters::get( database_connection, encounter_id ).await,
+                    Err( why ) => break 'return_message error_message(
+                        BotError::from( why ).embed( format!("{invoking_user_tag} failed to start an encounter") )
+                    )
+                };
+
+                match encounter {
+                    Ok( Some(encounter) ) => {
+                        info!("{invoking_user_tag} started {name} in {channel_id}");
+                        match turn_order_message( database_connection, &encounter ).await {
+                            Ok( message ) => message,
+                            Err( why ) => error_message( why.embed(format!("Failed to load {name}'s turn order")) )
+                        }
+                    },
+                    Ok( None ) => error_message( BotError::NoEncounter.embed("Encounter ended as soon as it started") ),
+                    Err( why ) => error_message( BotError::from( why ).embed("Failed to load the encounter that was just started") )
+                }
+            },
+
+            "join" => {
+                let encounter = match channel_encounter( database_connection, channel_id ).await {
+                    Ok( encounter ) => encounter,
+                    Err( why ) => break 'return_message error_message( why.embed(format!("{invoking_user_tag} couldn't join an encounter")) )
+                };
+
+                // Same as everywhere else, we fall back onto the active character
+                let character_id = match integer_option( subcommand_options, "character" ) {
+                    Some( id ) => id,
+                    None => match get_active_character( ctx, &invoking_user_id ).await {
+                        Some(( id, _ )) => id,
+                        None => break 'return_message error_message(
+                            BotError::NoActiveCharacter.embed( format!("{invoking_user_tag} has no active character in /encounter join") )
+                        )
+                    }
+                };
+                let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                    Some( name ) => name,
+                    None => break 'return_message error_message(
+                        BotError::NotOwner.embed( format!("{invoking_user_tag} picked someone else's character in /encounter join") )
+                    )
+                };
+
+                let ( combatant, initiative_roll ) = match join_encounter( database_connection, &encounter, invoking_user_id, character_id, &character_name ).await {
+                    Ok( joined ) => joined,
+                    Err( why ) => break 'return_message error_message(
+                        why.embed( format!("{character_name} failed to join {}", encounter.name) )
+                    )
+                };
+
+                info!("{character_name} joined {} with {} initiative", encounter.name, combatant.initiative);
+
+                log_roll(
+                    database_connection, interaction_data, invoking_user_id,
+                    Some(( character_id, &character_name )), INITIATIVE_LABEL,
+                    &initiative_roll.expression, &initiative_roll.roll
+                ).await;
+
+                let joined_embed = CreateEmbed::new()
+                    .title( format!("{character_name} joined the fight") )
+                    .description( format!("Initiative: {}", initiative_roll.breakdown()) )
+                    .colour( EmbedColours::good() );
+
+                match turn_order_message( database_connection, &encounter ).await {
+                    Ok( message ) => message.add_embed( add_active_character_footer(ctx, &invoking_user_id, joined_embed).await ),
+                    Err( why ) => error_message( why.embed(format!("Failed to load {}'s turn order", encounter.name)) )
+                }
+            },
+
+            "npc" => {
+                let encounter = match channel_encounter( database_connection, channel_id ).await {
+                    Ok( encounter ) => encounter,
+                    Err( why ) => break 'return_message error_message( why.embed(format!("{invoking_user_tag} couldn't add an NPC")) )
+                };
+
+                if encounter.game_master_id != invoking_user_id as i64 {
+                    break 'return_message error_message(
+                        BotError::NotGameMaster.embed( format!("{invoking_user_tag} tried to add an NPC to {}", encounter.name) )
+                    )
+                }
+
+                let name = string_option( subcommand_options, "name" )?.trim();
+                if name.is_empty() {
+                    break 'return_message error_message( error_embed("NPCs need a name", "Please give them one that isn't blank") )
+                }
+
+                let initiative_bonus = integer_option( subcommand_options, "initiative_bonus" ).unwrap_or( 0 );
+                let initiative = integer_option( subcommand_options, "initiative" );
+
+                let ( combatant, initiative_roll ) = match add_npc( database_connection, &encounter, name, initiative_bonus, initiative ).await {
+                    Ok( added ) => added,
+                    Err( why ) => break 'return_message error_message( why.embed(format!("Failed to add {name} to {}", encounter.name)) )
+                };
+
+                info!("{invoking_user_tag} added {name} to {} with {} initiative", encounter.name, combatant.initiative);
+
+                let initiative_text = match initiative_roll {
+                    Some( initiative_roll ) => initiative_roll.breakdown(),
+                    None => format!("**{}**", combatant.initiative)
+                };
+
+                let joined_embed = CreateEmbed::new()
+                    .title( format!("{name} joined the fight") )
+                    .description( format!("Initiative: {initiative_text}") )
+                    .colour( EmbedColours::good() );
+
+                match turn_order_message( database_connection, &encounter ).await {
+                    Ok( message ) => message.add_embed( add_active_character_footer(ctx, &invoking_user_id, joined_embed).await ),
+                    Err( why ) => error_message( why.embed(format!("Failed to load {}'s turn order", encounter.name)) )
+                }
+            },
+
+            _ => return None
+        }
+    };
+
+    Some( CreateInteractionResponse::Message(response_message) )
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = match interaction_data.data.autocomplete() {
+        Some( option ) if option.name == "character" => search_user_characters( ctx, &invoking_user_id, option.value )
+            .await
+            .into_iter()
+            .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+            .collect(),
+        _ => vec![]
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /encounter")
+    }
+}
+
+
+// Step through the turn order or end the fight. The state is read back from the database on every
+// press, so a turn order from before a restart is just as good as a new one
+pub async fn handle_component( interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create these ids ourselves, there's nothing we can do with a mangled one
+    let ( action, encounter_id ): (&str, i64) = match id_components.as_slice() {
+        [ _, action, encounter_id ] => match encounter_id.parse() {
+            Ok( encounter_id ) => ( *action, encounter_id ),
+            Err(_) => return
+        },
+        _ => return
+    };
+
+    let response = 'response: {
+        let error_response = |embed| CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().embed( embed ).ephemeral( true )
+        );
+
+        // --== LOAD ENCOUNTER ==-- //
+
+            let encounter = match db::encounters::get( database_connection, encounter_id ).await {
+                Ok( Some(encounter) ) => encounter,
+                Ok( None ) => break 'response CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(
+                            CreateEmbed::new()
+                                .title("This fight is over")
+                                .colour(EmbedColours::info())
+                        )
+                        .components(vec![])
+                ),
+                Err( why ) => {
+                    warn!(error = %why, "Failed to load encounter");
+                    return
+                }
+            };
+
+            let turn_order = match db::encounters::turn_order( database_connection, encounter_id ).await {
+                Ok( turn_order ) => turn_order,
+                Err( why ) => {
+                    warn!(error = %why, "Failed to load turn order");
+                    return
+                }
+            };
+        // ==--
+
+        // --== PERMISSION TEST ==-- //
+
+            // The game master can do anything. Players can only end their own turn, which needs
+            // the fight to have begun
+            let is_game_master = encounter.game_master_id == invoking_user_id as i64;
+            let is_current_player = encounter.round > 0 && turn_order
+                .iter()
+                .any( |combatant| Some(combatant.combatant_id) == encounter.current_combatant_id && combatant.owner_id == invoking_user_id as i64 );
+
+            let allowed = match action {
+                "next" => is_game_master || is_current_player,
+                _ => is_game_master
+            };
+
+            if !allowed {
+                break 'response error_response(
+                    BotError::NotGameMaster.embed( format!("{invoking_user_tag} pressed {action} on {}", encounter.name) )
+                )
+            }
+        // ==--
+
+        // --== APPLY ACTION ==-- //
+
+            let step = match action {
+                "next" => TurnStep::Next,
+                "previous" => TurnStep::Previous,
+                "end" => {
+                    if let Err( why ) = db::encounters::end( database_connection, encounter_id ).await {
+                        break 'response error_response( BotError::from( why ).embed(format!("Failed to end {}", encounter.name)) )
+                    }
+
+                    info!("{invoking_user_tag} ended {} after {} round(s)", encounter.name, encounter.round);
+
+                    // The final turn order stays up, without any buttons to press
+                    break 'response CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed(
+                                turn_order_embed( &encounter, &turn_order )
+                                    .title( format!("{} is over", encounter.name) )
+                                    .description( format!("The fight lasted {} round(s)", encounter.round) )
+                            )
+                            .components(vec![])
+                    )
+                },
+                _ => return
+            };
+
+            let ( round, current_combatant_id ) = step_turn( &turn_order, encounter.round, encounter.current_combatant_id, step );
+
+            // If someone else stepped first, their step stands and this one is dropped. Either
+            // way the message is updated to show where the fight is at
+            let stepped = db::encounters::set_turn( database_connection, &encounter, round, current_combatant_id ).await;
+            let encounter = match stepped {
+                Ok(_) => db::encounters::get( database_connection, encounter_id ).await,
+                Err( why ) => Err( why )
+            };
+
+            match encounter {
+                Ok( Some(encounter) ) => match turn_order_message( database_connection, &encounter ).await {
+                    Ok( message ) => CreateInteractionResponse::UpdateMessage( message ),
+                    Err( why ) => error_response( why.embed(format!("Failed to load {}'s turn order", encounter.name)) )
+                },
+                Ok( None ) => error_response( BotError::NoEncounter.embed("Encounter ended while stepping through it") ),
+                Err( why ) => error_response( BotError::from( why ).embed("Failed to step through the turn order") )
+            }
+        // ==--
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to respond to encounter interaction")
+    }
+}
+
+
+/// Routes /encounter and it's interactions to the functions above
+pub struct EncounterCommand;
+#[async_trait]
+impl SlashCommand for EncounterCommand {
+    fn name( &self ) -> &'static str {
+        "encounter"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+
+    async fn component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_component( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
@@ -0,0 +1,413 @@
+// Carry items from the server's catalogue around
+//
+// - `/inventory view` lists what a character is carrying, and how much of their carry capacity
+//     it takes up
+// - `/inventory add` and `/inventory remove` keep track of what a character picks up and drops.
+//     Added items come from the server's catalogue, removed ones from the character's inventory
+// - `/inventory give` hands items to one of another player's characters, their active one unless
+//     another is picked. Both sides change inside of one transaction, so items are never lost
+//     or duplicated along the way
+// - Carry capacity is worked out from Strength. Nothing can be added or given to a character that
+//     would take them past it, and characters without attributes can't carry anything
+// - Every character option autocompletes over your characters and defaults to your active one
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, ResolvedOption, ResolvedValue
+};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    commands::{
+        item::{item_choices, item_summary},
+        registry::SlashCommand
+    },
+    db::{self, InventoryItem},
+    derived_stats::load_stats,
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_user_character_name, integer_option,
+        no_catalogue_embed, search_user_characters, selected_character_id, user_option, CharacterId, EmbedColours
+    }
+};
+
+/// Most of an item that can be added, removed or given at once
+const MAX_QUANTITY: u64 = 10_000;
+
+
+/// Build the inventory command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+
+    let item_option = |description: &str| CreateCommandOption::new( CommandOptionType::Integer, "item", description )
+        .required(true)
+        .set_autocomplete(true);
+
+    let quantity_option = || CreateCommandOption::new( CommandOptionType::Integer, "quantity", "How many. Defaults to 1" )
+        .required(false)
+        .min_int_value(1)
+        .max_int_value( MAX_QUANTITY );
+
+    let character_option = || CreateCommandOption::new( CommandOptionType::Integer, "character", "One of your characters. Defaults to your active one" )
+        .required(false)
+        .set_autocomplete(true);
+
+    let subcommands = vec![
+        CreateCommandOption::new( CommandOptionType::SubCommand, "view", "See what your character is carrying" )
+            .add_sub_option( character_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "add", "Put an item from the catalogue into your character's inventory" )
+            .add_sub_option( item_option("The item from the catalogue") )
+            .add_sub_option( quantity_option() )
+            .add_sub_option( character_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "remove", "Take an item out of your character's inventory" )
+            .add_sub_option( item_option("One of the items your character is carrying") )
+            .add_sub_option( quantity_option() )
+            .add_sub_option( character_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "give", "Give an item to another player's character" )
+            .add_sub_option( item_option("One of the items your character is carrying") )
+            .add_sub_option(
+                CreateCommandOption::new( CommandOptionType::User, "user", "The player receiving it" )
+                    .required(true)
+            )
+            .add_sub_option( quantity_option() )
+            .add_sub_option( character_option() )
+            .add_sub_option(
+                CreateCommandOption::new( CommandOptionType::Integer, "to_character", "One of their characters. Defaults to their active one" )
+                    .required(false)
+                    .set_autocomplete(true)
+            ),
+    ];
+
+    CreateCommand::new("inventory")
+        .description("See and change what your characters are carrying")
+        .set_options(subcommands)
+}
+
+
+/// How much of a character's carry capacity is taken up, like `Carrying 12/40`. Characters
+/// without attributes have no capacity to compare against
+fn carrying_text( carried_weight: i64, carry_capacity: Option<i64> ) -> String {
+    match carry_capacity {
+        Some( capacity ) => format!("Carrying {carried_weight}/{capacity}"),
+        None => format!("Carrying {carried_weight}")
+    }
+}
+
+/// Show everything a character is carrying, one field per item
+pub fn inventory_embed( character_name: &str, inventory: &[InventoryItem], carry_capacity: Option<i64> ) -> CreateEmbed {
+    let carried_weight = inventory
+        .iter()
+        .map( |entry| entry.item.weight * entry.quantity )
+        .sum();
+
+    let mut description = carrying_text( carried_weight, carry_capacity );
+    if inventory.is_empty() {
+        description.push_str("\nThey aren't carrying anything");
+    }
+    // An embed can only hold 25 fields, anything past that is left out
+    if inventory.len() > 25 {
+        description.push_str( &format!("\nShowing 25 of {} items", inventory.len()) );
+    }
+
+    let fields = inventory
+        .iter()
+        .take(25)
+        .map( |entry| ( format!("{} ×{}", entry.item.name, entry.quantity), item_summary(&entry.item), false ) );
+
+    CreateEmbed::new()
+        .title( format!("{character_name}'s inventory") )
+        .description( description )
+        .fields( fields )
+        .colour( EmbedColours::info() )
+}
+
+/// Put items into a character's inventory, as long as they can carry them. Returns the weight
+/// they carry afterwards along with their carry capacity
+///
+/// Fails with `NoAttributes` if the character has no carry capacity yet, and with `Overburdened`
+/// if they'd carry more than it
+pub async fn add_items( database_connection: &SqlitePool, character_id: CharacterId, item_id: i64, quantity: i64 ) -> Result<(i64, i64), BotError> {
+    let stats = load_stats( database_connection, character_id ).await?;
+    let carried_weight = db::items::add_to_inventory( database_connection, character_id, item_id, quantity, stats.carry_capacity ).await?;
+
+    Ok(( carried_weight, stats.carry_capacity ))
+}
+
+/// Move items from one character to another, as long as the receiver can carry them. Returns the
+/// weight the receiver carries afterwards along with their carry capacity
+///
+/// Fails with `NoAttributes` if the receiver has no carry capacity yet, with `NotEnoughItems` if
+/// the giver doesn't have that many and with `Overburdened` if the receiver can't carry them
+pub async fn give_items( database_connection: &SqlitePool, giver_id: CharacterId, receiver_id: CharacterId, item_id: i64, quantity: i64 ) -> Result<(i64, i64), BotError> {
+    let stats = load_stats( database_connection, receiver_id ).await?;
+    let carried_weight = db::items::transfer( database_connection, giver_id, receiver_id, item_id, quantity, stats.carry_capacity ).await?;
+
+    Ok(( carried_weight, stats.carry_capacity ))
+}
+
+
+/// The subcommand used, along with it's options
+fn subcommand<'a>( options: &'a [ResolvedOption<'a>] ) -> Option<( &'a str, &'a [ResolvedOption<'a>] )> {
+    match options.first() {
+        Some( ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. } ) => Some(( *name, sub_options.as_slice() )),
+        _ => None
+    }
+}
+
+fn not_carried_embed( character_name: &str ) -> CreateEmbed {
+    error_embed( format!("{character_name} isn't carrying that"), "See what they're carrying with /inventory view" )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    // Our command only consists of subcommands, so the first option will always be one
+    let options = interaction_data.data.options();
+    let ( subcommand_name, subcommand_options ) = subcommand( &options )?;
+    let quantity = integer_option( subcommand_options, "quantity" ).unwrap_or(1);
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        // --== FIND CHARACTER ==-- //
+
+            let character_id = match selected_character_id( ctx, &invoking_user_id, integer_option(subcommand_options, "character") ).await {
+                Some( id ) => id,
+                None => break 'return_embed BotError::NoActiveCharacter
+                    .embed( format!("{invoking_user_tag} has no active character in /inventory") )
+            };
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'return_embed BotError::NotOwner
+                    .embed( format!("{invoking_user_tag} picked someone else's character in /inventory") )
+            };
+        // ==--
+
+        match subcommand_name {
+
+            "view" => {
+                let inventory = match db::items::get_inventory( database_connection, character_id ).await {
+                    Ok( inventory ) => inventory,
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to get {character_name}'s inventory") )
+                };
+
+                // Characters without attributes still get to see what they're carrying
+                let carry_capacity = match load_stats( database_connection, character_id ).await {
+                    Ok( stats ) => Some( stats.carry_capacity ),
+                    Err( BotError::NoAttributes ) => None,
+                    Err( why ) => break 'return_embed why.embed( format!("Failed to get {character_name}'s carry capacity") )
+                };
+
+                add_active_character_footer( ctx, &invoking_user_id, inventory_embed(&character_name, &inventory, carry_capacity) ).await
+            },
+
+            "add" => {
+                let item_id = integer_option( subcommand_options, "item" )?;
+                let guild_id = match interaction_data.guild_id {
+                    Some( guild_id ) => guild_id.get(),
+                    None => break 'return_embed no_catalogue_embed( "Items" )
+                };
+
+                let item = match db::items::get( database_connection, guild_id, item_id ).await {
+                    Ok( Some(item) ) => item,
+                    Ok( None ) => break 'return_embed error_embed( "There's no such item", "Only items from this server's catalogue can be added" ),
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to fetch an item for {invoking_user_tag}") )
+                };
+
+                match add_items( database_connection, character_id, item_id, quantity ).await {
+                    Ok(( carried_weight, carry_capacity )) => {
+                        info!("{invoking_user_tag} added {quantity} {} to {character_name}'s inventory", item.name);
+
+                        let embed = CreateEmbed::new()
+                            .title( format!("{character_name} picked up {quantity} × {}", item.name) )
+                            .description( carrying_text(carried_weight, Some(carry_capacity)) )
+                            .colour( EmbedColours::good() );
+                        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                    },
+                    Err( why ) => why.embed( format!("{character_name} couldn't pick up {}", item.name) )
+                }
+            },
+
+            "remove" => {
+                let item_id = integer_option( subcommand_options, "item" )?;
+
+                // The item might come from another server's catalogue, so it's looked up in the
+                // character's inventory
+                let item = match db::items::get_inventory( database_connection, character_id ).await {
+                    Ok( inventory ) => match inventory.into_iter().find( |entry| entry.item.item_id == item_id ) {
+                        Some( entry ) => entry.item,
+                        None => break 'return_embed not_carried_embed( &character_name )
+                    },
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to get {character_name}'s inventory") )
+                };
+
+                match db::items::take_from_inventory( database_connection, character_id, item_id, quantity ).await {
+                    Ok(()) => {
+                        info!("{invoking_user_tag} removed {quantity} {} from {character_name}'s inventory", item.name);
+
+                        let embed = CreateEmbed::new()
+                            .title( format!("{character_name} dropped {quantity} × {}", item.name) )
+                            .colour( EmbedColours::good() );
+                        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                    },
+                    Err( why ) => BotError::from( why ).embed( format!("{character_name} couldn't drop {}", item.name) )
+                }
+            },
+
+            "give" => {
+                let item_id = integer_option( subcommand_options, "item" )?;
+                let target_user_id = user_option( subcommand_options, "user" )?;
+
+                // --== FIND RECEIVER ==-- //
+
+                    let receiver_id = match selected_character_id( ctx, &target_user_id, integer_option(subcommand_options, "to_character") ).await {
+                        Some( id ) => id,
+                        None => break 'return_embed error_embed( "No character to give it to", format!("<@{target_user_id}> isn't playing as anyone, pick one of their characters") )
+                    };
+                    let receiver_name = match get_user_character_name( ctx, &target_user_id, receiver_id ).await {
+                        Some( name ) => name,
+                        None => break 'return_embed BotError::NotOwner
+                            .embed( format!("{invoking_user_tag} picked a character <@{target_user_id}> doesn't own in /inventory") )
+                    };
+                    if receiver_id == character_id {
+                        break 'return_embed error_embed( format!("{character_name} already has it"), "Pick another character to give it to" )
+                    }
+                // ==--
+
+                let item = match db::items::get_inventory( database_connection, character_id ).await {
+                    Ok( inventory ) => match inventory.into_iter().find( |entry| entry.item.item_id == item_id ) {
+                        Some( entry ) => entry.item,
+                        None => break 'return_embed not_carried_embed( &character_name )
+                    },
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to get {character_name}'s inventory") )
+                };
+
+                match give_items( database_connection, character_id, receiver_id, item_id, quantity ).await {
+                    Ok(( carried_weight, carry_capacity )) => {
+                        info!("{invoking_user_tag} had {character_name} give {quantity} {} to {receiver_name}", item.name);
+
+                        let embed = CreateEmbed::new()
+                            .title( format!("{character_name} gave {quantity} × {} to {receiver_name}", item.name) )
+                            .description( format!("<@{target_user_id}>'s {receiver_name} is now carrying {carried_weight}/{carry_capacity}") )
+                            .colour( EmbedColours::good() );
+                        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                    },
+                    Err( why ) => why.embed( format!("{character_name} couldn't give {} to {receiver_name}", item.name) )
+                }
+            },
+
+            _ => return None
+        }
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) => option,
+            None => break 'choices vec![]
+        };
+        let options = interaction_data.data.options();
+        let ( subcommand_name, subcommand_options ) = match subcommand( &options ) {
+            Some( subcommand ) => subcommand,
+            None => break 'choices vec![]
+        };
+
+        match focused_option.name {
+
+            "character" => {
+                search_user_characters( ctx, &invoking_user_id, focused_option.value )
+                    .await
+                    .into_iter()
+                    .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                    .collect()
+            },
+
+            // Suggest the characters of whoever is receiving the items
+            "to_character" => {
+                let target_user_id = match user_option( subcommand_options, "user" ) {
+                    Some( id ) => id,
+                    None => break 'choices vec![]
+                };
+
+                search_user_characters( ctx, &target_user_id, focused_option.value )
+                    .await
+                    .into_iter()
+                    .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                    .collect()
+            },
+
+            // Just like in the other autocompletes, we won't log the errors as there are far too
+            // many autocomplete interactions. An empty list will have to do
+            "item" if subcommand_name == "add" => match interaction_data.guild_id {
+                Some( guild_id ) => match db::items::get_catalogue( &discord_bot.database_connection, guild_id.get() ).await {
+                    Ok( catalogue ) => item_choices( catalogue, focused_option.value ),
+                    Err(_) => vec![]
+                },
+                None => vec![]
+            },
+
+            // Removing and giving suggest what one of the user's own characters is carrying, so
+            // we don't leak other people's inventories
+            "item" => {
+                let character_id = match selected_character_id( ctx, &invoking_user_id, integer_option(subcommand_options, "character") ).await {
+                    Some( id ) => id,
+                    None => break 'choices vec![]
+                };
+                if get_user_character_name( ctx, &invoking_user_id, character_id ).await.is_none() {
+                    break 'choices vec![]
+                }
+
+                match db::items::get_inventory( &discord_bot.database_connection, character_id ).await {
+                    Ok( inventory ) => item_choices( inventory.into_iter().map( |entry| entry.item ).collect(), focused_option.value ),
+                    Err(_) => vec![]
+                }
+            },
+
+            _ => vec![]
+        }
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /inventory")
+    }
+}
+
+
+/// Routes /inventory and it's autocomplete to the functions above
+pub struct InventoryCommand;
+#[async_trait]
+impl SlashCommand for InventoryCommand {
+    fn name( &self ) -> &'static str {
+        "inventory"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
@@ -0,0 +1,470 @@
+// Author the server's item catalogue
+//
+// - Only members who can manage the server see `/item` by default, the same as `/spell`
+// - `/item create` and `/item edit` dispatch a modal asking for the item's name, weight, value,
+//     tags and description. Edit's modal comes prefilled with the current values
+// - `/item remove` takes an item out of the catalogue, and out of every inventory it was in
+// - `/item list` shows the whole catalogue
+// - `/item gear` sets the slot an item is equipped into and what it adds to each attribute.
+//     Modifiers that are left out are set back to 0, and picking no slot makes it a plain item
+// - Every server keeps it's own catalogue, so items only exist in servers. Players carry them
+//     around with `/inventory`
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateActionRow,
+    CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateEmbed, CreateInputText,
+    CreateInteractionResponse, CreateModal, InputTextStyle, ModalInteraction, Permissions,
+    ResolvedOption, ResolvedValue
+};
+use tracing::{info, warn};
+
+use crate::{
+    attributes::{Attribute, AttributeSpread},
+    commands::registry::SlashCommand,
+    db::{self, Item},
+    equipment::{modifiers_text, Slot},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, find_option, integer_option, message_response,
+        modal_input_values, no_catalogue_embed, EmbedColours
+    }
+};
+
+/// Longest name an item can have, also used as the max length of the modal's name field
+const MAX_NAME_LENGTH: u16 = 100;
+/// Longest list of tags an item can have
+const MAX_TAGS_LENGTH: u16 = 100;
+/// Longest description an item can have. Leaves room in an embed field's 1024 characters for the
+/// line summing up the item's weight, value and tags
+const MAX_DESCRIPTION_LENGTH: u16 = 800;
+/// Most an item can raise or lower a single attribute by
+const MAX_MODIFIER: i64 = 10;
+/// Value of the slot option for items that can't be equipped
+const NO_SLOT: &str = "none";
+
+
+/// Build the item command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+
+    let item_option = || CreateCommandOption::new( CommandOptionType::Integer, "item", "The item from the catalogue" )
+        .required(true)
+        .set_autocomplete(true);
+
+    let subcommands = vec![
+        CreateCommandOption::new( CommandOptionType::SubCommand, "create", "Add a new item to the catalogue" ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "edit", "Change an item in the catalogue" )
+            .add_sub_option( item_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "remove", "Take an item out of the catalogue" )
+            .add_sub_option( item_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "list", "List every item in the catalogue" ),
+        gear_subcommand( item_option() ),
+    ];
+
+    CreateCommand::new("item")
+        .description("Manage the server's item catalogue")
+        .default_member_permissions( Permissions::MANAGE_GUILD )
+        .set_options(subcommands)
+}
+
+
+/// The gear subcommand, which takes a slot and one modifier per attribute
+fn gear_subcommand( item_option: CreateCommandOption ) -> CreateCommandOption {
+    let mut slot_option = CreateCommandOption::new( CommandOptionType::String, "slot", "Where it's equipped" )
+        .required(true)
+        .add_string_choice( "Can't be equipped", NO_SLOT );
+    for slot in Slot::ALL {
+        slot_option = slot_option.add_string_choice( slot.name(), slot.id() );
+    }
+
+    let mut subcommand = CreateCommandOption::new( CommandOptionType::SubCommand, "gear", "Make an item equippable, and set what it does to attributes" )
+        .add_sub_option( item_option )
+        .add_sub_option( slot_option );
+    for attribute in Attribute::ALL {
+        subcommand = subcommand.add_sub_option(
+            CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    attribute.name().to_lowercase(),
+                    format!("Added to {} while it's equipped. Defaults to 0", attribute.name())
+                )
+                .required(false)
+        );
+    }
+
+    subcommand
+}
+
+
+/// Sums up an item for embed fields, like `Weight 2 · Worth 15 · weapon, melee`, then
+/// `Main hand · +2 Strength` if it can be equipped, followed by it's description
+pub fn item_summary( item: &Item ) -> String {
+    let mut summary = format!("Weight {} · Worth {}", item.weight, item.value);
+    if !item.tags.is_empty() {
+        summary.push_str( &format!(" · {}", item.tags) );
+    }
+
+    if let Some( slot ) = item.equip_slot() {
+        summary.push_str( &format!("\n{}", slot.name()) );
+
+        let modifiers = modifiers_text( &item.modifiers() );
+        if !modifiers.is_empty() {
+            summary.push_str( &format!(" · {modifiers}") );
+        }
+    }
+
+    format!("{summary}\n{}", item.description)
+}
+
+/// Suggest the items whose name contains the query
+pub fn item_choices( items: Vec<Item>, query: &str ) -> Vec<AutocompleteChoice> {
+    let query = query.to_lowercase();
+
+    items
+        .into_iter()
+        .filter( |item| item.name.to_lowercase().contains(&query) )
+        .take(25)
+        .map( |item| AutocompleteChoice::new(item.name, item.item_id) )
+        .collect()
+}
+
+/// Tidy up comma separated tags: trimmed, lowercase and without empty or repeated ones
+pub fn normalise_tags( tags: &str ) -> String {
+    let mut normalised: Vec<String> = vec![];
+
+    for tag in tags.split(',').map( |tag| tag.trim().to_lowercase() ) {
+        if !tag.is_empty() && !normalised.contains( &tag ) {
+            normalised.push( tag );
+        }
+    }
+
+    normalised.join(", ")
+}
+
+/// Build an item out of what was typed into the modal, in the modal's order. Returns `None`
+/// unless the weight and value are both at least 0
+pub fn item_from_inputs( item_id: i64, guild_id: u64, inputs: &[String] ) -> Option<Item> {
+    let [name, weight, value, tags, description] = inputs else {
+        return None
+    };
+
+    let weight: i64 = weight.trim().parse().ok()?;
+    let value: i64 = value.trim().parse().ok()?;
+    if weight < 0 || value < 0 {
+        return None
+    }
+
+    Some( Item {
+        item_id,
+        guild_id:    guild_id as i64,
+        name:        name.trim().to_owned(),
+        weight,
+        value,
+        description: description.trim().to_owned(),
+        tags:        normalise_tags( tags ),
+        // The modal leaves the gear alone, it's set with `/item gear`
+        ..Item::default()
+    })
+}
+
+/// Build the modal used by both `create` and `edit`. `current` holds the item when editing
+fn item_modal( custom_id: String, title: String, current: Option<Item> ) -> CreateModal {
+    let mut name_input = CreateInputText::new( InputTextStyle::Short, "Item Name", "name" )
+        .max_length( MAX_NAME_LENGTH );
+    let mut weight_input = CreateInputText::new( InputTextStyle::Short, "Weight", "weight" )
+        .max_length( 6 );
+    let mut value_input = CreateInputText::new( InputTextStyle::Short, "Value", "value" )
+        .max_length( 9 );
+    let mut tags_input = CreateInputText::new( InputTextStyle::Short, "Tags, separated by commas", "tags" )
+        .max_length( MAX_TAGS_LENGTH )
+        .required( false );
+    let mut description_input = CreateInputText::new( InputTextStyle::Paragraph, "Description", "description" )
+        .max_length( MAX_DESCRIPTION_LENGTH );
+
+    if let Some( item ) = current {
+        name_input        = name_input.value( item.name );
+        weight_input      = weight_input.value( item.weight.to_string() );
+        value_input       = value_input.value( item.value.to_string() );
+        tags_input        = tags_input.value( item.tags );
+        description_input = description_input.value( item.description );
+    }
+
+    CreateModal::new( custom_id, title )
+        .components(vec![
+            CreateActionRow::InputText( name_input ),
+            CreateActionRow::InputText( weight_input ),
+            CreateActionRow::InputText( value_input ),
+            CreateActionRow::InputText( tags_input ),
+            CreateActionRow::InputText( description_input )
+        ])
+}
+
+fn unknown_item_embed() -> CreateEmbed {
+    error_embed( "There's no such item", "Use /item list to see the catalogue" )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    // Our command only consists of subcommands, so the first option will always be one
+    let options = interaction_data.data.options();
+    let ( subcommand_name, subcommand_options ) = match options.first() {
+        Some( ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. } ) => ( *name, sub_options ),
+        _ => return None
+    };
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        let guild_id = match interaction_data.guild_id {
+            Some( guild_id ) => guild_id.get(),
+            None => break 'return_embed no_catalogue_embed( "Items" )
+        };
+
+        match subcommand_name {
+
+            "create" => return Some( CreateInteractionResponse::Modal(item_modal(
+                "item:create".to_owned(),
+                "New item".to_owned(),
+                None
+            ))),
+
+            "edit" => {
+                let item_id = integer_option( subcommand_options, "item" )?;
+
+                match db::items::get( database_connection, guild_id, item_id ).await {
+                    Ok( Some(item) ) => return Some( CreateInteractionResponse::Modal(item_modal(
+                        format!("item:edit:{item_id}"),
+                        format!("Editing {}", item.name),
+                        Some( item )
+                    ))),
+                    Ok( None ) => unknown_item_embed(),
+                    Err( why ) => BotError::from( why ).embed( format!("Failed to fetch an item for {invoking_user_tag}") )
+                }
+            },
+
+            "remove" => {
+                let item_id = integer_option( subcommand_options, "item" )?;
+
+                let item = match db::items::get( database_connection, guild_id, item_id ).await {
+                    Ok( Some(item) ) => item,
+                    Ok( None ) => break 'return_embed unknown_item_embed(),
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to fetch an item for {invoking_user_tag}") )
+                };
+
+                match db::items::remove( database_connection, guild_id, item_id ).await {
+                    Ok( false ) => unknown_item_embed(),
+                    Ok( true ) => {
+                        info!("{invoking_user_tag} removed the item {}", item.name);
+
+                        let embed = CreateEmbed::new()
+                            .title( format!("Removed {} from the catalogue", item.name) )
+                            .description("It's gone from every inventory it was in")
+                            .colour( EmbedColours::good() );
+                        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                    },
+                    Err( why ) => BotError::from( why ).embed( format!("Failed to remove an item for {invoking_user_tag}") )
+                }
+            },
+
+            "list" => match db::items::get_catalogue( database_connection, guild_id ).await {
+                Ok( catalogue ) if catalogue.is_empty() => {
+                    let embed = CreateEmbed::new()
+                        .title("The catalogue is empty")
+                        .description("Add an item with /item create")
+                        .colour( EmbedColours::info() );
+                    add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                },
+                Ok( catalogue ) => {
+                    // An embed can only hold 25 fields, anything past that is left out
+                    let item_count = catalogue.len();
+                    let fields = catalogue
+                        .iter()
+                        .take(25)
+                        .map( |item| ( item.name.clone(), item_summary(item), false ) );
+
+                    let embed = CreateEmbed::new()
+                        .title("Item catalogue")
+                        .fields( fields )
+                        .colour( EmbedColours::info() );
+
+                    let embed = match item_count > 25 {
+                        true  => embed.description( format!("Showing 25 of {item_count} items") ),
+                        false => embed
+                    };
+                    add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                },
+                Err( why ) => BotError::from( why ).embed( format!("Failed to list the items for {invoking_user_tag}") )
+            },
+
+            "gear" => {
+                let item_id = integer_option( subcommand_options, "item" )?;
+                let slot = match find_option( subcommand_options, "slot" )? {
+                    ResolvedValue::String( NO_SLOT ) => None,
+                    ResolvedValue::String( slot_id ) => Some( Slot::from_id(slot_id)? ),
+                    _ => return None
+                };
+
+                let mut modifiers = AttributeSpread([0; 6]);
+                for attribute in Attribute::ALL {
+                    if let Some( modifier ) = integer_option( subcommand_options, &attribute.name().to_lowercase() ) {
+                        modifiers.set( attribute, modifier );
+                    }
+                }
+
+                // Integer options can't be given a negative minimum, so the range is checked here
+                if modifiers.0.iter().any( |modifier| modifier.abs() > MAX_MODIFIER ) {
+                    break 'return_embed error_embed( "Couldn't save that gear", format!("Modifiers have to be between -{MAX_MODIFIER} and +{MAX_MODIFIER}") )
+                }
+
+                let mut item = match db::items::get( database_connection, guild_id, item_id ).await {
+                    Ok( Some(item) ) => item,
+                    Ok( None ) => break 'return_embed unknown_item_embed(),
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to fetch an item for {invoking_user_tag}") )
+                };
+
+                match db::items::set_gear( database_connection, guild_id, item_id, slot, &modifiers ).await {
+                    Ok( false ) => unknown_item_embed(),
+                    Ok( true ) => {
+                        info!("{invoking_user_tag} changed the gear of the item {}", item.name);
+
+                        item.slot = slot.map( |slot| slot.id().to_owned() );
+                        item.set_modifiers( &modifiers );
+
+                        let embed = CreateEmbed::new()
+                            .title( format!("Saved {}", item.name) )
+                            .description( item_summary(&item) )
+                            .colour( EmbedColours::good() );
+                        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                    },
+                    Err( why ) => BotError::from( why ).embed( format!("Failed to change an item's gear for {invoking_user_tag}") )
+                }
+            },
+
+            _ => return None
+        }
+    };
+
+    Some( message_response(embed) )
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = match ( interaction_data.data.autocomplete(), interaction_data.guild_id ) {
+
+        // Just like in the other autocompletes, we won't log the error as there are far too many
+        // autocomplete interactions. An empty list will have to do
+        ( Some(focused_option), Some(guild_id) ) if focused_option.name == "item" => {
+            match db::items::get_catalogue( &discord_bot.database_connection, guild_id.get() ).await {
+                Ok( catalogue ) => item_choices( catalogue, focused_option.value ),
+                Err(_) => vec![]
+            }
+        },
+        _ => vec![]
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /item")
+    }
+}
+
+
+// Both the create and edit modals land here. Their custom ids look like:
+//   - item:create
+//   - item:edit:<item_id>
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create both the modal and it's id ourselves, so if anything here is missing there's
+    // nothing we can do but return
+    let item_id: Option<i64> = match ( id_components.get(1), id_components.get(2) ) {
+        ( Some(&"create"), None ) => None,
+        ( Some(&"edit"), Some(item_id) ) => match item_id.parse() {
+            Ok( id ) => Some( id ),
+            Err(_) => return
+        },
+        _ => return
+    };
+    let inputs = match modal_input_values( interaction_data ) {
+        Some( inputs ) => inputs,
+        None => return
+    };
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        let guild_id = match interaction_data.guild_id {
+            Some( guild_id ) => guild_id.get(),
+            None => break 'return_embed no_catalogue_embed( "Items" )
+        };
+
+        let item = match item_from_inputs( item_id.unwrap_or_default(), guild_id, &inputs ) {
+            Some( item ) => item,
+            None => break 'return_embed BotError::InvalidItem
+                .embed( format!("{invoking_user_tag} typed an invalid weight or value") )
+        };
+
+        let query_result = match item_id {
+            None => db::items::add( database_connection, &item ).await.map( |_| true ),
+            Some(_) => db::items::update( database_connection, &item ).await
+        };
+
+        match query_result {
+            // Editing an item that was removed after the modal got opened doesn't update anything
+            Ok( false ) => unknown_item_embed(),
+            Ok( true ) => {
+                info!("{invoking_user_tag} saved the item {}", item.name);
+
+                let embed = CreateEmbed::new()
+                    .title( format!("Saved {}", item.name) )
+                    .description( item_summary(&item) )
+                    .colour( EmbedColours::good() );
+                add_active_character_footer( ctx, &invoking_user_id, embed ).await
+            },
+            Err( why ) => BotError::from( why ).embed( format!("Failed to save {invoking_user_tag}'s item") )
+        }
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, message_response(embed) ).await {
+        warn!(error = %why, "Failed to send response in /item")
+    }
+}
+
+
+/// Routes /item and it's interactions to the functions above
+pub struct ItemCommand;
+#[async_trait]
+impl SlashCommand for ItemCommand {
+    fn name( &self ) -> &'static str {
+        "item"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
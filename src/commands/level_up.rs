@@ -0,0 +1,309 @@
+// Spend the attribute points granted by levelling up
+//
+// - `/level_up` responds with the same allocation message used when building a character, only
+//     starting from the character's current attributes. They can be raised with the points the
+//     character has got left, up to the configured maximum, but never lowered
+// - The character option autocompletes over your characters and defaults to your active one
+// - Without points to spend, the character's level and every level up so far are shown instead
+// - The rules of the allocation are worked out from the database on every press, so a stale
+//     message can't spend points twice or lower attributes raised from another message
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, ComponentInteraction,
+    ComponentInteractionDataKind, CreateAutocompleteResponse, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage
+};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    attributes::{AllocationAction, AllocationRules, AllocationState, Attribute},
+    commands::registry::SlashCommand,
+    config,
+    db::{self, Attributes, Progression},
+    error::BotError,
+    event_handler::DiscordBot,
+    progression::progress_text,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_active_character, get_user_character_name,
+        integer_option, search_user_characters, CharacterId, EmbedColours
+    }
+};
+
+
+/// Build the level_up command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    CreateCommand::new("level_up")
+        .description("Spend the attribute points your character got from levelling up")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "character", "The character to level up. Defaults to your active one" )
+                .required(false)
+                .set_autocomplete(true)
+        )
+}
+
+
+/// Work out the rules of a character's level up allocation from where they are now. Returns their
+/// progression alongside, as spending the points needs to know how many they had
+pub async fn level_up_rules( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<(AllocationRules, Progression), BotError> {
+    let current = match db::attributes::get( database_connection, character_id ).await? {
+        Some( attributes ) => attributes.spread(),
+        None => return Err( BotError::NoAttributes )
+    };
+    let progression = db::progression::get( database_connection, character_id ).await?;
+
+    let rules = AllocationRules::level_up( current, progression.unspent_points, config::get().max_attribute );
+    Ok(( rules, progression ))
+}
+
+/// Shows a character's level and every level they gained, for when there are no points to spend
+fn history_embed( character_name: &str, progression: &Progression, level_ups: &[db::LevelUp] ) -> CreateEmbed {
+    let history = level_ups
+        .iter()
+        .map( |level_up| format!("Level {} on <t:{}:d>", level_up.level, level_up.levelled_at) )
+        .collect::<Vec<String>>();
+
+    let description = match history.is_empty() {
+        true  => "They haven't levelled up yet".to_owned(),
+        false => history.join("\n")
+    };
+
+    CreateEmbed::new()
+        .title( format!("{character_name} has no attribute points to spend") )
+        .description( description )
+        .field( "Level", progress_text(progression), false )
+        .colour( EmbedColours::info() )
+}
+
+fn ephemeral_error( embed: CreateEmbed ) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed ).ephemeral(true)
+    )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    let character_option = integer_option( &interaction_data.data.options(), "character" );
+
+    let response = 'response: {
+
+        // --== FIND CHARACTER ==-- //
+
+            let character_id = match character_option {
+                Some( id ) => id,
+                None => match get_active_character( ctx, &invoking_user_id ).await {
+                    Some(( id, _ )) => id,
+                    None => break 'response ephemeral_error( BotError::NoActiveCharacter
+                        .embed( format!("{invoking_user_tag} has no active character in /level_up") ) )
+                }
+            };
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'response ephemeral_error( BotError::NotOwner
+                    .embed( format!("{invoking_user_tag} picked someone else's character in /level_up") ) )
+            };
+        // ==--
+
+        let ( rules, progression ) = match level_up_rules( database_connection, character_id ).await {
+            Ok( rules ) => rules,
+            Err( why ) => break 'response ephemeral_error( why.embed(format!("Failed to level up {character_name}")) )
+        };
+
+        if progression.unspent_points <= 0 {
+            let embed = match db::progression::get_level_ups( database_connection, character_id ).await {
+                Ok( level_ups ) => history_embed( &character_name, &progression, &level_ups ),
+                Err( why ) => BotError::from( why ).embed( format!("Failed to get {character_name}'s level ups") )
+            };
+
+            break 'response CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+            )
+        }
+
+        let allocation_state = AllocationState::new( character_id, &rules );
+        let embed = allocation_state.embed( &character_name, &rules )
+            .title( format!("Level up {character_name}") );
+
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+                .components( allocation_state.components("level_up", &rules) )
+        )
+    };
+
+    Some( response )
+}
+
+
+// Every press of a button, or change of the select menu, in the level up message lands here. Just
+// like in /build_character the allocation is carried in the custom id, but the rules are worked
+// out fresh every time
+pub async fn handle_component( interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    let response = 'response: {
+
+        // We create these ids ourselves, so a mangled one shouldn't occur
+        let ( action, mut allocation_state ) = match AllocationState::parse_custom_id( &interaction_data.data.custom_id ) {
+            Some( parsed ) => parsed,
+            None => {
+                warn!("Recived mangled allocation id: {}", interaction_data.data.custom_id);
+                return
+            }
+        };
+
+        // The message is visible to everyone in the channel, only the owner may spend the points
+        let character_name = match get_user_character_name( ctx, &invoking_user_id, allocation_state.character_id ).await {
+            Some( name ) => name,
+            None => break 'response ephemeral_error( BotError::NotOwner
+                .embed( format!("{invoking_user_tag} tried to level up someone else's character") ) )
+        };
+
+        let ( rules, progression ) = match level_up_rules( database_connection, allocation_state.character_id ).await {
+            Ok( rules ) => rules,
+            Err( why ) => break 'response ephemeral_error( why.embed(format!("Failed to level up {character_name}")) )
+        };
+
+        // --== APPLY ACTION ==-- //
+
+            match action {
+                AllocationAction::Select => {
+                    // The value of a select menu is the index of the chosen attribute
+                    if let ComponentInteractionDataKind::StringSelect { values } = &interaction_data.data.kind {
+                        let chosen_attribute = values
+                            .first()
+                            .and_then( |value| value.parse().ok() )
+                            .and_then( Attribute::from_index );
+
+                        if let Some( attribute ) = chosen_attribute {
+                            allocation_state.selected = attribute;
+                        }
+                    }
+                },
+                AllocationAction::Decrease => allocation_state.adjust( &rules, -1 ),
+                AllocationAction::Increase => allocation_state.adjust( &rules,  1 ),
+                AllocationAction::Confirm  => {
+
+                    // Points spent from another message since this one was sent leave this
+                    // spread below the new floor, or spending more than is left
+                    if !rules.is_valid( &allocation_state.spread ) {
+                        break 'response ephemeral_error(
+                            error_embed( "These points were already spent", "Use /level_up again to see what's left" )
+                        )
+                    }
+
+                    let points_spent = rules.budget - rules.remaining_points( &allocation_state.spread );
+                    if points_spent == 0 {
+                        break 'response ephemeral_error(
+                            error_embed( "Nothing to save", "Raise an attribute before confirming" )
+                        )
+                    }
+                    let character_attributes = Attributes::from_spread( allocation_state.character_id, &allocation_state.spread );
+
+                    let query_result = db::progression::spend_points(
+                        database_connection, &character_attributes, progression.unspent_points, points_spent
+                    ).await;
+
+                    let embed = match query_result {
+                        Ok( true ) => {
+                            info!("{invoking_user_tag} spent {points_spent} attribute points on {character_name}");
+
+                            allocation_state.embed( &character_name, &rules )
+                                .title( format!("{character_name} spent {points_spent} attribute points") )
+                                .description( match progression.unspent_points - points_spent {
+                                    0 => "Their attributes have been saved".to_owned(),
+                                    left => format!("Their attributes have been saved, {left} points are left for later")
+                                })
+                                .colour( EmbedColours::good() )
+                        },
+                        Ok( false ) => break 'response ephemeral_error(
+                            error_embed( "These points were already spent", "Use /level_up again to see what's left" )
+                        ),
+                        Err( why ) => break 'response ephemeral_error(
+                            BotError::from( why ).embed( format!("Failed to save {invoking_user_tag}'s level up") )
+                        )
+                    };
+
+                    // Once confirmed, the components go so the points can't be spent from here again
+                    break 'response CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+                            .components( vec![] )
+                    )
+                }
+            }
+        // ==--
+
+        let embed = allocation_state.embed( &character_name, &rules )
+            .title( format!("Level up {character_name}") );
+
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+                .components( allocation_state.components("level_up", &rules) )
+        )
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to respond to level up interaction")
+    }
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = match interaction_data.data.autocomplete() {
+        Some( focused_option ) if focused_option.name == "character" => {
+            search_user_characters( ctx, &invoking_user_id, focused_option.value )
+                .await
+                .into_iter()
+                .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                .collect()
+        },
+        _ => vec![]
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /level_up")
+    }
+}
+
+
+/// Routes /level_up and it's interactions to the functions above
+pub struct LevelUpCommand;
+#[async_trait]
+impl SlashCommand for LevelUpCommand {
+    fn name( &self ) -> &'static str {
+        "level_up"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+
+    async fn component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_component( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
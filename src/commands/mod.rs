@@ -4,9 +4,65 @@ pub mod deregister;
 
 //
 pub mod build_character;
+pub mod edit_character;
 pub mod delete_character;
+pub mod switch_character;
+pub mod character;
+pub mod ability;
+
+//
+pub mod roll;
+pub mod check;
+pub mod rolls;
+pub mod encounter;
+pub mod vitals;
+pub mod spell;
+pub mod spellbook;
+pub mod cast;
+pub mod award_xp;
+pub mod level_up;
+pub mod item;
+pub mod inventory;
 
 // test stuff
 pub mod dump_cache;
 pub mod tmp;
 
+
+pub mod registry;
+
+use registry::{CommandRegistry, SlashCommand};
+
+
+/// Every command the bot has. Adding a command to this list is all it takes for it to be
+/// registered with Discord, and for it's interactions to be routed to it
+pub fn registry() -> CommandRegistry {
+    let commands: Vec<Box<dyn SlashCommand>> = vec![
+        Box::new( register::RegisterCommand ),
+        Box::new( deregister::DeregisterCommand ),
+        Box::new( build_character::BuildCharacterCommand ),
+        Box::new( edit_character::EditCharacterCommand ),
+        Box::new( delete_character::DeleteCharacterCommand ),
+        Box::new( switch_character::SwitchCharacterCommand ),
+        Box::new( character::CharacterCommand ),
+        Box::new( ability::AbilityCommand ),
+        Box::new( roll::RollCommand ),
+        Box::new( check::CheckCommand ),
+        Box::new( rolls::RollsCommand ),
+        Box::new( encounter::EncounterCommand ),
+        Box::new( vitals::VitalsCommand(vitals::VitalsChange::Damage) ),
+        Box::new( vitals::VitalsCommand(vitals::VitalsChange::Heal) ),
+        Box::new( vitals::VitalsCommand(vitals::VitalsChange::SpendMana) ),
+        Box::new( spell::SpellCommand ),
+        Box::new( spellbook::SpellbookCommand ),
+        Box::new( cast::CastCommand ),
+        Box::new( award_xp::AwardXpCommand ),
+        Box::new( level_up::LevelUpCommand ),
+        Box::new( item::ItemCommand ),
+        Box::new( inventory::InventoryCommand ),
+        Box::new( tmp::TmpCommand ),
+        Box::new( dump_cache::DumpCacheCommand ),
+    ];
+
+    CommandRegistry::new( commands )
+}
@@ -0,0 +1,193 @@
+// Every command, and the interactions that belong to it, in one place
+//
+// - A command implements `SlashCommand`, and is added to the list in `commands::registry`. That
+//     is all that's needed for it to be registered with Discord and have it's interactions routed
+//     to it
+// - Modals and components are routed by the start of their custom id, which must be the name of
+//     the command that created them followed by a ':'
+// - Anything that can't be routed is reported the same way, no matter what kind of interaction
+//     it was
+
+use serenity::{
+    all::{
+        CommandInteraction, ComponentInteraction, CreateCommand,
+        CreateInteractionResponse, CreateInteractionResponseMessage, ModalInteraction
+    },
+    async_trait
+};
+
+use tracing::{error, info_span, warn, Instrument, Span};
+
+use crate::{
+    config,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::error_embed
+};
+
+
+/// A slash command, along with the handlers for every interaction it creates
+///
+/// The optional handlers return `false` when the command has no handler of that kind, which gets
+/// reported as an unknown route
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+
+    /// Name of the command, as registered with Discord
+    fn name( &self ) -> &'static str;
+
+    /// Build the command's signature to be sent to Discord's Gateway
+    fn build( &self ) -> CreateCommand;
+
+    /// Run the command. A returned response gets sent by the registry, commands that respond by
+    /// themselves return `None`
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse>;
+
+    async fn autocomplete( &self, _interaction_data: &CommandInteraction, _ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        false
+    }
+
+    async fn modal( &self, _interaction_data: &ModalInteraction, _ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        false
+    }
+
+    async fn component( &self, _interaction_data: &ComponentInteraction, _ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        false
+    }
+}
+
+
+pub struct CommandRegistry {
+    commands: Vec<Box<dyn SlashCommand>>
+}
+impl CommandRegistry {
+
+    pub fn new( commands: Vec<Box<dyn SlashCommand>> ) -> CommandRegistry {
+        CommandRegistry { commands }
+    }
+
+    /// Names of every command, enabled or not
+    pub fn names( &self ) -> Vec<&'static str> {
+        self.commands.iter().map( |command| command.name() ).collect()
+    }
+
+    /// Signatures of the commands that are enabled in the config
+    pub fn build_enabled( &self ) -> Vec<CreateCommand> {
+        self.commands
+            .iter()
+            .filter( |command| config::get().is_command_enabled(command.name()) )
+            .map( |command| command.build() )
+            .collect()
+    }
+
+    fn get( &self, command_name: &str ) -> Option<&dyn SlashCommand> {
+        self.commands
+            .iter()
+            .find( |command| command.name() == command_name )
+            .map( |command| command.as_ref() )
+    }
+
+    /// Find the enabled command an interaction's route leads to. Modals and components are routed
+    /// by the part of their custom id before the first ':'
+    fn route( &self, route: &str ) -> Option<&dyn SlashCommand> {
+        let command_name = route.split(':').next().unwrap_or_default();
+
+        self.get( command_name )
+            .filter( |command| config::get().is_command_enabled(command.name()) )
+    }
+
+
+    pub async fn dispatch_command( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+        let command_name = interaction_data.data.name.as_str();
+        let span = interaction_span( "command", command_name, interaction_data.user.id.get(), interaction_data.guild_id.map(|id| id.get()) );
+
+        async {
+            let response = match self.get( command_name ) {
+                // Discord can take a while to forget about commands that have been disabled, so they
+                // might still get invoked
+                Some(_) if !config::get().is_command_enabled( command_name ) => {
+                    let embed = error_embed( format!("/{command_name} is disabled"), "It has been turned off by the Bot Administrator" );
+
+                    Some( CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new().embed(embed).ephemeral(true)
+                    ))
+                },
+                Some( command ) => command.run( interaction_data, ctx, discord_bot ).await,
+                None => {
+                    report_unknown_route( "command", command_name );
+                    None
+                }
+            };
+
+            if let Some( response ) = response {
+                // Although not being a fatal error, not responding to an interaction is still
+                // unwanted as the user may be missing out on important information
+                if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+                    error!(error = %why, "Failed to send response to command interaction")
+                }
+            }
+        }.instrument( span ).await
+    }
+
+    pub async fn dispatch_autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+        let route = interaction_data.data.name.as_str();
+        let span = interaction_span( "autocomplete", route, interaction_data.user.id.get(), interaction_data.guild_id.map(|id| id.get()) );
+
+        async {
+            let handled = match self.route( route ) {
+                Some( command ) => command.autocomplete( interaction_data, ctx, discord_bot ).await,
+                None => false
+            };
+
+            if !handled {
+                report_unknown_route( "autocomplete", route );
+            }
+        }.instrument( span ).await
+    }
+
+    pub async fn dispatch_modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+        let route = interaction_data.data.custom_id.as_str();
+        let span = interaction_span( "modal", route, interaction_data.user.id.get(), interaction_data.guild_id.map(|id| id.get()) );
+
+        async {
+            let handled = match self.route( route ) {
+                Some( command ) => command.modal( interaction_data, ctx, discord_bot ).await,
+                None => false
+            };
+
+            if !handled {
+                report_unknown_route( "modal", route );
+            }
+        }.instrument( span ).await
+    }
+
+    pub async fn dispatch_component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+        let route = interaction_data.data.custom_id.as_str();
+        let span = interaction_span( "component", route, interaction_data.user.id.get(), interaction_data.guild_id.map(|id| id.get()) );
+
+        async {
+            let handled = match self.route( route ) {
+                Some( command ) => command.component( interaction_data, ctx, discord_bot ).await,
+                None => false
+            };
+
+            if !handled {
+                report_unknown_route( "component", route );
+            }
+        }.instrument( span ).await
+    }
+}
+
+
+/// Span every event logged while handling an interaction is recorded under. For modals and
+/// components the command is the route prefix of their custom id
+fn interaction_span( kind: &str, route: &str, user_id: u64, guild_id: Option<u64> ) -> Span {
+    let command_name = route.split(':').next().unwrap_or_default();
+
+    info_span!( "interaction", kind, command = command_name, user_id, guild_id )
+}
+
+/// Log an interaction that no command could be found to handle
+pub fn report_unknown_route( kind: &str, route: &str ) {
+    warn!(kind, route, "Recived unknown interaction")
+}
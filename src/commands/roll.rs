@@ -0,0 +1,150 @@
+// Roll dice
+//
+// - `/roll dice:<expression>` rolls an expression written in dice notation, see `dice.rs` for
+//     what's understood
+// - The response shows every die that was rolled, grouped by the term they belong to, along with
+//     the total. Dropped dice are struck through, and dice that exploded are marked with a `!`
+// - Every roll is recorded in the roll history, under the user's active character if they have one
+
+use serenity::all::{
+    async_trait, CommandInteraction, CommandOptionType, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage, ResolvedValue
+};
+
+use tracing::info;
+
+use crate::{
+    commands::{registry::SlashCommand, rolls::log_roll},
+    dice::{self, DiceGroup, Expression, Roll, MAX_EXPRESSION_LENGTH},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{add_active_character_footer, get_active_character, EmbedColours}
+};
+
+
+/// What plain rolls are called in the roll history
+const ROLL_LABEL: &str = "Roll";
+
+/// Discord doesn't allow more fields than this in one embed. One is kept for the overflow note
+const MAX_GROUP_FIELDS: usize = 24;
+/// Discord doesn't allow a field's value to be longer than this
+const MAX_FIELD_LENGTH: usize = 1024;
+
+
+/// Build the roll command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    CreateCommand::new("roll")
+        .description("Roll some dice")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::String, "dice", "What to roll, like 2d20kh1+3, 4d6dl1 or d%" )
+                .required(true)
+                .max_length( MAX_EXPRESSION_LENGTH as u16 )
+        )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let expression = match interaction_data.data.options().first()?.value {
+        ResolvedValue::String( expression ) => expression,
+        _ => return None
+    };
+
+    // The thread's generator can't be held across an await, so the roll happens all in one go
+    let rolled = dice::parse( expression )
+        .and_then( |parsed| Ok(( parsed.roll( &mut rand::thread_rng() )?, parsed )) );
+
+    let embed = match rolled {
+        Ok(( roll, parsed )) => {
+            info!("{invoking_user_tag} rolled {parsed} for {}", roll.total);
+
+            // Rolls are made as the active character, if there is one
+            let active_character = get_active_character( ctx, &invoking_user_id ).await;
+            log_roll(
+                &discord_bot.database_connection, interaction_data, invoking_user_id,
+                active_character.as_ref().map( |(character_id, character_name)| (*character_id, character_name.as_str()) ),
+                ROLL_LABEL, &parsed, &roll
+            ).await;
+
+            add_active_character_footer( ctx, &invoking_user_id, roll_embed(&parsed, &roll) ).await
+        },
+        Err( why ) => BotError::from( why ).embed( format!("Failed to roll {expression:?} for {invoking_user_tag}") )
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+/// Show a roll, with a field for each group of dice
+pub fn roll_embed( expression: &Expression, roll: &Roll ) -> CreateEmbed {
+    let mut fields: Vec<(String, String, bool)> = roll.groups
+        .iter()
+        .take( MAX_GROUP_FIELDS )
+        .map( |group| ( group.term.to_string(), format_dice(group), true ) )
+        .collect();
+
+    if roll.groups.len() > MAX_GROUP_FIELDS {
+        fields.push((
+            "And more".to_owned(),
+            format!("{} more group(s) of dice", roll.groups.len() - MAX_GROUP_FIELDS),
+            false
+        ));
+    }
+
+    CreateEmbed::new()
+        .title( format!("Rolled {expression}") )
+        .description( format!("Total: **{}**", roll.total) )
+        .fields( fields )
+        .colour( EmbedColours::info() )
+}
+
+/// List the dice of a group, like `~~3~~, 17`. Stops early rather than breaking the field limit
+pub fn format_dice( group: &DiceGroup ) -> String {
+    let mut formatted = String::new();
+
+    for ( index, die ) in group.dice.iter().enumerate() {
+        let die_text = match ( die.kept, die.exploded ) {
+            ( true, false )  => format!("{}", die.value),
+            ( true, true )   => format!("{}!", die.value),
+            ( false, false ) => format!("~~{}~~", die.value),
+            ( false, true )  => format!("~~{}!~~", die.value),
+        };
+
+        let separator = if index == 0 { "" } else { ", " };
+
+        // Leave room for the note saying how many dice got left out
+        if formatted.len() + separator.len() + die_text.len() > MAX_FIELD_LENGTH - 20 {
+            formatted.push_str( &format!(" and {} more", group.dice.len() - index) );
+            break
+        }
+
+        formatted.push_str( separator );
+        formatted.push_str( &die_text );
+    }
+
+    formatted
+}
+
+
+/// Routes /roll to the functions above
+pub struct RollCommand;
+#[async_trait]
+impl SlashCommand for RollCommand {
+    fn name( &self ) -> &'static str {
+        "roll"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+}
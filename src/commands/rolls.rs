@@ -0,0 +1,382 @@
+// Look back at past rolls
+//
+// - Every /roll and /check is recorded in the Rolls table, along with who rolled, as which
+//     character, and where. Rolls are kept per guild, so each server only sees it's own
+// - `/rolls history` lists a user's rolls, newest first. The character option narrows it down to
+//     one of their characters, and autocompletes over them
+// - `/rolls stats` sums up a user's rolls, one page per character: how many, the averages, how
+//     often the d20 came up on each face and how many natural 20s and 1s
+// - Both default to the invoking user and can be flipped through with buttons. Their custom ids
+//     look like: `rolls:history:<user_id>:<character_id|all>:<page>` and
+//     `rolls:stats:<user_id>:<page>`. The page number goes in the description, as the footer
+//     names the active character of whoever is looking
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, ButtonStyle, CommandInteraction, CommandOptionType,
+    ComponentInteraction, CreateActionRow, CreateAutocompleteResponse, CreateButton, CreateCommand,
+    CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, ResolvedOption, ResolvedValue
+};
+use sqlx::SqlitePool;
+use tracing::warn;
+
+use crate::{
+    commands::registry::SlashCommand,
+    db::{self, RollRecord},
+    dice::{Expression, Roll},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, integer_option, search_user_characters, unix_now, user_option,
+        CharacterId, EmbedColours
+    }
+};
+
+
+/// How many rolls are shown on each page of the history
+pub const HISTORY_PAGE_SIZE: usize = 10;
+/// Longest bar of the d20 distribution
+const DISTRIBUTION_BAR_LENGTH: i64 = 15;
+
+
+/// Build the rolls command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    let user_option = || CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "Look at this user's rolls instead of your own"
+        )
+        .required(false);
+
+    CreateCommand::new("rolls")
+        .description("Look back at past rolls")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "history", "List past rolls, newest first" )
+                .add_sub_option( user_option() )
+                .add_sub_option(
+                    CreateCommandOption::new( CommandOptionType::Integer, "character", "Only list this character's rolls" )
+                        .required(false)
+                        .set_autocomplete(true)
+                )
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::SubCommand, "stats", "Sum up past rolls for each character" )
+                .add_sub_option( user_option() )
+        )
+}
+
+
+/// Record a roll in the history. A failure is only logged, the roll itself already happened
+pub async fn log_roll(
+    database_connection: &SqlitePool,
+    interaction_data: &CommandInteraction,
+    user_id: u64,
+    character: Option<(CharacterId, &str)>,
+    label: &str,
+    expression: &Expression,
+    roll: &Roll
+    ) {
+
+    let record = RollRecord {
+        roll_id:        0,
+        discord_id:     user_id as i64,
+        character_id:   character.map( |(character_id, _)| character_id ),
+        character_name: character.map( |(_, character_name)| character_name.to_owned() ),
+        guild_id:       interaction_data.guild_id.map( |guild_id| guild_id.get() as i64 ),
+        channel_id:     interaction_data.channel_id.get() as i64,
+        label:          label.to_owned(),
+        expression:     expression.to_string(),
+        total:          roll.total,
+        natural_d20:    roll.natural_d20().map( i64::from ),
+        rolled_at:      unix_now()
+    };
+
+    if let Err( why ) = db::rolls::add( database_connection, &record ).await {
+        warn!(error = %why, "Failed to record roll")
+    }
+}
+
+
+/// Buttons to flip between pages, or none if everything fits onto one
+fn page_buttons( custom_id_prefix: &str, page: usize, page_count: usize ) -> Vec<CreateActionRow> {
+    if page_count <= 1 {
+        return vec![]
+    }
+
+    let buttons = vec![
+        CreateButton::new( format!("{custom_id_prefix}:{}", page.saturating_sub(1)) )
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled( page == 0 ),
+        CreateButton::new( format!("{custom_id_prefix}:{}", page + 1) )
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled( page + 1 >= page_count ),
+    ];
+
+    vec![ CreateActionRow::Buttons(buttons) ]
+}
+
+
+/// Build a page of a user's roll history, along with the buttons to flip through it. Pages past
+/// the last one show the last page
+pub async fn history_message(
+    database_connection: &SqlitePool,
+    user_id: u64,
+    guild_id: Option<u64>,
+    character_id: Option<CharacterId>,
+    page: usize
+    ) -> Result<(CreateEmbed, Vec<CreateActionRow>), BotError> {
+
+    let roll_count = db::rolls::count( database_connection, user_id, guild_id, character_id ).await?;
+    if roll_count == 0 {
+        let embed = CreateEmbed::new()
+            .title("No rolls yet")
+            .description( format!("<@{user_id}> hasn't rolled anything here yet") )
+            .colour( EmbedColours::info() );
+
+        return Ok(( embed, vec![] ))
+    }
+
+    let page_count = roll_count.div_ceil( HISTORY_PAGE_SIZE );
+    let page = page.min( page_count - 1 );
+
+    let page_rolls = db::rolls::get_page( database_connection, user_id, guild_id, character_id, page, HISTORY_PAGE_SIZE ).await?;
+
+    let lines: Vec<String> = page_rolls
+        .iter()
+        .map( |roll| format!(
+            "<t:{}:R> **{}** {}: `{}` = **{}** in <#{}>",
+            roll.rolled_at,
+            roll.character_name.as_deref().unwrap_or("No character"),
+            roll.label,
+            roll.expression,
+            roll.total,
+            roll.channel_id
+        ))
+        .collect();
+
+    let embed = CreateEmbed::new()
+        .title("Roll history")
+        .description( format!("Rolls by <@{user_id}>, page {}/{page_count}\n\n{}", page + 1, lines.join("\n")) )
+        .colour( EmbedColours::info() );
+
+    let character_filter = match character_id {
+        Some( character_id ) => character_id.to_string(),
+        None => "all".to_owned()
+    };
+
+    Ok(( embed, page_buttons(&format!("rolls:history:{user_id}:{character_filter}"), page, page_count) ))
+}
+
+
+/// Build a page of a user's roll statistics, one page per character they rolled as, along with
+/// the buttons to flip through them. Pages past the last one show the last page
+pub async fn stats_message(
+    database_connection: &SqlitePool,
+    user_id: u64,
+    guild_id: Option<u64>,
+    page: usize
+    ) -> Result<(CreateEmbed, Vec<CreateActionRow>), BotError> {
+
+    let character_stats = db::rolls::stats( database_connection, user_id, guild_id ).await?;
+    if character_stats.is_empty() {
+        let embed = CreateEmbed::new()
+            .title("No rolls yet")
+            .description( format!("<@{user_id}> hasn't rolled anything here yet") )
+            .colour( EmbedColours::info() );
+
+        return Ok(( embed, vec![] ))
+    }
+
+    let page = page.min( character_stats.len() - 1 );
+    let stats = &character_stats[page];
+
+    let mut embed = CreateEmbed::new()
+        .title( match &stats.character_name {
+            Some( character_name ) => format!("Roll statistics for {character_name}"),
+            None => "Roll statistics without a character".to_owned()
+        })
+        .description( format!("Rolls by <@{user_id}>, page {}/{}", page + 1, character_stats.len()) )
+        .field( "Rolls", stats.roll_count.to_string(), true )
+        .field( "Average total", format!("{:.1}", stats.average_total), true )
+        .colour( EmbedColours::info() );
+
+    // Only rolls with a single d20 have a natural result, plain damage rolls and such don't
+    if let Some( average_natural ) = stats.average_natural {
+        let distribution = db::rolls::natural_distribution( database_connection, user_id, guild_id, stats ).await?;
+
+        embed = embed
+            .field( "Average d20", format!("{average_natural:.1}"), true )
+            .field( "Natural 20s", stats.natural_twenties.to_string(), true )
+            .field( "Natural 1s", stats.natural_ones.to_string(), true )
+            .field( "d20 distribution", format_distribution(&distribution), false );
+    }
+
+    Ok(( embed, page_buttons(&format!("rolls:stats:{user_id}"), page, character_stats.len()) ))
+}
+
+/// Draw the d20 distribution as a bar chart, one line per face
+fn format_distribution( distribution: &[i64; 20] ) -> String {
+    let most_common = distribution.iter().copied().max().unwrap_or_default().max(1);
+
+    let lines: Vec<String> = distribution
+        .iter()
+        .enumerate()
+        .map( |(index, face_count)| {
+            // Round up, so faces that came up at all always get a bar
+            let bar_length = ( face_count * DISTRIBUTION_BAR_LENGTH + most_common - 1 ) / most_common;
+            format!("{:>2} {} {face_count}", index + 1, "█".repeat(bar_length as usize))
+        })
+        .collect();
+
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id = interaction_data.user.id.get();
+    let guild_id = interaction_data.guild_id.map( |guild_id| guild_id.get() );
+
+    let options = interaction_data.data.options();
+    let ( subcommand_name, subcommand_options ) = match options.first() {
+        Some( ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. } ) => ( *name, sub_options ),
+        _ => return None
+    };
+
+    let target_user_id = user_option( subcommand_options, "user" ).unwrap_or( invoking_user_id );
+
+    let message = match subcommand_name {
+        "history" => {
+            let character_id = integer_option( subcommand_options, "character" );
+
+            history_message( &discord_bot.database_connection, target_user_id, guild_id, character_id, 0 ).await
+        },
+        "stats" => stats_message( &discord_bot.database_connection, target_user_id, guild_id, 0 ).await,
+        _ => return None
+    };
+
+    let response_message = match message {
+        Ok(( embed, components )) => CreateInteractionResponseMessage::new()
+            .embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+            .components( components ),
+        Err( why ) => CreateInteractionResponseMessage::new()
+            .embed( why.embed(format!("Failed to load rolls of {target_user_id} in /rolls {subcommand_name}")) )
+    };
+
+    Some( CreateInteractionResponse::Message(response_message) )
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) if option.name == "character" => option,
+            _ => break 'choices vec![]
+        };
+
+        // Suggest the characters of whoever's rolls are being looked at
+        let options = interaction_data.data.options();
+        let target_user_id = match options.first() {
+            Some( ResolvedOption { value: ResolvedValue::SubCommand(sub_options), .. } ) => user_option( sub_options, "user" ),
+            _ => None
+        }.unwrap_or( invoking_user_id );
+
+        search_user_characters( ctx, &target_user_id, focused_option.value )
+            .await
+            .into_iter()
+            .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+            .collect()
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /rolls")
+    }
+}
+
+
+// Flip the page of the history or stats. Like character sheets, anyone is allowed to
+pub async fn handle_component( interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+    let guild_id = interaction_data.guild_id.map( |guild_id| guild_id.get() );
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create these ids ourselves, there's nothing we can do with a mangled one
+    let message = match id_components.as_slice() {
+        [ _, "history", user_id, character_filter, page ] => {
+            let character_id = match *character_filter {
+                "all" => None,
+                character_id => match character_id.parse() {
+                    Ok( character_id ) => Some( character_id ),
+                    Err(_) => return
+                }
+            };
+
+            match ( user_id.parse(), page.parse() ) {
+                ( Ok(user_id), Ok(page) ) => history_message( &discord_bot.database_connection, user_id, guild_id, character_id, page ).await,
+                _ => return
+            }
+        },
+        [ _, "stats", user_id, page ] => match ( user_id.parse(), page.parse() ) {
+            ( Ok(user_id), Ok(page) ) => stats_message( &discord_bot.database_connection, user_id, guild_id, page ).await,
+            _ => return
+        },
+        _ => return
+    };
+
+    let ( embed, components ) = match message {
+        Ok( message ) => message,
+        Err( why ) => {
+            warn!(error = %why, "Failed to load rolls");
+            return
+        }
+    };
+
+    let response = CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+            .embed( add_active_character_footer(ctx, &invoking_user_id, embed).await )
+            .components( components )
+    );
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        warn!(error = %why, "Failed to flip rolls page")
+    }
+}
+
+
+/// Routes /rolls and it's interactions to the functions above
+pub struct RollsCommand;
+#[async_trait]
+impl SlashCommand for RollsCommand {
+    fn name( &self ) -> &'static str {
+        "rolls"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+
+    async fn component( &self, interaction_data: &ComponentInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_component( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
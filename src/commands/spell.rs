@@ -0,0 +1,473 @@
+// Author the spell catalogue and hand spells out to characters
+//
+// - Only members who can manage the server see `/spell` by default. Server admins can hand it to
+//     their game masters' role in the server's integration settings
+// - `/spell create` and `/spell edit` dispatch a modal asking for the spell's name, school, mana
+//     cost, difficulty and effect. Edit's modal comes prefilled with the current values
+// - `/spell remove` takes a spell out of the catalogue, and every character forgets it
+// - `/spell list` shows the whole catalogue
+// - `/spell teach` and `/spell forget` change which spells one of a user's characters knows. The
+//     character autocompletes over that user's characters and defaults to their active one
+// - Every server keeps it's own catalogue, the same as items, so spells only exist in servers.
+//     Characters remember the spells they learnt wherever they go, and can cast them anywhere
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateActionRow,
+    CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateEmbed, CreateInputText,
+    CreateInteractionResponse, CreateModal, InputTextStyle, ModalInteraction, Permissions,
+    ResolvedOption, ResolvedValue
+};
+use tracing::{info, warn};
+
+use crate::{
+    commands::registry::SlashCommand,
+    db::{self, Spell},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, error_embed, get_active_character, get_user_character_name,
+        integer_option, message_response, modal_input_values, no_catalogue_embed, search_user_characters,
+        user_option, EmbedColours
+    }
+};
+
+/// Longest name a spell can have, also used as the max length of the modal's name field
+const MAX_NAME_LENGTH: u16 = 100;
+/// Longest school a spell can have
+const MAX_SCHOOL_LENGTH: u16 = 50;
+/// Longest effect a spell can have. Leaves room in an embed field's 1024 characters for the line
+/// summing up the spell's school, cost and difficulty
+const MAX_EFFECT_LENGTH: u16 = 900;
+
+
+/// Build the spell command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+
+    let spell_option = || CreateCommandOption::new( CommandOptionType::Integer, "spell", "The spell from the catalogue" )
+        .required(true)
+        .set_autocomplete(true);
+
+    let user_option = || CreateCommandOption::new( CommandOptionType::User, "user", "The player whose character it is" )
+        .required(true);
+
+    let character_option = || CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "character",
+            "One of the player's characters. Defaults to their active one"
+        )
+        .required(false)
+        .set_autocomplete(true);
+
+    let subcommands = vec![
+        CreateCommandOption::new( CommandOptionType::SubCommand, "create", "Add a new spell to the catalogue" ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "edit", "Change a spell in the catalogue" )
+            .add_sub_option( spell_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "remove", "Take a spell out of the catalogue" )
+            .add_sub_option( spell_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "list", "List every spell in the catalogue" ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "teach", "Teach a character a spell" )
+            .add_sub_option( spell_option() )
+            .add_sub_option( user_option() )
+            .add_sub_option( character_option() ),
+        CreateCommandOption::new( CommandOptionType::SubCommand, "forget", "Make a character forget a spell" )
+            .add_sub_option( spell_option() )
+            .add_sub_option( user_option() )
+            .add_sub_option( character_option() ),
+    ];
+
+    CreateCommand::new("spell")
+        .description("Manage the spell catalogue and who knows which spells")
+        .default_member_permissions( Permissions::MANAGE_GUILD )
+        .set_options(subcommands)
+}
+
+
+/// Sums up a spell for embed fields, like `Evocation · 5 mana · DC 12` followed by it's effect
+pub fn spell_summary( spell: &Spell ) -> String {
+    format!("{} · {} mana · DC {}\n{}", spell.school, spell.mana_cost, spell.difficulty, spell.effect)
+}
+
+/// Suggest the spells whose name contains the query
+pub fn spell_choices( spells: Vec<Spell>, query: &str ) -> Vec<AutocompleteChoice> {
+    let query = query.to_lowercase();
+
+    spells
+        .into_iter()
+        .filter( |spell| spell.name.to_lowercase().contains(&query) )
+        .take(25)
+        .map( |spell| AutocompleteChoice::new(spell.name, spell.spell_id) )
+        .collect()
+}
+
+/// Build a spell out of what was typed into the modal, in the modal's order. Returns `None`
+/// unless the mana cost is at least 0 and the difficulty at least 1
+pub fn spell_from_inputs( spell_id: i64, guild_id: u64, inputs: &[String] ) -> Option<Spell> {
+    let [name, school, mana_cost, difficulty, effect] = inputs else {
+        return None
+    };
+
+    let mana_cost: i64 = mana_cost.trim().parse().ok()?;
+    let difficulty: i64 = difficulty.trim().parse().ok()?;
+    if mana_cost < 0 || difficulty < 1 {
+        return None
+    }
+
+    Some( Spell {
+        spell_id,
+        guild_id: guild_id as i64,
+        name:   name.trim().to_owned(),
+        school: school.trim().to_owned(),
+        mana_cost,
+        difficulty,
+        effect: effect.trim().to_owned()
+    })
+}
+
+
+/// Build the modal used by both `create` and `edit`. `current` holds the spell when editing
+fn spell_modal( custom_id: String, title: String, current: Option<Spell> ) -> CreateModal {
+    let mut name_input = CreateInputText::new( InputTextStyle::Short, "Spell Name", "name" )
+        .max_length( MAX_NAME_LENGTH );
+    let mut school_input = CreateInputText::new( InputTextStyle::Short, "School", "school" )
+        .max_length( MAX_SCHOOL_LENGTH );
+    let mut mana_cost_input = CreateInputText::new( InputTextStyle::Short, "Mana Cost", "mana_cost" )
+        .max_length( 6 );
+    let mut difficulty_input = CreateInputText::new( InputTextStyle::Short, "Difficulty", "difficulty" )
+        .max_length( 6 );
+    let mut effect_input = CreateInputText::new( InputTextStyle::Paragraph, "Effect", "effect" )
+        .max_length( MAX_EFFECT_LENGTH );
+
+    if let Some( spell ) = current {
+        name_input       = name_input.value( spell.name );
+        school_input     = school_input.value( spell.school );
+        mana_cost_input  = mana_cost_input.value( spell.mana_cost.to_string() );
+        difficulty_input = difficulty_input.value( spell.difficulty.to_string() );
+        effect_input     = effect_input.value( spell.effect );
+    }
+
+    CreateModal::new( custom_id, title )
+        .components(vec![
+            CreateActionRow::InputText( name_input ),
+            CreateActionRow::InputText( school_input ),
+            CreateActionRow::InputText( mana_cost_input ),
+            CreateActionRow::InputText( difficulty_input ),
+            CreateActionRow::InputText( effect_input )
+        ])
+}
+
+fn unknown_spell_embed() -> CreateEmbed {
+    error_embed( "There's no such spell", "Use /spell list to see the catalogue" )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    // Our command only consists of subcommands, so the first option will always be one
+    let options = interaction_data.data.options();
+    let ( subcommand_name, subcommand_options ) = match options.first() {
+        Some( ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. } ) => ( *name, sub_options ),
+        _ => return None
+    };
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        let guild_id = match interaction_data.guild_id {
+            Some( guild_id ) => guild_id.get(),
+            None => break 'return_embed no_catalogue_embed( "Spells" )
+        };
+
+        match subcommand_name {
+
+            "create" => return Some( CreateInteractionResponse::Modal(spell_modal(
+                "spell:create".to_owned(),
+                "New spell".to_owned(),
+                None
+            ))),
+
+            "edit" => {
+                let spell_id = integer_option( subcommand_options, "spell" )?;
+
+                match db::spells::get( database_connection, guild_id, spell_id ).await {
+                    Ok( Some(spell) ) => return Some( CreateInteractionResponse::Modal(spell_modal(
+                        format!("spell:edit:{spell_id}"),
+                        format!("Editing {}", spell.name),
+                        Some( spell )
+                    ))),
+                    Ok( None ) => unknown_spell_embed(),
+                    Err( why ) => BotError::from( why ).embed( format!("Failed to fetch a spell for {invoking_user_tag}") )
+                }
+            },
+
+            "remove" => {
+                let spell_id = integer_option( subcommand_options, "spell" )?;
+
+                let spell = match db::spells::get( database_connection, guild_id, spell_id ).await {
+                    Ok( Some(spell) ) => spell,
+                    Ok( None ) => break 'return_embed unknown_spell_embed(),
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to fetch a spell for {invoking_user_tag}") )
+                };
+
+                match db::spells::remove( database_connection, guild_id, spell_id ).await {
+                    Ok( false ) => unknown_spell_embed(),
+                    Ok( true ) => {
+                        info!("{invoking_user_tag} removed the spell {}", spell.name);
+
+                        let embed = CreateEmbed::new()
+                            .title( format!("Removed {} from the catalogue", spell.name) )
+                            .description("Every character who knew it has forgotten it")
+                            .colour( EmbedColours::good() );
+                        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                    },
+                    Err( why ) => BotError::from( why ).embed( format!("Failed to remove a spell for {invoking_user_tag}") )
+                }
+            },
+
+            "list" => match db::spells::get_catalogue( database_connection, guild_id ).await {
+                Ok( catalogue ) if catalogue.is_empty() => {
+                    let embed = CreateEmbed::new()
+                        .title("The catalogue is empty")
+                        .description("Add a spell with /spell create")
+                        .colour( EmbedColours::info() );
+                    add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                },
+                Ok( catalogue ) => {
+                    // An embed can only hold 25 fields, anything past that is left out
+                    let spell_count = catalogue.len();
+                    let fields = catalogue
+                        .iter()
+                        .take(25)
+                        .map( |spell| ( spell.name.clone(), spell_summary(spell), false ) );
+
+                    let embed = CreateEmbed::new()
+                        .title("Spell catalogue")
+                        .fields( fields )
+                        .colour( EmbedColours::info() );
+
+                    let embed = match spell_count > 25 {
+                        true  => embed.description( format!("Showing 25 of {spell_count} spells") ),
+                        false => embed
+                    };
+                    add_active_character_footer( ctx, &invoking_user_id, embed ).await
+                },
+                Err( why ) => BotError::from( why ).embed( format!("Failed to list the spells for {invoking_user_tag}") )
+            },
+
+            "teach" | "forget" => {
+                let spell_id = integer_option( subcommand_options, "spell" )?;
+                let target_user_id = user_option( subcommand_options, "user" )?;
+
+                // --== FIND CHARACTER ==-- //
+
+                    let character_id = match integer_option( subcommand_options, "character" ) {
+                        Some( id ) => id,
+                        None => match get_active_character( ctx, &target_user_id ).await {
+                            Some(( id, _ )) => id,
+                            None => break 'return_embed BotError::NoActiveCharacter
+                                .embed( format!("{invoking_user_tag} picked no character, and <@{target_user_id}> has no active one in /spell") )
+                        }
+                    };
+                    let character_name = match get_user_character_name( ctx, &target_user_id, character_id ).await {
+                        Some( name ) => name,
+                        None => break 'return_embed BotError::NotOwner
+                            .embed( format!("{invoking_user_tag} picked a character <@{target_user_id}> doesn't own in /spell") )
+                    };
+                // ==--
+
+                let spell = match db::spells::get( database_connection, guild_id, spell_id ).await {
+                    Ok( Some(spell) ) => spell,
+                    Ok( None ) => break 'return_embed unknown_spell_embed(),
+                    Err( why ) => break 'return_embed BotError::from( why ).embed( format!("Failed to fetch a spell for {invoking_user_tag}") )
+                };
+
+                let teaching = subcommand_name == "teach";
+                let query_result = match teaching {
+                    true  => db::spells::learn( database_connection, character_id, spell_id ).await,
+                    false => db::spells::forget( database_connection, character_id, spell_id ).await
+                };
+
+                let embed = match ( teaching, query_result ) {
+                    ( true, Ok(true) ) => {
+                        info!("{invoking_user_tag} taught {character_name} {}", spell.name);
+
+                        CreateEmbed::new()
+                            .title( format!("{character_name} learnt {}", spell.name) )
+                            .description( spell_summary(&spell) )
+                            .colour( EmbedColours::good() )
+                    },
+                    ( true, Ok(false) ) => CreateEmbed::new()
+                        .title( format!("{character_name} already knows {}", spell.name) )
+                        .colour( EmbedColours::info() ),
+                    ( false, Ok(true) ) => {
+                        info!("{invoking_user_tag} made {character_name} forget {}", spell.name);
+
+                        CreateEmbed::new()
+                            .title( format!("{character_name} forgot {}", spell.name) )
+                            .colour( EmbedColours::good() )
+                    },
+                    ( false, Ok(false) ) => CreateEmbed::new()
+                        .title( format!("{character_name} doesn't know {}", spell.name) )
+                        .colour( EmbedColours::info() ),
+                    ( _, Err(why) ) => break 'return_embed BotError::from( why )
+                        .embed( format!("Failed to change {character_name}'s spells for {invoking_user_tag}") )
+                };
+                add_active_character_footer( ctx, &invoking_user_id, embed ).await
+            },
+
+            _ => return None
+        }
+    };
+
+    Some( message_response(embed) )
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) => option,
+            None => break 'choices vec![]
+        };
+
+        match focused_option.name {
+
+            // Just like in the other autocompletes, we won't log the error as there are far too
+            // many autocomplete interactions. An empty list will have to do
+            "spell" => match interaction_data.guild_id {
+                Some( guild_id ) => match db::spells::get_catalogue( &discord_bot.database_connection, guild_id.get() ).await {
+                    Ok( catalogue ) => spell_choices( catalogue, focused_option.value ),
+                    Err(_) => vec![]
+                },
+                None => vec![]
+            },
+
+            // Suggest the characters of whoever is being taught
+            "character" => {
+                let options = interaction_data.data.options();
+                let target_user_id = match options.first() {
+                    Some( ResolvedOption { value: ResolvedValue::SubCommand(sub_options), .. } ) => user_option( sub_options, "user" ),
+                    _ => None
+                };
+                let target_user_id = match target_user_id {
+                    Some( id ) => id,
+                    None => break 'choices vec![]
+                };
+
+                search_user_characters( ctx, &target_user_id, focused_option.value )
+                    .await
+                    .into_iter()
+                    .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                    .collect()
+            },
+
+            _ => vec![]
+        }
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /spell")
+    }
+}
+
+
+// Both the create and edit modals land here. Their custom ids look like:
+//   - spell:create
+//   - spell:edit:<spell_id>
+pub async fn handle_modal( interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let database_connection = &discord_bot.database_connection;
+
+    let id_components = interaction_data.data.custom_id
+        .split(':')
+        .collect::<Vec<&str>>();
+
+    // We create both the modal and it's id ourselves, so if anything here is missing there's
+    // nothing we can do but return
+    let spell_id: Option<i64> = match ( id_components.get(1), id_components.get(2) ) {
+        ( Some(&"create"), None ) => None,
+        ( Some(&"edit"), Some(spell_id) ) => match spell_id.parse() {
+            Ok( id ) => Some( id ),
+            Err(_) => return
+        },
+        _ => return
+    };
+    let inputs = match modal_input_values( interaction_data ) {
+        Some( inputs ) => inputs,
+        None => return
+    };
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        let guild_id = match interaction_data.guild_id {
+            Some( guild_id ) => guild_id.get(),
+            None => break 'return_embed no_catalogue_embed( "Spells" )
+        };
+
+        let spell = match spell_from_inputs( spell_id.unwrap_or_default(), guild_id, &inputs ) {
+            Some( spell ) => spell,
+            None => break 'return_embed BotError::InvalidSpell
+                .embed( format!("{invoking_user_tag} typed an invalid mana cost or difficulty") )
+        };
+
+        let query_result = match spell_id {
+            None => db::spells::add( database_connection, &spell ).await.map( |_| true ),
+            Some(_) => db::spells::update( database_connection, &spell ).await
+        };
+
+        match query_result {
+            // Editing a spell that was removed after the modal got opened doesn't update anything
+            Ok( false ) => unknown_spell_embed(),
+            Ok( true ) => {
+                info!("{invoking_user_tag} saved the spell {}", spell.name);
+
+                let embed = CreateEmbed::new()
+                    .title( format!("Saved {}", spell.name) )
+                    .description( spell_summary(&spell) )
+                    .colour( EmbedColours::good() );
+                add_active_character_footer( ctx, &invoking_user_id, embed ).await
+            },
+            Err( why ) => BotError::from( why ).embed( format!("Failed to save {invoking_user_tag}'s spell") )
+        }
+    };
+
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, message_response(embed) ).await {
+        warn!(error = %why, "Failed to send response in /spell")
+    }
+}
+
+
+/// Routes /spell and it's interactions to the functions above
+pub struct SpellCommand;
+#[async_trait]
+impl SlashCommand for SpellCommand {
+    fn name( &self ) -> &'static str {
+        "spell"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+
+    async fn modal( &self, interaction_data: &ModalInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> bool {
+        handle_modal( interaction_data, ctx, discord_bot ).await;
+        true
+    }
+}
@@ -0,0 +1,136 @@
+// List the spells a character knows
+//
+// - `/spellbook` shows every spell of the selected character, with it's school, mana cost,
+//     difficulty and effect. The character option autocompletes over your characters and
+//     defaults to your active one
+// - Spells are taught by whoever runs the game with `/spell teach`
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage
+};
+use tracing::warn;
+
+use crate::{
+    commands::{registry::SlashCommand, spell::spell_summary},
+    db,
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, get_active_character, get_user_character_name, integer_option,
+        search_user_characters, EmbedColours
+    }
+};
+
+
+/// Build the spellbook command's signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    CreateCommand::new("spellbook")
+        .description("List the spells your character knows")
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "character", "The character whose spells to list. Defaults to your active one" )
+                .required(false)
+                .set_autocomplete(true)
+        )
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let character_option = integer_option( &interaction_data.data.options(), "character" );
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        // --== FIND CHARACTER ==-- //
+
+            let character_id = match character_option {
+                Some( id ) => id,
+                None => match get_active_character( ctx, &invoking_user_id ).await {
+                    Some(( id, _ )) => id,
+                    None => break 'return_embed BotError::NoActiveCharacter
+                        .embed( format!("{invoking_user_tag} has no active character in /spellbook") )
+                }
+            };
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'return_embed BotError::NotOwner
+                    .embed( format!("{invoking_user_tag} picked someone else's character in /spellbook") )
+            };
+        // ==--
+
+        match db::spells::get_known( &discord_bot.database_connection, character_id ).await {
+            Ok( known_spells ) if known_spells.is_empty() => CreateEmbed::new()
+                .title( format!("{character_name} doesn't know any spells") )
+                .description("Spells are taught by whoever runs the game")
+                .colour( EmbedColours::info() ),
+            Ok( known_spells ) => {
+                // An embed can only hold 25 fields, anything past that is left out
+                let fields = known_spells
+                    .iter()
+                    .take(25)
+                    .map( |spell| ( spell.name.clone(), spell_summary(spell), false ) );
+
+                CreateEmbed::new()
+                    .title( format!("{character_name}'s spellbook") )
+                    .fields( fields )
+                    .colour( EmbedColours::info() )
+            },
+            Err( why ) => BotError::from( why ).embed( format!("Failed to list {character_name}'s spells") )
+        }
+    };
+
+    let embed = add_active_character_footer( ctx, &invoking_user_id, embed ).await;
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = match interaction_data.data.autocomplete() {
+        Some( focused_option ) if focused_option.name == "character" => {
+            search_user_characters( ctx, &invoking_user_id, focused_option.value )
+                .await
+                .into_iter()
+                .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+                .collect()
+        },
+        _ => vec![]
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /spellbook")
+    }
+}
+
+
+/// Routes /spellbook and it's autocomplete to the functions above
+pub struct SpellbookCommand;
+#[async_trait]
+impl SlashCommand for SpellbookCommand {
+    fn name( &self ) -> &'static str {
+        "spellbook"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+}
@@ -0,0 +1,155 @@
+// Set the character a user is currently playing as
+//
+// - Dispatched with a slash command, which has an autocomplete field to select one of the user's
+//     characters (it returns character ids)
+// - The choice is written to DiscordUsers.fk_currentCharacter, and then mirrored into the
+//     ActiveCharactersCache so other commands can default to it
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, ResolvedValue
+};
+
+use tracing::warn;
+
+use crate::{
+    commands::registry::SlashCommand,
+    db,
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, get_user_character_name, search_user_characters,
+        ActiveCharactersCache, EmbedColours
+    }
+};
+
+
+/// Build the switch_character's command signature to be sent to Discord's Gateway
+pub fn build() -> CreateCommand {
+    let options = vec![
+        CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "character",
+                "The character to play as. Must belong to you"
+            )
+            .required(true)
+            .set_autocomplete(true)
+    ];
+
+    CreateCommand::new("switch_character")
+        .description("Choose which of your characters you are playing as")
+        .set_options(options)
+}
+
+
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+
+    let selected_id = match interaction_data.data.options().first()?.value {
+        ResolvedValue::Integer( id ) => id,
+        _ => return None
+    };
+
+    let embed_for_message = 'return_embed: {
+
+        // --== CHARACTER OWNERSHIP TEST ==-- //
+
+            let character_name = match get_user_character_name( ctx, &invoking_user_id, selected_id ).await {
+                Some( name ) => name,
+                None => break 'return_embed BotError::NotOwner
+                    .embed( format!("Failed to switch {invoking_user_tag}'s character") )
+            };
+        // ==--
+
+        let query_result = db::discord_users::set_current_character(
+            &discord_bot.database_connection,
+            invoking_user_id,
+            selected_id
+        ).await;
+
+        if let Err( why ) = query_result {
+            break 'return_embed BotError::from( why )
+                .embed( format!("Failed to switch {invoking_user_tag}'s character") )
+        }
+
+        // --== SYNC CACHE TO DATABASE ==-- //
+
+            {
+                let data_read = ctx.data.read().await;
+                let active_map_mutex = data_read
+                    .get::<ActiveCharactersCache>()
+                    .expect("Key 'ActiveCharactersCache' must be in map, as it get's inserted in main.rs");
+
+                match active_map_mutex.lock() {
+                    Ok( mut active_map ) => {
+                        active_map.insert( invoking_user_id, selected_id );
+                    },
+                    Err(_) => {
+                        break 'return_embed BotError::CachePoisoned
+                            .embed( format!("Switched {invoking_user_tag} to {character_name} in /switch_character") )
+                    }
+                };
+            }
+        // ==--
+
+        // The cache was just updated, so the footer already names the new character
+        let embed = CreateEmbed::new()
+            .title(format!("You are now playing as {character_name}"))
+            .description("Commands with a character option will use them when it's left out")
+            .colour(EmbedColours::good());
+        add_active_character_footer( ctx, &invoking_user_id, embed ).await
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed_for_message )
+    ))
+}
+
+
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let query = match interaction_data.data.autocomplete() {
+        Some( option ) => option.value,
+        None => ""
+    };
+
+    let autocomplete_choices = search_user_characters( ctx, &invoking_user_id, query )
+        .await
+        .into_iter()
+        .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+        .collect();
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /switch_character")
+    }
+}
+
+
+/// Routes /switch_character and it's interactions to the functions above
+pub struct SwitchCharacterCommand;
+#[async_trait]
+impl SlashCommand for SwitchCharacterCommand {
+    fn name( &self ) -> &'static str {
+        "switch_character"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+}
@@ -1,10 +1,15 @@
 use serenity::{
-    all::{AutocompleteChoice, CommandOptionType, CreateAutocompleteResponse, CreateCommandOption, CreateInteractionResponse}, builder::{
+    all::{async_trait, AutocompleteChoice, CommandOptionType, CreateAutocompleteResponse, CreateCommandOption, CreateInteractionResponse}, builder::{
         CreateCommand, CreateInteractionResponseMessage
-    }, client::Context, model::application::{CommandInteraction, ResolvedValue}
+    }, model::application::{CommandInteraction, ResolvedValue}
 };
-use crate::utils::{
-    create_log_message, DatabaseCharactersCache, LogLevel
+use tracing::{error, warn};
+
+use crate::{
+    commands::registry::SlashCommand,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{CharacterId, DatabaseCharactersCache}
 };
 
 pub fn build() -> CreateCommand {
@@ -12,7 +17,7 @@ pub fn build() -> CreateCommand {
         .description("Testing some stuff")
         .add_option(CreateCommandOption::new(CommandOptionType::String, "test", "Autocomplete? Please?").set_autocomplete(true).required(true))
 }
-pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Option<CreateInteractionResponse> {
+pub async fn run( interaction_data: &CommandInteraction, ctx: &BotContext ) -> Option<CreateInteractionResponse> {
     
     let option = match interaction_data.data.options()[0].value {
         ResolvedValue::String( data ) => data,
@@ -28,18 +33,18 @@ pub async fn run( interaction_data: &CommandInteraction, ctx: &Context ) -> Opti
             .content( option )
     );
 
-    if let Err( why ) = interaction_data.create_response( &ctx.http, response).await {
-        print!("{}", why);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, response ).await {
+        error!(error = %why, "Failed to send response to /tmp")
     }
 
     None
 }
-pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &Context ) {
+pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &BotContext ) {
 
     // We'll need the id of the calling user
     let invoking_user_id = interaction_data.user.id.get();
 
-    let raw_responses: Vec<(u16, String)> = 'responses: {
+    let raw_responses: Vec<(CharacterId, String)> = 'responses: {
         // We need to get the what the user typed into our autocomplete field.
         //
         // We know for a fact that the command has it, so we can just index directly into the option
@@ -62,7 +67,7 @@ pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &C
                 .expect("Key must be inserted at startup in main.rs")
                 .lock();
 
-            let user_characters: Vec<(u16, String)> = match character_map_mutex {
+            let user_characters: Vec<(CharacterId, String)> = match character_map_mutex {
                 Ok( character_map ) => {
                     //
                     match character_map.get(&invoking_user_id) {
@@ -149,13 +154,32 @@ pub async fn handle_autocomplete( interaction_data: &CommandInteraction, ctx: &C
         choices
     };
 
-    if let Err(why) = interaction_data.create_response( &ctx.http,
-        CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new().set_choices(autocomplete_choices))
+    if let Err(why) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(CreateAutocompleteResponse::new().set_choices(autocomplete_choices))
     ).await {
-        println!("{}", create_log_message(
-                format!("Failed to respond to autocomplete interaction_data: \n\t{why}"),
-                LogLevel::Warning
-        ))
+        warn!(error = %why, "Failed to respond to autocomplete interaction_data")
     }
     
 }
+
+
+/// Routes /tmp and it's interactions to the functions above
+pub struct TmpCommand;
+#[async_trait]
+impl SlashCommand for TmpCommand {
+    fn name( &self ) -> &'static str {
+        "tmp"
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build()
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( interaction_data, ctx ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( interaction_data, ctx ).await;
+        true
+    }
+}
@@ -0,0 +1,299 @@
+// Change a character's HP and mana
+//
+// - `/damage amount:<n>` takes HP away, `/heal amount:<n>` gives it back and
+//     `/spend_mana amount:<n>` uses up mana. Each is kept between 0 and the character's maximum
+//     instead of failing, see `derived_stats.rs` for where the maximums come from
+// - The character option autocompletes over the target's characters and defaults to their active
+//     one. `/damage` and `/heal` can target another user's character with `user`, as whoever
+//     runs the fight usually hands those out. Only game masters can, which are members who can
+//     manage the server, and whoever started the encounter in the channel for the characters in
+//     it. Mana is only ever spent by it's owner
+// - Dropping to 0 HP is announced as falling unconscious, and healing from 0 as coming round.
+//     Running out of mana is announced as it being depleted
+
+use serenity::all::{
+    async_trait, AutocompleteChoice, CommandInteraction, CommandOptionType, CreateAutocompleteResponse,
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage
+};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    commands::registry::SlashCommand,
+    db,
+    derived_stats::{adjust_vitals, Vitals},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::BotContext,
+    utils::{
+        add_active_character_footer, get_active_character, get_user_character_name, integer_option, manages_guild,
+        search_user_characters, user_option, CharacterId, EmbedColours
+    }
+};
+
+
+/// Which of the commands was used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VitalsChange {
+    Damage,
+    Heal,
+    SpendMana
+}
+impl VitalsChange {
+    pub fn command_name( &self ) -> &'static str {
+        match self {
+            VitalsChange::Damage    => "damage",
+            VitalsChange::Heal      => "heal",
+            VitalsChange::SpendMana => "spend_mana",
+        }
+    }
+
+    /// How much HP and mana change by, as (hp_change, mana_change)
+    pub fn changes( &self, amount: i64 ) -> (i64, i64) {
+        match self {
+            VitalsChange::Damage    => ( -amount, 0 ),
+            VitalsChange::Heal      => ( amount, 0 ),
+            VitalsChange::SpendMana => ( 0, -amount ),
+        }
+    }
+
+    /// Whether the command can be used on other users' characters
+    fn targets_others( &self ) -> bool {
+        *self != VitalsChange::SpendMana
+    }
+}
+
+
+/// Build the signature of one of the commands to be sent to Discord's Gateway
+pub fn build( change: VitalsChange ) -> CreateCommand {
+    let ( description, amount_description ) = match change {
+        VitalsChange::Damage    => ( "Take HP away from a character", "How much damage is dealt" ),
+        VitalsChange::Heal      => ( "Give HP back to a character", "How much HP is healed" ),
+        VitalsChange::SpendMana => ( "Use up some of your character's mana", "How much mana is spent" ),
+    };
+
+    let mut command = CreateCommand::new( change.command_name() )
+        .description( description )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "amount", amount_description )
+                .required(true)
+                .min_int_value(1)
+        )
+        .add_option(
+            CreateCommandOption::new( CommandOptionType::Integer, "character", "The character affected. Defaults to the active one" )
+                .required(false)
+                .set_autocomplete(true)
+        );
+
+    if change.targets_others() {
+        command = command.add_option(
+            CreateCommandOption::new( CommandOptionType::User, "user", "Pick one of this user's characters instead of your own. Game masters only" )
+                .required(false)
+        );
+    }
+
+    command
+}
+
+
+
+/// Announce a change to a character's HP or mana, pointing out when they fall unconscious, come
+/// round or run out of mana
+pub fn vitals_embed( change: VitalsChange, character_name: &str, amount: i64, before: &Vitals, after: &Vitals ) -> CreateEmbed {
+
+    let hp_field   = ( "HP", format!("{}/{}", after.hp, after.max_hp), true );
+    let mana_field = ( "Mana", format!("{}/{}", after.mana, after.max_mana), true );
+
+    let embed = CreateEmbed::new().colour( EmbedColours::info() );
+
+    match change {
+        VitalsChange::Damage => {
+            let embed = embed
+                .title( format!("{character_name} took {amount} damage") )
+                .fields( [hp_field] );
+
+            match ( before.is_unconscious(), after.is_unconscious() ) {
+                ( false, true ) => embed.description( format!("**{character_name} falls unconscious**") ).colour( EmbedColours::error() ),
+                ( true, _ )     => embed.description( format!("{character_name} is still unconscious") ).colour( EmbedColours::error() ),
+                _ => embed
+            }
+        },
+        VitalsChange::Heal => {
+            let embed = embed
+                .title( format!("{character_name} healed {} HP", after.hp - before.hp) )
+                .fields( [hp_field] );
+
+            match ( before.is_unconscious(), after.is_unconscious(), after.hp == after.max_hp ) {
+                ( true, false, _ ) => embed.description( format!("**{character_name} comes round**") ).colour( EmbedColours::good() ),
+                ( _, _, true )     => embed.description( format!("{character_name} is at full health") ).colour( EmbedColours::good() ),
+                _ => embed
+            }
+        },
+        VitalsChange::SpendMana => {
+            let spent = before.mana - after.mana;
+            let embed = embed
+                .title( format!("{character_name} spent {spent} mana") )
+                .fields( [mana_field] );
+
+            let shortfall_note = match spent < amount {
+                true  => format!("\nThey only had {} of the {amount} mana", before.mana),
+                false => String::new()
+            };
+
+            match after.is_depleted() {
+                true  => embed.description( format!("**{character_name}'s mana is depleted**{shortfall_note}") ).colour( EmbedColours::error() ),
+                false => embed
+            }
+        }
+    }
+}
+
+
+/// Whether the user runs the game for the given character. That's anyone who can manage the
+/// server, the same as for /award_xp and the catalogues. Whoever started the encounter in the
+/// channel does too, but only for the characters fighting in it, and only in a server, as anyone
+/// can start one
+async fn is_game_master( interaction_data: &CommandInteraction, database_connection: &SqlitePool, character_id: CharacterId ) -> Result<bool, BotError> {
+    if manages_guild( interaction_data ) {
+        return Ok( true )
+    }
+
+    let encounter = match db::encounters::get_by_channel( database_connection, interaction_data.channel_id.get() ).await? {
+        Some( encounter ) => encounter,
+        None => return Ok( false )
+    };
+
+    let in_this_guild = encounter.guild_id.is_some()
+        && encounter.guild_id == interaction_data.guild_id.map( |guild_id| guild_id.get() as i64 );
+    if !in_this_guild || encounter.game_master_id != interaction_data.user.id.get() as i64 {
+        return Ok( false )
+    }
+
+    let combatants = db::encounters::turn_order( database_connection, encounter.encounter_id ).await?;
+    Ok( combatants.iter().any(|combatant| combatant.character_id == Some(character_id)) )
+}
+
+
+pub async fn run( change: VitalsChange, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+
+    let invoking_user_id  = interaction_data.user.id.get();
+    let invoking_user_tag = interaction_data.user.tag();
+    let command_name = change.command_name();
+
+    // --== READ OPTIONS ==-- //
+
+        let options = interaction_data.data.options();
+
+        let amount = integer_option( &options, "amount" )?;
+        let character_option = integer_option( &options, "character" );
+        let target_user_id = match change.targets_others() {
+            true  => user_option( &options, "user" ).unwrap_or( invoking_user_id ),
+            false => invoking_user_id
+        };
+    // ==--
+
+    let embed: CreateEmbed = 'return_embed: {
+
+        // --== FIND CHARACTER ==-- //
+
+            // Same as everywhere else, we fall back onto the active character
+            let character_id = match character_option {
+                Some( id ) => id,
+                None => match get_active_character( ctx, &target_user_id ).await {
+                    Some(( id, _ )) => id,
+                    None => break 'return_embed BotError::NoActiveCharacter
+                        .embed( format!("{invoking_user_tag} picked no character, and <@{target_user_id}> has no active one in /{command_name}") )
+                }
+            };
+            let character_name = match get_user_character_name( ctx, &target_user_id, character_id ).await {
+                Some( name ) => name,
+                None => break 'return_embed BotError::NotOwner
+                    .embed( format!("{invoking_user_tag} picked a character <@{target_user_id}> doesn't own in /{command_name}") )
+            };
+        // ==--
+
+        // --== PERMISSION TEST ==-- //
+
+            // Anyone can change their own characters, someone else's are left to the game master
+            if target_user_id != invoking_user_id {
+                match is_game_master( interaction_data, &discord_bot.database_connection, character_id ).await {
+                    Ok( true ) => {},
+                    Ok( false ) => break 'return_embed BotError::CantTargetOthers
+                        .embed( format!("{invoking_user_tag} tried to use /{command_name} on <@{target_user_id}>'s character") ),
+                    Err( why ) => break 'return_embed why.embed( format!("Failed to check whether {invoking_user_tag} runs the game in /{command_name}") )
+                }
+            }
+        // ==--
+
+        let ( hp_change, mana_change ) = change.changes( amount );
+        let ( before, after ) = match adjust_vitals( &discord_bot.database_connection, character_id, hp_change, mana_change ).await {
+            Ok( vitals ) => vitals,
+            Err( why ) => break 'return_embed why.embed( format!("Failed to change {character_name}'s vitals in /{command_name}") )
+        };
+
+        info!(
+            "{invoking_user_tag} used /{command_name} {amount} on {character_name}: HP {} -> {}, mana {} -> {}",
+            before.hp, after.hp, before.mana, after.mana
+        );
+
+        add_active_character_footer( ctx, &invoking_user_id, vitals_embed(change, &character_name, amount, &before, &after) ).await
+    };
+
+    Some( CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    ))
+}
+
+
+pub async fn handle_autocomplete( change: VitalsChange, interaction_data: &CommandInteraction, ctx: &BotContext ) {
+
+    let invoking_user_id = interaction_data.user.id.get();
+
+    let autocomplete_choices: Vec<AutocompleteChoice> = 'choices: {
+        let focused_option = match interaction_data.data.autocomplete() {
+            Some( option ) if option.name == "character" => option,
+            _ => break 'choices vec![]
+        };
+
+        // Suggest the characters of whoever is being targeted
+        let target_user_id = match change.targets_others() {
+            true  => user_option( &interaction_data.data.options(), "user" ).unwrap_or( invoking_user_id ),
+            false => invoking_user_id
+        };
+
+        search_user_characters( ctx, &target_user_id, focused_option.value )
+            .await
+            .into_iter()
+            .map( |(character_id, character_name)| AutocompleteChoice::new(character_name, character_id) )
+            .collect()
+    };
+
+    let response = CreateAutocompleteResponse::new().set_choices(autocomplete_choices);
+    if let Err( why ) = ctx.responses.create_response( interaction_data.id, &interaction_data.token, CreateInteractionResponse::Autocomplete(response) ).await {
+        warn!(error = %why, "Failed to send autocomplete response in /{}", change.command_name())
+    }
+}
+
+
+/// Routes /damage, /heal and /spend_mana to the functions above
+pub struct VitalsCommand( pub VitalsChange );
+#[async_trait]
+impl SlashCommand for VitalsCommand {
+    fn name( &self ) -> &'static str {
+        self.0.command_name()
+    }
+
+    fn build( &self ) -> CreateCommand {
+        build( self.0 )
+    }
+
+    async fn run( &self, interaction_data: &CommandInteraction, ctx: &BotContext, discord_bot: &DiscordBot ) -> Option<CreateInteractionResponse> {
+        run( self.0, interaction_data, ctx, discord_bot ).await
+    }
+
+    async fn autocomplete( &self, interaction_data: &CommandInteraction, ctx: &BotContext, _discord_bot: &DiscordBot ) -> bool {
+        handle_autocomplete( self.0, interaction_data, ctx ).await;
+        true
+    }
+}
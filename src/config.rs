@@ -0,0 +1,550 @@
+// Settings the bot is started with
+//
+// - Read from `config.toml` in the working directory, or from the file given with
+//     `--config <path>`. Every setting has a default, so the file, any of it's tables, or any of
+//     their keys can be left out. A missing file is only an error when it was asked for by name
+// - Environment variables prefixed with `MAGICIAN_` override whatever the file says
+// - Everything is validated up front, and every problem found is reported at once, so the bot
+//     never starts half configured
+// - Once loaded the config is stored globally, as things like embed colours are needed in places
+//     that have no access to the client's data
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    sync::OnceLock
+};
+
+use serde::Deserialize;
+use serenity::{
+    model::{gateway::GatewayIntents, id::ChannelId, Colour}
+};
+
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::Rotation;
+
+use crate::{
+    attributes::Attribute,
+    commands,
+    derived_stats::StatFormula,
+    progression::LevelCurve
+};
+
+
+/// File read when no `--config` flag is given
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Longest characters can be kept in the trash, a hundred years. Anything longer wouldn't fit in
+/// a timestamp once turned into seconds
+const MAX_TRASH_RETENTION_DAYS: u64 = 36500;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+
+// --== FILE LAYOUT ==-- //
+
+    // These mirror the layout of the toml file. Values are kept as they were written, and only
+    // turned into their proper types once they've been validated
+
+    #[derive(Deserialize, Default)]
+    #[serde(default, deny_unknown_fields)]
+    struct ConfigFile {
+        database:      DatabaseTable,
+        discord:       DiscordTable,
+        embed_colours: EmbedColoursTable,
+        logging:       LoggingTable,
+        deletion:      DeletionTable,
+        allocation:    AllocationTable,
+        derived_stats: DerivedStatsTable,
+        progression:   ProgressionTable,
+        /// Command names mapped to whether they are enabled. Commands left out are enabled
+        commands:      HashMap<String, bool>
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct DatabaseTable {
+        path: String
+    }
+    impl Default for DatabaseTable {
+        fn default() -> Self {
+            DatabaseTable { path: "kerm-maw_db".to_owned() }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct DiscordTable {
+        /// Channel the wakeup message is sent to. No message is sent if it's left out, only a
+        /// warning is logged
+        wakeup_channel:  Option<u64>,
+        /// Names of gateway intents, as written in Discord's documentation
+        gateway_intents: Vec<String>
+    }
+    impl Default for DiscordTable {
+        fn default() -> Self {
+            DiscordTable {
+                wakeup_channel:  None,
+                gateway_intents: vec![
+                    "GUILD_MESSAGES".to_owned(),
+                    "MESSAGE_CONTENT".to_owned(),
+                    "GUILD_MESSAGE_REACTIONS".to_owned()
+                ]
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct EmbedColoursTable {
+        info:  String,
+        good:  String,
+        error: String
+    }
+    impl Default for EmbedColoursTable {
+        fn default() -> Self {
+            EmbedColoursTable {
+                info:  "#007FFF".to_owned(),
+                good:  "#00FF7F".to_owned(),
+                error: "#FF7F00".to_owned()
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct LoggingTable {
+        /// Least severe level that still gets logged
+        level:     String,
+        /// Directory the JSON lines log files are written to
+        directory: String,
+        /// How often a new log file is started
+        rotation:  String,
+        /// How many log files are kept before the oldest gets removed
+        max_files: usize
+    }
+    impl Default for LoggingTable {
+        fn default() -> Self {
+            LoggingTable {
+                level:     "info".to_owned(),
+                directory: "logs".to_owned(),
+                rotation:  "daily".to_owned(),
+                max_files: 7
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct DeletionTable {
+        /// Whether the name typed to confirm a deletion has to match the character's name's case
+        confirm_name_case_sensitive: bool,
+        /// How many days deleted characters stay in the trash before they're removed for good
+        trash_retention_days:        u64
+    }
+    impl Default for DeletionTable {
+        fn default() -> Self {
+            DeletionTable {
+                confirm_name_case_sensitive: false,
+                trash_retention_days:        30
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct AllocationTable {
+        /// Points a new character gets to spend on top of `min`
+        budget: i64,
+        /// Lowest value of every attribute, which is also where the allocation starts from
+        min:    i64,
+        /// Highest a new character's attributes can be
+        max:    i64
+    }
+    impl Default for AllocationTable {
+        fn default() -> Self {
+            AllocationTable {
+                budget: 15,
+                min:    1,
+                max:    10
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct DerivedStatsTable {
+        max_hp:         FormulaTable,
+        max_mana:       FormulaTable,
+        carry_capacity: FormulaTable
+    }
+    impl Default for DerivedStatsTable {
+        fn default() -> Self {
+            DerivedStatsTable {
+                max_hp:         FormulaTable { attribute: "Constitution".to_owned(), base: 10, per_point: 5 },
+                max_mana:       FormulaTable { attribute: "Casting".to_owned(), base: 0, per_point: 5 },
+                carry_capacity: FormulaTable { attribute: "Strength".to_owned(), base: 10, per_point: 10 }
+            }
+        }
+    }
+
+    /// A derived stat's formula: `base + per_point × attribute`
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct FormulaTable {
+        /// Name of the attribute, as written in the database
+        attribute: String,
+        base:      i64,
+        per_point: i64
+    }
+
+    #[derive(Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct ProgressionTable {
+        /// Experience needed to reach level 2, 3 and so on
+        level_curve:      Vec<i64>,
+        /// Attribute points granted for every level gained
+        points_per_level: i64,
+        /// Highest an attribute can be raised to by levelling up
+        max_attribute:    i64
+    }
+    impl Default for ProgressionTable {
+        fn default() -> Self {
+            ProgressionTable {
+                level_curve:      vec![100, 300, 600, 1000, 1500, 2100, 2800, 3600, 4500],
+                points_per_level: 2,
+                max_attribute:    20
+            }
+        }
+    }
+// ==--
+
+
+/// Validated settings
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_path:               String,
+    pub wakeup_channel:              Option<ChannelId>,
+    pub gateway_intents:             GatewayIntents,
+    pub colour_info:                 Colour,
+    pub colour_good:                 Colour,
+    pub colour_error:                Colour,
+    pub log_level:                   LevelFilter,
+    pub log_directory:               String,
+    pub log_rotation:                Rotation,
+    pub log_max_files:               usize,
+    pub confirm_name_case_sensitive: bool,
+    pub trash_retention_days:        u64,
+    pub allocation_budget:           i64,
+    pub allocation_min:              i64,
+    pub allocation_max:              i64,
+    pub max_hp_formula:              StatFormula,
+    pub max_mana_formula:            StatFormula,
+    pub carry_capacity_formula:      StatFormula,
+    pub level_curve:                 LevelCurve,
+    pub points_per_level:            i64,
+    pub max_attribute:               i64,
+    pub disabled_commands:           Vec<String>
+}
+impl Default for Config {
+    fn default() -> Self {
+        ConfigFile::default()
+            .validate()
+            .expect("Default configuration must be valid")
+    }
+}
+impl Config {
+
+    /// Load the config file named by the `--config` flag in the given arguments, falling back to
+    /// `config.toml`, and apply environment overrides. Returns every problem that was found
+    pub fn load( args: impl Iterator<Item = String> ) -> Result<Config, Vec<String>> {
+
+        // --== FIND CONFIG FILE ==-- //
+
+            let mut config_path = None;
+            let mut args = args.skip(1);  // The first argument is the binary itself
+            while let Some( arg ) = args.next() {
+                if arg == "--config" {
+                    match args.next() {
+                        Some( path ) => config_path = Some( path ),
+                        None => return Err( vec!["--config is missing a path".to_owned()] )
+                    }
+                }
+                else if let Some( path ) = arg.strip_prefix("--config=") {
+                    config_path = Some( path.to_owned() );
+                }
+            }
+        // ==--
+
+        // --== READ CONFIG FILE ==-- //
+
+            let mut config_file = match &config_path {
+                Some( path ) => match fs::read_to_string( path ) {
+                    Ok( contents ) => parse( path, &contents )?,
+                    Err( why ) => return Err( vec![format!("Couldn't read {path}: {why}")] )
+                },
+                None => match fs::read_to_string( DEFAULT_CONFIG_PATH ) {
+                    Ok( contents ) => parse( DEFAULT_CONFIG_PATH, &contents )?,
+                    // Running without a config file is fine, everything has a default
+                    Err(_) => ConfigFile::default()
+                }
+            };
+        // ==--
+
+        config_file.apply_env_overrides()?;
+        config_file.validate()
+    }
+
+    pub fn is_command_enabled( &self, command_name: &str ) -> bool {
+        !self.disabled_commands.iter().any( |disabled| disabled == command_name )
+    }
+}
+
+
+fn parse( path: &str, contents: &str ) -> Result<ConfigFile, Vec<String>> {
+    toml::from_str( contents ).map_err( |why| vec![format!("Couldn't parse {path}: {}", why.message())] )
+}
+
+impl ConfigFile {
+
+    /// Replace values with those set in the environment
+    fn apply_env_overrides( &mut self ) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+
+        if let Ok( path ) = env::var("MAGICIAN_DATABASE_PATH") {
+            self.database.path = path;
+        }
+        if let Ok( channel ) = env::var("MAGICIAN_WAKEUP_CHANNEL") {
+            match channel.trim().parse() {
+                Ok( channel_id ) => self.discord.wakeup_channel = Some( channel_id ),
+                Err(_) => errors.push(format!("MAGICIAN_WAKEUP_CHANNEL must be a channel id, not '{channel}'"))
+            }
+        }
+        if let Ok( intents ) = env::var("MAGICIAN_GATEWAY_INTENTS") {
+            self.discord.gateway_intents = split_list( &intents );
+        }
+        if let Ok( level ) = env::var("MAGICIAN_LOG_LEVEL") {
+            self.logging.level = level;
+        }
+        if let Ok( directory ) = env::var("MAGICIAN_LOG_DIRECTORY") {
+            self.logging.directory = directory;
+        }
+        if let Ok( days ) = env::var("MAGICIAN_TRASH_RETENTION_DAYS") {
+            match days.trim().parse() {
+                Ok( days ) => self.deletion.trash_retention_days = days,
+                Err(_) => errors.push(format!("MAGICIAN_TRASH_RETENTION_DAYS must be a number of days, not '{days}'"))
+            }
+        }
+        if let Ok( colour ) = env::var("MAGICIAN_COLOUR_INFO") {
+            self.embed_colours.info = colour;
+        }
+        if let Ok( colour ) = env::var("MAGICIAN_COLOUR_GOOD") {
+            self.embed_colours.good = colour;
+        }
+        if let Ok( colour ) = env::var("MAGICIAN_COLOUR_ERROR") {
+            self.embed_colours.error = colour;
+        }
+        if let Ok( commands ) = env::var("MAGICIAN_DISABLED_COMMANDS") {
+            for command_name in split_list( &commands ) {
+                self.commands.insert( command_name, false );
+            }
+        }
+
+        match errors.is_empty() {
+            true  => Ok(()),
+            false => Err( errors )
+        }
+    }
+
+    /// Turn the raw values into their proper types, collecting every problem along the way
+    fn validate( self ) -> Result<Config, Vec<String>> {
+        let mut errors = vec![];
+
+        if self.database.path.trim().is_empty() {
+            errors.push("database.path can't be empty".to_owned());
+        }
+
+        let wakeup_channel = match self.discord.wakeup_channel {
+            Some( 0 ) => {
+                errors.push("discord.wakeup_channel can't be 0".to_owned());
+                None
+            },
+            Some( channel_id ) => Some( ChannelId::new(channel_id) ),
+            None => None
+        };
+
+        let mut gateway_intents = GatewayIntents::empty();
+        for intent_name in self.discord.gateway_intents.iter() {
+            match GatewayIntents::from_name( &intent_name.to_uppercase() ) {
+                Some( intent ) => gateway_intents |= intent,
+                None => errors.push(format!("discord.gateway_intents has an unknown intent '{intent_name}'"))
+            }
+        }
+
+        let mut colour = |key: &str, value: &str| match parse_colour( value ) {
+            Some( colour ) => colour,
+            None => {
+                errors.push(format!("embed_colours.{key} must look like #RRGGBB, not '{value}'"));
+                Colour::default()
+            }
+        };
+        let colour_info  = colour( "info",  &self.embed_colours.info );
+        let colour_good  = colour( "good",  &self.embed_colours.good );
+        let colour_error = colour( "error", &self.embed_colours.error );
+
+        let log_level = match self.logging.level.to_lowercase().as_str() {
+            "off"               => LevelFilter::OFF,
+            "error"             => LevelFilter::ERROR,
+            "warn" | "warning"  => LevelFilter::WARN,
+            "info"              => LevelFilter::INFO,
+            "debug"             => LevelFilter::DEBUG,
+            "trace"             => LevelFilter::TRACE,
+            level => {
+                errors.push(format!("logging.level must be one of off, error, warning, info, debug or trace, not '{level}'"));
+                LevelFilter::INFO
+            }
+        };
+
+        if self.logging.directory.trim().is_empty() {
+            errors.push("logging.directory can't be empty".to_owned());
+        }
+
+        let log_rotation = match self.logging.rotation.to_lowercase().as_str() {
+            "minutely" => Rotation::MINUTELY,
+            "hourly"   => Rotation::HOURLY,
+            "daily"    => Rotation::DAILY,
+            "never"    => Rotation::NEVER,
+            rotation => {
+                errors.push(format!("logging.rotation must be one of minutely, hourly, daily or never, not '{rotation}'"));
+                Rotation::DAILY
+            }
+        };
+
+        if self.logging.max_files == 0 {
+            errors.push("logging.max_files must be at least 1".to_owned());
+        }
+
+        if self.deletion.trash_retention_days == 0 {
+            errors.push("deletion.trash_retention_days must be at least 1".to_owned());
+        }
+        if self.deletion.trash_retention_days > MAX_TRASH_RETENTION_DAYS {
+            errors.push(format!("deletion.trash_retention_days can't be more than {MAX_TRASH_RETENTION_DAYS}"));
+        }
+
+        if self.allocation.min < 0 {
+            errors.push("allocation.min can't be negative".to_owned());
+        }
+        if self.allocation.max < self.allocation.min {
+            errors.push("allocation.max can't be less than allocation.min".to_owned());
+        }
+        // Points that can't be spent anywhere would only sit there, so the budget has to fit
+        // between the limits of the six attributes
+        let most_spendable = ( self.allocation.max - self.allocation.min ).max(0) * Attribute::ALL.len() as i64;
+        if !(0..=most_spendable).contains( &self.allocation.budget ) {
+            errors.push(format!("allocation.budget must be between 0 and {most_spendable}"));
+        }
+
+        let mut formula = |key: &str, table: &FormulaTable| {
+            let attribute = match Attribute::from_name( &table.attribute ) {
+                Some( attribute ) => attribute,
+                None => {
+                    errors.push(format!("derived_stats.{key}.attribute must be the name of an attribute, not '{}'", table.attribute));
+                    Attribute::Strength
+                }
+            };
+
+            StatFormula { attribute, base: table.base, per_point: table.per_point }
+        };
+        let max_hp_formula         = formula( "max_hp", &self.derived_stats.max_hp );
+        let max_mana_formula       = formula( "max_mana", &self.derived_stats.max_mana );
+        let carry_capacity_formula = formula( "carry_capacity", &self.derived_stats.carry_capacity );
+
+        let level_curve = match LevelCurve::new( self.progression.level_curve ) {
+            Some( level_curve ) => level_curve,
+            None => {
+                errors.push("progression.level_curve must start above 0 and keep rising".to_owned());
+                LevelCurve::new( vec![] ).expect("An empty curve is always valid")
+            }
+        };
+
+        if self.progression.points_per_level < 0 {
+            errors.push("progression.points_per_level can't be negative".to_owned());
+        }
+
+        // Characters are built with attributes up to the creation maximum, levelling up has to be
+        // able to keep them there
+        if self.progression.max_attribute < self.allocation.max {
+            errors.push("progression.max_attribute must be at least allocation.max".to_owned());
+        }
+
+        let command_names = commands::registry().names();
+        let mut disabled_commands = vec![];
+        for ( command_name, enabled ) in self.commands.into_iter() {
+            if !command_names.contains( &command_name.as_str() ) {
+                errors.push(format!("commands has an unknown command '{command_name}'"));
+            }
+            else if !enabled {
+                disabled_commands.push( command_name );
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err( errors )
+        }
+
+        Ok( Config {
+            database_path: self.database.path,
+            wakeup_channel,
+            gateway_intents,
+            colour_info,
+            colour_good,
+            colour_error,
+            log_level,
+            log_directory: self.logging.directory,
+            log_rotation,
+            log_max_files: self.logging.max_files,
+            confirm_name_case_sensitive: self.deletion.confirm_name_case_sensitive,
+            trash_retention_days: self.deletion.trash_retention_days,
+            allocation_budget: self.allocation.budget,
+            allocation_min: self.allocation.min,
+            allocation_max: self.allocation.max,
+            max_hp_formula,
+            max_mana_formula,
+            carry_capacity_formula,
+            level_curve,
+            points_per_level: self.progression.points_per_level,
+            max_attribute: self.progression.max_attribute,
+            disabled_commands
+        })
+    }
+}
+
+
+/// Split a comma separated list, as used by environment overrides
+fn split_list( list: &str ) -> Vec<String> {
+    list.split(',')
+        .map( |item| item.trim().to_owned() )
+        .filter( |item| !item.is_empty() )
+        .collect()
+}
+
+/// Parse a colour written as `#RRGGBB`
+fn parse_colour( value: &str ) -> Option<Colour> {
+    let hex = value.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None
+    }
+
+    u32::from_str_radix( hex, 16 ).ok().map( Colour::new )
+}
+
+
+/// Store the config for the rest of the runtime. Only the first call has any effect
+pub fn set( config: Config ) {
+    let _ = CONFIG.set( config );
+}
+
+/// The config the bot was started with. If none was set, the defaults are used
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init( Config::default )
+}
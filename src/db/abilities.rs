@@ -0,0 +1,67 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::abilities,
+    utils::CharacterId
+};
+use super::{Ability, DbError};
+
+
+/// Give a character a new ability. It's ID is allocated per character
+pub async fn add( database_connection: &SqlitePool, character_id: CharacterId, name: &str, description: &str ) -> Result<(), DbError> {
+    sqlx::query( abilities::ADD_ABILITY )
+        .bind( character_id )   // fk_pk_characterId
+        .bind( name )           // abilityName
+        .bind( description )    // abilityDescription
+        .execute( database_connection )
+        .await?;
+
+    Ok(())
+}
+
+/// Get a single ability of a character
+pub async fn get( database_connection: &SqlitePool, character_id: CharacterId, ability_id: i64 ) -> Result<Option<Ability>, DbError> {
+    let ability = sqlx::query_as( abilities::SELECT_BY_ID )
+        .bind( character_id )   // fk_pk_characterId
+        .bind( ability_id )     // pk_abilityId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( ability )
+}
+
+/// Get every ability of a character, ordered by their ID
+pub async fn get_by_character( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Vec<Ability>, DbError> {
+    let character_abilities = sqlx::query_as( abilities::SELECT_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_pk_characterId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( character_abilities )
+}
+
+/// Change the name and description of an ability. Returns whether the ability existed
+pub async fn update( database_connection: &SqlitePool, ability: &Ability ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( abilities::UPDATE_ABILITY )
+    // -= Bind Values =- //
+        .bind( ability.character_id )   // fk_pk_characterId
+        .bind( ability.ability_id )     // pk_abilityId
+        .bind( &ability.name )          // abilityName
+        .bind( &ability.description )   // abilityDescription
+    // =-
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Remove an ability from a character. Returns whether the ability existed
+pub async fn remove( database_connection: &SqlitePool, character_id: CharacterId, ability_id: i64 ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( abilities::REMOVE_ABILITY )
+        .bind( character_id )   // fk_pk_characterId
+        .bind( ability_id )     // pk_abilityId
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
@@ -0,0 +1,41 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::attributes,
+    utils::CharacterId
+};
+use super::{Attributes, DbError};
+
+
+/// Give a character their first attributes
+///
+/// Fails with `AttributesAlreadySet` if they already have some, in which case nothing changes
+pub async fn insert( database_connection: &SqlitePool, character_attributes: &Attributes ) -> Result<(), DbError> {
+    let query_result = sqlx::query( attributes::INSERT_ATTRIBUTES )
+    // -= Bind Values =- //
+        .bind( character_attributes.character_id )  // fk_pk_characterId
+        .bind( character_attributes.strength )      // Strength
+        .bind( character_attributes.dexterity )     // Dexterity
+        .bind( character_attributes.preception )    // Preception
+        .bind( character_attributes.knowledge )     // Knowledge
+        .bind( character_attributes.constitution )  // Constitution
+        .bind( character_attributes.casting )       // Casting
+    // =-
+        .execute( database_connection )
+        .await?;
+
+    match query_result.rows_affected() {
+        0 => Err( DbError::AttributesAlreadySet ),
+        _ => Ok(())
+    }
+}
+
+/// Get a character's attributes. `None` if they haven't been allocated yet
+pub async fn get( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Option<Attributes>, DbError> {
+    let character_attributes = sqlx::query_as( attributes::SELECT_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_pk_characterId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( character_attributes )
+}
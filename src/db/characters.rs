@@ -0,0 +1,262 @@
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::{
+    sql_scripts::{abilities, attributes, characters, discord_users, encounters, equipment, items, progression, resources, rolls, spells},
+    utils::CharacterId
+};
+use super::{sqlite_error_code, Character, DbError, SQLITE_CONSTRAINT_FOREIGNKEY};
+
+
+/// Add a character to the database, returning it's newly allocated ID
+///
+/// Fails with `DuplicateCharacter` if the owner already has a character of the same name, and
+/// with `NotRegistered` if the owner doesn't have a profile
+pub async fn create( database_connection: &SqlitePool, owner_id: u64, name: &str, species: &str, backstory: &str ) -> Result<CharacterId, DbError> {
+
+    // The name check and the insert happen inside of one transaction, and the new character's ID
+    // is handed straight back by the INSERT. That way two characters created at the same time
+    // can't both pass the check, nor read back each other's ID
+    let mut transaction = database_connection.begin().await?;
+
+    let existing_character = sqlx::query( characters::SELECT_BY_NAME_AND_OWNER_ID )
+        .bind( owner_id as i64 )    // fk_discordId
+        .bind( name )               // pk_name
+        .fetch_optional( &mut *transaction )
+        .await?;
+
+    if existing_character.is_some() {
+        // Dropping the transaction rolls it back
+        return Err( DbError::DuplicateCharacter )
+    }
+
+    let query_result = sqlx::query_scalar( characters::ADD_CHARACTER )
+    // -= Bind Values =- //
+        .bind( owner_id as i64 )    // fk_discordId
+        .bind( name )               // pk_name
+        .bind( species )            // species
+        .bind( backstory )          // backstory
+    // =-
+        .fetch_one( &mut *transaction )
+        .await;
+
+    let character_id = match query_result {
+        Ok( character_id ) => character_id,
+        // The owner needs to be in DiscordUsers for the foreign key to be satisfied
+        Err( why ) if sqlite_error_code(&why) == Some(SQLITE_CONSTRAINT_FOREIGNKEY) => return Err( DbError::NotRegistered ),
+        Err( why ) => return Err( why.into() )
+    };
+
+    transaction.commit().await?;
+    Ok( character_id )
+}
+
+/// Get a single character by it's ID
+pub async fn get( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Option<Character>, DbError> {
+    let character = sqlx::query_as( characters::SELECT_BY_ID )
+        .bind( character_id )   // pk_characterId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( character )
+}
+
+/// Get every character owned by a user
+pub async fn get_by_owner( database_connection: &SqlitePool, owner_id: u64 ) -> Result<Vec<Character>, DbError> {
+    let characters = sqlx::query_as( characters::SELECT_BY_OWNER_ID )
+        .bind( owner_id as i64 )    // fk_discordId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( characters )
+}
+
+/// Get the owner's Discord ID, the character's ID and name of every character in the database
+pub async fn all_ids_and_names( database_connection: &SqlitePool ) -> Result<Vec<(u64, CharacterId, String)>, DbError> {
+    let rows: Vec<(i64, CharacterId, String)> = sqlx::query_as( characters::SELECT_ALL_CHARACTER_IDS_AND_NAME )
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( rows
+        .into_iter()
+        .map( |(owner_id, character_id, name)| ( owner_id as u64, character_id, name ) )
+        .collect()
+    )
+}
+
+/// Change a character's name, species and backstory
+///
+/// Fails with `DuplicateCharacter` if the owner already has a different character of the new name
+pub async fn update( database_connection: &SqlitePool, character: &Character ) -> Result<(), DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    let existing_character: Option<Character> = sqlx::query_as( characters::SELECT_BY_NAME_AND_OWNER_ID )
+        .bind( character.owner_id )     // fk_discordId
+        .bind( &character.name )        // pk_name
+        .fetch_optional( &mut *transaction )
+        .await?;
+
+    // The character keeping it's own name is of course fine
+    if existing_character.is_some_and( |existing| existing.character_id != character.character_id ) {
+        return Err( DbError::DuplicateCharacter )
+    }
+
+    sqlx::query( characters::UPDATE_CHARACTER )
+    // -= Bind Values =- //
+        .bind( character.character_id ) // pk_characterId
+        .bind( &character.name )        // pk_name
+        .bind( &character.species )     // species
+        .bind( &character.backstory )   // backstory
+    // =-
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Get every archived character owned by a user, most recently archived first
+pub async fn get_archived_by_owner( database_connection: &SqlitePool, owner_id: u64 ) -> Result<Vec<Character>, DbError> {
+    let characters = sqlx::query_as( characters::SELECT_ARCHIVED_BY_OWNER_ID )
+        .bind( owner_id as i64 )    // fk_discordId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( characters )
+}
+
+/// Move a character into the trash, at the given time in seconds since the unix epoch. Anyone
+/// playing as them has their current character unset and they leave every encounter, as archived
+/// characters can't be played
+pub async fn archive( database_connection: &SqlitePool, character_id: CharacterId, archived_at: i64 ) -> Result<(), DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    sqlx::query( discord_users::CLEAR_CURRENT_CHARACTER )
+        .bind( character_id )   // fk_currentCharacter
+        .execute( &mut *transaction )
+        .await?;
+
+    sqlx::query( encounters::REMOVE_CHARACTER )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *transaction )
+        .await?;
+
+    sqlx::query( characters::ARCHIVE_CHARACTER )
+        .bind( character_id )   // pk_characterId
+        .bind( archived_at )    // archived_at
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Take a character back out of the trash
+///
+/// Fails with `DuplicateCharacter` if the owner has since made another character of the same name
+pub async fn restore( database_connection: &SqlitePool, character: &Character ) -> Result<(), DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    let existing_character = sqlx::query( characters::SELECT_BY_NAME_AND_OWNER_ID )
+        .bind( character.owner_id )     // fk_discordId
+        .bind( &character.name )        // pk_name
+        .fetch_optional( &mut *transaction )
+        .await?;
+
+    if existing_character.is_some() {
+        return Err( DbError::DuplicateCharacter )
+    }
+
+    sqlx::query( characters::RESTORE_CHARACTER )
+        .bind( character.character_id ) // pk_characterId
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Remove every character that was archived before the given time for good, along with their
+/// attributes and abilities. Returns how many were removed
+pub async fn remove_archived_before( database_connection: &SqlitePool, archived_before: i64 ) -> Result<usize, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    let character_ids: Vec<CharacterId> = sqlx::query_scalar( characters::SELECT_ARCHIVED_BEFORE )
+        .bind( archived_before )    // archived_at
+        .fetch_all( &mut *transaction )
+        .await?;
+
+    for character_id in character_ids.iter() {
+        remove_with_dependents( &mut transaction, *character_id ).await?;
+    }
+
+    transaction.commit().await?;
+    Ok( character_ids.len() )
+}
+
+/// Remove a character and every row referring to it, as part of a larger transaction. Anyone
+/// playing as them has their current character unset first, as the foreign key would otherwise
+/// stop the removal. They're taken out of any encounter, and their rolls are kept, but unlinked
+pub(super) async fn remove_with_dependents( connection: &mut SqliteConnection, character_id: CharacterId ) -> Result<(), DbError> {
+    sqlx::query( discord_users::CLEAR_CURRENT_CHARACTER )
+        .bind( character_id )   // fk_currentCharacter
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( attributes::REMOVE_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_pk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( abilities::REMOVE_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_pk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( spells::FORGET_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( equipment::REMOVE_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( items::REMOVE_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( resources::REMOVE_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( progression::REMOVE_LEVEL_UPS )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( progression::REMOVE_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( encounters::REMOVE_CHARACTER )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    // Rolls are kept for the history, they just stop pointing at the character
+    sqlx::query( rolls::CLEAR_CHARACTER )
+        .bind( character_id )   // fk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( characters::REMOVE_CHARACTER )
+        .bind( character_id )   // pk_characterId
+        .execute( &mut *connection )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::{characters, discord_users},
+    utils::CharacterId
+};
+use super::{sqlite_error_code, Character, DbError, DiscordUser, SQLITE_CONSTRAINT_PRIMARYKEY};
+
+
+/// Add a user's profile to the database
+///
+/// Fails with `AlreadyRegistered` if they already have one
+pub async fn register( database_connection: &SqlitePool, discord_id: u64 ) -> Result<(), DbError> {
+    let query_result = sqlx::query( discord_users::REGISTER )
+        .bind( discord_id as i64 )  // pk_discordId
+        .execute( database_connection )
+        .await;
+
+    match query_result {
+        Ok(_) => Ok(()),
+        Err( why ) if sqlite_error_code(&why) == Some(SQLITE_CONSTRAINT_PRIMARYKEY) => Err( DbError::AlreadyRegistered ),
+        Err( why ) => Err( why.into() )
+    }
+}
+
+/// Get a user's profile, if they have one
+pub async fn get( database_connection: &SqlitePool, discord_id: u64 ) -> Result<Option<DiscordUser>, DbError> {
+    let user = sqlx::query_as( discord_users::SELECT_BY_ID )
+        .bind( discord_id as i64 )  // pk_discordId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( user )
+}
+
+/// Remove a user's profile. Returns whether there was one to remove
+pub async fn remove( database_connection: &SqlitePool, discord_id: u64 ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( discord_users::REMOVE_ENTRY )
+        .bind( discord_id as i64 )  // pk_discordId
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Remove a user's profile along with every character they own, and everything belonging to
+/// those characters. Either all of it gets removed, or none of it does. Returns the IDs of the
+/// removed characters
+///
+/// Fails with `NotRegistered` if the user doesn't have a profile
+pub async fn purge( database_connection: &SqlitePool, discord_id: u64 ) -> Result<Vec<CharacterId>, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    let user: Option<DiscordUser> = sqlx::query_as( discord_users::SELECT_BY_ID )
+        .bind( discord_id as i64 )  // pk_discordId
+        .fetch_optional( &mut *transaction )
+        .await?;
+
+    if user.is_none() {
+        return Err( DbError::NotRegistered )
+    }
+
+    let owned_characters: Vec<Character> = sqlx::query_as( characters::SELECT_BY_OWNER_ID )
+        .bind( discord_id as i64 )  // fk_discordId
+        .fetch_all( &mut *transaction )
+        .await?;
+
+    for character in owned_characters.iter() {
+        super::characters::remove_with_dependents( &mut transaction, character.character_id ).await?;
+    }
+
+    sqlx::query( discord_users::REMOVE_ENTRY )
+        .bind( discord_id as i64 )  // pk_discordId
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok( owned_characters.into_iter().map( |character| character.character_id ).collect() )
+}
+
+/// Set the character a user is currently playing as
+pub async fn set_current_character( database_connection: &SqlitePool, discord_id: u64, character_id: CharacterId ) -> Result<(), DbError> {
+    sqlx::query( discord_users::SET_CURRENT_CHARACTER )
+        .bind( discord_id as i64 )  // pk_discordId
+        .bind( character_id )       // fk_currentCharacter
+        .execute( database_connection )
+        .await?;
+
+    Ok(())
+}
+
+/// Get every user that has a current character set
+pub async fn all_with_current_character( database_connection: &SqlitePool ) -> Result<Vec<DiscordUser>, DbError> {
+    let users = sqlx::query_as( discord_users::SELECT_ALL_CURRENT_CHARACTERS )
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( users )
+}
@@ -0,0 +1,125 @@
+use sqlx::SqlitePool;
+
+use crate::sql_scripts::encounters;
+use super::{sqlite_error_code, Combatant, DbError, Encounter, SQLITE_CONSTRAINT_UNIQUE};
+
+
+/// Start an encounter in a channel, returning it's newly allocated ID
+///
+/// Fails with `EncounterRunning` if the channel already has one
+pub async fn start(
+    database_connection: &SqlitePool,
+    guild_id: Option<u64>,
+    channel_id: u64,
+    game_master_id: u64,
+    name: &str,
+    started_at: i64
+    ) -> Result<i64, DbError> {
+
+    let query_result = sqlx::query_scalar( encounters::START_ENCOUNTER )
+    // -= Bind Values =- //
+        .bind( guild_id.map(|id| id as i64) )   // guildId
+        .bind( channel_id as i64 )              // channelId
+        .bind( game_master_id as i64 )          // gameMasterId
+        .bind( name )                           // encounterName
+        .bind( started_at )                     // startedAt
+    // =-
+        .fetch_one( database_connection )
+        .await;
+
+    match query_result {
+        Ok( encounter_id ) => Ok( encounter_id ),
+        Err( why ) if sqlite_error_code(&why) == Some(SQLITE_CONSTRAINT_UNIQUE) => Err( DbError::EncounterRunning ),
+        Err( why ) => Err( why.into() )
+    }
+}
+
+/// Get an encounter by it's ID. Encounters that have ended are gone
+pub async fn get( database_connection: &SqlitePool, encounter_id: i64 ) -> Result<Option<Encounter>, DbError> {
+    let encounter = sqlx::query_as( encounters::SELECT_BY_ID )
+        .bind( encounter_id )   // pk_encounterId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( encounter )
+}
+
+/// Get the encounter running in a channel, if there is one
+pub async fn get_by_channel( database_connection: &SqlitePool, channel_id: u64 ) -> Result<Option<Encounter>, DbError> {
+    let encounter = sqlx::query_as( encounters::SELECT_BY_CHANNEL )
+        .bind( channel_id as i64 )  // channelId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( encounter )
+}
+
+/// Move an encounter onto the given turn. Two people pressing Next at once shouldn't skip a
+/// turn, so this only happens if the encounter is still on the turn it was read with. Returns
+/// whether it was moved
+pub async fn set_turn( database_connection: &SqlitePool, encounter: &Encounter, round: i64, current_combatant_id: Option<i64> ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( encounters::SET_TURN )
+    // -= Bind Values =- //
+        .bind( round )                              // round
+        .bind( current_combatant_id )               // fk_currentCombatantId
+        .bind( encounter.encounter_id )             // pk_encounterId
+        .bind( encounter.round )                    // round, as read
+        .bind( encounter.current_combatant_id )     // fk_currentCombatantId, as read
+    // =-
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// End an encounter, removing it along with everyone in it. Returns whether there was one to end
+pub async fn end( database_connection: &SqlitePool, encounter_id: i64 ) -> Result<bool, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    sqlx::query( encounters::REMOVE_COMBATANTS )
+        .bind( encounter_id )   // fk_encounterId
+        .execute( &mut *transaction )
+        .await?;
+
+    let query_result = sqlx::query( encounters::REMOVE_ENCOUNTER )
+        .bind( encounter_id )   // pk_encounterId
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Add someone to an encounter, returning their newly allocated ID. The combatant's
+/// `combatant_id` is ignored
+///
+/// Fails with `AlreadyJoined` if their character is already in the encounter
+pub async fn add_combatant( database_connection: &SqlitePool, combatant: &Combatant ) -> Result<i64, DbError> {
+    let query_result = sqlx::query_scalar( encounters::ADD_COMBATANT )
+    // -= Bind Values =- //
+        .bind( combatant.encounter_id )     // fk_encounterId
+        .bind( combatant.character_id )     // fk_characterId
+        .bind( combatant.owner_id )         // discordId
+        .bind( &combatant.name )            // combatantName
+        .bind( combatant.initiative )       // initiative
+        .bind( combatant.dexterity )        // dexterity
+    // =-
+        .fetch_one( database_connection )
+        .await;
+
+    match query_result {
+        Ok( combatant_id ) => Ok( combatant_id ),
+        Err( why ) if sqlite_error_code(&why) == Some(SQLITE_CONSTRAINT_UNIQUE) => Err( DbError::AlreadyJoined ),
+        Err( why ) => Err( why.into() )
+    }
+}
+
+/// Get everyone in an encounter, in the order they take their turns
+pub async fn turn_order( database_connection: &SqlitePool, encounter_id: i64 ) -> Result<Vec<Combatant>, DbError> {
+    let combatants = sqlx::query_as( encounters::SELECT_TURN_ORDER )
+        .bind( encounter_id )   // fk_encounterId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( combatants )
+}
@@ -0,0 +1,43 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    equipment::Slot,
+    sql_scripts::equipment,
+    utils::CharacterId
+};
+use super::{DbError, EquippedItem};
+
+
+/// Get everything a character has equipped, in the order of `Slot::ALL`
+pub async fn get_equipped( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Vec<EquippedItem>, DbError> {
+    let mut equipped: Vec<EquippedItem> = sqlx::query_as( equipment::SELECT_EQUIPPED )
+        .bind( character_id )   // fk_characterId
+        .fetch_all( database_connection )
+        .await?;
+
+    equipped.sort_by_key( |equipped_item| Slot::ALL.iter().position( |slot| slot.id() == equipped_item.slot ) );
+    Ok( equipped )
+}
+
+/// Equip an item from a character's inventory into it's slot, replacing whatever was in there.
+/// Returns whether it was equipped, which it isn't unless they're carrying it and it has a slot
+pub async fn equip( database_connection: &SqlitePool, character_id: CharacterId, item_id: i64 ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( equipment::EQUIP )
+        .bind( character_id )   // fk_characterId
+        .bind( item_id )        // fk_itemId
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Take whatever's in one of a character's slots off. Returns whether there was anything
+pub async fn unequip( database_connection: &SqlitePool, character_id: CharacterId, slot: Slot ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( equipment::UNEQUIP )
+        .bind( character_id )   // fk_characterId
+        .bind( slot.id() )      // slot
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
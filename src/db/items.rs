@@ -0,0 +1,286 @@
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::{
+    attributes::AttributeSpread,
+    equipment::Slot,
+    sql_scripts::{equipment, items},
+    utils::CharacterId
+};
+use super::{sqlite_error_code, DbError, InventoryItem, Item, SQLITE_CONSTRAINT_UNIQUE};
+
+
+/// Turn a UNIQUE constraint failure on the item's name into `DuplicateItem`
+fn duplicate_item( error: sqlx::Error ) -> DbError {
+    match sqlite_error_code( &error ) == Some( SQLITE_CONSTRAINT_UNIQUE ) {
+        true  => DbError::DuplicateItem,
+        false => error.into()
+    }
+}
+
+/// Add an item to it's server's catalogue, returning it's newly allocated ID. The item's
+/// `item_id` is ignored
+///
+/// Fails with `DuplicateItem` if the server already has an item of the same name
+pub async fn add( database_connection: &SqlitePool, item: &Item ) -> Result<i64, DbError> {
+    sqlx::query_scalar( items::ADD_ITEM )
+    // -= Bind Values =- //
+        .bind( item.guild_id )      // guildId
+        .bind( &item.name )         // itemName
+        .bind( item.weight )        // weight
+        .bind( item.value )         // value
+        .bind( &item.description )  // description
+        .bind( &item.tags )         // tags
+    // =-
+        .fetch_one( database_connection )
+        .await
+        .map_err( duplicate_item )
+}
+
+/// Get a single item from a server's catalogue by it's ID
+pub async fn get( database_connection: &SqlitePool, guild_id: u64, item_id: i64 ) -> Result<Option<Item>, DbError> {
+    let item = sqlx::query_as( items::SELECT_BY_ID )
+        .bind( item_id )            // pk_itemId
+        .bind( guild_id as i64 )    // guildId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( item )
+}
+
+/// Get every item in a server's catalogue, ordered by name
+pub async fn get_catalogue( database_connection: &SqlitePool, guild_id: u64 ) -> Result<Vec<Item>, DbError> {
+    let catalogue = sqlx::query_as( items::SELECT_BY_GUILD )
+        .bind( guild_id as i64 )    // guildId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( catalogue )
+}
+
+/// Overwrite an item in it's server's catalogue. Returns whether there was one to overwrite
+///
+/// Fails with `DuplicateItem` if it's renamed to the name of another item in the server
+pub async fn update( database_connection: &SqlitePool, item: &Item ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( items::UPDATE_ITEM )
+    // -= Bind Values =- //
+        .bind( &item.name )         // itemName
+        .bind( item.weight )        // weight
+        .bind( item.value )         // value
+        .bind( &item.description )  // description
+        .bind( &item.tags )         // tags
+        .bind( item.item_id )       // pk_itemId
+        .bind( item.guild_id )      // guildId
+    // =-
+        .execute( database_connection )
+        .await
+        .map_err( duplicate_item )?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Set the slot an item is equipped into and the modifiers it gives, taking it off everyone who
+/// has it equipped in another slot. Returns whether there was one to change
+pub async fn set_gear( database_connection: &SqlitePool, guild_id: u64, item_id: i64, slot: Option<Slot>, modifiers: &AttributeSpread ) -> Result<bool, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    let [ strength, dexterity, preception, knowledge, constitution, casting ] = modifiers.0;
+    let query_result = sqlx::query( items::SET_GEAR )
+    // -= Bind Values =- //
+        .bind( slot.map(|slot| slot.id()) )     // slot
+        .bind( strength )                       // strengthModifier
+        .bind( dexterity )                      // dexterityModifier
+        .bind( preception )                     // preceptionModifier
+        .bind( knowledge )                      // knowledgeModifier
+        .bind( constitution )                   // constitutionModifier
+        .bind( casting )                        // castingModifier
+        .bind( item_id )                        // pk_itemId
+        .bind( guild_id as i64 )                // guildId
+    // =-
+        .execute( &mut *transaction )
+        .await?;
+
+    // Only touch the equipment once we know the item is in this server's catalogue
+    if query_result.rows_affected() == 0 {
+        return Ok( false )
+    }
+
+    sqlx::query( equipment::UNEQUIP_OTHER_SLOTS )
+        .bind( item_id )                        // fk_itemId
+        .bind( slot.map(|slot| slot.id()) )     // slot
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok( true )
+}
+
+/// Remove an item from a server's catalogue, and from every inventory it's in. Returns whether
+/// there was one to remove
+pub async fn remove( database_connection: &SqlitePool, guild_id: u64, item_id: i64 ) -> Result<bool, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    // Make sure the item is in this server's catalogue before emptying anyone's inventory of it
+    let item: Option<Item> = sqlx::query_as( items::SELECT_BY_ID )
+        .bind( item_id )            // pk_itemId
+        .bind( guild_id as i64 )    // guildId
+        .fetch_optional( &mut *transaction )
+        .await?;
+    if item.is_none() {
+        return Ok( false )
+    }
+
+    sqlx::query( equipment::REMOVE_BY_ITEM_ID )
+        .bind( item_id )    // fk_itemId
+        .execute( &mut *transaction )
+        .await?;
+
+    sqlx::query( items::REMOVE_BY_ITEM_ID )
+        .bind( item_id )    // fk_itemId
+        .execute( &mut *transaction )
+        .await?;
+
+    let query_result = sqlx::query( items::REMOVE_ITEM )
+        .bind( item_id )            // pk_itemId
+        .bind( guild_id as i64 )    // guildId
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok( query_result.rows_affected() > 0 )
+}
+
+
+/// Get everything a character is carrying, ordered by name
+pub async fn get_inventory( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Vec<InventoryItem>, DbError> {
+    let inventory = sqlx::query_as( items::SELECT_INVENTORY )
+        .bind( character_id )   // fk_characterId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( inventory )
+}
+
+/// Get the total weight of everything a character is carrying
+pub async fn carried_weight( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<i64, DbError> {
+    let weight = sqlx::query_scalar( items::SELECT_CARRIED_WEIGHT )
+        .bind( character_id )   // fk_characterId
+        .fetch_one( database_connection )
+        .await?;
+
+    Ok( weight )
+}
+
+/// Put items into a character's inventory, as part of a larger transaction. Returns the weight
+/// they carry afterwards
+///
+/// Fails with `Overburdened` if the items add weight and take them past `carry_capacity`. The
+/// items are already added by then, so the transaction has to be dropped
+async fn put_into( connection: &mut SqliteConnection, character_id: CharacterId, item_id: i64, quantity: i64, carry_capacity: i64 ) -> Result<i64, DbError> {
+    let weight_before: i64 = sqlx::query_scalar( items::SELECT_CARRIED_WEIGHT )
+        .bind( character_id )   // fk_characterId
+        .fetch_one( &mut *connection )
+        .await?;
+
+    sqlx::query( items::ADD_TO_INVENTORY )
+    // -= Bind Values =- //
+        .bind( character_id )   // fk_characterId
+        .bind( item_id )        // fk_itemId
+        .bind( quantity )       // quantity
+    // =-
+        .execute( &mut *connection )
+        .await?;
+
+    let weight_after: i64 = sqlx::query_scalar( items::SELECT_CARRIED_WEIGHT )
+        .bind( character_id )   // fk_characterId
+        .fetch_one( &mut *connection )
+        .await?;
+
+    // Weightless items can still be picked up by a character who's already overburdened
+    match weight_after > weight_before && weight_after > carry_capacity {
+        true  => Err( DbError::Overburdened ),
+        false => Ok( weight_after )
+    }
+}
+
+/// Take items out of a character's inventory, as part of a larger transaction. If that was the
+/// last of them, they're unequipped too
+///
+/// Fails with `NotEnoughItems` if they don't have that many
+async fn take_out( connection: &mut SqliteConnection, character_id: CharacterId, item_id: i64, quantity: i64 ) -> Result<(), DbError> {
+    let query_result = sqlx::query( items::TAKE_FROM_INVENTORY )
+    // -= Bind Values =- //
+        .bind( character_id )   // fk_characterId
+        .bind( item_id )        // fk_itemId
+        .bind( quantity )       // quantity
+    // =-
+        .execute( &mut *connection )
+        .await?;
+
+    if query_result.rows_affected() == 0 {
+        return Err( DbError::NotEnoughItems )
+    }
+
+    sqlx::query( items::REMOVE_EMPTY )
+        .bind( character_id )   // fk_characterId
+        .bind( item_id )        // fk_itemId
+        .execute( &mut *connection )
+        .await?;
+
+    sqlx::query( equipment::UNEQUIP_IF_NOT_CARRIED )
+        .bind( character_id )   // fk_characterId
+        .bind( item_id )        // fk_itemId
+        .execute( &mut *connection )
+        .await?;
+
+    Ok(())
+}
+
+/// Put items into a character's inventory. Returns the weight they carry afterwards
+///
+/// Fails with `Overburdened` if they'd carry more than `carry_capacity`, in which case nothing is
+/// added
+pub async fn add_to_inventory( database_connection: &SqlitePool, character_id: CharacterId, item_id: i64, quantity: i64, carry_capacity: i64 ) -> Result<i64, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    // Dropping the transaction on an error rolls it back
+    let carried_weight = put_into( &mut transaction, character_id, item_id, quantity, carry_capacity ).await?;
+
+    transaction.commit().await?;
+    Ok( carried_weight )
+}
+
+/// Take items out of a character's inventory
+///
+/// Fails with `NotEnoughItems` if they don't have that many, in which case nothing is taken
+pub async fn take_from_inventory( database_connection: &SqlitePool, character_id: CharacterId, item_id: i64, quantity: i64 ) -> Result<(), DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    take_out( &mut transaction, character_id, item_id, quantity ).await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Move items from one character's inventory into another's, inside of one transaction so that
+/// they're never lost or duplicated along the way. Returns the weight the receiver carries
+/// afterwards
+///
+/// Fails with `NotEnoughItems` if the giver doesn't have that many, and with `Overburdened` if
+/// the receiver would carry more than `carry_capacity`. Either way nothing changes hands
+pub async fn transfer(
+    database_connection: &SqlitePool,
+    giver_id: CharacterId,
+    receiver_id: CharacterId,
+    item_id: i64,
+    quantity: i64,
+    carry_capacity: i64
+    ) -> Result<i64, DbError> {
+
+    let mut transaction = database_connection.begin().await?;
+
+    take_out( &mut transaction, giver_id, item_id, quantity ).await?;
+    let carried_weight = put_into( &mut transaction, receiver_id, item_id, quantity, carry_capacity ).await?;
+
+    transaction.commit().await?;
+    Ok( carried_weight )
+}
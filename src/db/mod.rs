@@ -0,0 +1,387 @@
+// Typed access to the database
+//
+// Every table gets a struct its rows are read into, and a module of async functions wrapping the
+// queries in `sql_scripts`. Commands should go through these instead of binding parameters to the
+// raw queries themselves, so that the bind order and the parsing of SQLite's error codes only live
+// in one place
+
+use std::fmt;
+
+use sqlx::FromRow;
+
+use crate::{
+    attributes::AttributeSpread,
+    equipment::Slot,
+    utils::CharacterId
+};
+
+pub mod discord_users;
+pub mod characters;
+pub mod attributes;
+pub mod abilities;
+pub mod rolls;
+pub mod encounters;
+pub mod resources;
+pub mod spells;
+pub mod progression;
+pub mod items;
+pub mod equipment;
+
+
+/// SQLite's extended error code for a FOREIGN KEY constraint failure
+const SQLITE_CONSTRAINT_FOREIGNKEY: i64 = 787;
+/// SQLite's extended error code for a PRIMARY KEY constraint failure
+const SQLITE_CONSTRAINT_PRIMARYKEY: i64 = 1555;
+/// SQLite's extended error code for a UNIQUE constraint failure
+const SQLITE_CONSTRAINT_UNIQUE: i64 = 2067;
+
+
+/// Everything that can go wrong when talking to the database
+#[derive(Debug)]
+pub enum DbError {
+    /// The user doesn't have a profile in the DiscordUsers table
+    NotRegistered,
+    /// The user already has a profile in the DiscordUsers table
+    AlreadyRegistered,
+    /// The user already has a character of the given name
+    DuplicateCharacter,
+    /// The character's attributes have already been allocated
+    AttributesAlreadySet,
+    /// There's already an encounter running in the channel
+    EncounterRunning,
+    /// The character is already taking part in the encounter
+    AlreadyJoined,
+    /// There's already a spell of the given name
+    DuplicateSpell,
+    /// There's already an item of the given name in the server's catalogue
+    DuplicateItem,
+    /// The character doesn't have as many of the item as were asked for
+    NotEnoughItems,
+    /// The items would take the character past their carry capacity
+    Overburdened,
+    /// Anything we don't have a bespoke variant for
+    Sqlx( sqlx::Error )
+}
+impl From<sqlx::Error> for DbError {
+    fn from( error: sqlx::Error ) -> Self {
+        DbError::Sqlx( error )
+    }
+}
+impl fmt::Display for DbError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result {
+        match self {
+            DbError::NotRegistered        => write!(f, "User is not registered"),
+            DbError::AlreadyRegistered    => write!(f, "User is already registered"),
+            DbError::DuplicateCharacter   => write!(f, "User already has a character of that name"),
+            DbError::AttributesAlreadySet => write!(f, "Character already has attributes"),
+            DbError::EncounterRunning     => write!(f, "Channel already has an encounter running"),
+            DbError::AlreadyJoined        => write!(f, "Character is already in the encounter"),
+            DbError::DuplicateSpell       => write!(f, "There's already a spell of that name"),
+            DbError::DuplicateItem        => write!(f, "There's already an item of that name"),
+            DbError::NotEnoughItems       => write!(f, "Character doesn't have enough of that item"),
+            DbError::Overburdened         => write!(f, "Character can't carry that much"),
+            DbError::Sqlx( why )          => write!(f, "{why}"),
+        }
+    }
+}
+
+/// Get SQLite's extended error code out of an error, if it has one
+fn sqlite_error_code( error: &sqlx::Error ) -> Option<i64> {
+    match error {
+        sqlx::Error::Database( sqlite_error ) => sqlite_error.code()?.parse().ok(),
+        _ => None
+    }
+}
+
+
+/// A row of the DiscordUsers table
+#[derive(Debug, Clone, FromRow)]
+pub struct DiscordUser {
+    #[sqlx(rename = "pk_discordId")]
+    pub discord_id:        i64,
+    #[sqlx(rename = "fk_currentCharacter")]
+    pub current_character: Option<CharacterId>
+}
+
+/// A row of the Characters table
+#[derive(Debug, Clone, FromRow)]
+pub struct Character {
+    #[sqlx(rename = "pk_characterId")]
+    pub character_id: CharacterId,
+    #[sqlx(rename = "fk_discordId")]
+    pub owner_id:     i64,
+    #[sqlx(rename = "pk_name")]
+    pub name:         String,
+    pub species:      String,
+    pub backstory:    String,
+    /// When the character was moved into the trash, as seconds since the unix epoch
+    pub archived_at:  Option<i64>
+}
+
+/// A row of the Atributes table
+#[derive(Debug, Clone, FromRow)]
+pub struct Attributes {
+    #[sqlx(rename = "fk_pk_characterId")]
+    pub character_id: CharacterId,
+    #[sqlx(rename = "Strength")]
+    pub strength:     i64,
+    #[sqlx(rename = "Dexterity")]
+    pub dexterity:    i64,
+    #[sqlx(rename = "Preception")]
+    pub preception:   i64,
+    #[sqlx(rename = "Knowledge")]
+    pub knowledge:    i64,
+    #[sqlx(rename = "Constitution")]
+    pub constitution: i64,
+    #[sqlx(rename = "Casting")]
+    pub casting:      i64
+}
+impl Attributes {
+    pub fn from_spread( character_id: CharacterId, spread: &AttributeSpread ) -> Attributes {
+        let [ strength, dexterity, preception, knowledge, constitution, casting ] = spread.0;
+        Attributes { character_id, strength, dexterity, preception, knowledge, constitution, casting }
+    }
+
+    pub fn spread( &self ) -> AttributeSpread {
+        AttributeSpread([
+            self.strength, self.dexterity, self.preception,
+            self.knowledge, self.constitution, self.casting
+        ])
+    }
+}
+
+/// A row of the CharacterResources table
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Resources {
+    #[sqlx(rename = "fk_characterId")]
+    pub character_id: CharacterId,
+    #[sqlx(rename = "currentHp")]
+    pub current_hp:   i64,
+    #[sqlx(rename = "currentMana")]
+    pub current_mana: i64
+}
+
+/// A row of the CharacterAbilities table
+#[derive(Debug, Clone, FromRow)]
+pub struct Ability {
+    #[sqlx(rename = "fk_pk_characterId")]
+    pub character_id: CharacterId,
+    #[sqlx(rename = "pk_abilityId")]
+    pub ability_id:   i64,
+    #[sqlx(rename = "abilityName")]
+    pub name:         String,
+    #[sqlx(rename = "abilityDescription")]
+    pub description:  String
+}
+
+/// A row of the Rolls table
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct RollRecord {
+    #[sqlx(rename = "pk_rollId")]
+    pub roll_id:        i64,
+    #[sqlx(rename = "discordId")]
+    pub discord_id:     i64,
+    #[sqlx(rename = "fk_characterId")]
+    pub character_id:   Option<CharacterId>,
+    #[sqlx(rename = "characterName")]
+    pub character_name: Option<String>,
+    #[sqlx(rename = "guildId")]
+    pub guild_id:       Option<i64>,
+    #[sqlx(rename = "channelId")]
+    pub channel_id:     i64,
+    pub label:          String,
+    pub expression:     String,
+    pub total:          i64,
+    #[sqlx(rename = "naturalD20")]
+    pub natural_d20:    Option<i64>,
+    /// Seconds since the unix epoch
+    #[sqlx(rename = "rolledAt")]
+    pub rolled_at:      i64
+}
+
+/// A user's rolls as one of their characters, summed up
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct RollStats {
+    #[sqlx(rename = "fk_characterId")]
+    pub character_id:     Option<CharacterId>,
+    /// `None` for rolls made without a character
+    #[sqlx(rename = "characterName")]
+    pub character_name:   Option<String>,
+    #[sqlx(rename = "rollCount")]
+    pub roll_count:       i64,
+    #[sqlx(rename = "averageTotal")]
+    pub average_total:    f64,
+    /// `None` when none of the rolls had a natural d20
+    #[sqlx(rename = "averageNatural")]
+    pub average_natural:  Option<f64>,
+    #[sqlx(rename = "naturalTwenties")]
+    pub natural_twenties: i64,
+    #[sqlx(rename = "naturalOnes")]
+    pub natural_ones:     i64
+}
+
+/// A row of the Encounters table
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Encounter {
+    #[sqlx(rename = "pk_encounterId")]
+    pub encounter_id:         i64,
+    #[sqlx(rename = "guildId")]
+    pub guild_id:             Option<i64>,
+    #[sqlx(rename = "channelId")]
+    pub channel_id:           i64,
+    #[sqlx(rename = "gameMasterId")]
+    pub game_master_id:       i64,
+    #[sqlx(rename = "encounterName")]
+    pub name:                 String,
+    /// 0 until the first turn is taken
+    pub round:                i64,
+    #[sqlx(rename = "fk_currentCombatantId")]
+    pub current_combatant_id: Option<i64>,
+    /// Seconds since the unix epoch
+    #[sqlx(rename = "startedAt")]
+    pub started_at:           i64
+}
+
+/// A row of the Combatants table
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Combatant {
+    #[sqlx(rename = "pk_combatantId")]
+    pub combatant_id: i64,
+    #[sqlx(rename = "fk_encounterId")]
+    pub encounter_id: i64,
+    /// `None` for NPCs
+    #[sqlx(rename = "fk_characterId")]
+    pub character_id: Option<CharacterId>,
+    /// Whoever added them to the encounter
+    #[sqlx(rename = "discordId")]
+    pub owner_id:     i64,
+    #[sqlx(rename = "combatantName")]
+    pub name:         String,
+    pub initiative:   i64,
+    pub dexterity:    i64
+}
+
+/// A row of the Spells table
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Spell {
+    #[sqlx(rename = "pk_spellId")]
+    pub spell_id:   i64,
+    /// The server whose catalogue it's in
+    #[sqlx(rename = "guildId")]
+    pub guild_id:   i64,
+    #[sqlx(rename = "spellName")]
+    pub name:       String,
+    pub school:     String,
+    #[sqlx(rename = "manaCost")]
+    pub mana_cost:  i64,
+    /// The Casting check needs to reach this
+    pub difficulty: i64,
+    pub effect:     String
+}
+
+/// A row of the CharacterProgression table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct Progression {
+    #[sqlx(rename = "fk_characterId")]
+    pub character_id:   CharacterId,
+    pub experience:     i64,
+    pub level:          i64,
+    /// Attribute points granted by levelling up that haven't been allocated yet
+    #[sqlx(rename = "unspentPoints")]
+    pub unspent_points: i64
+}
+impl Progression {
+    /// Where characters without a row stand: level 1, with no experience
+    pub fn starting( character_id: CharacterId ) -> Progression {
+        Progression { character_id, experience: 0, level: 1, unspent_points: 0 }
+    }
+}
+
+/// A row of the LevelUps table
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct LevelUp {
+    #[sqlx(rename = "pk_levelUpId")]
+    pub level_up_id:    i64,
+    #[sqlx(rename = "fk_characterId")]
+    pub character_id:   CharacterId,
+    pub level:          i64,
+    pub experience:     i64,
+    #[sqlx(rename = "pointsGranted")]
+    pub points_granted: i64,
+    #[sqlx(rename = "awardedBy")]
+    pub awarded_by:     i64,
+    #[sqlx(rename = "levelledAt")]
+    pub levelled_at:    i64
+}
+
+/// A row of the Items table
+#[derive(Debug, Clone, Default, PartialEq, Eq, FromRow)]
+pub struct Item {
+    #[sqlx(rename = "pk_itemId")]
+    pub item_id:               i64,
+    /// The server whose catalogue it's in
+    #[sqlx(rename = "guildId")]
+    pub guild_id:              i64,
+    #[sqlx(rename = "itemName")]
+    pub name:                  String,
+    /// In the same units as carry capacity
+    pub weight:                i64,
+    pub value:                 i64,
+    pub description:           String,
+    /// Comma separated, like `weapon, melee`
+    pub tags:                  String,
+    /// The id of the slot it's equipped into, `None` if it can't be
+    pub slot:                  Option<String>,
+    #[sqlx(rename = "strengthModifier")]
+    pub strength_modifier:     i64,
+    #[sqlx(rename = "dexterityModifier")]
+    pub dexterity_modifier:    i64,
+    #[sqlx(rename = "preceptionModifier")]
+    pub preception_modifier:   i64,
+    #[sqlx(rename = "knowledgeModifier")]
+    pub knowledge_modifier:    i64,
+    #[sqlx(rename = "constitutionModifier")]
+    pub constitution_modifier: i64,
+    #[sqlx(rename = "castingModifier")]
+    pub casting_modifier:      i64
+}
+impl Item {
+    /// The slot it's equipped into, `None` if it can't be
+    pub fn equip_slot( &self ) -> Option<Slot> {
+        Slot::from_id( self.slot.as_deref()? )
+    }
+
+    /// What having it equipped adds to each attribute
+    pub fn modifiers( &self ) -> AttributeSpread {
+        AttributeSpread([
+            self.strength_modifier, self.dexterity_modifier, self.preception_modifier,
+            self.knowledge_modifier, self.constitution_modifier, self.casting_modifier
+        ])
+    }
+
+    /// Set what having it equipped adds to each attribute
+    pub fn set_modifiers( &mut self, modifiers: &AttributeSpread ) {
+        [
+            self.strength_modifier, self.dexterity_modifier, self.preception_modifier,
+            self.knowledge_modifier, self.constitution_modifier, self.casting_modifier
+        ] = modifiers.0;
+    }
+}
+
+/// A row of the InventoryItems table, along with the item it refers to
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct InventoryItem {
+    #[sqlx(flatten)]
+    pub item:     Item,
+    pub quantity: i64
+}
+
+/// A row of the EquippedItems table, along with the item it refers to
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct EquippedItem {
+    /// The id of the slot it's equipped in
+    #[sqlx(rename = "equippedSlot")]
+    pub slot: String,
+    #[sqlx(flatten)]
+    pub item: Item
+}
@@ -0,0 +1,123 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::{attributes, progression},
+    utils::CharacterId
+};
+use super::{Attributes, DbError, LevelUp, Progression};
+
+
+/// Get a character's experience, level and unspent attribute points. Characters without a row
+/// get `Progression::starting`
+pub async fn get( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Progression, DbError> {
+    let stored: Option<Progression> = sqlx::query_as( progression::SELECT_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( stored.unwrap_or( Progression::starting(character_id) ) )
+}
+
+/// Read a character's progression and replace it with what `award` makes of it, recording the
+/// level ups it hands back alongside. Both happen inside of one transaction, so two awards made
+/// at once can't overwrite each other. Returns the progression from before and after
+pub async fn award(
+    database_connection: &SqlitePool,
+    character_id: CharacterId,
+    award: impl FnOnce( &Progression ) -> (Progression, Vec<LevelUp>)
+    ) -> Result<(Progression, Progression), DbError> {
+
+    let mut transaction = database_connection.begin().await?;
+
+    let stored: Option<Progression> = sqlx::query_as( progression::SELECT_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .fetch_optional( &mut *transaction )
+        .await?;
+    let stored = stored.unwrap_or( Progression::starting(character_id) );
+
+    let ( updated, level_ups ) = award( &stored );
+
+    sqlx::query( progression::SET_PROGRESSION )
+    // -= Bind Values =- //
+        .bind( character_id )               // fk_characterId
+        .bind( updated.experience )         // experience
+        .bind( updated.level )              // level
+        .bind( updated.unspent_points )     // unspentPoints
+    // =-
+        .execute( &mut *transaction )
+        .await?;
+
+    for level_up in level_ups.iter() {
+        sqlx::query( progression::ADD_LEVEL_UP )
+        // -= Bind Values =- //
+            .bind( character_id )               // fk_characterId
+            .bind( level_up.level )             // level
+            .bind( level_up.experience )        // experience
+            .bind( level_up.points_granted )    // pointsGranted
+            .bind( level_up.awarded_by )        // awardedBy
+            .bind( level_up.levelled_at )       // levelledAt
+        // =-
+            .execute( &mut *transaction )
+            .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(( stored, updated ))
+}
+
+/// Save attributes raised with unspent points, taking the points away in the same transaction.
+/// `expected_points` is how many the character had when they started allocating, if they've got
+/// a different amount by now the points were already spent elsewhere and nothing is saved. Nor is
+/// it for characters whose attributes were never allocated. Returns whether the attributes were
+/// saved
+pub async fn spend_points(
+    database_connection: &SqlitePool,
+    character_attributes: &Attributes,
+    expected_points: i64,
+    points_spent: i64
+    ) -> Result<bool, DbError> {
+
+    let mut transaction = database_connection.begin().await?;
+
+    let query_result = sqlx::query( progression::SPEND_POINTS )
+        .bind( character_attributes.character_id )  // fk_characterId
+        .bind( points_spent )                       // Points spent
+        .bind( expected_points )                    // unspentPoints
+        .execute( &mut *transaction )
+        .await?;
+
+    // Dropping the transaction rolls it back
+    if query_result.rows_affected() == 0 {
+        return Ok( false )
+    }
+
+    let query_result = sqlx::query( attributes::UPDATE_ATTRIBUTES )
+    // -= Bind Values =- //
+        .bind( character_attributes.character_id )  // fk_pk_characterId
+        .bind( character_attributes.strength )      // Strength
+        .bind( character_attributes.dexterity )     // Dexterity
+        .bind( character_attributes.preception )    // Preception
+        .bind( character_attributes.knowledge )     // Knowledge
+        .bind( character_attributes.constitution )  // Constitution
+        .bind( character_attributes.casting )       // Casting
+    // =-
+        .execute( &mut *transaction )
+        .await?;
+
+    if query_result.rows_affected() == 0 {
+        return Ok( false )
+    }
+
+    transaction.commit().await?;
+    Ok( true )
+}
+
+/// Get every level a character gained, the earliest first
+pub async fn get_level_ups( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Vec<LevelUp>, DbError> {
+    let level_ups = sqlx::query_as( progression::SELECT_LEVEL_UPS )
+        .bind( character_id )   // fk_characterId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( level_ups )
+}
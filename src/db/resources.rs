@@ -0,0 +1,53 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::resources,
+    utils::CharacterId
+};
+use super::{DbError, Resources};
+
+
+/// Get how much HP and mana a character has left. `None` means they're at full
+pub async fn get( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Option<Resources>, DbError> {
+    let character_resources = sqlx::query_as( resources::SELECT_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( character_resources )
+}
+
+/// Read a character's resources and replace them with what `update` makes of them, inside of one
+/// transaction so that two changes made at once can't overwrite each other. When `update` gives
+/// back `None` nothing is changed. Returns the resources from before and after
+pub async fn update(
+    database_connection: &SqlitePool,
+    character_id: CharacterId,
+    update: impl FnOnce( Option<&Resources> ) -> Option<Resources>
+    ) -> Result<(Option<Resources>, Option<Resources>), DbError> {
+
+    let mut transaction = database_connection.begin().await?;
+
+    let stored: Option<Resources> = sqlx::query_as( resources::SELECT_BY_CHARACTER_ID )
+        .bind( character_id )   // fk_characterId
+        .fetch_optional( &mut *transaction )
+        .await?;
+
+    let updated = match update( stored.as_ref() ) {
+        Some( updated ) => updated,
+        // Dropping the transaction rolls it back
+        None => return Ok(( stored, None ))
+    };
+
+    sqlx::query( resources::SET_RESOURCES )
+    // -= Bind Values =- //
+        .bind( character_id )           // fk_characterId
+        .bind( updated.current_hp )     // currentHp
+        .bind( updated.current_mana )   // currentMana
+    // =-
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok(( stored, Some(updated) ))
+}
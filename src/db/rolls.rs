@@ -0,0 +1,96 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::rolls,
+    utils::CharacterId
+};
+use super::{DbError, RollRecord, RollStats};
+
+
+/// Record a roll. It's `roll_id` is ignored, as the database allocates one
+pub async fn add( database_connection: &SqlitePool, roll: &RollRecord ) -> Result<(), DbError> {
+    sqlx::query( rolls::ADD_ROLL )
+    // -= Bind Values =- //
+        .bind( roll.discord_id )        // discordId
+        .bind( roll.character_id )      // fk_characterId
+        .bind( &roll.character_name )   // characterName
+        .bind( roll.guild_id )          // guildId
+        .bind( roll.channel_id )        // channelId
+        .bind( &roll.label )            // label
+        .bind( &roll.expression )       // expression
+        .bind( roll.total )             // total
+        .bind( roll.natural_d20 )       // naturalD20
+        .bind( roll.rolled_at )         // rolledAt
+    // =-
+        .execute( database_connection )
+        .await?;
+
+    Ok(())
+}
+
+/// Get a page of a user's rolls in a guild, newest first. Only the given character's rolls are
+/// included if there is one
+pub async fn get_page(
+    database_connection: &SqlitePool,
+    discord_id: u64,
+    guild_id: Option<u64>,
+    character_id: Option<CharacterId>,
+    page: usize,
+    page_size: usize
+    ) -> Result<Vec<RollRecord>, DbError> {
+
+    let page_rolls = sqlx::query_as( rolls::SELECT_PAGE )
+        .bind( discord_id as i64 )                  // discordId
+        .bind( guild_id.map(|id| id as i64) )       // guildId
+        .bind( character_id )                       // fk_characterId
+        .bind( page_size as i64 )                   // LIMIT
+        .bind( (page * page_size) as i64 )          // OFFSET
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( page_rolls )
+}
+
+/// Count a user's rolls in a guild, filtered the same way as `get_page`
+pub async fn count( database_connection: &SqlitePool, discord_id: u64, guild_id: Option<u64>, character_id: Option<CharacterId> ) -> Result<usize, DbError> {
+    let roll_count: i64 = sqlx::query_scalar( rolls::COUNT_ROLLS )
+        .bind( discord_id as i64 )              // discordId
+        .bind( guild_id.map(|id| id as i64) )   // guildId
+        .bind( character_id )                   // fk_characterId
+        .fetch_one( database_connection )
+        .await?;
+
+    Ok( roll_count as usize )
+}
+
+/// Sum up a user's rolls in a guild, once for every character they rolled as
+pub async fn stats( database_connection: &SqlitePool, discord_id: u64, guild_id: Option<u64> ) -> Result<Vec<RollStats>, DbError> {
+    let character_stats = sqlx::query_as( rolls::SELECT_STATS )
+        .bind( discord_id as i64 )              // discordId
+        .bind( guild_id.map(|id| id as i64) )   // guildId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( character_stats )
+}
+
+/// How often each face of the d20 came up, for the rolls summed up in `stats`. Indexed by the
+/// face minus one
+pub async fn natural_distribution( database_connection: &SqlitePool, discord_id: u64, guild_id: Option<u64>, stats: &RollStats ) -> Result<[i64; 20], DbError> {
+    let face_counts: Vec<(i64, i64)> = sqlx::query_as( rolls::SELECT_NATURAL_DISTRIBUTION )
+        .bind( discord_id as i64 )              // discordId
+        .bind( guild_id.map(|id| id as i64) )   // guildId
+        .bind( stats.character_id )             // fk_characterId
+        .bind( &stats.character_name )          // characterName
+        .fetch_all( database_connection )
+        .await?;
+
+    let mut distribution = [0; 20];
+    for ( face, face_count ) in face_counts {
+        if let Some( slot ) = distribution.get_mut( (face - 1) as usize ) {
+            *slot = face_count;
+        }
+    }
+
+    Ok( distribution )
+}
@@ -0,0 +1,139 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    sql_scripts::spells,
+    utils::CharacterId
+};
+use super::{sqlite_error_code, DbError, Spell, SQLITE_CONSTRAINT_UNIQUE};
+
+
+/// Turn a UNIQUE constraint failure on the spell's name into `DuplicateSpell`
+fn duplicate_spell( error: sqlx::Error ) -> DbError {
+    match sqlite_error_code( &error ) == Some( SQLITE_CONSTRAINT_UNIQUE ) {
+        true  => DbError::DuplicateSpell,
+        false => error.into()
+    }
+}
+
+/// Add a spell to it's server's catalogue, returning it's newly allocated ID. The spell's
+/// `spell_id` is ignored
+///
+/// Fails with `DuplicateSpell` if the server already has a spell of the same name
+pub async fn add( database_connection: &SqlitePool, spell: &Spell ) -> Result<i64, DbError> {
+    sqlx::query_scalar( spells::ADD_SPELL )
+    // -= Bind Values =- //
+        .bind( spell.guild_id )     // guildId
+        .bind( &spell.name )        // spellName
+        .bind( &spell.school )      // school
+        .bind( spell.mana_cost )    // manaCost
+        .bind( spell.difficulty )   // difficulty
+        .bind( &spell.effect )      // effect
+    // =-
+        .fetch_one( database_connection )
+        .await
+        .map_err( duplicate_spell )
+}
+
+/// Get a single spell from a server's catalogue by it's ID
+pub async fn get( database_connection: &SqlitePool, guild_id: u64, spell_id: i64 ) -> Result<Option<Spell>, DbError> {
+    let spell = sqlx::query_as( spells::SELECT_BY_ID )
+        .bind( spell_id )           // pk_spellId
+        .bind( guild_id as i64 )    // guildId
+        .fetch_optional( database_connection )
+        .await?;
+
+    Ok( spell )
+}
+
+/// Get every spell in a server's catalogue, ordered by name
+pub async fn get_catalogue( database_connection: &SqlitePool, guild_id: u64 ) -> Result<Vec<Spell>, DbError> {
+    let catalogue = sqlx::query_as( spells::SELECT_BY_GUILD )
+        .bind( guild_id as i64 )    // guildId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( catalogue )
+}
+
+/// Overwrite a spell in it's server's catalogue. Returns whether there was one to overwrite
+///
+/// Fails with `DuplicateSpell` if it's renamed to the name of another spell in the server
+pub async fn update( database_connection: &SqlitePool, spell: &Spell ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( spells::UPDATE_SPELL )
+    // -= Bind Values =- //
+        .bind( &spell.name )        // spellName
+        .bind( &spell.school )      // school
+        .bind( spell.mana_cost )    // manaCost
+        .bind( spell.difficulty )   // difficulty
+        .bind( &spell.effect )      // effect
+        .bind( spell.spell_id )     // pk_spellId
+        .bind( spell.guild_id )     // guildId
+    // =-
+        .execute( database_connection )
+        .await
+        .map_err( duplicate_spell )?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Remove a spell from a server's catalogue, and from every character who knew it. Returns
+/// whether there was one to remove
+pub async fn remove( database_connection: &SqlitePool, guild_id: u64, spell_id: i64 ) -> Result<bool, DbError> {
+    let mut transaction = database_connection.begin().await?;
+
+    // Make sure the spell is in this server's catalogue before anyone forgets it
+    let spell: Option<Spell> = sqlx::query_as( spells::SELECT_BY_ID )
+        .bind( spell_id )           // pk_spellId
+        .bind( guild_id as i64 )    // guildId
+        .fetch_optional( &mut *transaction )
+        .await?;
+    if spell.is_none() {
+        return Ok( false )
+    }
+
+    sqlx::query( spells::FORGET_BY_SPELL_ID )
+        .bind( spell_id )   // fk_spellId
+        .execute( &mut *transaction )
+        .await?;
+
+    let query_result = sqlx::query( spells::REMOVE_SPELL )
+        .bind( spell_id )           // pk_spellId
+        .bind( guild_id as i64 )    // guildId
+        .execute( &mut *transaction )
+        .await?;
+
+    transaction.commit().await?;
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Teach a character a spell. Returns whether it was new to them
+pub async fn learn( database_connection: &SqlitePool, character_id: CharacterId, spell_id: i64 ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( spells::LEARN_SPELL )
+        .bind( character_id )   // fk_characterId
+        .bind( spell_id )       // fk_spellId
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Make a character forget a spell. Returns whether they knew it
+pub async fn forget( database_connection: &SqlitePool, character_id: CharacterId, spell_id: i64 ) -> Result<bool, DbError> {
+    let query_result = sqlx::query( spells::FORGET_SPELL )
+        .bind( character_id )   // fk_characterId
+        .bind( spell_id )       // fk_spellId
+        .execute( database_connection )
+        .await?;
+
+    Ok( query_result.rows_affected() > 0 )
+}
+
+/// Get every spell a character knows, ordered by name
+pub async fn get_known( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<Vec<Spell>, DbError> {
+    let known_spells = sqlx::query_as( spells::SELECT_KNOWN )
+        .bind( character_id )   // fk_characterId
+        .fetch_all( database_connection )
+        .await?;
+
+    Ok( known_spells )
+}
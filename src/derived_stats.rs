@@ -0,0 +1,155 @@
+// Stats worked out from a character's attributes, rather than allocated
+//
+// - Each derived stat has a formula set in the config: a base plus so many points for every point
+//     of one attribute. The defaults give max HP from Constitution, mana from Casting and carry
+//     capacity from Strength
+// - HP and mana get used up, so how much of them is left is kept in the CharacterResources table.
+//     Characters without a row there are at full, and whatever is stored gets clamped to the
+//     current maximum, so raising an attribute or changing a formula never leaves anyone above it
+// - A character at 0 HP is unconscious, and at 0 mana their mana is depleted
+
+use std::fmt;
+
+use sqlx::SqlitePool;
+
+use crate::{
+    attributes::{Attribute, AttributeSpread},
+    config,
+    db::{self, Resources},
+    error::BotError,
+    utils::CharacterId
+};
+
+
+/// How a derived stat is worked out: `base + per_point × attribute`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatFormula {
+    pub attribute: Attribute,
+    pub base:      i64,
+    pub per_point: i64
+}
+impl StatFormula {
+    /// Work out the stat for the given attributes. Stats can't go below 0
+    pub fn evaluate( &self, spread: &AttributeSpread ) -> i64 {
+        self.base
+            .saturating_add( self.per_point.saturating_mul(spread.get(self.attribute)) )
+            .max(0)
+    }
+}
+impl fmt::Display for StatFormula {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result {
+        write!(f, "{} + {} × {}", self.base, self.per_point, self.attribute.name())
+    }
+}
+
+
+/// Every derived stat of a character, worked out with the formulas in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerivedStats {
+    pub max_hp:         i64,
+    pub max_mana:       i64,
+    pub carry_capacity: i64
+}
+impl DerivedStats {
+    pub fn from_spread( spread: &AttributeSpread ) -> DerivedStats {
+        let config = config::get();
+
+        DerivedStats {
+            max_hp:         config.max_hp_formula.evaluate( spread ),
+            max_mana:       config.max_mana_formula.evaluate( spread ),
+            carry_capacity: config.carry_capacity_formula.evaluate( spread )
+        }
+    }
+}
+
+
+/// How much HP and mana a character has left, next to their maximums
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vitals {
+    pub hp:       i64,
+    pub max_hp:   i64,
+    pub mana:     i64,
+    pub max_mana: i64
+}
+impl Vitals {
+
+    /// Combine a character's derived stats with what's stored of their resources, clamping the
+    /// stored values to the maximums. Without a stored row, the character is at full
+    pub fn new( stats: &DerivedStats, resources: Option<&Resources> ) -> Vitals {
+        let ( hp, mana ) = match resources {
+            Some( resources ) => ( resources.current_hp, resources.current_mana ),
+            None => ( stats.max_hp, stats.max_mana )
+        };
+
+        Vitals {
+            hp:       hp.clamp( 0, stats.max_hp ),
+            max_hp:   stats.max_hp,
+            mana:     mana.clamp( 0, stats.max_mana ),
+            max_mana: stats.max_mana
+        }
+    }
+
+    pub fn is_unconscious( &self ) -> bool {
+        self.hp == 0
+    }
+
+    pub fn is_depleted( &self ) -> bool {
+        self.mana == 0
+    }
+
+    /// HP and mana after the changes, each kept between 0 and their maximum
+    pub fn adjusted( &self, hp_change: i64, mana_change: i64 ) -> Vitals {
+        Vitals {
+            hp:   self.hp.saturating_add( hp_change ).clamp( 0, self.max_hp ),
+            mana: self.mana.saturating_add( mana_change ).clamp( 0, self.max_mana ),
+            ..*self
+        }
+    }
+}
+
+
+/// The attributes a character's derived stats are worked out from
+async fn character_spread( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<AttributeSpread, BotError> {
+    match db::attributes::get( database_connection, character_id ).await? {
+        Some( attributes ) => Ok( attributes.spread() ),
+        None => Err( BotError::NoAttributes )
+    }
+}
+
+/// Get a character's derived stats
+pub async fn load_stats( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<DerivedStats, BotError> {
+    Ok( DerivedStats::from_spread( &character_spread(database_connection, character_id).await? ) )
+}
+
+/// Change a character's HP and mana, clamped between 0 and their maximums. Returns their vitals
+/// from before and after the change
+pub async fn adjust_vitals( database_connection: &SqlitePool, character_id: CharacterId, hp_change: i64, mana_change: i64 ) -> Result<(Vitals, Vitals), BotError> {
+    let stats = load_stats( database_connection, character_id ).await?;
+
+    let ( stored, updated ) = db::resources::update( database_connection, character_id, |stored| {
+        let after = Vitals::new( &stats, stored ).adjusted( hp_change, mana_change );
+        Some( Resources { character_id, current_hp: after.hp, current_mana: after.mana } )
+    }).await?;
+
+    Ok(( Vitals::new(&stats, stored.as_ref()), Vitals::new(&stats, updated.as_ref()) ))
+}
+
+/// Spend exactly `cost` mana, as casting a spell does. Unlike `adjust_vitals` nothing is clamped,
+/// instead it fails with `NotEnoughMana` if the character has less than that left. Returns their
+/// vitals from before and after
+pub async fn spend_mana( database_connection: &SqlitePool, character_id: CharacterId, cost: i64 ) -> Result<(Vitals, Vitals), BotError> {
+    let stats = load_stats( database_connection, character_id ).await?;
+
+    let ( stored, updated ) = db::resources::update( database_connection, character_id, |stored| {
+        let before = Vitals::new( &stats, stored );
+        match before.mana >= cost {
+            true  => Some( Resources { character_id, current_hp: before.hp, current_mana: before.mana - cost } ),
+            false => None
+        }
+    }).await?;
+
+    match updated {
+        Some( updated ) => Ok(( Vitals::new(&stats, stored.as_ref()), Vitals::new(&stats, Some(&updated)) )),
+        None => Err( BotError::NotEnoughMana )
+    }
+}
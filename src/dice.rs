@@ -0,0 +1,521 @@
+// Dice notation, parsed into an expression which can then be rolled
+//
+// - Expressions are dice and whole numbers joined with `+ - * /` and brackets, like `2d20kh1+3`
+// - `NdS` rolls N dice with S sides each. N defaults to 1, and `d%` is short for `d100`
+// - After the sides, dice can be kept or dropped: `kh`/`kl` keep the highest/lowest N, `dh`/`dl`
+//     drop them. N defaults to 1, so `2d20kh` is a roll with advantage. A lone `k` is `kh`
+// - `!` explodes the dice: every die landing on it's highest face adds another die to the roll,
+//     which can explode again. Keeping and dropping happens after the explosions
+// - Everything is a whole number, so division rounds towards zero
+// - Rolling takes the random number generator as an argument, so tests can pass in a seeded one
+
+use std::fmt;
+
+use rand::Rng;
+
+
+/// Most characters an expression can have, to keep rolls readable
+pub const MAX_EXPRESSION_LENGTH: usize = 100;
+/// Most dice a single term can roll, before any explosions
+pub const MAX_DICE: u32 = 100;
+/// Most sides a die can have
+pub const MAX_SIDES: u32 = 1000;
+/// Most dice a single term can end up with once it has exploded. Stops unlucky rolls, or lucky
+/// ones depending on who you ask, from going on forever
+pub const MAX_EXPLODED_DICE: usize = 200;
+
+
+/// Why an expression couldn't be parsed or rolled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiceError {
+    /// There was nothing but whitespace
+    Empty,
+    /// The expression is longer than `MAX_EXPRESSION_LENGTH`
+    TooLong,
+    /// A character which doesn't belong where it was found
+    UnexpectedCharacter( char ),
+    /// The expression stopped in the middle of something, like `2d` or `(1+2`
+    UnexpectedEnd,
+    /// No dice, or more than `MAX_DICE`, are being rolled
+    InvalidCount,
+    /// Dice with no sides, or more than `MAX_SIDES`
+    InvalidSides,
+    /// Single sided dice always land on their highest face, so they'd explode forever
+    CantExplode,
+    DivisionByZero,
+    /// A number, or the result of the arithmetic, doesn't fit in an i64
+    Overflow
+}
+impl fmt::Display for DiceError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result {
+        match self {
+            DiceError::Empty                  => write!(f, "There's nothing to roll"),
+            DiceError::TooLong                => write!(f, "Rolls can be at most {MAX_EXPRESSION_LENGTH} characters long"),
+            DiceError::UnexpectedCharacter(c) => write!(f, "Didn't expect '{c}'"),
+            DiceError::UnexpectedEnd          => write!(f, "The roll ends too early"),
+            DiceError::InvalidCount           => write!(f, "Between 1 and {MAX_DICE} dice can be rolled at a time"),
+            DiceError::InvalidSides           => write!(f, "Dice need between 1 and {MAX_SIDES} sides"),
+            DiceError::CantExplode            => write!(f, "Dice with one side can't explode, they'd never stop"),
+            DiceError::DivisionByZero         => write!(f, "Can't divide by zero"),
+            DiceError::Overflow               => write!(f, "The numbers got too big"),
+        }
+    }
+}
+
+
+/// Which dice of a term count towards the total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    KeepHighest( u32 ),
+    KeepLowest( u32 ),
+    DropHighest( u32 ),
+    DropLowest( u32 )
+}
+
+/// A group of identical dice, like `4d6dl1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiceTerm {
+    pub count:     u32,
+    pub sides:     u32,
+    pub exploding: bool,
+    pub selection: Option<Selection>
+}
+impl fmt::Display for DiceTerm {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+
+        if self.exploding {
+            write!(f, "!")?;
+        }
+
+        match self.selection {
+            Some( Selection::KeepHighest(n) ) => write!(f, "kh{n}"),
+            Some( Selection::KeepLowest(n) )  => write!(f, "kl{n}"),
+            Some( Selection::DropHighest(n) ) => write!(f, "dh{n}"),
+            Some( Selection::DropLowest(n) )  => write!(f, "dl{n}"),
+            None => Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide
+}
+impl Operator {
+    /// How tightly the operator binds, higher binds tighter
+    fn precedence( &self ) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract    => 1,
+            Operator::Multiply | Operator::Divide => 2,
+        }
+    }
+
+    fn symbol( &self ) -> char {
+        match self {
+            Operator::Add      => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide   => '/',
+        }
+    }
+}
+
+/// A parsed expression, ready to be rolled as many times as needed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Number( i64 ),
+    Dice( DiceTerm ),
+    Negate( Box<Expression> ),
+    Binary( Box<Expression>, Operator, Box<Expression> )
+}
+impl Expression {
+
+    /// Numbers, dice and negations bind tighter than any operator
+    fn precedence( &self ) -> u8 {
+        match self {
+            Expression::Binary( _, operator, _ ) => operator.precedence(),
+            _ => 3
+        }
+    }
+
+    /// Roll every die in the expression and work out the total
+    pub fn roll( &self, rng: &mut impl Rng ) -> Result<Roll, DiceError> {
+        let mut groups = vec![];
+        let total = self.evaluate( rng, &mut groups )?;
+
+        Ok( Roll { total, groups } )
+    }
+
+    fn evaluate( &self, rng: &mut impl Rng, groups: &mut Vec<DiceGroup> ) -> Result<i64, DiceError> {
+        match self {
+            Expression::Number( value ) => Ok( *value ),
+            Expression::Dice( term ) => {
+                let group = DiceGroup::roll( term, rng );
+                let total = group.total();
+                groups.push( group );
+
+                Ok( total )
+            },
+            Expression::Negate( inner ) => inner.evaluate( rng, groups )?
+                .checked_neg()
+                .ok_or( DiceError::Overflow ),
+            Expression::Binary( left, operator, right ) => {
+                let left  = left.evaluate( rng, groups )?;
+                let right = right.evaluate( rng, groups )?;
+
+                match operator {
+                    Operator::Add      => left.checked_add( right ).ok_or( DiceError::Overflow ),
+                    Operator::Subtract => left.checked_sub( right ).ok_or( DiceError::Overflow ),
+                    Operator::Multiply => left.checked_mul( right ).ok_or( DiceError::Overflow ),
+                    Operator::Divide if right == 0 => Err( DiceError::DivisionByZero ),
+                    Operator::Divide   => left.checked_div( right ).ok_or( DiceError::Overflow ),
+                }
+            }
+        }
+    }
+}
+impl fmt::Display for Expression {
+    /// Writes the expression back out in a normalised form, only adding the brackets it needs
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result {
+        match self {
+            Expression::Number( value ) => write!(f, "{value}"),
+            Expression::Dice( term ) => write!(f, "{term}"),
+            Expression::Negate( inner ) if inner.precedence() < 3 => write!(f, "-({inner})"),
+            Expression::Negate( inner ) => write!(f, "-{inner}"),
+            Expression::Binary( left, operator, right ) => {
+
+                // Operators of the same precedence are evaluated left to right, so only the right
+                // hand side needs brackets when they match
+                match left.precedence() < operator.precedence() {
+                    true  => write!(f, "({left})")?,
+                    false => write!(f, "{left}")?,
+                }
+
+                write!(f, " {} ", operator.symbol())?;
+
+                match right.precedence() <= operator.precedence() {
+                    true  => write!(f, "({right})"),
+                    false => write!(f, "{right}"),
+                }
+            }
+        }
+    }
+}
+
+
+/// A single rolled die
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Die {
+    pub value:    u32,
+    /// Whether the die counts towards the total, or was dropped
+    pub kept:     bool,
+    /// Whether the die landed on it's highest face and made another die get rolled
+    pub exploded: bool
+}
+
+/// The dice rolled for one term of an expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceGroup {
+    pub term: DiceTerm,
+    pub dice: Vec<Die>
+}
+impl DiceGroup {
+
+    fn roll( term: &DiceTerm, rng: &mut impl Rng ) -> DiceGroup {
+        let mut dice = vec![];
+        let mut remaining = term.count as usize;
+
+        // Each exploding die adds one more to roll, until we hit the cap
+        while remaining > 0 {
+            remaining -= 1;
+
+            let value = rng.gen_range( 1..=term.sides );
+            let exploded = term.exploding && value == term.sides && dice.len() + remaining + 1 < MAX_EXPLODED_DICE;
+            if exploded {
+                remaining += 1;
+            }
+
+            dice.push( Die { value, kept: true, exploded } );
+        }
+
+        // --== KEEP AND DROP ==-- //
+
+            if let Some( selection ) = term.selection {
+
+                // Indices of the dice from lowest to highest. The sort is stable, so ties go to
+                // whichever die was rolled first
+                let mut by_value: Vec<usize> = (0..dice.len()).collect();
+                by_value.sort_by_key( |index| dice[*index].value );
+
+                let dice_count = dice.len();
+                let dropped = match selection {
+                    Selection::KeepHighest(n) => &by_value[ ..dice_count.saturating_sub(n as usize) ],
+                    Selection::KeepLowest(n)  => &by_value[ (n as usize).min(dice_count).. ],
+                    Selection::DropHighest(n) => &by_value[ dice_count.saturating_sub(n as usize).. ],
+                    Selection::DropLowest(n)  => &by_value[ ..(n as usize).min(dice_count) ],
+                };
+
+                for index in dropped {
+                    dice[*index].kept = false;
+                }
+            }
+        // ==--
+
+        DiceGroup { term: *term, dice }
+    }
+
+    /// Sum of the kept dice
+    pub fn total( &self ) -> i64 {
+        self.dice
+            .iter()
+            .filter( |die| die.kept )
+            .map( |die| die.value as i64 )
+            .sum()
+    }
+}
+
+/// The outcome of rolling an expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roll {
+    pub total:  i64,
+    /// Every dice term of the expression, in the order they appear in it
+    pub groups: Vec<DiceGroup>
+}
+impl Roll {
+    /// The kept d20, if exactly one d20 counted towards the total, like in a check. Natural 20s
+    /// and 1s only mean something for those
+    pub fn natural_d20( &self ) -> Option<u32> {
+        let mut kept_d20s = self.groups
+            .iter()
+            .filter( |group| group.term.sides == 20 )
+            .flat_map( |group| group.dice.iter() )
+            .filter( |die| die.kept );
+
+        match ( kept_d20s.next(), kept_d20s.next() ) {
+            ( Some(die), None ) => Some( die.value ),
+            _ => None
+        }
+    }
+}
+
+
+/// Parse an expression, see the top of this file for what's understood
+pub fn parse( expression: &str ) -> Result<Expression, DiceError> {
+    if expression.chars().count() > MAX_EXPRESSION_LENGTH {
+        return Err( DiceError::TooLong )
+    }
+
+    let mut parser = Parser {
+        characters: expression
+            .chars()
+            .filter( |character| !character.is_whitespace() )
+            .map( |character| character.to_ascii_lowercase() )
+            .collect(),
+        position: 0
+    };
+
+    if parser.characters.is_empty() {
+        return Err( DiceError::Empty )
+    }
+
+    let parsed = parser.sum()?;
+
+    match parser.peek() {
+        Some( character ) => Err( DiceError::UnexpectedCharacter(character) ),
+        None => Ok( parsed )
+    }
+}
+
+
+/// Recursive descent over the expression, with whitespace already stripped out. Each method
+/// parses one level of precedence
+struct Parser {
+    characters: Vec<char>,
+    position:   usize
+}
+impl Parser {
+
+    fn peek( &self ) -> Option<char> {
+        self.characters.get( self.position ).copied()
+    }
+
+    fn next( &mut self ) -> Option<char> {
+        let character = self.peek();
+        self.position += 1;
+        character
+    }
+
+    /// Consume the next character if it's the given one
+    fn eat( &mut self, expected: char ) -> bool {
+        let matches = self.peek() == Some( expected );
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn unexpected( &self ) -> DiceError {
+        match self.peek() {
+            Some( character ) => DiceError::UnexpectedCharacter( character ),
+            None => DiceError::UnexpectedEnd
+        }
+    }
+
+    /// sum := product (('+' | '-') product)*
+    fn sum( &mut self ) -> Result<Expression, DiceError> {
+        let mut left = self.product()?;
+
+        loop {
+            let operator = match self.peek() {
+                Some('+') => Operator::Add,
+                Some('-') => Operator::Subtract,
+                _ => return Ok( left )
+            };
+            self.position += 1;
+
+            left = Expression::Binary( Box::new(left), operator, Box::new(self.product()?) );
+        }
+    }
+
+    /// product := unary (('*' | '/') unary)*
+    fn product( &mut self ) -> Result<Expression, DiceError> {
+        let mut left = self.unary()?;
+
+        loop {
+            let operator = match self.peek() {
+                Some('*') => Operator::Multiply,
+                Some('/') => Operator::Divide,
+                _ => return Ok( left )
+            };
+            self.position += 1;
+
+            left = Expression::Binary( Box::new(left), operator, Box::new(self.unary()?) );
+        }
+    }
+
+    /// unary := '-' unary | '(' sum ')' | dice | number
+    fn unary( &mut self ) -> Result<Expression, DiceError> {
+        match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                Ok( Expression::Negate(Box::new( self.unary()? )) )
+            },
+            Some('(') => {
+                self.position += 1;
+                let inner = self.sum()?;
+
+                match self.eat(')') {
+                    true  => Ok( inner ),
+                    false => Err( self.unexpected() )
+                }
+            },
+            Some('d') => Ok( Expression::Dice( self.dice(1)? ) ),
+            Some( character ) if character.is_ascii_digit() => {
+                let number = self.number()?;
+
+                match self.peek() {
+                    Some('d') => {
+                        let count = u32::try_from( number ).map_err( |_| DiceError::InvalidCount )?;
+                        Ok( Expression::Dice( self.dice(count)? ) )
+                    },
+                    _ => Ok( Expression::Number(number) )
+                }
+            },
+            _ => Err( self.unexpected() )
+        }
+    }
+
+    /// number := digit+
+    fn number( &mut self ) -> Result<i64, DiceError> {
+        let mut number: i64 = 0;
+
+        while let Some( digit ) = self.peek().and_then( |character| character.to_digit(10) ) {
+            self.position += 1;
+            number = number
+                .checked_mul( 10 )
+                .and_then( |number| number.checked_add(digit as i64) )
+                .ok_or( DiceError::Overflow )?;
+        }
+
+        Ok( number )
+    }
+
+    /// An optional count after a keep or drop, which defaults to one
+    fn selection_count( &mut self ) -> Result<u32, DiceError> {
+        match self.peek() {
+            Some( character ) if character.is_ascii_digit() => u32::try_from( self.number()? )
+                .map_err( |_| DiceError::InvalidCount ),
+            _ => Ok( 1 )
+        }
+    }
+
+    /// dice := 'd' (number | '%') '!'? (('k' | 'kh' | 'kl' | 'dh' | 'dl') number?)?
+    ///
+    /// The count has already been parsed by the caller
+    fn dice( &mut self, count: u32 ) -> Result<DiceTerm, DiceError> {
+        if !(1..=MAX_DICE).contains( &count ) {
+            return Err( DiceError::InvalidCount )
+        }
+
+        // --== SIDES ==-- //
+
+            if !self.eat('d') {
+                return Err( self.unexpected() )
+            }
+
+            let sides = match self.peek() {
+                Some('%') => {
+                    self.position += 1;
+                    100
+                },
+                Some( character ) if character.is_ascii_digit() => u32::try_from( self.number()? )
+                    .map_err( |_| DiceError::InvalidSides )?,
+                _ => return Err( self.unexpected() )
+            };
+
+            if !(1..=MAX_SIDES).contains( &sides ) {
+                return Err( DiceError::InvalidSides )
+            }
+        // ==--
+
+        // --== MODIFIERS ==-- //
+
+            let exploding = self.eat('!');
+            if exploding && sides == 1 {
+                return Err( DiceError::CantExplode )
+            }
+
+            let selection = match self.peek() {
+                Some('k') => {
+                    self.position += 1;
+                    match self.peek() {
+                        Some('l') => {
+                            self.position += 1;
+                            Some( Selection::KeepLowest(self.selection_count()?) )
+                        },
+                        _ => {
+                            self.eat('h');
+                            Some( Selection::KeepHighest(self.selection_count()?) )
+                        }
+                    }
+                },
+                Some('d') => {
+                    self.position += 1;
+                    match self.next() {
+                        Some('h') => Some( Selection::DropHighest(self.selection_count()?) ),
+                        Some('l') => Some( Selection::DropLowest(self.selection_count()?) ),
+                        Some( character ) => return Err( DiceError::UnexpectedCharacter(character) ),
+                        None => return Err( DiceError::UnexpectedEnd )
+                    }
+                },
+                _ => None
+            };
+        // ==--
+
+        Ok( DiceTerm { count, sides, exploding, selection } )
+    }
+}
@@ -0,0 +1,114 @@
+// Gear characters wear and wield
+//
+// - Items from the catalogue can be given a slot with `/item gear`, along with modifiers to any
+//     of the six attributes. Items without a slot can only be carried
+// - A character can have one item equipped per slot, as long as it's in their inventory. Equipping
+//     and unequipping is done with the components under their character sheet
+// - The effective value of an attribute is it's base value plus the modifiers of everything
+//     equipped, and never below 0. Checks, casting and initiative all roll with effective values,
+//     while HP, mana and carry capacity are still worked out from the base ones, so taking off a
+//     helmet never knocks anyone out
+
+use sqlx::SqlitePool;
+
+use crate::{
+    attributes::{Attribute, AttributeSpread},
+    db::{self, EquippedItem},
+    error::BotError,
+    utils::CharacterId
+};
+
+
+/// The places an item can be equipped into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Head,
+    Body,
+    Hands,
+    MainHand,
+    OffHand,
+    Accessory
+}
+impl Slot {
+    /// Every slot, in the order they're shown on the character sheet
+    pub const ALL: [Slot; 6] = [
+        Slot::Head,
+        Slot::Body,
+        Slot::Hands,
+        Slot::MainHand,
+        Slot::OffHand,
+        Slot::Accessory
+    ];
+
+    /// The slot as it's written in the database and in custom ids
+    pub fn id( &self ) -> &'static str {
+        match self {
+            Slot::Head      => "head",
+            Slot::Body      => "body",
+            Slot::Hands     => "hands",
+            Slot::MainHand  => "main_hand",
+            Slot::OffHand   => "off_hand",
+            Slot::Accessory => "accessory",
+        }
+    }
+
+    /// Inverse of `id`
+    pub fn from_id( id: &str ) -> Option<Slot> {
+        Self::ALL
+            .into_iter()
+            .find( |slot| slot.id() == id )
+    }
+
+    /// The slot as it's shown to users
+    pub fn name( &self ) -> &'static str {
+        match self {
+            Slot::Head      => "Head",
+            Slot::Body      => "Body",
+            Slot::Hands     => "Hands",
+            Slot::MainHand  => "Main hand",
+            Slot::OffHand   => "Off hand",
+            Slot::Accessory => "Accessory",
+        }
+    }
+}
+
+
+/// Sums up what an item does to attributes, like `+2 Strength, -1 Dexterity`. Empty when it
+/// doesn't change any
+pub fn modifiers_text( modifiers: &AttributeSpread ) -> String {
+    Attribute::ALL
+        .into_iter()
+        .filter( |attribute| modifiers.get(*attribute) != 0 )
+        .map( |attribute| format!("{:+} {}", modifiers.get(attribute), attribute.name()) )
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Add the modifiers of everything equipped onto the base attributes
+pub fn effective_spread( base: &AttributeSpread, equipped: &[EquippedItem] ) -> AttributeSpread {
+    let mut effective = *base;
+
+    for attribute in Attribute::ALL {
+        let modifier: i64 = equipped
+            .iter()
+            .map( |equipped_item| equipped_item.item.modifiers().get(attribute) )
+            .sum();
+
+        effective.set( attribute, (base.get(attribute) + modifier).max(0) );
+    }
+
+    effective
+}
+
+/// Get a character's attributes, with the modifiers of everything they've got equipped
+///
+/// Fails with `NoAttributes` if they haven't been allocated yet
+pub async fn effective_attributes( database_connection: &SqlitePool, character_id: CharacterId ) -> Result<AttributeSpread, BotError> {
+    let base = match db::attributes::get( database_connection, character_id ).await? {
+        Some( attributes ) => attributes.spread(),
+        None => return Err( BotError::NoAttributes )
+    };
+    let equipped = db::equipment::get_equipped( database_connection, character_id ).await?;
+
+    Ok( effective_spread(&base, &equipped) )
+}
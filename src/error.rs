@@ -0,0 +1,342 @@
+// Errors a command can run into, and how they're shown to the user
+//
+// Every command reports its failures through `BotError::embed`, so that the same problem always
+// looks the same no matter which command hit it. Each report gets a correlation id, which is
+// logged next to the error and shown in the embed's footer. When a user asks for help, the id in
+// their screenshot leads straight to the matching line in the logs
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering}
+};
+
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+use tracing::{error, info, warn};
+
+use crate::{
+    db::DbError,
+    dice::DiceError,
+    utils::EmbedColours
+};
+
+
+/// Everything that can go wrong while handling an interaction
+#[derive(Debug)]
+pub enum BotError {
+    /// The user doesn't have a profile in the database
+    NotRegistered,
+    /// The user already has a profile in the database
+    AlreadyRegistered,
+    /// The user already has a character of the given name
+    DuplicateCharacter,
+    /// The user can't be removed while they still own characters
+    HasCharacters,
+    /// The selected character doesn't belong to the user
+    NotOwner,
+    /// No character was picked, and the user isn't playing as one
+    NoActiveCharacter,
+    /// The selected character isn't in the user's trash
+    NotInTrash,
+    /// The attribute spread doesn't follow the allocation rules
+    InvalidAllocation,
+    /// The character's attributes haven't been allocated yet
+    NoAttributes,
+    /// The character's attributes have already been allocated
+    AttributesAlreadySet,
+    /// The name typed to confirm a deletion isn't the character's name
+    NameMismatch,
+    /// What was typed to confirm a purge isn't the confirmation
+    PurgeNotConfirmed,
+    /// There's already an encounter running in the channel
+    EncounterRunning,
+    /// There's no encounter running in the channel
+    NoEncounter,
+    /// The character is already taking part in the encounter
+    AlreadyJoined,
+    /// Only the encounter's game master is allowed to do that
+    NotGameMaster,
+    /// Only server managers and the channel's game master can change other users' characters
+    CantTargetOthers,
+    /// Only members who can manage the server are allowed to do that
+    NotServerManager,
+    /// There's already a spell of the given name
+    DuplicateSpell,
+    /// The spell's mana cost or difficulty isn't a number in range
+    InvalidSpell,
+    /// The character doesn't know the selected spell
+    UnknownSpell,
+    /// The character doesn't have enough mana left to cast the spell
+    NotEnoughMana,
+    /// There's already an item of the given name in the server's catalogue
+    DuplicateItem,
+    /// The item's weight or value isn't a number in range
+    InvalidItem,
+    /// The character doesn't have as many of the item as were asked for
+    NotEnoughItems,
+    /// The items would take the character past their carry capacity
+    Overburdened,
+    /// The dice expression couldn't be parsed or rolled
+    InvalidDice( DiceError ),
+    /// A thread panicked while holding one of the caches' locks, so it's out of sync
+    CachePoisoned,
+    /// Anything unexpected coming from the database
+    Database( sqlx::Error ),
+    /// Anything unexpected coming from Discord
+    Discord( serenity::Error )
+}
+impl From<DbError> for BotError {
+    fn from( error: DbError ) -> Self {
+        match error {
+            DbError::NotRegistered        => BotError::NotRegistered,
+            DbError::AlreadyRegistered    => BotError::AlreadyRegistered,
+            DbError::DuplicateCharacter   => BotError::DuplicateCharacter,
+            DbError::AttributesAlreadySet => BotError::AttributesAlreadySet,
+            DbError::EncounterRunning     => BotError::EncounterRunning,
+            DbError::AlreadyJoined        => BotError::AlreadyJoined,
+            DbError::DuplicateSpell       => BotError::DuplicateSpell,
+            DbError::DuplicateItem        => BotError::DuplicateItem,
+            DbError::NotEnoughItems       => BotError::NotEnoughItems,
+            DbError::Overburdened         => BotError::Overburdened,
+            DbError::Sqlx( why )          => BotError::Database( why )
+        }
+    }
+}
+impl From<DiceError> for BotError {
+    fn from( error: DiceError ) -> Self {
+        BotError::InvalidDice( error )
+    }
+}
+impl From<sqlx::Error> for BotError {
+    fn from( error: sqlx::Error ) -> Self {
+        BotError::Database( error )
+    }
+}
+impl From<serenity::Error> for BotError {
+    fn from( error: serenity::Error ) -> Self {
+        BotError::Discord( error )
+    }
+}
+impl fmt::Display for BotError {
+    fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result {
+        match self {
+            BotError::NotRegistered        => write!(f, "User is not registered"),
+            BotError::AlreadyRegistered    => write!(f, "User is already registered"),
+            BotError::DuplicateCharacter   => write!(f, "User already has a character of that name"),
+            BotError::HasCharacters        => write!(f, "User still has characters"),
+            BotError::NotOwner             => write!(f, "User doesn't own the selected character"),
+            BotError::NoActiveCharacter    => write!(f, "No character picked and user has no active one"),
+            BotError::NotInTrash           => write!(f, "Selected character isn't archived"),
+            BotError::InvalidAllocation    => write!(f, "Attribute spread breaks the allocation rules"),
+            BotError::NoAttributes         => write!(f, "Character has no attributes"),
+            BotError::AttributesAlreadySet => write!(f, "Character already has attributes"),
+            BotError::NameMismatch         => write!(f, "Confirmation doesn't match the character's name"),
+            BotError::PurgeNotConfirmed    => write!(f, "Purge wasn't confirmed"),
+            BotError::EncounterRunning     => write!(f, "Channel already has an encounter running"),
+            BotError::NoEncounter          => write!(f, "Channel has no encounter running"),
+            BotError::AlreadyJoined        => write!(f, "Character is already in the encounter"),
+            BotError::NotGameMaster        => write!(f, "User isn't the encounter's game master"),
+            BotError::CantTargetOthers     => write!(f, "User can't change other users' characters"),
+            BotError::NotServerManager     => write!(f, "User can't manage the server"),
+            BotError::DuplicateSpell       => write!(f, "There's already a spell of that name"),
+            BotError::InvalidSpell         => write!(f, "Spell's mana cost or difficulty is out of range"),
+            BotError::UnknownSpell         => write!(f, "Character doesn't know the selected spell"),
+            BotError::NotEnoughMana        => write!(f, "Character doesn't have enough mana"),
+            BotError::DuplicateItem        => write!(f, "There's already an item of that name"),
+            BotError::InvalidItem          => write!(f, "Item's weight or value is out of range"),
+            BotError::NotEnoughItems       => write!(f, "Character doesn't have enough of that item"),
+            BotError::Overburdened         => write!(f, "Character can't carry that much"),
+            BotError::InvalidDice( why )   => write!(f, "Invalid dice: {why}"),
+            BotError::CachePoisoned        => write!(f, "Poisoned Mutex; Cache out of sync"),
+            BotError::Database( why )      => write!(f, "Database error: {why}"),
+            BotError::Discord( why )       => write!(f, "Discord error: {why}"),
+        }
+    }
+}
+impl BotError {
+
+    /// Log the error along with what was being done when it happened, and turn it into an embed
+    /// for the user. Both carry the same correlation id
+    pub fn embed( &self, context: impl fmt::Display ) -> CreateEmbed {
+        let correlation_id = new_correlation_id();
+
+        // How loud the error is in the logs depends on what it was. Mistakes made by users are
+        // expected, broken caches never are
+        match self {
+            BotError::NotRegistered
+            | BotError::AlreadyRegistered
+            | BotError::DuplicateCharacter
+            | BotError::HasCharacters
+            | BotError::NotOwner
+            | BotError::NoActiveCharacter
+            | BotError::NotInTrash
+            | BotError::InvalidAllocation
+            | BotError::NoAttributes
+            | BotError::AttributesAlreadySet
+            | BotError::NameMismatch
+            | BotError::PurgeNotConfirmed
+            | BotError::EncounterRunning
+            | BotError::NoEncounter
+            | BotError::AlreadyJoined
+            | BotError::NotGameMaster
+            | BotError::CantTargetOthers
+            | BotError::NotServerManager
+            | BotError::DuplicateSpell
+            | BotError::InvalidSpell
+            | BotError::UnknownSpell
+            | BotError::NotEnoughMana
+            | BotError::DuplicateItem
+            | BotError::InvalidItem
+            | BotError::NotEnoughItems
+            | BotError::Overburdened
+            | BotError::InvalidDice(_) => info!(%correlation_id, error = %self, "{context}"),
+            BotError::Database(_)
+            | BotError::Discord(_) => warn!(%correlation_id, error = %self, "{context}"),
+            BotError::CachePoisoned => error!(%correlation_id, error = %self, "{context}"),
+        }
+
+        let ( title, description ) = match self {
+            BotError::NotRegistered => (
+                "You haven't been added to the database",
+                "You can add yourself by using /register"
+            ),
+            BotError::AlreadyRegistered => (
+                "Your already in the database",
+                "No need to add you :P"
+            ),
+            BotError::DuplicateCharacter => (
+                "You already have a character of that name",
+                "Please pick a different name. If you want to remove the old one, use /delete_character"
+            ),
+            BotError::HasCharacters => (
+                "Can't remove you",
+                "You have character(s) in the database. We cannot remove your profile while they're there, including those in /character trash, unless you use /deregister purge:true to remove them too"
+            ),
+            BotError::NotOwner => (
+                "Selected character doesn't belong to you",
+                "We couldn't find the selected character from your owned ones"
+            ),
+            BotError::NoActiveCharacter => (
+                "No character selected",
+                "Pick a character, or set your active one with /switch_character"
+            ),
+            BotError::NotInTrash => (
+                "That character isn't in your trash",
+                "Only characters in /character trash can be restored"
+            ),
+            BotError::InvalidAllocation => (
+                "Invalid attribute allocation",
+                "Please try again"
+            ),
+            BotError::NoAttributes => (
+                "That character has no attributes yet",
+                "Their attribute points have to be allocated before they can make checks"
+            ),
+            BotError::AttributesAlreadySet => (
+                "That character's attributes are already set",
+                "Attributes are only allocated once, when a character is built. Points earned later are spent with /level_up"
+            ),
+            BotError::NameMismatch => (
+                "The name you typed doesn't match",
+                "Your character hasn't been removed. To remove them, type their name exactly as it's shown"
+            ),
+            BotError::PurgeNotConfirmed => (
+                "Purge wasn't confirmed",
+                "Nothing has been removed. To remove everything, type the confirmation exactly as it's shown"
+            ),
+            BotError::EncounterRunning => (
+                "There's already a fight going on here",
+                "Only one encounter can run in a channel at a time. Press End on it's turn order to finish it"
+            ),
+            BotError::NoEncounter => (
+                "There's no fight going on here",
+                "Start one with /encounter start"
+            ),
+            BotError::AlreadyJoined => (
+                "That character is already in the fight",
+                "Each character can only join an encounter once"
+            ),
+            BotError::NotGameMaster => (
+                "Only the game master can do that",
+                "Whoever started the encounter runs it. Players can only end their own turn"
+            ),
+            BotError::CantTargetOthers => (
+                "Only a game master can do that to someone else's character",
+                "Server managers can pick another user, and whoever runs the fight in this channel can pick the characters in it. Leave the user option out to pick one of your own characters"
+            ),
+            BotError::NotServerManager => (
+                "Only a server manager can do that",
+                "You need the Manage Server permission in this server"
+            ),
+            BotError::DuplicateSpell => (
+                "There's already a spell of that name",
+                "Please pick a different name, or change the existing spell with /spell edit"
+            ),
+            BotError::InvalidSpell => (
+                "Couldn't save that spell",
+                "The mana cost has to be a whole number of at least 0, and the difficulty one of at least 1"
+            ),
+            BotError::UnknownSpell => (
+                "That character doesn't know that spell",
+                "Spells have to be taught by the game master first. See which ones they know with /spellbook"
+            ),
+            BotError::NotEnoughMana => (
+                "Not enough mana",
+                "The spell costs more mana than the character has left"
+            ),
+            BotError::DuplicateItem => (
+                "There's already an item of that name",
+                "Please pick a different name, or change the existing item with /item edit"
+            ),
+            BotError::InvalidItem => (
+                "Couldn't save that item",
+                "The weight and value have to be whole numbers of at least 0"
+            ),
+            BotError::NotEnoughItems => (
+                "Not enough of that item",
+                "The character doesn't have that many. See what they're carrying with /inventory view"
+            ),
+            BotError::Overburdened => (
+                "That's too heavy",
+                "It would take the character past their carry capacity, which rises with Strength"
+            ),
+            BotError::InvalidDice(_) => (
+                "Couldn't roll that",
+                "Rolls look like 2d20kh1+3, 4d6dl1 or d%"
+            ),
+            BotError::CachePoisoned => (
+                "A unexpected error occured",
+                "Cache is out of sync due to an unexpected error. Please notify Bot Administrator"
+            ),
+            BotError::Database(_) | BotError::Discord(_) => (
+                "A unexpected error occured",
+                "If it persists, feel free to open an issue on the bot's github page"
+            ),
+        };
+
+        let embed = CreateEmbed::new()
+            .title(title)
+            .description(description)
+            .footer( CreateEmbedFooter::new(format!("Error ID: {correlation_id}")) )
+            .colour(EmbedColours::error());
+
+        // Dice can go wrong in too many ways for one description, so the user is also told which
+        match self {
+            BotError::InvalidDice( why ) => embed.field( "Problem", why.to_string(), false ),
+            _ => embed
+        }
+    }
+}
+
+
+/// Create a short id to tell error reports apart. The counter keeps ids created in the same
+/// instant from colliding, the randomly seeded hasher keeps them from repeating across restarts
+fn new_correlation_id() -> String {
+    static REPORT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64( REPORT_COUNTER.fetch_add(1, Ordering::Relaxed) );
+
+    format!("{:08x}", hasher.finish() as u32)
+}
@@ -1,6 +1,4 @@
 use crate::utils::{
-    create_log_message,
-    LogLevel,
     EmbedColours
 };
 
@@ -14,15 +12,21 @@ use serenity::{
         Context, EventHandler
      },
      model::{
-        application::Command, gateway::Ready, id::ChannelId, Timestamp
+        application::Command, gateway::Ready, Timestamp
      }
 };
+use tracing::{error, info, warn};
 
-use crate::commands;
+use crate::{
+    commands::registry::{report_unknown_route, CommandRegistry},
+    config,
+    responses::BotContext
+};
 
 
 pub struct DiscordBot {
-    pub database_connection: sqlx::SqlitePool
+    pub database_connection: sqlx::SqlitePool,
+    pub commands:            CommandRegistry
 }
 
 #[async_trait]
@@ -31,20 +35,16 @@ impl EventHandler for DiscordBot {
     async fn ready( &self, ctx: Context, _ready: Ready ) {
 
         // Notify terminal that the bot has connected to gateway
-        println!( "{}",
-            create_log_message( "Connected to Gateway; Bot Online", LogLevel::Info )
-        );
+        info!("Connected to Gateway; Bot Online");
 
 
         // --== CREATE WAKEUP MESSAGE ==-- //
             
             // To make the wakeup message pretty, we'll use an embed.
             // And so, we're gonna need a colour and timestamp for it
-            // and also the channel to send it to
-            let ( embed_colour, embed_timestamp, wakeup_channel ) = (
-                EmbedColours::INFO,  // A nice ocean blue
-                Timestamp::now(),
-                ChannelId::from( 1314610244223238317 )  // For now I'm just hard coding it in
+            let ( embed_colour, embed_timestamp ) = (
+                EmbedColours::info(),  // A nice ocean blue, unless configured otherwise
+                Timestamp::now()
             );
 
             // Next up, we need to construct the embed that we will send
@@ -59,177 +59,64 @@ impl EventHandler for DiscordBot {
         
         // --== SEND WAKEUP MESSAGE ==-- //
             
-            // This is our final step, send the message. If that fails just report it
-            if wakeup_channel.send_message( &ctx.http, wakeup_message ).await.is_err() {
-                
-                println!( "{}",
-                    create_log_message( "Failed to send Wakeup Message", LogLevel::Warning )
-                );
+            // This is our final step, send the message to the configured channel. Before the
+            // channel was configurable it was always sent, so a missing one is worth pointing out.
+            // If sending fails just report it
+            match config::get().wakeup_channel {
+                Some( wakeup_channel ) => {
+                    if wakeup_channel.send_message( &ctx.http, wakeup_message ).await.is_err() {
+
+                        warn!("Failed to send Wakeup Message");
+                    }
+                },
+                None => warn!("No discord.wakeup_channel is configured, so no Wakeup Message was sent")
             }
         // ==--
 
         // --== REGISTER SLASH COMMANDS ==-- //
         
-            let slash_commands = vec![
-                commands::register::build(),
-                commands::deregister::build(),
-                commands::build_character::build(),
-                commands::delete_character::build(),
-                commands::tmp::build(),
-                commands::dump_cache::build()
-            ];
+            // Commands disabled in the config aren't registered at all
+            let slash_commands = self.commands.build_enabled();
 
             if let Err( why ) = Command::set_global_commands( &ctx.http, slash_commands ).await {
-                println!( "{}",
-                    create_log_message( format!("Failed to register slash commands:\n\t{why}"), LogLevel::Fatal )
-                )
+                error!(error = %why, "Failed to register slash commands")
             }
         // ==--
     }
 
-    #[allow(clippy::single_match)]
     async fn interaction_create( &self, ctx: Context, interaction_data: Interaction ) {
-        // Here we see *what* kind of interaction we recived. Based upon that we de what we can and
-        // can't do.
-        match interaction_data {
-                
-            // --== COMMAND INTERACTIONS ==-- //
-                
-                Interaction::Command( inbound_command_data ) => {
-
-                    // Depending on which command was called, execute the right code. We expect a
-                    // return type of Option<CreateInteractionRespone> after .await'ing. Theoretically
-                    // we could use a Result<T, E> enum to log errors, but I've decided that it would
-                    // be better to handle that inside of the function to not force a structure upon
-                    // ourseleves. Besdies, we could respond to the interaction inside of the function,
-                    // therefore not doing anything more here would be optimal.
-                    let response_opt = match inbound_command_data.data.name.clone().as_str() {
-
-                        "register" => commands::register::run(
-                                &inbound_command_data, &ctx, self
-                        ).await,
-
-                        "deregister" => commands::deregister::run(
-                                &inbound_command_data, &ctx, self
-                        ).await,
-
-                        "tmp" => commands::tmp::run(
-                                &inbound_command_data, &ctx
-                        ).await,
-
-                        "build_character" => commands::build_character::run(
-                                &inbound_command_data, &ctx
-                        ).await,
-
-                        "delete_character" => commands::delete_character::run(
-                                &inbound_command_data, &ctx
-                        ).await,
-
-                        "dump_cache" => commands::dump_cache::run(
-                            &inbound_command_data, &ctx
-                        ).await,
-
-                        _ => { None }
-                    };
-
-                    if let Some( response ) = response_opt {
-                        // If there is a response message given to us, atempt to send it as a response
-                        // to the interaction. If that fails, log it.
-                        // Although not being a fatal error, not responding to an interaction is still
-                        // unwanted as the user may be missing out on important information
-                        if let Err( why ) = inbound_command_data.create_response( &ctx.http, response ).await {
-                            println!( "{}", create_log_message(
-                                    format!("Failed to send response to command interaction:\n\t{why}"),
-                                    LogLevel::Error
-                            ))
-                        }
-                    }
+        self.handle_interaction( &BotContext::from_serenity(&ctx), interaction_data ).await
+    }
 
-                    
-                },
-            // ==--
+}
 
-            // --== AUTOCOMPLETE INTERACTIONS ==-- //
-            
-                Interaction::Autocomplete( inbound_autocomplete_data ) => {
-                    let interaction_name = inbound_autocomplete_data.data.name.clone();
-
-                    match interaction_name.as_str() {
-                        
-                        "tmp" => commands::tmp::handle_autocomplete(
-                                &inbound_autocomplete_data, &ctx
-                        ).await,
-
-                        "delete_character" => commands::delete_character::handle_autocomplete(
-                                &inbound_autocomplete_data, &ctx
-                        ).await,
-
-                        _ => {
-                            println!( "{}", create_log_message(
-                                    format!("Recived unknown autocomplete interaction. Name: {interaction_name}"),
-                                    LogLevel::Warning
-                            ))
-                        }
-                    }
-                },
-            // ==--
-
-            // --== MODAL INTERACTIONS ==-- //
-                
-                Interaction::Modal( inbound_modal_data ) => {
-                    let modal_id: String = inbound_modal_data.data.custom_id.clone();
-                    let modal_id_components = modal_id
-                        .split(':')
-                        .collect::<Vec<&str>>();
-
-                    // If the ID of the modal is mangled, i.e. doesn't contain a ':' character to
-                    // seperate the name of the interaction that called it, we will print the
-                    // mangled ID to the terminal in an error message and then break as we can't do
-                    // anything more with it unfortunetly
-                    let modal_name = modal_id_components.first();
-                    if modal_name.is_none() {
-                        println!( "{}", create_log_message(
-                                format!("Recived modal interaction with mangled id: {}", modal_id ),
-                                LogLevel::Warning
-                        ));
-                        // If the ID is mangled, we can't do anything with it so we will just break
-                        // early
-                        return;
-                    }
-                    let modal_name = modal_name
-                        .expect("Handled None case already")
-                        .to_owned();  // Looks better if we later match on a new String than a
-                                      // &&str
+impl DiscordBot {
 
-                    match modal_name {
+    /// Hand an interaction to the command it belongs to. Kept apart from `interaction_create`, as
+    /// serenity's `Context` can't be made without a connection to Discord while a `BotContext`
+    /// can
+    pub async fn handle_interaction( &self, ctx: &BotContext, interaction_data: Interaction ) {
+        // Here we see *what* kind of interaction we recived, and hand it to the command it belongs
+        // to. The registry reports anything it can't find a command for
+        match interaction_data {
 
-                        "build_character" => commands::build_character::handle_modal(
-                                &inbound_modal_data, &ctx, self
-                        ).await,
+            Interaction::Command( inbound_command_data ) => self.commands.dispatch_command(
+                    &inbound_command_data, ctx, self
+            ).await,
 
-                        "delete_character" => commands::delete_character::handle_modal(
-                                &inbound_modal_data, &ctx, self
-                        ).await,
+            Interaction::Autocomplete( inbound_autocomplete_data ) => self.commands.dispatch_autocomplete(
+                    &inbound_autocomplete_data, ctx, self
+            ).await,
 
-                        _ => {
-                            println!( "{}", create_log_message(
-                                format!("Recived unknown modal interaction. Name: {}", modal_name ),
-                                LogLevel::Warning
-                            ));
+            Interaction::Modal( inbound_modal_data ) => self.commands.dispatch_modal(
+                    &inbound_modal_data, ctx, self
+            ).await,
 
-                        }
-                    };
-                },
-            // ==--
+            Interaction::Component( inbound_component_data ) => self.commands.dispatch_component(
+                    &inbound_component_data, ctx, self
+            ).await,
 
-            _ => {
-                println!( "{}", create_log_message(
-                        format!("Recived unknown interaction: {:?}", interaction_data.kind() ),
-                        LogLevel::Warning
-                ))
-            }
+            _ => report_unknown_route( "unsupported", &format!("{:?}", interaction_data.kind()) )
         }
     }
-
 }
-
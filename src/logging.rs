@@ -0,0 +1,61 @@
+// Where log messages end up
+//
+// - Every event is printed to the terminal in a human readable format, and written to a file in
+//     the configured log directory as a line of JSON. The files are rotated and pruned as set in
+//     the config
+// - Events below the configured level are dropped for both outputs
+// - Interactions are handled inside of a span carrying the user, guild and command they belong
+//     to, so every event logged while handling one can be traced back to it. In the JSON lines
+//     they show up under `span` and `spans`
+
+use std::fs;
+
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::RollingFileAppender
+};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, util::SubscriberInitExt
+};
+
+use crate::config::Config;
+
+
+/// Start logging as set in the config. The returned guard flushes the log file once it's dropped,
+/// so it has to be held for as long as the bot runs
+pub fn init( bot_config: &Config ) -> Result<WorkerGuard, String> {
+
+    // The appender prunes old files on startup, and complains if there's no directory to prune
+    fs::create_dir_all( &bot_config.log_directory )
+        .map_err( |why| format!("Couldn't create the log directory {}: {why}", bot_config.log_directory) )?;
+
+    let file_appender = RollingFileAppender::builder()
+        .rotation( bot_config.log_rotation.clone() )
+        .filename_prefix("magician")
+        .filename_suffix("jsonl")
+        .max_log_files( bot_config.log_max_files )
+        .build( &bot_config.log_directory )
+        .map_err( |why| format!("Couldn't open the log directory {}: {why}", bot_config.log_directory) )?;
+
+    // Writing to the file happens on a background thread, so handling interactions never has to
+    // wait on the disk
+    let ( file_writer, guard ) = tracing_appender::non_blocking( file_appender );
+
+    let terminal_layer = fmt::layer()
+        .with_target(false);
+
+    let file_layer = fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_writer( file_writer );
+
+    tracing_subscriber::registry()
+        .with( bot_config.log_level )
+        .with( terminal_layer )
+        .with( file_layer )
+        .try_init()
+        .map_err( |why| why.to_string() )?;
+
+    Ok( guard )
+}
@@ -1,4 +1,3 @@
-use core::panic;
 // xxxxxxxxxxxxxxxxx //
 // --== CRATES == -- //
 // xxxxxxxxxxxxxxxxx //
@@ -7,17 +6,30 @@ use std::{
     env, sync::{Arc, Mutex}
 };
 
-use serenity::{
-    model::gateway::GatewayIntents, Client
-};
-use sqlx::{sqlite::SqliteConnectOptions, SqlitePool, Row};
-use utils::DatabaseCharactersCache;
+use serenity::Client;
+use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+use utils::{ActiveCharactersCache, DatabaseCharactersCache};
 
+mod config;
 mod sql_scripts;
+mod db;
+mod error;
+mod logging;
+mod attributes;
+mod dice;
+mod derived_stats;
+mod progression;
+mod equipment;
+mod character_sheet;
 mod event_handler;
+mod responses;
 mod commands;
+mod trash;
 mod utils;
 
+#[cfg(test)]
+mod tests;
+
 
 // xxxxxxxxxxxxxx //
 // --== MAIN ==-- //
@@ -27,13 +39,52 @@ async fn main() {
 
     println!( "{}", utils::TITLE );
 
+    // Flushes the log file when dropped, so it has to outlive the client
+    let mut _log_guard = None;
+
     let bot_client: Result< serenity::Client, i32 > = 'main: {
 
+        // --== READ CONFIGURATION ==-- //
+
+            print!("Reading Configuration...");
+            let bot_config = match config::Config::load( env::args() ) {
+                Ok(bot_config) => {
+                    println!("Ok");
+                    bot_config
+                },
+                Err(errors) => {
+                    println!("Error:");
+                    for why in errors.iter() {
+                        println!("\t{why}");
+                    }
+                    break 'main Err( 1 );
+                }
+            };
+
+            // Everything after this point can reach the config through `config::get`
+            config::set( bot_config.clone() );
+        // ==--
+
+        // --== SET UP LOGGING ==-- //
+
+            print!("Setting up Logging...");
+            match logging::init( &bot_config ) {
+                Ok( guard ) => {
+                    println!("Ok");
+                    _log_guard = Some( guard );
+                },
+                Err( why ) => {
+                    println!("Error: {why}");
+                    break 'main Err( 1 );
+                }
+            }
+        // ==--
+
         // --== LOAD/CREATE DATABASE ==-- //
 
             print!("Opening Connection to Database...");
             let sqlite_connection_options = SqliteConnectOptions::new()
-                .filename("kerm-maw_db")
+                .filename(&bot_config.database_path)
                 .create_if_missing(true);
 
             let database_connection = SqlitePool::connect_with(sqlite_connection_options).await;
@@ -85,14 +136,13 @@ async fn main() {
 
         // --== SETUP CONNECTION TO GATEWAY ==-- //   
             
-            let gateway_intents = GatewayIntents::GUILD_MESSAGES
-                | GatewayIntents::MESSAGE_CONTENT
-                | GatewayIntents::GUILD_MESSAGE_REACTIONS;
+            let gateway_intents = bot_config.gateway_intents;
 
             print!("Setting up Client...");
 
             let client = event_handler::DiscordBot {
-                database_connection: sqlx_connection
+                database_connection: sqlx_connection,
+                commands:            commands::registry()
             };
             println!("Ok");
         // ==--
@@ -101,20 +151,13 @@ async fn main() {
 
             // Firstly, we grab all the characters that currently exist in the database
             print!("Syncing Cache to Database..." );
-            let query_result = sqlx::query( sql_scripts::characters::SELECT_ALL_CHARACTER_IDS_AND_NAME )
-                .fetch_all( &client.database_connection )
-                .await;
+            let query_result = db::characters::all_ids_and_names( &client.database_connection ).await;
 
             let characters_cache = match query_result {
                 Ok(query_data) => {
 
-                    let mut user_characters_map: HashMap<u64, Vec<(u16, String)>> = HashMap::new();
-                    for entry in query_data.iter() {
-                        let ( user_id, character_id, character_name ): (u64,u16,String) = (
-                            entry.get(0),
-                            entry.get(1),
-                            entry.get(2)
-                        );
+                    let mut user_characters_map: utils::CharacterMap = HashMap::new();
+                    for ( user_id, character_id, character_name ) in query_data.into_iter() {
 
                         // If user isn't in the hashmap, insert them with character data
                         // Else appened character data
@@ -145,6 +188,37 @@ async fn main() {
             };
         // ==--
 
+        // --== CREATE AND POPULATE ACTIVE CHARACTER CACHE ==-- //
+
+            // Next, every user who is currently playing as one of their characters
+            print!("Syncing Active Characters to Database...");
+            let query_result = db::discord_users::all_with_current_character( &client.database_connection ).await;
+
+            let active_characters_cache = match query_result {
+                Ok(query_data) => {
+                    let active_characters_map: HashMap<u64, utils::CharacterId> = query_data
+                        .iter()
+                        .filter_map( |user| Some(( user.discord_id as u64, user.current_character? )) )
+                        .collect();
+
+                    println!("Ok");
+                    active_characters_map
+                },
+                Err(why) => {
+                    println!("Error: {}", why);
+                    break 'main Err( 1 );
+                }
+            };
+        // ==--
+
+        // --== START EMPTYING TRASH ==-- //
+
+            // Characters that have been in the trash for too long get removed in the background
+            print!("Starting Trash Emptying Task...");
+            trash::spawn_emptying_task( client.database_connection.clone() );
+            println!("Ok");
+        // ==--
+
         // --== BUILD CLIENT ==-- // 
 
             print!("Building Client...");
@@ -152,12 +226,15 @@ async fn main() {
                 Ok(client_builder) => {
                     println!("Ok");
 
-                    // We insert the cache to allow it to be used in the future
+                    // We insert the caches to allow them to be used in the future
                     {
                         let mut data_write = client_builder.data.write().await;
                         data_write.insert::<DatabaseCharactersCache>(
                             Arc::new(Mutex::new(  characters_cache  ))
                         );
+                        data_write.insert::<ActiveCharactersCache>(
+                            Arc::new(Mutex::new(  active_characters_cache  ))
+                        );
                     }
                     client_builder
                 },
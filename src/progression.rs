@@ -0,0 +1,103 @@
+// Experience and levelling up
+//
+// - Characters gain experience when it's awarded to them with `/award_xp`. The level curve in the
+//     config says how much experience each level needs, everyone starts at level 1
+// - Every level gained grants attribute points, which are spent with `/level_up` through the same
+//     allocation message used when building a character. Attributes can only be raised that way
+// - Each level gained is recorded in the LevelUps table. The stored level never drops, even if
+//     the level curve is changed to need more experience
+
+use sqlx::SqlitePool;
+
+use crate::{
+    config,
+    db::{self, LevelUp, Progression},
+    error::BotError,
+    utils::{unix_now, CharacterId}
+};
+
+
+/// How much experience each level needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelCurve {
+    /// Experience needed for level 2, 3 and so on. Always rising
+    thresholds: Vec<i64>
+}
+impl LevelCurve {
+    /// Build a curve out of the experience needed for level 2 onwards. Returns `None` unless
+    /// every threshold is above 0 and higher than the one before
+    pub fn new( thresholds: Vec<i64> ) -> Option<LevelCurve> {
+        let rising = thresholds.first().is_none_or( |first| *first > 0 )
+            && thresholds.windows(2).all( |pair| pair[0] < pair[1] );
+
+        match rising {
+            true  => Some( LevelCurve { thresholds } ),
+            false => None
+        }
+    }
+
+    /// The level reached with the given experience
+    pub fn level_for( &self, experience: i64 ) -> i64 {
+        1 + self.thresholds.iter().filter( |threshold| **threshold <= experience ).count() as i64
+    }
+
+    /// Experience needed to reach a level. `None` past the last level of the curve
+    pub fn experience_for( &self, level: i64 ) -> Option<i64> {
+        match level {
+            ..=1 => Some( 0 ),
+            _ => self.thresholds.get( (level - 2) as usize ).copied()
+        }
+    }
+}
+
+
+/// Add experience to a character's progression, levelling them up as far as the curve allows.
+/// Returns the new progression and a record of every level gained
+pub fn apply_experience( progression: &Progression, amount: i64, awarded_by: u64, awarded_at: i64 ) -> (Progression, Vec<LevelUp>) {
+    let config = config::get();
+
+    let experience = progression.experience.saturating_add( amount );
+    let level = config.level_curve.level_for( experience ).max( progression.level );
+
+    let level_ups = ( progression.level + 1 ..= level )
+        .map( |reached_level| LevelUp {
+            level_up_id:    0,
+            character_id:   progression.character_id,
+            level:          reached_level,
+            experience,
+            points_granted: config.points_per_level,
+            awarded_by:     awarded_by as i64,
+            levelled_at:    awarded_at
+        })
+        .collect::<Vec<LevelUp>>();
+
+    let updated = Progression {
+        experience,
+        level,
+        unspent_points: progression.unspent_points + config.points_per_level * level_ups.len() as i64,
+        ..*progression
+    };
+
+    ( updated, level_ups )
+}
+
+/// Award experience to a character, recording any levels they gain. Returns their progression
+/// from before and after
+pub async fn award_experience( database_connection: &SqlitePool, character_id: CharacterId, amount: i64, awarded_by: u64 ) -> Result<(Progression, Progression), BotError> {
+    let awarded_at = unix_now();
+
+    let progressions = db::progression::award( database_connection, character_id, |stored| {
+        apply_experience( stored, amount, awarded_by, awarded_at )
+    }).await?;
+
+    Ok( progressions )
+}
+
+/// Sums up where a character stands, like `Level 3 · 450/600 XP`. At the last level of the curve
+/// there's nothing left to reach, so only the experience is shown
+pub fn progress_text( progression: &Progression ) -> String {
+    match config::get().level_curve.experience_for( progression.level + 1 ) {
+        Some( next_level_at ) => format!("Level {} · {}/{next_level_at} XP", progression.level, progression.experience),
+        None => format!("Level {} · {} XP", progression.level, progression.experience)
+    }
+}
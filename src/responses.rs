@@ -0,0 +1,60 @@
+// Where responses to interactions get sent
+//
+// - Handlers never talk to Discord's HTTP API themselves, they hand their responses to a
+//     `ResponseSink`. The bot uses serenity's `Http` as it's sink, while tests can point one at
+//     anything that records what was sent
+// - Serenity's `Context` can only be made by a live gateway connection, so handlers are given a
+//     `BotContext` instead. It carries the client's data and the sink, neither of which need
+//     Discord to be made
+
+use std::sync::Arc;
+
+use serenity::{
+    all::{
+        async_trait, Builder, Context, CreateInteractionResponse, EditInteractionResponse, Http,
+        InteractionId
+    },
+    prelude::{RwLock, TypeMap}
+};
+
+
+/// Something responses to interactions can be sent to
+#[async_trait]
+pub trait ResponseSink: Send + Sync {
+
+    /// Send the first response to an interaction
+    async fn create_response( &self, interaction_id: InteractionId, token: &str, response: CreateInteractionResponse ) -> serenity::Result<()>;
+
+    /// Edit the response that was already sent to an interaction
+    async fn edit_response( &self, token: &str, edit: EditInteractionResponse ) -> serenity::Result<()>;
+}
+
+#[async_trait]
+impl ResponseSink for Http {
+    async fn create_response( &self, interaction_id: InteractionId, token: &str, response: CreateInteractionResponse ) -> serenity::Result<()> {
+        response.execute( self, (interaction_id, token) ).await
+    }
+
+    async fn edit_response( &self, token: &str, edit: EditInteractionResponse ) -> serenity::Result<()> {
+        edit.execute( self, token ).await?;
+        Ok(())
+    }
+}
+
+
+/// Everything a handler needs to respond to an interaction
+pub struct BotContext {
+    /// The client's data, holding the caches
+    pub data:      Arc<RwLock<TypeMap>>,
+    pub responses: Arc<dyn ResponseSink>
+}
+impl BotContext {
+
+    /// Take what handlers need out of the `Context` serenity hands to event handlers
+    pub fn from_serenity( ctx: &Context ) -> BotContext {
+        BotContext {
+            data:      ctx.data.clone(),
+            responses: ctx.http.clone()
+        }
+    }
+}
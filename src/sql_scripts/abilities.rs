@@ -0,0 +1,83 @@
+/// Give a character a new ability. The ability's ID is allocated per character, starting at 1
+///
+/// Binds:
+///   - fk_pk_characterId
+///   - abilityName
+///   - abilityDescription
+pub const ADD_ABILITY: &str = "
+    INSERT INTO CharacterAbilities ( fk_pk_characterId, pk_abilityId, abilityName, abilityDescription )
+    VALUES (
+        ?1,
+        (SELECT IFNULL(MAX(pk_abilityId), 0) + 1 FROM CharacterAbilities WHERE fk_pk_characterId = ?1),
+        ?2,
+        ?3
+    )
+";
+
+/// Select every ability of a character, ordered by their ID
+///
+/// Binds:
+///   - fk_pk_characterId
+///
+/// Returns:
+///   - fk_pk_characterId
+///   - pk_abilityId
+///   - abilityName
+///   - abilityDescription
+pub const SELECT_BY_CHARACTER_ID: &str = "
+    SELECT fk_pk_characterId, pk_abilityId, abilityName, abilityDescription
+    FROM CharacterAbilities
+    WHERE fk_pk_characterId = ?1
+    ORDER BY pk_abilityId;
+";
+
+/// Select a single ability of a character
+///
+/// Binds:
+///   - fk_pk_characterId
+///   - pk_abilityId
+///
+/// Returns:
+///   - fk_pk_characterId
+///   - pk_abilityId
+///   - abilityName
+///   - abilityDescription
+pub const SELECT_BY_ID: &str = "
+    SELECT fk_pk_characterId, pk_abilityId, abilityName, abilityDescription
+    FROM CharacterAbilities
+    WHERE fk_pk_characterId = ?1 AND pk_abilityId = ?2;
+";
+
+/// Change the name and description of an ability
+///
+/// Binds:
+///   - fk_pk_characterId
+///   - pk_abilityId
+///   - abilityName
+///   - abilityDescription
+pub const UPDATE_ABILITY: &str = "
+    UPDATE CharacterAbilities
+    SET abilityName = ?3, abilityDescription = ?4
+    WHERE fk_pk_characterId = ?1 AND pk_abilityId = ?2;
+";
+
+/// Remove an ability from a character
+///
+/// Binds:
+///   - fk_pk_characterId
+///   - pk_abilityId
+pub const REMOVE_ABILITY: &str = "
+    DELETE
+    FROM CharacterAbilities
+    WHERE fk_pk_characterId = ?1 AND pk_abilityId = ?2;
+";
+
+/// Remove every ability of a character
+///
+/// Binds:
+///   - fk_pk_characterId
+pub const REMOVE_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM CharacterAbilities
+    WHERE fk_pk_characterId = ?1;
+";
@@ -0,0 +1,68 @@
+/// Change the attributes of a character. Characters who haven't had their attributes allocated
+/// yet don't have a row, so nothing changes for them
+///
+/// Binds:
+///   - fk_pk_characterId
+///   - Strength
+///   - Dexterity
+///   - Preception
+///   - Knowledge
+///   - Constitution
+///   - Casting
+pub const UPDATE_ATTRIBUTES: &str = "
+    UPDATE Atributes
+    SET Strength     = ?2,
+        Dexterity    = ?3,
+        Preception   = ?4,
+        Knowledge    = ?5,
+        Constitution = ?6,
+        Casting      = ?7
+    WHERE fk_pk_characterId = ?1;
+";
+
+/// Give a character their first attributes. If the character already has attributes, nothing
+/// changes
+///
+/// Binds:
+///   - fk_pk_characterId
+///   - Strength
+///   - Dexterity
+///   - Preception
+///   - Knowledge
+///   - Constitution
+///   - Casting
+pub const INSERT_ATTRIBUTES: &str = "
+    INSERT INTO Atributes ( fk_pk_characterId, Strength, Dexterity, Preception, Knowledge, Constitution, Casting )
+    VALUES ( ?1, ?2, ?3, ?4, ?5, ?6, ?7 )
+    ON CONFLICT ( fk_pk_characterId ) DO NOTHING;
+";
+
+
+/// Select a character's attributes
+///
+/// Binds:
+///   - fk_pk_characterId
+///
+/// Returns:
+///   - fk_pk_characterId
+///   - Strength
+///   - Dexterity
+///   - Preception
+///   - Knowledge
+///   - Constitution
+///   - Casting
+pub const SELECT_BY_CHARACTER_ID: &str = "
+    SELECT fk_pk_characterId, Strength, Dexterity, Preception, Knowledge, Constitution, Casting
+    FROM Atributes
+    WHERE fk_pk_characterId = ?1;
+";
+
+/// Remove a character's attributes
+///
+/// Binds:
+///   - fk_pk_characterId
+pub const REMOVE_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM Atributes
+    WHERE fk_pk_characterId = ?1;
+";
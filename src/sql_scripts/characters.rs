@@ -1,19 +1,18 @@
-/// Add a user's character to the database. Auto increments
+/// Add a user's character to the database. The ID is allocated by SQLite itself, as
+/// pk_characterId is an alias of the rowid
 ///
 /// Binds:
 ///   - fk_discordId
 ///   - pk_name       // Needs to be manually enforced
 ///   - species
 ///   - backstory
+///
+/// Returns:
+///   - pk_characterId
 pub const ADD_CHARACTER: &str = "
-    INSERT INTO Characters ( pk_characterId, fk_discordId, pk_name, species, backstory )
-    VALUES (
-        (SELECT IFNULL(MAX(pk_characterId), 0) + 1 FROM Characters),
-        ?1,
-        ?2,
-        ?3,
-        ?4
-    )
+    INSERT INTO Characters ( fk_discordId, pk_name, species, backstory )
+    VALUES ( ?1, ?2, ?3, ?4 )
+    RETURNING pk_characterId;
 ";
 
 /// Select by owner's discord ID
@@ -26,7 +25,8 @@ pub const SELECT_BY_OWNER_ID: &str = "
     WHERE fk_discordId = ?1;
 ";
 
-/// Select characters by name and discord user ID
+/// Select characters by name and discord user ID. Archived characters are left out, so their names
+/// can be used again
 ///
 /// Binds:
 ///   - fk_discordId
@@ -34,10 +34,50 @@ pub const SELECT_BY_OWNER_ID: &str = "
 pub const SELECT_BY_NAME_AND_OWNER_ID: &str = "
     SELECT *
     FROM Characters
-    WHERE fk_discordId = ?1 AND pk_name = ?2;
+    WHERE fk_discordId = ?1 AND pk_name = ?2 AND archived_at IS NULL;
+";
+
+/// Select the archived characters of a discord user, most recently archived first
+///
+/// Binds:
+///   - fk_discordId
+pub const SELECT_ARCHIVED_BY_OWNER_ID: &str = "
+    SELECT *
+    FROM Characters
+    WHERE fk_discordId = ?1 AND archived_at IS NOT NULL
+    ORDER BY archived_at DESC;
+";
+
+/// Select the IDs of characters archived before the given time
+///
+/// Binds:
+///   - archived_at
+pub const SELECT_ARCHIVED_BEFORE: &str = "
+    SELECT pk_characterId
+    FROM Characters
+    WHERE archived_at < ?1;
+";
+
+/// Select a single character by it's ID
+///
+/// Binds:
+///   - pk_characterId
+///
+/// Returns:
+///   - pk_characterId
+///   - fk_discordId
+///   - pk_name
+///   - species
+///   - backstory
+///   - archived_at
+pub const SELECT_BY_ID: &str = "
+    SELECT pk_characterId, fk_discordId, pk_name, species, backstory, archived_at
+    FROM Characters
+    WHERE pk_characterId = ?1;
 ";
 
-/// Get the owner's DiscordID, character's ID, and name for every character in the database
+/// Get the owner's DiscordID, character's ID, and name for every character in the database that
+/// isn't archived
 ///
 /// Returns:
 ///   - fk_discordId
@@ -45,18 +85,48 @@ pub const SELECT_BY_NAME_AND_OWNER_ID: &str = "
 ///   - pk_name
 pub const SELECT_ALL_CHARACTER_IDS_AND_NAME: &str = "
     SELECT fk_discordId, pk_characterId, pk_name
-    FROM Characters;
+    FROM Characters
+    WHERE archived_at IS NULL;
+";
+
+/// Change a character's name, species and backstory
+///
+/// Binds:
+///   - pk_characterId
+///   - pk_name       // Needs to be manually enforced
+///   - species
+///   - backstory
+pub const UPDATE_CHARACTER: &str = "
+    UPDATE Characters
+    SET pk_name = ?2, species = ?3, backstory = ?4
+    WHERE pk_characterId = ?1;
+";
+
+/// Move a character into the trash. Characters already in it keep the time they were archived
+///
+/// Binds:
+///   - pk_characterId
+///   - archived_at
+pub const ARCHIVE_CHARACTER: &str = "
+    UPDATE Characters
+    SET archived_at = ?2
+    WHERE pk_characterId = ?1 AND archived_at IS NULL;
 ";
 
-pub const GET_NEWEST_CHARACTER_ID: &str = "
-    SELECT MAX(pk_characterId)
-    FROM Characters;
+/// Take a character back out of the trash
+///
+/// Binds:
+///   - pk_characterId
+pub const RESTORE_CHARACTER: &str = "
+    UPDATE Characters
+    SET archived_at = null
+    WHERE pk_characterId = ?1;
 ";
 
 /// Remove a character
 ///
 /// Fails:
-///   - This query can fail if foreign keys refer to it
+///   - This query can fail if foreign keys refer to it. `db::characters::remove_with_dependents` clears them
 ///
 /// Binds:
 ///   - pk_characterId
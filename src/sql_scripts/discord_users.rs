@@ -14,3 +14,36 @@ pub const REMOVE_ENTRY: &str = "
     WHERE pk_discordId = ?1
 ";
 
+
+/// Set the character a user is currently playing as
+///
+/// Binds:
+///   - pk_discordId
+///   - fk_currentCharacter
+pub const SET_CURRENT_CHARACTER: &str = "
+    UPDATE DiscordUsers
+    SET fk_currentCharacter = ?2
+    WHERE pk_discordId = ?1
+";
+
+/// Unset the current character of whoever is playing as the given character. Needs to be ran
+/// before the character is removed, as the foreign key would otherwise block it
+///
+/// Binds:
+///   - fk_currentCharacter
+pub const CLEAR_CURRENT_CHARACTER: &str = "
+    UPDATE DiscordUsers
+    SET fk_currentCharacter = null
+    WHERE fk_currentCharacter = ?1
+";
+
+/// Get every user that has a current character set
+///
+/// Returns:
+///   - pk_discordId
+///   - fk_currentCharacter
+pub const SELECT_ALL_CURRENT_CHARACTERS: &str = "
+    SELECT pk_discordId, fk_currentCharacter
+    FROM DiscordUsers
+    WHERE fk_currentCharacter IS NOT NULL
+";
@@ -0,0 +1,120 @@
+/// Start an encounter, returning it's newly allocated ID
+///
+/// Binds:
+///   - guildId
+///   - channelId
+///   - gameMasterId
+///   - encounterName
+///   - startedAt
+///
+/// Returns:
+///   - pk_encounterId
+pub const START_ENCOUNTER: &str = "
+    INSERT INTO Encounters (guildId, channelId, gameMasterId, encounterName, startedAt)
+    VALUES ( ?1, ?2, ?3, ?4, ?5 )
+    RETURNING pk_encounterId;
+";
+
+/// Select an encounter by it's ID
+///
+/// Binds:
+///   - pk_encounterId
+///
+/// Returns:
+///   - Every column of Encounters
+pub const SELECT_BY_ID: &str = "
+    SELECT pk_encounterId, guildId, channelId, gameMasterId, encounterName, round, fk_currentCombatantId, startedAt
+    FROM Encounters
+    WHERE pk_encounterId = ?1;
+";
+
+/// Select the encounter running in a channel
+///
+/// Binds:
+///   - channelId
+///
+/// Returns:
+///   - Every column of Encounters
+pub const SELECT_BY_CHANNEL: &str = "
+    SELECT pk_encounterId, guildId, channelId, gameMasterId, encounterName, round, fk_currentCombatantId, startedAt
+    FROM Encounters
+    WHERE channelId = ?1;
+";
+
+/// Move an encounter onto another turn, but only if nobody else has moved it since it was read.
+/// No rows are changed otherwise
+///
+/// Binds:
+///   - round
+///   - fk_currentCombatantId
+///   - pk_encounterId
+///   - The round it was read with
+///   - The fk_currentCombatantId it was read with
+pub const SET_TURN: &str = "
+    UPDATE Encounters
+    SET round = ?1, fk_currentCombatantId = ?2
+    WHERE pk_encounterId = ?3 AND round = ?4 AND fk_currentCombatantId IS ?5;
+";
+
+/// Remove an encounter. It's combatants have to be removed first
+///
+/// Binds:
+///   - pk_encounterId
+pub const REMOVE_ENCOUNTER: &str = "
+    DELETE
+    FROM Encounters
+    WHERE pk_encounterId = ?1;
+";
+
+/// Add someone to an encounter, returning their newly allocated ID
+///
+/// Binds:
+///   - fk_encounterId
+///   - fk_characterId
+///   - discordId
+///   - combatantName
+///   - initiative
+///   - dexterity
+///
+/// Returns:
+///   - pk_combatantId
+pub const ADD_COMBATANT: &str = "
+    INSERT INTO Combatants (fk_encounterId, fk_characterId, discordId, combatantName, initiative, dexterity)
+    VALUES ( ?1, ?2, ?3, ?4, ?5, ?6 )
+    RETURNING pk_combatantId;
+";
+
+/// Select everyone in an encounter in the order they take their turns. Higher initiative goes
+/// first, then higher dexterity, then whoever joined first
+///
+/// Binds:
+///   - fk_encounterId
+///
+/// Returns:
+///   - Every column of Combatants
+pub const SELECT_TURN_ORDER: &str = "
+    SELECT pk_combatantId, fk_encounterId, fk_characterId, discordId, combatantName, initiative, dexterity
+    FROM Combatants
+    WHERE fk_encounterId = ?1
+    ORDER BY initiative DESC, dexterity DESC, pk_combatantId ASC;
+";
+
+/// Remove everyone from an encounter
+///
+/// Binds:
+///   - fk_encounterId
+pub const REMOVE_COMBATANTS: &str = "
+    DELETE
+    FROM Combatants
+    WHERE fk_encounterId = ?1;
+";
+
+/// Take a character out of every encounter they're in
+///
+/// Binds:
+///   - fk_characterId
+pub const REMOVE_CHARACTER: &str = "
+    DELETE
+    FROM Combatants
+    WHERE fk_characterId = ?1;
+";
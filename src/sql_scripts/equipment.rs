@@ -0,0 +1,89 @@
+/// Select everything a character has equipped
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - slot, the one it's equipped in
+///   - Every column of Items
+pub const SELECT_EQUIPPED: &str = "
+    SELECT EquippedItems.slot AS equippedSlot, Items.pk_itemId, guildId, itemName, weight, value, description, tags, Items.slot,
+        strengthModifier, dexterityModifier, preceptionModifier, knowledgeModifier, constitutionModifier, castingModifier
+    FROM EquippedItems
+    JOIN Items ON Items.pk_itemId = EquippedItems.fk_itemId
+    WHERE fk_characterId = ?1;
+";
+
+/// Equip an item into it's slot, replacing whatever was in there. Nothing is equipped unless the
+/// character is carrying the item and it has a slot
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_itemId
+pub const EQUIP: &str = "
+    INSERT INTO EquippedItems (fk_characterId, slot, fk_itemId)
+    SELECT InventoryItems.fk_characterId, Items.slot, Items.pk_itemId
+    FROM InventoryItems
+    JOIN Items ON Items.pk_itemId = InventoryItems.fk_itemId
+    WHERE InventoryItems.fk_characterId = ?1 AND InventoryItems.fk_itemId = ?2 AND Items.slot IS NOT NULL
+    ON CONFLICT (fk_characterId, slot)
+    DO UPDATE SET fk_itemId = excluded.fk_itemId;
+";
+
+/// Take whatever's in one of a character's slots off
+///
+/// Binds:
+///   - fk_characterId
+///   - slot
+pub const UNEQUIP: &str = "
+    DELETE
+    FROM EquippedItems
+    WHERE fk_characterId = ?1 AND slot = ?2;
+";
+
+/// Take an item off a character once they're no longer carrying any of it
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_itemId
+pub const UNEQUIP_IF_NOT_CARRIED: &str = "
+    DELETE
+    FROM EquippedItems
+    WHERE fk_characterId = ?1 AND fk_itemId = ?2
+        AND NOT EXISTS (
+            SELECT 1
+            FROM InventoryItems
+            WHERE fk_characterId = ?1 AND fk_itemId = ?2
+        );
+";
+
+/// Take an item off everyone who has it equipped in any other slot than the given one
+///
+/// Binds:
+///   - fk_itemId
+///   - slot, NULL to take it off everyone
+pub const UNEQUIP_OTHER_SLOTS: &str = "
+    DELETE
+    FROM EquippedItems
+    WHERE fk_itemId = ?1 AND slot IS NOT ?2;
+";
+
+/// Take an item off everyone
+///
+/// Binds:
+///   - fk_itemId
+pub const REMOVE_BY_ITEM_ID: &str = "
+    DELETE
+    FROM EquippedItems
+    WHERE fk_itemId = ?1;
+";
+
+/// Take everything off a character
+///
+/// Binds:
+///   - fk_characterId
+pub const REMOVE_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM EquippedItems
+    WHERE fk_characterId = ?1;
+";
@@ -0,0 +1,187 @@
+/// Add an item to a server's catalogue, returning it's newly allocated ID
+///
+/// Binds:
+///   - guildId
+///   - itemName
+///   - weight
+///   - value
+///   - description
+///   - tags
+///
+/// Returns:
+///   - pk_itemId
+pub const ADD_ITEM: &str = "
+    INSERT INTO Items (guildId, itemName, weight, value, description, tags)
+    VALUES ( ?1, ?2, ?3, ?4, ?5, ?6 )
+    RETURNING pk_itemId;
+";
+
+/// Select an item from a server's catalogue by it's ID
+///
+/// Binds:
+///   - pk_itemId
+///   - guildId
+///
+/// Returns:
+///   - Every column of Items
+pub const SELECT_BY_ID: &str = "
+    SELECT pk_itemId, guildId, itemName, weight, value, description, tags, slot,
+        strengthModifier, dexterityModifier, preceptionModifier, knowledgeModifier, constitutionModifier, castingModifier
+    FROM Items
+    WHERE pk_itemId = ?1 AND guildId = ?2;
+";
+
+/// Select every item in a server's catalogue, ordered by name
+///
+/// Binds:
+///   - guildId
+///
+/// Returns:
+///   - Every column of Items
+pub const SELECT_BY_GUILD: &str = "
+    SELECT pk_itemId, guildId, itemName, weight, value, description, tags, slot,
+        strengthModifier, dexterityModifier, preceptionModifier, knowledgeModifier, constitutionModifier, castingModifier
+    FROM Items
+    WHERE guildId = ?1
+    ORDER BY itemName;
+";
+
+/// Overwrite an item in a server's catalogue
+///
+/// Binds:
+///   - itemName
+///   - weight
+///   - value
+///   - description
+///   - tags
+///   - pk_itemId
+///   - guildId
+pub const UPDATE_ITEM: &str = "
+    UPDATE Items
+    SET itemName = ?1, weight = ?2, value = ?3, description = ?4, tags = ?5
+    WHERE pk_itemId = ?6 AND guildId = ?7;
+";
+
+/// Set the slot an item is equipped into, and the modifiers it gives to each attribute
+///
+/// Binds:
+///   - slot, NULL if it can't be equipped
+///   - strengthModifier
+///   - dexterityModifier
+///   - preceptionModifier
+///   - knowledgeModifier
+///   - constitutionModifier
+///   - castingModifier
+///   - pk_itemId
+///   - guildId
+pub const SET_GEAR: &str = "
+    UPDATE Items
+    SET slot = ?1,
+        strengthModifier     = ?2,
+        dexterityModifier    = ?3,
+        preceptionModifier   = ?4,
+        knowledgeModifier    = ?5,
+        constitutionModifier = ?6,
+        castingModifier      = ?7
+    WHERE pk_itemId = ?8 AND guildId = ?9;
+";
+
+/// Remove an item from a server's catalogue. It has to be taken out of every inventory first
+///
+/// Binds:
+///   - pk_itemId
+///   - guildId
+pub const REMOVE_ITEM: &str = "
+    DELETE
+    FROM Items
+    WHERE pk_itemId = ?1 AND guildId = ?2;
+";
+
+/// Select everything a character is carrying, ordered by name
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - Every column of Items
+///   - quantity
+pub const SELECT_INVENTORY: &str = "
+    SELECT Items.pk_itemId, guildId, itemName, weight, value, description, tags, slot,
+        strengthModifier, dexterityModifier, preceptionModifier, knowledgeModifier, constitutionModifier, castingModifier,
+        quantity
+    FROM InventoryItems
+    JOIN Items ON Items.pk_itemId = InventoryItems.fk_itemId
+    WHERE fk_characterId = ?1
+    ORDER BY itemName;
+";
+
+/// Sum up the weight of everything a character is carrying
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - The total weight, 0 if they're carrying nothing
+pub const SELECT_CARRIED_WEIGHT: &str = "
+    SELECT COALESCE( SUM(quantity * weight), 0 )
+    FROM InventoryItems
+    JOIN Items ON Items.pk_itemId = InventoryItems.fk_itemId
+    WHERE fk_characterId = ?1;
+";
+
+/// Put some of an item into a character's inventory, on top of what they've already got
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_itemId
+///   - quantity
+pub const ADD_TO_INVENTORY: &str = "
+    INSERT INTO InventoryItems (fk_characterId, fk_itemId, quantity)
+    VALUES ( ?1, ?2, ?3 )
+    ON CONFLICT (fk_characterId, fk_itemId)
+    DO UPDATE SET quantity = quantity + excluded.quantity;
+";
+
+/// Take some of an item out of a character's inventory. Nothing is taken unless they've got at
+/// least that many
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_itemId
+///   - quantity
+pub const TAKE_FROM_INVENTORY: &str = "
+    UPDATE InventoryItems
+    SET quantity = quantity - ?3
+    WHERE fk_characterId = ?1 AND fk_itemId = ?2 AND quantity >= ?3;
+";
+
+/// Remove an item from a character's inventory once they've got none of it left
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_itemId
+pub const REMOVE_EMPTY: &str = "
+    DELETE
+    FROM InventoryItems
+    WHERE fk_characterId = ?1 AND fk_itemId = ?2 AND quantity <= 0;
+";
+
+/// Take an item out of every inventory
+///
+/// Binds:
+///   - fk_itemId
+pub const REMOVE_BY_ITEM_ID: &str = "
+    DELETE
+    FROM InventoryItems
+    WHERE fk_itemId = ?1;
+";
+
+/// Empty a character's inventory
+///
+/// Binds:
+///   - fk_characterId
+pub const REMOVE_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM InventoryItems
+    WHERE fk_characterId = ?1;
+";
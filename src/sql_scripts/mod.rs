@@ -1,3 +1,11 @@
 pub mod discord_users;
 pub mod characters;
-
+pub mod attributes;
+pub mod abilities;
+pub mod rolls;
+pub mod encounters;
+pub mod resources;
+pub mod spells;
+pub mod progression;
+pub mod items;
+pub mod equipment;
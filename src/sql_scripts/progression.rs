@@ -0,0 +1,87 @@
+/// Select a character's experience, level and unspent attribute points
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - Every column of CharacterProgression
+pub const SELECT_BY_CHARACTER_ID: &str = "
+    SELECT fk_characterId, experience, level, unspentPoints
+    FROM CharacterProgression
+    WHERE fk_characterId = ?1;
+";
+
+/// Set a character's progression, adding their row if they don't have one
+///
+/// Binds:
+///   - fk_characterId
+///   - experience
+///   - level
+///   - unspentPoints
+pub const SET_PROGRESSION: &str = "
+    INSERT INTO CharacterProgression (fk_characterId, experience, level, unspentPoints)
+    VALUES ( ?1, ?2, ?3, ?4 )
+    ON CONFLICT (fk_characterId) DO UPDATE
+    SET experience = excluded.experience, level = excluded.level, unspentPoints = excluded.unspentPoints;
+";
+
+/// Take spent attribute points away from a character, but only if they still have as many as
+/// when they started spending them. Guards against the same points being spent twice
+///
+/// Binds:
+///   - fk_characterId
+///   - Points spent
+///   - unspentPoints the character is expected to have
+pub const SPEND_POINTS: &str = "
+    UPDATE CharacterProgression
+    SET unspentPoints = unspentPoints - ?2
+    WHERE fk_characterId = ?1 AND unspentPoints = ?3;
+";
+
+/// Remove a character's progression
+///
+/// Binds:
+///   - fk_characterId
+pub const REMOVE_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM CharacterProgression
+    WHERE fk_characterId = ?1;
+";
+
+/// Record a level gained by a character
+///
+/// Binds:
+///   - fk_characterId
+///   - level
+///   - experience
+///   - pointsGranted
+///   - awardedBy
+///   - levelledAt
+pub const ADD_LEVEL_UP: &str = "
+    INSERT INTO LevelUps (fk_characterId, level, experience, pointsGranted, awardedBy, levelledAt)
+    VALUES ( ?1, ?2, ?3, ?4, ?5, ?6 );
+";
+
+/// Select every level a character gained, the earliest first
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - Every column of LevelUps
+pub const SELECT_LEVEL_UPS: &str = "
+    SELECT pk_levelUpId, fk_characterId, level, experience, pointsGranted, awardedBy, levelledAt
+    FROM LevelUps
+    WHERE fk_characterId = ?1
+    ORDER BY level ASC, pk_levelUpId ASC;
+";
+
+/// Remove a character's level ups
+///
+/// Binds:
+///   - fk_characterId
+pub const REMOVE_LEVEL_UPS: &str = "
+    DELETE
+    FROM LevelUps
+    WHERE fk_characterId = ?1;
+";
@@ -0,0 +1,35 @@
+/// Select how much HP and mana a character has left
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - Every column of CharacterResources
+pub const SELECT_BY_CHARACTER_ID: &str = "
+    SELECT fk_characterId, currentHp, currentMana
+    FROM CharacterResources
+    WHERE fk_characterId = ?1;
+";
+
+/// Set how much HP and mana a character has left, adding their row if they don't have one
+///
+/// Binds:
+///   - fk_characterId
+///   - currentHp
+///   - currentMana
+pub const SET_RESOURCES: &str = "
+    INSERT INTO CharacterResources (fk_characterId, currentHp, currentMana)
+    VALUES ( ?1, ?2, ?3 )
+    ON CONFLICT (fk_characterId) DO UPDATE
+    SET currentHp = excluded.currentHp, currentMana = excluded.currentMana;
+";
+
+/// Remove a character's resources, putting them back at full
+///
+/// Binds:
+///   - fk_characterId
+pub const REMOVE_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM CharacterResources
+    WHERE fk_characterId = ?1;
+";
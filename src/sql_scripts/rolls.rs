@@ -0,0 +1,113 @@
+/// Record a roll
+///
+/// Binds:
+///   - discordId
+///   - fk_characterId
+///   - characterName
+///   - guildId
+///   - channelId
+///   - label
+///   - expression
+///   - total
+///   - naturalD20
+///   - rolledAt
+pub const ADD_ROLL: &str = "
+    INSERT INTO Rolls (
+        discordId, fk_characterId, characterName, guildId, channelId,
+        label, expression, total, naturalD20, rolledAt
+    )
+    VALUES ( ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10 );
+";
+
+/// Select a page of a user's rolls in a guild, newest first. When fk_characterId is bound to
+/// NULL, rolls of every character are selected. guildId is NULL for DMs
+///
+/// Binds:
+///   - discordId
+///   - guildId
+///   - fk_characterId
+///   - LIMIT
+///   - OFFSET
+///
+/// Returns:
+///   - Every column of Rolls
+pub const SELECT_PAGE: &str = "
+    SELECT
+        pk_rollId, discordId, fk_characterId, characterName, guildId, channelId,
+        label, expression, total, naturalD20, rolledAt
+    FROM Rolls
+    WHERE discordId = ?1 AND guildId IS ?2 AND (?3 IS NULL OR fk_characterId = ?3)
+    ORDER BY rolledAt DESC, pk_rollId DESC
+    LIMIT ?4 OFFSET ?5;
+";
+
+/// Count a user's rolls in a guild, with the same filters as `SELECT_PAGE`
+///
+/// Binds:
+///   - discordId
+///   - guildId
+///   - fk_characterId
+pub const COUNT_ROLLS: &str = "
+    SELECT COUNT(*)
+    FROM Rolls
+    WHERE discordId = ?1 AND guildId IS ?2 AND (?3 IS NULL OR fk_characterId = ?3);
+";
+
+/// Sum up a user's rolls in a guild for each character they rolled as, ordered by the name.
+/// Rolls made without a character come last
+///
+/// Binds:
+///   - discordId
+///   - guildId
+///
+/// Returns:
+///   - fk_characterId
+///   - characterName
+///   - rollCount
+///   - averageTotal
+///   - averageNatural
+///   - naturalTwenties
+///   - naturalOnes
+pub const SELECT_STATS: &str = "
+    SELECT
+        fk_characterId,
+        characterName,
+        COUNT(*)                  AS rollCount,
+        AVG(total)                AS averageTotal,
+        AVG(naturalD20)           AS averageNatural,
+        COUNT(*) FILTER (WHERE naturalD20 = 20)  AS naturalTwenties,
+        COUNT(*) FILTER (WHERE naturalD20 = 1)   AS naturalOnes
+    FROM Rolls
+    WHERE discordId = ?1 AND guildId IS ?2
+    GROUP BY fk_characterId, characterName
+    ORDER BY characterName IS NULL, characterName, fk_characterId;
+";
+
+/// Count how often each face of the d20 came up for one row of `SELECT_STATS`
+///
+/// Binds:
+///   - discordId
+///   - guildId
+///   - fk_characterId
+///   - characterName
+///
+/// Returns:
+///   - naturalD20
+///   - COUNT(*)
+pub const SELECT_NATURAL_DISTRIBUTION: &str = "
+    SELECT naturalD20, COUNT(*)
+    FROM Rolls
+    WHERE discordId = ?1 AND guildId IS ?2 AND fk_characterId IS ?3 AND characterName IS ?4
+        AND naturalD20 IS NOT NULL
+    GROUP BY naturalD20;
+";
+
+/// Unlink a character's rolls from it, keeping the rolls themselves
+///
+/// Binds:
+///   - fk_characterId
+pub const CLEAR_CHARACTER: &str = "
+    UPDATE Rolls
+    SET fk_characterId = NULL
+    WHERE fk_characterId = ?1;
+";
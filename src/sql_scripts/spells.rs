@@ -0,0 +1,128 @@
+/// Add a spell to a server's catalogue, returning it's newly allocated ID
+///
+/// Binds:
+///   - guildId
+///   - spellName
+///   - school
+///   - manaCost
+///   - difficulty
+///   - effect
+///
+/// Returns:
+///   - pk_spellId
+pub const ADD_SPELL: &str = "
+    INSERT INTO Spells (guildId, spellName, school, manaCost, difficulty, effect)
+    VALUES ( ?1, ?2, ?3, ?4, ?5, ?6 )
+    RETURNING pk_spellId;
+";
+
+/// Select a spell from a server's catalogue by it's ID
+///
+/// Binds:
+///   - pk_spellId
+///   - guildId
+///
+/// Returns:
+///   - Every column of Spells
+pub const SELECT_BY_ID: &str = "
+    SELECT pk_spellId, guildId, spellName, school, manaCost, difficulty, effect
+    FROM Spells
+    WHERE pk_spellId = ?1 AND guildId = ?2;
+";
+
+/// Select every spell in a server's catalogue, ordered by name
+///
+/// Binds:
+///   - guildId
+///
+/// Returns:
+///   - Every column of Spells
+pub const SELECT_BY_GUILD: &str = "
+    SELECT pk_spellId, guildId, spellName, school, manaCost, difficulty, effect
+    FROM Spells
+    WHERE guildId = ?1
+    ORDER BY spellName;
+";
+
+/// Overwrite a spell in a server's catalogue
+///
+/// Binds:
+///   - spellName
+///   - school
+///   - manaCost
+///   - difficulty
+///   - effect
+///   - pk_spellId
+///   - guildId
+pub const UPDATE_SPELL: &str = "
+    UPDATE Spells
+    SET spellName = ?1, school = ?2, manaCost = ?3, difficulty = ?4, effect = ?5
+    WHERE pk_spellId = ?6 AND guildId = ?7;
+";
+
+/// Remove a spell from a server's catalogue. Everyone has to forget it first
+///
+/// Binds:
+///   - pk_spellId
+///   - guildId
+pub const REMOVE_SPELL: &str = "
+    DELETE
+    FROM Spells
+    WHERE pk_spellId = ?1 AND guildId = ?2;
+";
+
+/// Teach a character a spell. Teaching a spell they already know changes nothing
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_spellId
+pub const LEARN_SPELL: &str = "
+    INSERT OR IGNORE INTO KnownSpells (fk_characterId, fk_spellId)
+    VALUES ( ?1, ?2 );
+";
+
+/// Make a character forget a spell
+///
+/// Binds:
+///   - fk_characterId
+///   - fk_spellId
+pub const FORGET_SPELL: &str = "
+    DELETE
+    FROM KnownSpells
+    WHERE fk_characterId = ?1 AND fk_spellId = ?2;
+";
+
+/// Select every spell a character knows, ordered by name
+///
+/// Binds:
+///   - fk_characterId
+///
+/// Returns:
+///   - Every column of Spells
+pub const SELECT_KNOWN: &str = "
+    SELECT Spells.pk_spellId, guildId, spellName, school, manaCost, difficulty, effect
+    FROM KnownSpells
+    JOIN Spells ON Spells.pk_spellId = KnownSpells.fk_spellId
+    WHERE fk_characterId = ?1
+    ORDER BY spellName;
+";
+
+/// Make everyone forget a spell
+///
+/// Binds:
+///   - fk_spellId
+pub const FORGET_BY_SPELL_ID: &str = "
+    DELETE
+    FROM KnownSpells
+    WHERE fk_spellId = ?1;
+";
+
+/// Make a character forget every spell they know
+///
+/// Binds:
+///   - fk_characterId
+pub const FORGET_BY_CHARACTER_ID: &str = "
+    DELETE
+    FROM KnownSpells
+    WHERE fk_characterId = ?1;
+";
@@ -0,0 +1,494 @@
+// Registering, building, deleting and deregistering, checked against the database and caches
+
+use crate::{
+    attributes::{AllocationRules, AllocationState, Attribute, AttributeSpread},
+    commands::{build_character, character, delete_character, deregister, register},
+    config,
+    db::{self, Attributes},
+    error::BotError,
+    trash,
+    utils::unix_now
+};
+use super::{TestHarness, OTHER_PLAYER, PLAYER};
+
+
+/// Long enough for anything deleted during a test to have outstayed the retention period
+fn after_retention() -> i64 {
+    unix_now() + config::get().trash_retention_days as i64 * 24 * 60 * 60 + 1
+}
+
+
+/// Register a user and build them a character, returning it's ID
+async fn registered_with_character( harness: &TestHarness, user_id: u64, name: &str ) -> i64 {
+    register::register_profile( &harness.database_connection, user_id ).await
+        .expect("Registering a new user succeeds");
+
+    build_character::create_character(
+        &harness.database_connection, &harness.characters_cache,
+        user_id, name, "Human", "Born yesterday"
+    ).await
+        .expect("Building a character for a registered user succeeds")
+}
+
+/// A spread that spends every point, as the allocation message would submit it
+fn finished_allocation( character_id: i64, rules: &AllocationRules ) -> AllocationState {
+    let mut allocation_state = AllocationState::new( character_id, rules );
+    for attribute in Attribute::ALL {
+        allocation_state.selected = attribute;
+        while rules.remaining_points( &allocation_state.spread ) > 0 && allocation_state.spread.get(attribute) < rules.max {
+            allocation_state.adjust( rules, 1 );
+        }
+    }
+
+    allocation_state
+}
+
+
+// --== REGISTER ==-- //
+
+    #[tokio::test]
+    async fn register_adds_profile() {
+        let harness = TestHarness::new().await;
+
+        register::register_profile( &harness.database_connection, PLAYER ).await.unwrap();
+
+        assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 1 );
+    }
+
+    #[tokio::test]
+    async fn register_twice_is_rejected() {
+        let harness = TestHarness::new().await;
+
+        register::register_profile( &harness.database_connection, PLAYER ).await.unwrap();
+        let result = register::register_profile( &harness.database_connection, PLAYER ).await;
+
+        assert!( matches!(result, Err(BotError::AlreadyRegistered)) );
+        assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 1 );
+    }
+// ==--
+
+// --== DEREGISTER ==-- //
+
+    #[tokio::test]
+    async fn deregister_removes_profile() {
+        let harness = TestHarness::new().await;
+        register::register_profile( &harness.database_connection, PLAYER ).await.unwrap();
+
+        deregister::remove_profile( &harness.database_connection, PLAYER ).await.unwrap();
+
+        assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 0 );
+    }
+
+    #[tokio::test]
+    async fn deregister_without_profile_is_rejected() {
+        let harness = TestHarness::new().await;
+
+        let result = deregister::remove_profile( &harness.database_connection, PLAYER ).await;
+
+        assert!( matches!(result, Err(BotError::NotRegistered)) );
+    }
+
+    #[tokio::test]
+    async fn deregister_with_characters_is_rejected() {
+        let harness = TestHarness::new().await;
+        registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+        let result = deregister::remove_profile( &harness.database_connection, PLAYER ).await;
+
+        assert!( matches!(result, Err(BotError::HasCharacters)) );
+        assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 1 );
+        assert_eq!( harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 1 );
+    }
+
+    #[tokio::test]
+    async fn purge_removes_profile_characters_and_their_rows() {
+        let harness = TestHarness::new().await;
+        let first_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let second_id = build_character::create_character(
+            &harness.database_connection, &harness.characters_cache,
+            PLAYER, "Morgana", "Human", "Born today"
+        ).await.unwrap();
+        let rules = AllocationRules::character_creation();
+        build_character::save_attributes( &harness.database_connection, &finished_allocation(first_id, &rules), &rules ).await.unwrap();
+        db::abilities::add( &harness.database_connection, second_id, "Fireball", "Very hot" ).await.unwrap();
+        db::discord_users::set_current_character( &harness.database_connection, PLAYER, second_id ).await.unwrap();
+        harness.active_characters_cache.lock().unwrap().insert( PLAYER, second_id );
+
+        let removed_count = deregister::purge_profile(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache, PLAYER
+        ).await.unwrap();
+
+        assert_eq!( removed_count, 2 );
+        assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 0 );
+        assert_eq!( harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 0 );
+        assert_eq!( harness.count_rows("Atributes", "fk_pk_characterId", first_id).await, 0 );
+        assert_eq!( harness.count_rows("CharacterAbilities", "fk_pk_characterId", second_id).await, 0 );
+        assert!( harness.cached_characters(PLAYER).is_empty() );
+        assert!( !harness.active_characters_cache.lock().unwrap().contains_key(&PLAYER) );
+    }
+
+    #[tokio::test]
+    async fn purge_leaves_other_users_alone() {
+        let harness = TestHarness::new().await;
+        registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let other_id = registered_with_character( &harness, OTHER_PLAYER, "Morgana" ).await;
+
+        deregister::purge_profile(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache, PLAYER
+        ).await.unwrap();
+
+        assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", OTHER_PLAYER as i64).await, 1 );
+        assert_eq!( harness.cached_characters(OTHER_PLAYER), vec![( other_id, "Morgana".to_owned() )] );
+    }
+
+    #[tokio::test]
+    async fn purge_without_profile_is_rejected() {
+        let harness = TestHarness::new().await;
+
+        let result = deregister::purge_profile(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache, PLAYER
+        ).await;
+
+        assert!( matches!(result, Err(BotError::NotRegistered)) );
+    }
+// ==--
+
+// --== BUILD CHARACTER ==-- //
+
+    #[tokio::test]
+    async fn build_adds_character_to_database_and_cache() {
+        let harness = TestHarness::new().await;
+
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+        let character = db::characters::get( &harness.database_connection, character_id ).await.unwrap()
+            .expect("Character row exists");
+        assert_eq!( character.owner_id, PLAYER as i64 );
+        assert_eq!( character.name, "Merlin" );
+        assert_eq!( character.species, "Human" );
+
+        assert_eq!( harness.cached_characters(PLAYER), vec![( character_id, "Merlin".to_owned() )] );
+    }
+
+    #[tokio::test]
+    async fn build_without_profile_is_rejected() {
+        let harness = TestHarness::new().await;
+
+        let result = build_character::create_character(
+            &harness.database_connection, &harness.characters_cache,
+            PLAYER, "Merlin", "Human", "Born yesterday"
+        ).await;
+
+        assert!( matches!(result, Err(BotError::NotRegistered)) );
+        assert_eq!( harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 0 );
+        assert!( harness.cached_characters(PLAYER).is_empty() );
+    }
+
+    #[tokio::test]
+    async fn build_duplicate_name_is_rejected() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+        let result = build_character::create_character(
+            &harness.database_connection, &harness.characters_cache,
+            PLAYER, "Merlin", "Elf", "Born today"
+        ).await;
+
+        assert!( matches!(result, Err(BotError::DuplicateCharacter)) );
+        assert_eq!( harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 1 );
+        assert_eq!( harness.cached_characters(PLAYER), vec![( character_id, "Merlin".to_owned() )] );
+    }
+
+    #[tokio::test]
+    async fn build_same_name_for_different_owners() {
+        let harness = TestHarness::new().await;
+
+        let first_id  = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let second_id = registered_with_character( &harness, OTHER_PLAYER, "Merlin" ).await;
+
+        assert_ne!( first_id, second_id );
+        assert_eq!( harness.cached_characters(PLAYER), vec![( first_id, "Merlin".to_owned() )] );
+        assert_eq!( harness.cached_characters(OTHER_PLAYER), vec![( second_id, "Merlin".to_owned() )] );
+    }
+
+    #[tokio::test]
+    async fn build_saves_finished_allocation() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let rules = AllocationRules::character_creation();
+        let allocation_state = finished_allocation( character_id, &rules );
+
+        build_character::save_attributes( &harness.database_connection, &allocation_state, &rules ).await.unwrap();
+
+        let character_attributes = db::attributes::get( &harness.database_connection, character_id ).await.unwrap()
+            .expect("Attributes row exists");
+        assert_eq!( character_attributes.spread().0, allocation_state.spread.0 );
+    }
+
+    #[tokio::test]
+    async fn build_rejects_overspent_allocation() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let rules = AllocationRules::character_creation();
+
+        // The buttons can't get here, but an edited custom id can
+        let mut allocation_state = AllocationState::new( character_id, &rules );
+        allocation_state.spread = AttributeSpread( [rules.max; 6] );
+
+        let result = build_character::save_attributes( &harness.database_connection, &allocation_state, &rules ).await;
+
+        assert!( matches!(result, Err(BotError::InvalidAllocation)) );
+        assert_eq!( harness.count_rows("Atributes", "fk_pk_characterId", character_id).await, 0 );
+    }
+
+    #[tokio::test]
+    async fn build_never_resets_saved_attributes() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let rules = AllocationRules::character_creation();
+        db::attributes::insert( &harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([12; 6])) ).await.unwrap();
+
+        // A replayed Confirm from the allocation message
+        let result = build_character::save_attributes( &harness.database_connection, &finished_allocation(character_id, &rules), &rules ).await;
+
+        assert!( matches!(result, Err(BotError::AttributesAlreadySet)) );
+        let character_attributes = db::attributes::get( &harness.database_connection, character_id ).await.unwrap()
+            .expect("Attributes row exists");
+        assert_eq!( character_attributes.spread().0, [12; 6] );
+    }
+// ==--
+
+// --== DELETE CHARACTER ==-- //
+
+    #[tokio::test]
+    async fn delete_archives_character_and_clears_caches() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+        db::discord_users::set_current_character( &harness.database_connection, PLAYER, character_id ).await.unwrap();
+        harness.active_characters_cache.lock().unwrap().insert( PLAYER, character_id );
+
+        let ( removed_name, archived_at ) = delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            PLAYER, character_id, "Merlin"
+        ).await.unwrap();
+
+        assert_eq!( removed_name, "Merlin" );
+
+        let character = db::characters::get( &harness.database_connection, character_id ).await.unwrap()
+            .expect("Archived characters are kept");
+        assert_eq!( character.archived_at, Some(archived_at) );
+
+        let user = db::discord_users::get( &harness.database_connection, PLAYER ).await.unwrap()
+            .expect("Profile is kept");
+        assert_eq!( user.current_character, None );
+
+        assert!( harness.cached_characters(PLAYER).is_empty() );
+        assert!( !harness.active_characters_cache.lock().unwrap().contains_key(&PLAYER) );
+    }
+
+    #[tokio::test]
+    async fn delete_someone_elses_character_is_rejected() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        register::register_profile( &harness.database_connection, OTHER_PLAYER ).await.unwrap();
+
+        let result = delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            OTHER_PLAYER, character_id, "Merlin"
+        ).await;
+
+        assert!( matches!(result, Err(BotError::NotOwner)) );
+        assert_eq!( harness.count_rows("Characters", "pk_characterId", character_id).await, 1 );
+        assert_eq!( harness.cached_characters(PLAYER), vec![( character_id, "Merlin".to_owned() )] );
+    }
+
+    #[tokio::test]
+    async fn delete_keeps_attributes_and_abilities() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let rules = AllocationRules::character_creation();
+        build_character::save_attributes( &harness.database_connection, &finished_allocation(character_id, &rules), &rules ).await.unwrap();
+        db::abilities::add( &harness.database_connection, character_id, "Fireball", "Very hot" ).await.unwrap();
+
+        delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            PLAYER, character_id, "Merlin"
+        ).await.unwrap();
+
+        assert_eq!( harness.count_rows("Atributes", "fk_pk_characterId", character_id).await, 1 );
+        assert_eq!( harness.count_rows("CharacterAbilities", "fk_pk_characterId", character_id).await, 1 );
+    }
+
+    #[tokio::test]
+    async fn delete_with_mismatched_name_is_rejected() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+        let result = delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            PLAYER, character_id, "Morgana"
+        ).await;
+
+        assert!( matches!(result, Err(BotError::NameMismatch)) );
+        assert_eq!( harness.count_rows("Characters", "pk_characterId", character_id).await, 1 );
+        assert_eq!( harness.cached_characters(PLAYER), vec![( character_id, "Merlin".to_owned() )] );
+    }
+
+    #[test]
+    fn confirmation_ignores_case_unless_configured() {
+        assert!( delete_character::confirmation_matches("Merlin", "merlin", false) );
+        assert!( delete_character::confirmation_matches("Merlin", "  Merlin ", true) );
+        assert!( !delete_character::confirmation_matches("Merlin", "merlin", true) );
+        assert!( !delete_character::confirmation_matches("Merlin", "Merli", false) );
+    }
+// ==--
+
+// --== TRASH ==-- //
+
+    /// Register a user, build them a character and delete it, returning it's ID
+    async fn registered_with_deleted_character( harness: &TestHarness, user_id: u64, name: &str ) -> i64 {
+        let character_id = registered_with_character( harness, user_id, name ).await;
+
+        delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            user_id, character_id, name
+        ).await
+            .expect("Deleting a character the user owns succeeds");
+
+        character_id
+    }
+
+    #[tokio::test]
+    async fn deleted_characters_are_listed_in_trash() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_deleted_character( &harness, PLAYER, "Merlin" ).await;
+
+        let archived_characters = db::characters::get_archived_by_owner( &harness.database_connection, PLAYER ).await.unwrap();
+        assert_eq!( archived_characters.len(), 1 );
+        assert_eq!( archived_characters[0].character_id, character_id );
+
+        // Nor are they loaded into the cache on startup
+        let cached_on_startup = db::characters::all_ids_and_names( &harness.database_connection ).await.unwrap();
+        assert!( cached_on_startup.is_empty() );
+    }
+
+    #[tokio::test]
+    async fn restore_puts_character_back() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_deleted_character( &harness, PLAYER, "Merlin" ).await;
+
+        let restored_name = character::restore_character(
+            &harness.database_connection, &harness.characters_cache, PLAYER, character_id
+        ).await.unwrap();
+
+        assert_eq!( restored_name, "Merlin" );
+        assert_eq!( harness.cached_characters(PLAYER), vec![( character_id, "Merlin".to_owned() )] );
+
+        let character = db::characters::get( &harness.database_connection, character_id ).await.unwrap()
+            .expect("Restored characters exist");
+        assert_eq!( character.archived_at, None );
+    }
+
+    #[tokio::test]
+    async fn restore_over_a_new_character_of_the_same_name_is_rejected() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_deleted_character( &harness, PLAYER, "Merlin" ).await;
+        let new_character_id = build_character::create_character(
+            &harness.database_connection, &harness.characters_cache,
+            PLAYER, "Merlin", "Elf", "Born today"
+        ).await.unwrap();
+
+        let result = character::restore_character(
+            &harness.database_connection, &harness.characters_cache, PLAYER, character_id
+        ).await;
+
+        assert!( matches!(result, Err(BotError::DuplicateCharacter)) );
+        assert_eq!( harness.cached_characters(PLAYER), vec![( new_character_id, "Merlin".to_owned() )] );
+    }
+
+    #[tokio::test]
+    async fn restore_character_not_in_trash_is_rejected() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+        let result = character::restore_character(
+            &harness.database_connection, &harness.characters_cache, PLAYER, character_id
+        ).await;
+
+        assert!( matches!(result, Err(BotError::NotInTrash)) );
+    }
+
+    #[tokio::test]
+    async fn restore_someone_elses_character_is_rejected() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_deleted_character( &harness, PLAYER, "Merlin" ).await;
+
+        let result = character::restore_character(
+            &harness.database_connection, &harness.characters_cache, OTHER_PLAYER, character_id
+        ).await;
+
+        assert!( matches!(result, Err(BotError::NotOwner)) );
+        assert!( harness.cached_characters(OTHER_PLAYER).is_empty() );
+    }
+
+    #[tokio::test]
+    async fn emptying_trash_keeps_recently_deleted_characters() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_deleted_character( &harness, PLAYER, "Merlin" ).await;
+
+        let removed_count = trash::empty( &harness.database_connection, unix_now() ).await.unwrap();
+
+        assert_eq!( removed_count, 0 );
+        assert_eq!( harness.count_rows("Characters", "pk_characterId", character_id).await, 1 );
+    }
+
+    #[tokio::test]
+    async fn emptying_trash_removes_old_characters_with_attributes_and_abilities() {
+        let harness = TestHarness::new().await;
+        let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+        let rules = AllocationRules::character_creation();
+        build_character::save_attributes( &harness.database_connection, &finished_allocation(character_id, &rules), &rules ).await.unwrap();
+        db::abilities::add( &harness.database_connection, character_id, "Fireball", "Very hot" ).await.unwrap();
+        let kept_character_id = build_character::create_character(
+            &harness.database_connection, &harness.characters_cache,
+            PLAYER, "Morgana", "Human", "Born yesterday"
+        ).await.unwrap();
+
+        delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            PLAYER, character_id, "Merlin"
+        ).await.unwrap();
+
+        let removed_count = trash::empty( &harness.database_connection, after_retention() ).await.unwrap();
+
+        assert_eq!( removed_count, 1 );
+        assert_eq!( harness.count_rows("Characters", "pk_characterId", character_id).await, 0 );
+        assert_eq!( harness.count_rows("Atributes", "fk_pk_characterId", character_id).await, 0 );
+        assert_eq!( harness.count_rows("CharacterAbilities", "fk_pk_characterId", character_id).await, 0 );
+        assert_eq!( harness.count_rows("Characters", "pk_characterId", kept_character_id).await, 1 );
+    }
+// ==--
+
+#[tokio::test]
+async fn full_character_lifecycle() {
+    let harness = TestHarness::new().await;
+
+    let character_id = registered_with_character( &harness, PLAYER, "Merlin" ).await;
+
+    delete_character::delete_character(
+        &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+        PLAYER, character_id, "Merlin"
+    ).await.unwrap();
+
+    // The profile can't go while the character is still in the trash
+    let result = deregister::remove_profile( &harness.database_connection, PLAYER ).await;
+    assert!( matches!(result, Err(BotError::HasCharacters)) );
+
+    trash::empty( &harness.database_connection, after_retention() ).await.unwrap();
+    deregister::remove_profile( &harness.database_connection, PLAYER ).await.unwrap();
+
+    assert_eq!( harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 0 );
+    assert_eq!( harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 0 );
+    assert!( harness.cached_characters(PLAYER).is_empty() );
+}
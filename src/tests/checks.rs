@@ -0,0 +1,82 @@
+// Attribute checks, rolled with seeded generators against characters in the database
+
+use std::cmp::Ordering;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    attributes::{Attribute, AttributeSpread},
+    commands::check::{self, RollMode},
+    db::{self, Attributes},
+    error::BotError
+};
+use super::{TestHarness, PLAYER};
+
+
+// --== ROLLING ==-- //
+
+    #[test]
+    fn check_adds_attribute_to_a_d20() {
+        for seed in 0..50 {
+            let check = check::roll_check( "Merlin", Attribute::Casting, 7, RollMode::Normal, &mut StdRng::seed_from_u64(seed) );
+            let d20 = &check.roll.groups[0];
+
+            assert_eq!( d20.dice.len(), 1 );
+            assert_eq!( check.total(), d20.dice[0].value as i64 + 7 );
+            assert!( (8..=27).contains(&check.total()) );
+        }
+    }
+
+    #[test]
+    fn advantage_keeps_the_higher_die_and_disadvantage_the_lower() {
+        for seed in 0..50 {
+            let advantage = check::roll_check( "Merlin", Attribute::Strength, 0, RollMode::Advantage, &mut StdRng::seed_from_u64(seed) );
+            let disadvantage = check::roll_check( "Merlin", Attribute::Strength, 0, RollMode::Disadvantage, &mut StdRng::seed_from_u64(seed) );
+
+            // Seeded the same, so both rolled the same pair of dice
+            let dice: Vec<i64> = advantage.roll.groups[0].dice.iter().map( |die| die.value as i64 ).collect();
+            assert_eq!( dice.len(), 2 );
+            assert_eq!( advantage.total(), *dice.iter().max().unwrap() );
+            assert_eq!( disadvantage.total(), *dice.iter().min().unwrap() );
+        }
+    }
+
+    #[test]
+    fn dc_is_met_by_reaching_it() {
+        let check = check::roll_check( "Merlin", Attribute::Knowledge, 3, RollMode::Normal, &mut StdRng::seed_from_u64(1) );
+
+        assert!( check::meets_dc(&check, check.total()) );
+        assert!( !check::meets_dc(&check, check.total() + 1) );
+    }
+
+    #[test]
+    fn opposed_checks_go_to_the_higher_total() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let strong = check::roll_check( "Merlin", Attribute::Strength, 100, RollMode::Normal, &mut rng );
+        let weak   = check::roll_check( "Morgana", Attribute::Strength, -100, RollMode::Normal, &mut rng );
+
+        assert_eq!( check::opposed_outcome(&strong, &weak), Ordering::Greater );
+        assert_eq!( check::opposed_outcome(&weak, &strong), Ordering::Less );
+        assert_eq!( check::opposed_outcome(&strong, &strong), Ordering::Equal );
+    }
+// ==--
+
+// --== ATTRIBUTES ==-- //
+
+    #[tokio::test]
+    async fn attribute_value_reads_the_characters_spread() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character( PLAYER, "Merlin" ).await;
+
+        let result = check::attribute_value( &harness.database_connection, character_id, Attribute::Casting ).await;
+        assert!( matches!(result, Err(BotError::NoAttributes)) );
+
+        let spread = AttributeSpread([ 1, 2, 3, 4, 5, 6 ]);
+        db::attributes::insert( &harness.database_connection, &Attributes::from_spread(character_id, &spread) ).await.unwrap();
+
+        for attribute in Attribute::ALL {
+            let value = check::attribute_value( &harness.database_connection, character_id, attribute ).await.unwrap();
+            assert_eq!( value, spread.get(attribute) );
+        }
+    }
+// ==--
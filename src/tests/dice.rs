@@ -0,0 +1,201 @@
+// Parsing and rolling dice expressions, with seeded generators so every roll can be replayed
+
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::dice::{
+    self, DiceError, DiceTerm, Expression, Operator, Selection,
+    MAX_DICE, MAX_EXPLODED_DICE, MAX_SIDES
+};
+
+
+/// Parse and roll an expression with a generator seeded with `seed`
+fn roll_seeded( expression: &str, seed: u64 ) -> Result<dice::Roll, DiceError> {
+    dice::parse( expression )?.roll( &mut StdRng::seed_from_u64(seed) )
+}
+
+
+// --== PARSING ==-- //
+
+    #[test]
+    fn parses_standard_notation() {
+        let advantage = DiceTerm { count: 2, sides: 20, exploding: false, selection: Some(Selection::KeepHighest(1)) };
+        assert_eq!(
+            dice::parse("2d20kh1+3"),
+            Ok( Expression::Binary(Box::new(Expression::Dice(advantage)), Operator::Add, Box::new(Expression::Number(3))) )
+        );
+
+        let stats = DiceTerm { count: 4, sides: 6, exploding: false, selection: Some(Selection::DropLowest(1)) };
+        assert_eq!( dice::parse("4d6dl1"), Ok(Expression::Dice(stats)) );
+
+        let percentile = DiceTerm { count: 1, sides: 100, exploding: false, selection: None };
+        assert_eq!( dice::parse("d%"), Ok(Expression::Dice(percentile)) );
+
+        let exploding = DiceTerm { count: 3, sides: 6, exploding: true, selection: Some(Selection::KeepHighest(1)) };
+        assert_eq!( dice::parse(" 3D6! K "), Ok(Expression::Dice(exploding)) );
+    }
+
+    #[test]
+    fn follows_operator_precedence() {
+        assert_eq!( dice::parse("1+2*3").unwrap().to_string(), "1 + 2 * 3" );
+        assert_eq!( dice::parse("(1+2)*3").unwrap().to_string(), "(1 + 2) * 3" );
+        assert_eq!( dice::parse("1-(2-3)").unwrap().to_string(), "1 - (2 - 3)" );
+        assert_eq!( dice::parse("-(d4+1)").unwrap().to_string(), "-(1d4 + 1)" );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert_eq!( dice::parse("   "), Err(DiceError::Empty) );
+        assert_eq!( dice::parse(&"1+".repeat(60)), Err(DiceError::TooLong) );
+        assert_eq!( dice::parse("2d"), Err(DiceError::UnexpectedEnd) );
+        assert_eq!( dice::parse("(1+2"), Err(DiceError::UnexpectedEnd) );
+        assert_eq!( dice::parse("2d6x"), Err(DiceError::UnexpectedCharacter('x')) );
+        assert_eq!( dice::parse("2d6dx"), Err(DiceError::UnexpectedCharacter('x')) );
+        assert_eq!( dice::parse("0d6"), Err(DiceError::InvalidCount) );
+        assert_eq!( dice::parse(&format!("{}d6", MAX_DICE + 1)), Err(DiceError::InvalidCount) );
+        assert_eq!( dice::parse("d0"), Err(DiceError::InvalidSides) );
+        assert_eq!( dice::parse(&format!("d{}", MAX_SIDES + 1)), Err(DiceError::InvalidSides) );
+        assert_eq!( dice::parse("4d1!"), Err(DiceError::CantExplode) );
+        assert_eq!( dice::parse("99999999999999999999"), Err(DiceError::Overflow) );
+    }
+// ==--
+
+// --== ROLLING ==-- //
+
+    #[test]
+    fn arithmetic_without_dice_is_exact() {
+        assert_eq!( roll_seeded("(2+3)*4-10/3", 0).unwrap().total, 17 );
+        assert_eq!( roll_seeded("-7/2", 0).unwrap().total, -3 );
+        assert_eq!( roll_seeded("1/(2-2)", 0), Err(DiceError::DivisionByZero) );
+    }
+
+    #[test]
+    fn keep_and_drop_mark_the_right_dice() {
+        for seed in 0..50 {
+            let roll = roll_seeded( "4d6dl1", seed ).unwrap();
+            let dice = &roll.groups[0].dice;
+
+            let lowest = dice.iter().map( |die| die.value ).min().unwrap();
+            let dropped: Vec<_> = dice.iter().filter( |die| !die.kept ).collect();
+            assert_eq!( dropped.len(), 1 );
+            assert_eq!( dropped[0].value, lowest );
+
+            let kept_total: i64 = dice.iter().filter( |die| die.kept ).map( |die| die.value as i64 ).sum();
+            assert_eq!( roll.total, kept_total );
+        }
+    }
+
+    #[test]
+    fn exploding_dice_roll_again_on_their_highest_face() {
+        for seed in 0..50 {
+            let roll = roll_seeded( "5d2!", seed ).unwrap();
+            let dice = &roll.groups[0].dice;
+
+            // Every 2 adds one more die, so there's always exactly one more die than there are 2s
+            let explosions = dice.iter().filter( |die| die.exploded ).count();
+            assert_eq!( dice.len(), 5 + explosions );
+            assert!( dice.iter().all( |die| die.exploded == (die.value == 2) ) );
+        }
+    }
+
+    #[test]
+    fn natural_d20_is_only_kept_for_a_single_d20() {
+        for seed in 0..20 {
+            let check = roll_seeded( "2d20kh1+5", seed ).unwrap();
+            let kept = check.groups[0].dice.iter().find( |die| die.kept ).unwrap().value;
+            assert_eq!( check.natural_d20(), Some(kept) );
+        }
+
+        assert_eq!( roll_seeded("2d20", 0).unwrap().natural_d20(), None );
+        assert_eq!( roll_seeded("d20+d20", 0).unwrap().natural_d20(), None );
+        assert_eq!( roll_seeded("3d6", 0).unwrap().natural_d20(), None );
+    }
+
+    #[test]
+    fn explosions_stop_at_the_cap() {
+        // A d2 explodes half the time, so this would go on for a long while without the cap
+        let roll = roll_seeded( &format!("{MAX_DICE}d2!"), 7 ).unwrap();
+        assert!( roll.groups[0].dice.len() <= MAX_EXPLODED_DICE );
+    }
+// ==--
+
+// --== PROPERTIES ==-- //
+
+    fn dice_term() -> impl Strategy<Value = DiceTerm> {
+        let selection = prop_oneof![
+            Just( None ),
+            (0..10u32).prop_map( |n| Some(Selection::KeepHighest(n)) ),
+            (0..10u32).prop_map( |n| Some(Selection::KeepLowest(n)) ),
+            (0..10u32).prop_map( |n| Some(Selection::DropHighest(n)) ),
+            (0..10u32).prop_map( |n| Some(Selection::DropLowest(n)) ),
+        ];
+
+        ( 1..=20u32, 1..=100u32, any::<bool>(), selection )
+            .prop_map( |(count, sides, exploding, selection)| DiceTerm {
+                count,
+                sides,
+                exploding: exploding && sides > 1,
+                selection
+            })
+    }
+
+    /// Small expressions, written out they stay under the length limit
+    fn expression() -> impl Strategy<Value = Expression> {
+        let leaf = prop_oneof![
+            (0..1000i64).prop_map( Expression::Number ),
+            dice_term().prop_map( Expression::Dice ),
+        ];
+
+        let operator = prop_oneof![
+            Just( Operator::Add ),
+            Just( Operator::Subtract ),
+            Just( Operator::Multiply ),
+            Just( Operator::Divide ),
+        ];
+
+        leaf.prop_recursive( 2, 6, 2, move |inner| prop_oneof![
+            inner.clone().prop_map( |inner| Expression::Negate(Box::new(inner)) ),
+            ( inner.clone(), operator.clone(), inner )
+                .prop_map( |(left, operator, right)| Expression::Binary(Box::new(left), operator, Box::new(right)) ),
+        ])
+    }
+
+    proptest! {
+        #[test]
+        fn written_expressions_parse_back_to_themselves( expression in expression() ) {
+            prop_assert_eq!( dice::parse(&expression.to_string()), Ok(expression) );
+        }
+
+        #[test]
+        fn same_seed_gives_same_roll( expression in expression(), seed in any::<u64>() ) {
+            let first  = expression.roll( &mut StdRng::seed_from_u64(seed) );
+            let second = expression.roll( &mut StdRng::seed_from_u64(seed) );
+            prop_assert_eq!( first, second );
+        }
+
+        #[test]
+        fn dice_land_on_their_faces( term in dice_term(), seed in any::<u64>() ) {
+            let roll = Expression::Dice( term ).roll( &mut StdRng::seed_from_u64(seed) ).unwrap();
+
+            for die in roll.groups[0].dice.iter() {
+                prop_assert!( (1..=term.sides).contains(&die.value) );
+            }
+        }
+
+        #[test]
+        fn plain_dice_total_stays_in_range( count in 1..=MAX_DICE, sides in 1..=MAX_SIDES, modifier in -100..100i64, seed in any::<u64>() ) {
+            let roll = roll_seeded( &format!("{count}d{sides}+{modifier}"), seed ).unwrap();
+
+            prop_assert!( roll.total >= count as i64 + modifier );
+            prop_assert!( roll.total <= (count * sides) as i64 + modifier );
+        }
+
+        #[test]
+        fn keeping_keeps_that_many_dice( count in 1..=20u32, kept in 0..30u32, seed in any::<u64>() ) {
+            let roll = roll_seeded( &format!("{count}d20kh{kept}"), seed ).unwrap();
+            let kept_dice = roll.groups[0].dice.iter().filter( |die| die.kept ).count();
+
+            prop_assert_eq!( kept_dice as u32, kept.min(count) );
+        }
+    }
+// ==--
@@ -0,0 +1,178 @@
+// Encounters, their turn order and stepping through it
+
+use crate::{
+    attributes::AttributeSpread,
+    commands::{delete_character, encounter::{self, TurnStep}},
+    db::{self, Combatant, Encounter},
+    error::BotError,
+    utils::unix_now
+};
+use super::{TestHarness, GAME_MASTER, PLAYER};
+
+
+const CHANNEL: u64 = 300;
+
+
+/// A combatant that only has an ID, for stepping through turn orders
+fn combatant( combatant_id: i64 ) -> Combatant {
+    Combatant {
+        combatant_id,
+        encounter_id: 1,
+        character_id: None,
+        owner_id:     GAME_MASTER as i64,
+        name:         format!("Goblin {combatant_id}"),
+        initiative:   10,
+        dexterity:    0
+    }
+}
+
+/// Start an encounter in `CHANNEL`, run by `GAME_MASTER`
+async fn start_encounter( harness: &TestHarness ) -> Encounter {
+    let encounter_id = db::encounters::start( &harness.database_connection, None, CHANNEL, GAME_MASTER, "Ambush", unix_now() ).await.unwrap();
+    db::encounters::get( &harness.database_connection, encounter_id ).await.unwrap().unwrap()
+}
+
+
+// --== TURNS ==-- //
+
+    #[test]
+    fn next_goes_down_the_order_and_wraps_into_the_next_round() {
+        let turn_order = [ combatant(1), combatant(2), combatant(3) ];
+
+        assert_eq!( encounter::step_turn(&turn_order, 0, None, TurnStep::Next), (1, Some(1)) );
+        assert_eq!( encounter::step_turn(&turn_order, 1, Some(1), TurnStep::Next), (1, Some(2)) );
+        assert_eq!( encounter::step_turn(&turn_order, 1, Some(3), TurnStep::Next), (2, Some(1)) );
+    }
+
+    #[test]
+    fn previous_goes_back_up_the_order_and_into_the_last_round() {
+        let turn_order = [ combatant(1), combatant(2), combatant(3) ];
+
+        assert_eq!( encounter::step_turn(&turn_order, 2, Some(2), TurnStep::Previous), (2, Some(1)) );
+        assert_eq!( encounter::step_turn(&turn_order, 2, Some(1), TurnStep::Previous), (1, Some(3)) );
+
+        // Stepping back from the very first turn undoes the start of the fight
+        assert_eq!( encounter::step_turn(&turn_order, 1, Some(1), TurnStep::Previous), (0, None) );
+        assert_eq!( encounter::step_turn(&turn_order, 0, None, TurnStep::Previous), (0, None) );
+    }
+
+    #[test]
+    fn turns_pick_up_from_the_top_when_the_current_combatant_left() {
+        let turn_order = [ combatant(1), combatant(2) ];
+
+        assert_eq!( encounter::step_turn(&turn_order, 3, Some(9), TurnStep::Next), (3, Some(1)) );
+        assert_eq!( encounter::step_turn(&[], 3, Some(9), TurnStep::Next), (3, Some(9)) );
+    }
+// ==--
+
+// --== DATABASE ==-- //
+
+    #[tokio::test]
+    async fn only_one_encounter_runs_per_channel() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+
+        let second_start = db::encounters::start( &harness.database_connection, None, CHANNEL, PLAYER, "Another", unix_now() ).await;
+        assert!( matches!(second_start.map_err(BotError::from), Err(BotError::EncounterRunning)) );
+
+        // Once it's over, the channel is free again
+        assert!( db::encounters::end(&harness.database_connection, encounter.encounter_id).await.unwrap() );
+        assert!( db::encounters::start(&harness.database_connection, None, CHANNEL, PLAYER, "Another", unix_now()).await.is_ok() );
+    }
+
+    #[tokio::test]
+    async fn turn_order_follows_initiative_then_dexterity() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+
+        encounter::add_npc( &harness.database_connection, &encounter, "Slow goblin", 1, Some(10) ).await.unwrap();
+        encounter::add_npc( &harness.database_connection, &encounter, "Quick goblin", 5, Some(10) ).await.unwrap();
+        encounter::add_npc( &harness.database_connection, &encounter, "Ogre", 0, Some(15) ).await.unwrap();
+
+        let names: Vec<String> = db::encounters::turn_order( &harness.database_connection, encounter.encounter_id )
+            .await
+            .unwrap()
+            .into_iter()
+            .map( |combatant| combatant.name )
+            .collect();
+
+        assert_eq!( names, vec!["Ogre", "Quick goblin", "Slow goblin"] );
+    }
+
+    #[tokio::test]
+    async fn characters_roll_initiative_from_dexterity_and_join_once() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([ 0, 4, 0, 0, 0, 0 ]) ).await;
+
+        let ( combatant, initiative_roll ) = encounter::join_encounter( &harness.database_connection, &encounter, PLAYER, character_id, "Merlin" ).await.unwrap();
+        assert_eq!( combatant.initiative, initiative_roll.total() );
+        assert!( (5..=24).contains(&combatant.initiative) );
+        assert_eq!( combatant.dexterity, 4 );
+
+        let second_join = encounter::join_encounter( &harness.database_connection, &encounter, PLAYER, character_id, "Merlin" ).await;
+        assert!( matches!(second_join, Err(BotError::AlreadyJoined)) );
+    }
+
+    #[tokio::test]
+    async fn characters_without_attributes_cant_join() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+
+        let character_id = harness.character( PLAYER, "Merlin" ).await;
+
+        let joined = encounter::join_encounter( &harness.database_connection, &encounter, PLAYER, character_id, "Merlin" ).await;
+        assert!( matches!(joined, Err(BotError::NoAttributes)) );
+    }
+
+    #[tokio::test]
+    async fn turns_only_move_from_the_turn_they_were_read_at() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+        let ( ogre, _ ) = encounter::add_npc( &harness.database_connection, &encounter, "Ogre", 0, Some(15) ).await.unwrap();
+
+        assert!( db::encounters::set_turn(&harness.database_connection, &encounter, 1, Some(ogre.combatant_id)).await.unwrap() );
+
+        // A second press made against the same, now stale, state is dropped
+        assert!( !db::encounters::set_turn(&harness.database_connection, &encounter, 1, Some(ogre.combatant_id)).await.unwrap() );
+
+        let moved = db::encounters::get( &harness.database_connection, encounter.encounter_id ).await.unwrap().unwrap();
+        assert_eq!( (moved.round, moved.current_combatant_id), (1, Some(ogre.combatant_id)) );
+    }
+
+    #[tokio::test]
+    async fn archived_characters_leave_their_encounters() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([ 0, 4, 0, 0, 0, 0 ]) ).await;
+        let ( merlin, _ ) = encounter::join_encounter( &harness.database_connection, &encounter, PLAYER, character_id, "Merlin" ).await.unwrap();
+        let ( ogre, _ ) = encounter::add_npc( &harness.database_connection, &encounter, "Ogre", 0, Some(1) ).await.unwrap();
+        assert!( db::encounters::set_turn(&harness.database_connection, &encounter, 1, Some(merlin.combatant_id)).await.unwrap() );
+
+        delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            PLAYER, character_id, "Merlin"
+        ).await.unwrap();
+
+        // It was their turn, so the next one picks up from the top of who's left
+        let turn_order = db::encounters::turn_order( &harness.database_connection, encounter.encounter_id ).await.unwrap();
+        assert_eq!( turn_order, vec![ogre.clone()] );
+        assert_eq!( encounter::step_turn(&turn_order, 1, Some(merlin.combatant_id), TurnStep::Next), (1, Some(ogre.combatant_id)) );
+    }
+
+    #[tokio::test]
+    async fn removed_characters_leave_their_encounters() {
+        let harness = TestHarness::new().await;
+        let encounter = start_encounter( &harness ).await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([ 0, 4, 0, 0, 0, 0 ]) ).await;
+        encounter::join_encounter( &harness.database_connection, &encounter, PLAYER, character_id, "Merlin" ).await.unwrap();
+
+        delete_character::delete_character(
+            &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+            PLAYER, character_id, "Merlin"
+        ).await.unwrap();
+        db::characters::remove_archived_before( &harness.database_connection, unix_now() + 1 ).await.unwrap();
+
+        assert_eq!( harness.count_rows("Combatants", "fk_encounterId", encounter.encounter_id).await, 0 );
+    }
+// ==--
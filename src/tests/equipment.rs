@@ -0,0 +1,187 @@
+// Gear: equipping items into slots, and the effective attributes that come of it
+
+use crate::{
+    attributes::{Attribute, AttributeSpread},
+    character_sheet::CharacterSheet,
+    commands::{check, inventory},
+    db,
+    equipment::{self, Slot},
+    utils::unix_now
+};
+use super::{TestHarness, GUILD, PLAYER};
+
+
+/// Add an item to the catalogue that goes into `slot`, returning it's ID
+async fn gear( harness: &TestHarness, name: &str, slot: Option<Slot>, modifiers: AttributeSpread ) -> i64 {
+    let item_id = db::items::add( &harness.database_connection, &TestHarness::item(name, 1) ).await.unwrap();
+
+    assert!( db::items::set_gear( &harness.database_connection, GUILD, item_id, slot, &modifiers ).await.unwrap() );
+    item_id
+}
+
+/// The items a character has equipped, by name
+async fn equipped_names( harness: &TestHarness, character_id: i64 ) -> Vec<String> {
+    db::equipment::get_equipped( &harness.database_connection, character_id )
+        .await
+        .unwrap()
+        .into_iter()
+        .map( |equipped_item| equipped_item.item.name )
+        .collect()
+}
+
+
+// --== EFFECTIVE ATTRIBUTES ==-- //
+
+    #[test]
+    fn slots_round_trip_through_their_ids() {
+        for slot in Slot::ALL {
+            assert_eq!( Slot::from_id(slot.id()), Some(slot) );
+        }
+        assert_eq!( Slot::from_id("tail"), None );
+    }
+
+    #[test]
+    fn modifiers_are_summed_up_with_their_sign() {
+        assert_eq!( equipment::modifiers_text( &AttributeSpread([2, 0, 0, 0, -1, 0]) ), "+2 Strength, -1 Constitution" );
+        assert_eq!( equipment::modifiers_text( &AttributeSpread([0; 6]) ), "" );
+    }
+
+    #[tokio::test]
+    async fn checks_roll_with_effective_attributes() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let sword_id = gear( &harness, "Sword", Some(Slot::MainHand), AttributeSpread([2, 0, 0, 0, 0, -5]) ).await;
+        inventory::add_items( &harness.database_connection, character_id, sword_id, 1 ).await.unwrap();
+
+        // Carrying it isn't enough, it has to be equipped
+        assert_eq!( check::attribute_value( &harness.database_connection, character_id, Attribute::Strength ).await.unwrap(), 3 );
+
+        assert!( db::equipment::equip( &harness.database_connection, character_id, sword_id ).await.unwrap() );
+        assert_eq!( check::attribute_value( &harness.database_connection, character_id, Attribute::Strength ).await.unwrap(), 5 );
+        // Attributes never drop below 0
+        assert_eq!( check::attribute_value( &harness.database_connection, character_id, Attribute::Casting ).await.unwrap(), 0 );
+
+        // The base attributes, and everything worked out from them, stay the same
+        let stored = db::attributes::get( &harness.database_connection, character_id ).await.unwrap().unwrap();
+        assert_eq!( stored.spread(), AttributeSpread([3; 6]) );
+    }
+// ==--
+
+
+// --== EQUIPPING ==-- //
+
+    #[tokio::test]
+    async fn only_carried_gear_can_be_equipped() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let helmet_id = gear( &harness, "Helmet", Some(Slot::Head), AttributeSpread([0; 6]) ).await;
+        let rope_id = gear( &harness, "Rope", None, AttributeSpread([0; 6]) ).await;
+
+        assert!( !db::equipment::equip( &harness.database_connection, character_id, helmet_id ).await.unwrap() );
+
+        inventory::add_items( &harness.database_connection, character_id, rope_id, 1 ).await.unwrap();
+        assert!( !db::equipment::equip( &harness.database_connection, character_id, rope_id ).await.unwrap() );
+        assert!( equipped_names( &harness, character_id ).await.is_empty() );
+    }
+
+    #[tokio::test]
+    async fn equipping_replaces_whatever_is_in_the_slot() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let sword_id = gear( &harness, "Sword", Some(Slot::MainHand), AttributeSpread([1, 0, 0, 0, 0, 0]) ).await;
+        let axe_id = gear( &harness, "Axe", Some(Slot::MainHand), AttributeSpread([2, 0, 0, 0, 0, 0]) ).await;
+        let helmet_id = gear( &harness, "Helmet", Some(Slot::Head), AttributeSpread([0, 0, 0, 0, 1, 0]) ).await;
+        for item_id in [sword_id, axe_id, helmet_id] {
+            inventory::add_items( &harness.database_connection, character_id, item_id, 1 ).await.unwrap();
+            db::equipment::equip( &harness.database_connection, character_id, item_id ).await.unwrap();
+        }
+
+        // Listed in the order of the slots
+        assert_eq!( equipped_names(&harness, character_id).await, vec!["Helmet", "Axe"] );
+        let effective = equipment::effective_attributes( &harness.database_connection, character_id ).await.unwrap();
+        assert_eq!( effective, AttributeSpread([5, 3, 3, 3, 4, 3]) );
+
+        assert!( db::equipment::unequip( &harness.database_connection, character_id, Slot::Head ).await.unwrap() );
+        assert!( !db::equipment::unequip( &harness.database_connection, character_id, Slot::Head ).await.unwrap() );
+        assert_eq!( equipped_names(&harness, character_id).await, vec!["Axe"] );
+    }
+
+    #[tokio::test]
+    async fn gear_comes_off_once_it_leaves_the_inventory() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let dagger_id = gear( &harness, "Dagger", Some(Slot::OffHand), AttributeSpread([0, 1, 0, 0, 0, 0]) ).await;
+        inventory::add_items( &harness.database_connection, character_id, dagger_id, 2 ).await.unwrap();
+        db::equipment::equip( &harness.database_connection, character_id, dagger_id ).await.unwrap();
+
+        // One of them is still carried, so it stays equipped
+        db::items::take_from_inventory( &harness.database_connection, character_id, dagger_id, 1 ).await.unwrap();
+        assert_eq!( equipped_names(&harness, character_id).await, vec!["Dagger"] );
+
+        db::items::take_from_inventory( &harness.database_connection, character_id, dagger_id, 1 ).await.unwrap();
+        assert!( equipped_names(&harness, character_id).await.is_empty() );
+    }
+
+    #[tokio::test]
+    async fn moving_gear_to_another_slot_takes_it_off() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let ring_id = gear( &harness, "Ring", Some(Slot::Accessory), AttributeSpread([0, 0, 0, 0, 0, 2]) ).await;
+        inventory::add_items( &harness.database_connection, character_id, ring_id, 1 ).await.unwrap();
+        db::equipment::equip( &harness.database_connection, character_id, ring_id ).await.unwrap();
+
+        // Changing only the modifiers keeps it on
+        db::items::set_gear( &harness.database_connection, GUILD, ring_id, Some(Slot::Accessory), &AttributeSpread([0, 0, 0, 0, 0, 3]) ).await.unwrap();
+        assert_eq!( check::attribute_value( &harness.database_connection, character_id, Attribute::Casting ).await.unwrap(), 6 );
+
+        db::items::set_gear( &harness.database_connection, GUILD, ring_id, Some(Slot::Hands), &AttributeSpread([0; 6]) ).await.unwrap();
+        assert!( equipped_names(&harness, character_id).await.is_empty() );
+    }
+
+    #[tokio::test]
+    async fn removed_characters_and_items_lose_their_equipment() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let helmet_id = gear( &harness, "Helmet", Some(Slot::Head), AttributeSpread([0; 6]) ).await;
+        let boots_id = gear( &harness, "Boots", Some(Slot::Body), AttributeSpread([0; 6]) ).await;
+        for item_id in [helmet_id, boots_id] {
+            inventory::add_items( &harness.database_connection, character_id, item_id, 1 ).await.unwrap();
+            db::equipment::equip( &harness.database_connection, character_id, item_id ).await.unwrap();
+        }
+
+        assert!( db::items::remove( &harness.database_connection, GUILD, helmet_id ).await.unwrap() );
+        assert_eq!( harness.count_rows("EquippedItems", "fk_itemId", helmet_id).await, 0 );
+
+        db::characters::archive( &harness.database_connection, character_id, unix_now() - 10 ).await.unwrap();
+        db::characters::remove_archived_before( &harness.database_connection, unix_now() ).await.unwrap();
+        assert_eq!( harness.count_rows("EquippedItems", "fk_characterId", character_id).await, 0 );
+    }
+// ==--
+
+
+// --== CHARACTER SHEET ==-- //
+
+    #[tokio::test]
+    async fn sheet_shows_base_and_effective_attributes() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let sword_id = gear( &harness, "Sword", Some(Slot::MainHand), AttributeSpread([2, 0, 0, 0, 0, 0]) ).await;
+        let shield_id = gear( &harness, "Shield", Some(Slot::OffHand), AttributeSpread([0; 6]) ).await;
+        for item_id in [sword_id, shield_id] {
+            inventory::add_items( &harness.database_connection, character_id, item_id, 1 ).await.unwrap();
+        }
+        db::equipment::equip( &harness.database_connection, character_id, sword_id ).await.unwrap();
+
+        let sheet = CharacterSheet::load( &harness.database_connection, character_id ).await.unwrap().unwrap();
+        let fields = sheet.pages().concat();
+        let field = |name: &str| fields.iter().find( |field| field.0 == name ).map( |field| field.1.clone() ).unwrap();
+
+        assert_eq!( field("Strength"), "5 (base 3)" );
+        assert_eq!( field("Dexterity"), "3" );
+        assert_eq!( field("Equipment"), "**Main hand:** Sword · +2 Strength" );
+
+        // The shield can still be equipped, and the sword taken off
+        assert_eq!( sheet.equippable.iter().map( |item| item.name.as_str() ).collect::<Vec<&str>>(), vec!["Shield"] );
+        assert_eq!( sheet.components("character", 0).len(), 2 );
+    }
+// ==--
@@ -0,0 +1,179 @@
+// A stand-in for Discord's HTTP API
+//
+// - Serenity's `Http` is pointed at it through it's proxy setting, so responses go through exactly
+//     the same serialization they would when talking to Discord
+// - Every request is recorded with it's JSON body, for tests to assert on
+// - Interaction callbacks are answered with `204 No Content`, like Discord does. Anything else is
+//     answered with the message Discord would send back, built from the request's body
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex}
+};
+
+use serde_json::{json, Value};
+use serenity::all::{ApplicationId, Http, HttpBuilder};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream}
+};
+
+
+/// Application ID the fake API pretends the bot has
+pub const APPLICATION_ID: u64 = 1;
+
+
+/// A request that reached the fake API
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path:   String,
+    pub body:   Value
+}
+
+pub struct FakeDiscord {
+    address:  SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>
+}
+impl FakeDiscord {
+
+    /// Start listening on a free local port. The server runs until the test's runtime shuts down
+    pub async fn start() -> FakeDiscord {
+        let listener = TcpListener::bind("127.0.0.1:0").await
+            .expect("Binding to a free local port shouldn't fail");
+        let address = listener.local_addr()
+            .expect("Bound listener has an address");
+
+        let requests = Arc::new( Mutex::new(vec![]) );
+
+        let recorded_requests = requests.clone();
+        tokio::spawn( async move {
+            while let Ok(( stream, _ )) = listener.accept().await {
+                tokio::spawn( serve_connection( stream, recorded_requests.clone() ) );
+            }
+        });
+
+        FakeDiscord { address, requests }
+    }
+
+    /// An `Http` client that sends every request to the fake API
+    pub fn http( &self ) -> Arc<Http> {
+        Arc::new( HttpBuilder::new("fake-token")
+            .proxy( format!("http://{}", self.address) )
+            .ratelimiter_disabled(true)
+            .application_id( ApplicationId::new(APPLICATION_ID) )
+            .build()
+        )
+    }
+
+    /// Every request received so far, in the order they arrived
+    pub fn requests( &self ) -> Vec<RecordedRequest> {
+        self.requests.lock()
+            .expect("Fake API doesn't panic while holding the lock")
+            .clone()
+    }
+
+    /// Bodies of every response to an interaction, in the order they were sent
+    pub fn interaction_responses( &self ) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter( |request| request.method == "POST" && request.path.ends_with("/callback") )
+            .map( |request| request.body )
+            .collect()
+    }
+
+    /// Bodies of every edit made to an original interaction response, in the order they were sent
+    pub fn response_edits( &self ) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter( |request| request.method == "PATCH" && request.path.ends_with("/messages/@original") )
+            .map( |request| request.body )
+            .collect()
+    }
+}
+
+
+/// Answer every request sent over a connection, until the client closes it
+async fn serve_connection( stream: TcpStream, requests: Arc<Mutex<Vec<RecordedRequest>>> ) {
+    let mut stream = BufReader::new( stream );
+
+    loop {
+        // --== READ REQUEST ==-- //
+
+            let mut request_line = String::new();
+            match stream.read_line( &mut request_line ).await {
+                Ok(0) | Err(_) => return,  // Connection closed
+                Ok(_) => {}
+            }
+
+            let mut request_parts = request_line.split_whitespace();
+            let method = request_parts.next().unwrap_or_default().to_owned();
+            let path   = request_parts.next().unwrap_or_default().to_owned();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                if stream.read_line( &mut header ).await.unwrap_or(0) == 0 {
+                    return
+                }
+
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break
+                }
+
+                if let Some(( name, value )) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            if stream.read_exact( &mut body ).await.is_err() {
+                return
+            }
+            let body: Value = serde_json::from_slice( &body ).unwrap_or(Value::Null);
+        // ==--
+
+        // --== ANSWER REQUEST ==-- //
+
+            let response = match path.ends_with("/callback") {
+                true  => "HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n".to_owned(),
+                false => {
+                    let message = message_json( &body ).to_string();
+                    format!( "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{message}", message.len() )
+                }
+            };
+
+            requests.lock()
+                .expect("Fake API doesn't panic while holding the lock")
+                .push( RecordedRequest { method, path, body } );
+
+            if stream.get_mut().write_all( response.as_bytes() ).await.is_err() {
+                return
+            }
+        // ==--
+    }
+}
+
+/// A message as Discord would send it back after it was created or edited
+pub fn message_json( body: &Value ) -> Value {
+    json!({
+        "id":               "1",
+        "channel_id":       "1",
+        "author":           { "id": APPLICATION_ID.to_string(), "username": "Magician", "discriminator": "0000", "avatar": null },
+        "content":          body.get("content").cloned().unwrap_or(json!("")),
+        "timestamp":        "2025-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts":              false,
+        "mention_everyone": false,
+        "mentions":         [],
+        "mention_roles":    [],
+        "attachments":      [],
+        "embeds":           body.get("embeds").cloned().unwrap_or(json!([])),
+        "components":       body.get("components").cloned().unwrap_or(json!([])),
+        "pinned":           false,
+        "type":             0
+    })
+}
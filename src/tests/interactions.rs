@@ -0,0 +1,932 @@
+// Whole interactions, fed to the bot the way the gateway would deliver them, with the responses
+// checked as they reach the fake Discord API
+
+use serde_json::{json, Value};
+
+use crate::{
+    attributes::AttributeSpread,
+    commands::{deregister, rolls},
+    db::{self, Attributes},
+    event_handler::DiscordBot
+};
+use super::{
+    fake_discord::{message_json, FakeDiscord, APPLICATION_ID},
+    interaction, TestHarness, GAME_MASTER, GUILD, PLAYER
+};
+
+
+// Interaction response types, as numbered by Discord
+const CHANNEL_MESSAGE_WITH_SOURCE: u64 = 4;
+const DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE: u64 = 5;
+const UPDATE_MESSAGE: u64 = 7;
+const MODAL: u64 = 9;
+
+
+/// Everything needed to send interactions to the bot and see what it sent back
+struct TestClient {
+    harness:      TestHarness,
+    fake_discord: FakeDiscord,
+    bot:          DiscordBot
+}
+impl TestClient {
+
+    async fn new() -> TestClient {
+        let harness = TestHarness::new().await;
+        let fake_discord = FakeDiscord::start().await;
+        let bot = harness.bot();
+
+        TestClient { harness, fake_discord, bot }
+    }
+
+    async fn send( &self, interaction_json: Value ) {
+        let ctx = self.harness.context( self.fake_discord.http() ).await;
+        self.bot.handle_interaction( &ctx, interaction(interaction_json) ).await;
+    }
+
+    /// The most recent response to an interaction
+    fn last_response( &self ) -> Value {
+        self.fake_discord.interaction_responses()
+            .pop()
+            .expect("The bot should have responded")
+    }
+}
+
+
+/// Fields every interaction sent by `user_id` has
+fn interaction_base( interaction_type: u8, user_id: u64, data: Value ) -> Value {
+    json!({
+        "id":             "10",
+        "application_id": APPLICATION_ID.to_string(),
+        "type":           interaction_type,
+        "data":           data,
+        "channel_id":     "20",
+        "user":           { "id": user_id.to_string(), "username": "player", "discriminator": "0", "avatar": null },
+        "token":          "interaction-token",
+        "version":        1,
+        "locale":         "en-GB",
+        "entitlements":   []
+    })
+}
+
+/// A slash command being invoked
+fn slash_command( user_id: u64, name: &str, options: Value ) -> Value {
+    interaction_base( 2, user_id, json!({ "id": "30", "name": name, "type": 1, "options": options }) )
+}
+
+/// A modal being submitted, with one text input per value
+fn modal_submit( user_id: u64, custom_id: &str, values: &[(&str, &str)] ) -> Value {
+    let components: Vec<Value> = values
+        .iter()
+        .map( |(input_id, value)| json!({
+            "type":       1,
+            "components": [{ "type": 4, "custom_id": input_id, "value": value }]
+        }))
+        .collect();
+
+    interaction_base( 5, user_id, json!({ "custom_id": custom_id, "components": components }) )
+}
+
+/// A button being clicked on one of the bot's messages
+fn button_click( user_id: u64, custom_id: &str ) -> Value {
+    let mut click = interaction_base( 3, user_id, json!({ "custom_id": custom_id, "component_type": 2 }) );
+    click["message"] = message_json( &json!({}) );
+    click
+}
+
+/// The same interaction, only sent from within a server instead of a DM
+fn in_guild( mut interaction_json: Value ) -> Value {
+    interaction_json["guild_id"] = json!( GUILD.to_string() );
+    interaction_json
+}
+
+/// The same interaction, sent by a member who can manage the server
+fn as_server_manager( mut interaction_json: Value ) -> Value {
+    interaction_json["member"] = json!({
+        "user":        interaction_json["user"].clone(),
+        "roles":       [],
+        "joined_at":   "2024-01-01T00:00:00Z",
+        "deaf":        false,
+        "mute":        false,
+        "flags":       0,
+        "permissions": "32"
+    });
+    in_guild( interaction_json )
+}
+
+/// The title of the first embed in a response or edit
+fn embed_title( body: &Value ) -> &str {
+    let embeds = body.get("data").unwrap_or( body )["embeds"].as_array()
+        .expect("Body should have embeds");
+
+    embeds[0]["title"].as_str().expect("Embed should have a title")
+}
+
+
+#[tokio::test]
+async fn register_responds_with_success_embed() {
+    let client = TestClient::new().await;
+
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], CHANNEL_MESSAGE_WITH_SOURCE );
+    assert_eq!( embed_title(&response), "Success! You've been added to the database!" );
+    assert_eq!( client.harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 1 );
+}
+
+#[tokio::test]
+async fn register_twice_responds_with_error_embed() {
+    let client = TestClient::new().await;
+
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Your already in the database" );
+
+    let footer = response["data"]["embeds"][0]["footer"]["text"].as_str().unwrap_or_default();
+    assert!( footer.starts_with("Error ID: "), "Error embeds carry a correlation id, got '{footer}'" );
+}
+
+#[tokio::test]
+async fn build_character_opens_modal_and_saves_submission() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+
+    client.send( slash_command(PLAYER, "build_character", json!([])) ).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], MODAL );
+    assert_eq!( response["data"]["custom_id"], "build_character" );
+
+    client.send( modal_submit(PLAYER, "build_character", &[
+        ("name", "Merlin"), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin successfully added! Now allocate their attributes" );
+    assert!( !response["data"]["components"].as_array().expect("Allocation has components").is_empty() );
+
+    let cached_characters = client.harness.cached_characters( PLAYER );
+    assert_eq!( cached_characters.len(), 1 );
+    assert_eq!( cached_characters[0].1, "Merlin" );
+    assert_eq!( client.harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 1 );
+}
+
+#[tokio::test]
+async fn delete_character_confirms_with_modal() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+    client.send( modal_submit(PLAYER, "build_character", &[
+        ("name", "Merlin"), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+    let character_id = client.harness.cached_characters( PLAYER )[0].0;
+
+    client.send( slash_command(PLAYER, "delete_character", json!([
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], MODAL );
+    let custom_id = format!("delete_character:{character_id}");
+    assert_eq!( response["data"]["custom_id"], custom_id.as_str() );
+
+    client.send( modal_submit(PLAYER, &custom_id, &[("confirm_name", "merlin")]) ).await;
+
+    assert_eq!( embed_title(&client.last_response()), "Moved Merlin to the trash" );
+    assert!( client.harness.cached_characters(PLAYER).is_empty() );
+    assert_eq!( client.harness.count_rows("Characters", "pk_characterId", character_id).await, 1 );
+}
+
+#[tokio::test]
+async fn deleted_character_can_be_found_in_trash_and_restored() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+    client.send( modal_submit(PLAYER, "build_character", &[
+        ("name", "Merlin"), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+    let character_id = client.harness.cached_characters( PLAYER )[0].0;
+    client.send( modal_submit(PLAYER, &format!("delete_character:{character_id}"), &[("confirm_name", "Merlin")]) ).await;
+
+    client.send( slash_command(PLAYER, "character", json!([
+        { "name": "trash", "type": 1, "options": [] }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], CHANNEL_MESSAGE_WITH_SOURCE );
+    assert_eq!( embed_title(&response), "Your trash" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][0]["name"], "Merlin" );
+
+    client.send( slash_command(PLAYER, "character", json!([
+        { "name": "restore", "type": 1, "options": [{ "name": "character", "type": 4, "value": character_id }] }
+    ]))).await;
+
+    assert_eq!( embed_title(&client.last_response()), "Merlin is back" );
+    assert_eq!( client.harness.cached_characters(PLAYER), vec![( character_id, "Merlin".to_owned() )] );
+}
+
+#[tokio::test]
+async fn empty_trash_says_so() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+
+    client.send( slash_command(PLAYER, "character", json!([
+        { "name": "trash", "type": 1, "options": [] }
+    ]))).await;
+
+    assert_eq!( embed_title(&client.last_response()), "Your trash is empty" );
+}
+
+#[tokio::test]
+async fn deregister_defers_then_edits_response() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+
+    client.send( slash_command(PLAYER, "deregister", json!([])) ).await;
+
+    assert_eq!( client.last_response()["type"], DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE );
+
+    let edits = client.fake_discord.response_edits();
+    assert_eq!( edits.len(), 1 );
+    assert_eq!( embed_title(&edits[0]), "Your profile has been successfully removed from the database" );
+    assert_eq!( client.harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 0 );
+}
+
+#[tokio::test]
+async fn unknown_command_gets_no_response() {
+    let client = TestClient::new().await;
+
+    client.send( slash_command(PLAYER, "not_a_command", json!([])) ).await;
+
+    assert!( client.fake_discord.requests().is_empty() );
+}
+
+#[tokio::test]
+async fn delete_character_with_wrong_name_keeps_character() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+    client.send( modal_submit(PLAYER, "build_character", &[
+        ("name", "Merlin"), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+    let character_id = client.harness.cached_characters( PLAYER )[0].0;
+
+    client.send( modal_submit(PLAYER, &format!("delete_character:{character_id}"), &[("confirm_name", "Morgana")]) ).await;
+
+    assert_eq!( embed_title(&client.last_response()), "The name you typed doesn't match" );
+    assert_eq!( client.harness.cached_characters(PLAYER).len(), 1 );
+    assert_eq!( client.harness.count_rows("Characters", "pk_characterId", character_id).await, 1 );
+}
+
+#[tokio::test]
+async fn delete_character_with_a_mangled_modal_id_is_ignored() {
+    let client = TestClient::new().await;
+    build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    let request_count = client.fake_discord.requests().len();
+
+    for custom_id in ["delete_character", "delete_character:Merlin", "delete_character:1:2"] {
+        client.send( modal_submit(PLAYER, custom_id, &[("confirm_name", "Merlin")]) ).await;
+    }
+
+    assert_eq!( client.fake_discord.requests().len(), request_count );
+    assert_eq!( client.harness.cached_characters(PLAYER).len(), 1 );
+}
+
+#[tokio::test]
+async fn deregister_purge_removes_everything_once_confirmed() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+    client.send( modal_submit(PLAYER, "build_character", &[
+        ("name", "Merlin"), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+
+    client.send( slash_command(PLAYER, "deregister", json!([
+        { "name": "purge", "type": 5, "value": true }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], MODAL );
+    assert_eq!( response["data"]["custom_id"], "deregister:purge" );
+    assert_eq!( client.harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 1 );
+
+    client.send( modal_submit(PLAYER, "deregister:purge", &[("confirm_purge", deregister::PURGE_CONFIRMATION)]) ).await;
+
+    assert_eq!( embed_title(&client.last_response()), "Your profile and 1 character(s) have been removed from the database" );
+    assert_eq!( client.harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 0 );
+    assert_eq!( client.harness.count_rows("Characters", "fk_discordId", PLAYER as i64).await, 0 );
+}
+
+#[tokio::test]
+async fn deregister_purge_without_confirmation_keeps_everything() {
+    let client = TestClient::new().await;
+    client.send( slash_command(PLAYER, "register", json!([])) ).await;
+    client.send( modal_submit(PLAYER, "build_character", &[
+        ("name", "Merlin"), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+
+    client.send( modal_submit(PLAYER, "deregister:purge", &[("confirm_purge", "yes")]) ).await;
+
+    assert_eq!( embed_title(&client.last_response()), "Purge wasn't confirmed" );
+    assert_eq!( client.harness.count_rows("DiscordUsers", "pk_discordId", PLAYER as i64).await, 1 );
+    assert_eq!( client.harness.cached_characters(PLAYER).len(), 1 );
+}
+
+#[tokio::test]
+async fn roll_shows_total_in_embed() {
+    let client = TestClient::new().await;
+
+    client.send( slash_command(PLAYER, "roll", json!([
+        { "name": "dice", "type": 3, "value": "2d20kh1+3" }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], CHANNEL_MESSAGE_WITH_SOURCE );
+    assert_eq!( embed_title(&response), "Rolled 2d20kh1 + 3" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][0]["name"], "2d20kh1" );
+}
+
+#[tokio::test]
+async fn roll_with_bad_notation_explains_why() {
+    let client = TestClient::new().await;
+
+    client.send( slash_command(PLAYER, "roll", json!([
+        { "name": "dice", "type": 3, "value": "2d20x" }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Couldn't roll that" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][0]["value"], "Didn't expect 'x'" );
+}
+
+/// Register the user and build them a character through interactions, returning it's ID
+async fn build_through_interactions( client: &TestClient, user_id: u64, name: &str ) -> i64 {
+    client.send( slash_command(user_id, "register", json!([])) ).await;
+    client.send( modal_submit(user_id, "build_character", &[
+        ("name", name), ("species", "Human"), ("backstory", "Born yesterday")
+    ])).await;
+
+    client.harness.cached_characters( user_id )[0].0
+}
+
+#[tokio::test]
+async fn check_rolls_against_the_selected_character() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    client.send( slash_command(PLAYER, "check", json!([
+        { "name": "attribute", "type": 3, "value": "Strength" },
+        { "name": "character", "type": 4, "value": character_id },
+        { "name": "advantage", "type": 3, "value": "advantage" }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin rolled a Strength check with advantage" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][0]["name"], "Merlin (Strength)" );
+}
+
+#[tokio::test]
+async fn opposed_check_rolls_for_both_characters() {
+    const OPPONENT: u64 = 200;
+
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    let opponent_character_id = build_through_interactions( &client, OPPONENT, "Morgana" ).await;
+    for id in [ character_id, opponent_character_id ] {
+        db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(id, &AttributeSpread([5; 6])) ).await.unwrap();
+    }
+
+    let mut check = slash_command( PLAYER, "check", json!([
+        { "name": "attribute", "type": 3, "value": "Dexterity" },
+        { "name": "character", "type": 4, "value": character_id },
+        { "name": "opponent", "type": 6, "value": OPPONENT.to_string() },
+        { "name": "opponent_character", "type": 4, "value": opponent_character_id },
+        { "name": "opponent_attribute", "type": 3, "value": "Preception" }
+    ]));
+    check["data"]["resolved"] = json!({ "users": { OPPONENT.to_string(): {
+        "id": OPPONENT.to_string(), "username": "opponent", "discriminator": "0", "avatar": null
+    }}});
+    client.send( check ).await;
+
+    let response = client.last_response();
+    let fields = &response["data"]["embeds"][0]["fields"];
+    assert_eq!( fields[0]["name"], "Merlin (Dexterity)" );
+    assert_eq!( fields[1]["name"], "Morgana (Preception)" );
+}
+
+#[tokio::test]
+async fn check_without_attributes_is_rejected() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+
+    client.send( slash_command(PLAYER, "check", json!([
+        { "name": "attribute", "type": 3, "value": "Casting" },
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+
+    assert_eq!( embed_title(&client.last_response()), "That character has no attributes yet" );
+}
+
+#[tokio::test]
+async fn rolls_are_recorded_and_listed_in_history() {
+    let client = TestClient::new().await;
+    build_through_interactions( &client, PLAYER, "Merlin" ).await;
+
+    // Enough rolls to need a second page
+    for _ in 0..=rolls::HISTORY_PAGE_SIZE {
+        client.send( slash_command(PLAYER, "roll", json!([
+            { "name": "dice", "type": 3, "value": "d20+1" }
+        ]))).await;
+    }
+
+    client.send( slash_command(PLAYER, "rolls", json!([
+        { "name": "history", "type": 1, "options": [] }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Roll history" );
+    assert!( response["data"]["embeds"][0]["description"].as_str().unwrap().starts_with(&format!("Rolls by <@{PLAYER}>, page 1/2")) );
+
+    let next_button = &response["data"]["components"][0]["components"][1];
+    client.send( button_click(PLAYER, next_button["custom_id"].as_str().unwrap()) ).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], UPDATE_MESSAGE );
+    assert!( response["data"]["embeds"][0]["description"].as_str().unwrap().starts_with(&format!("Rolls by <@{PLAYER}>, page 2/2")) );
+}
+
+#[tokio::test]
+async fn rolls_stats_sum_up_checks() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    client.send( slash_command(PLAYER, "check", json!([
+        { "name": "attribute", "type": 3, "value": "Casting" },
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+
+    client.send( slash_command(PLAYER, "rolls", json!([
+        { "name": "stats", "type": 1, "options": [] }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Roll statistics for Merlin" );
+
+    let fields = &response["data"]["embeds"][0]["fields"];
+    assert_eq!( fields[0]["name"], "Rolls" );
+    assert_eq!( fields[0]["value"], "1" );
+    assert_eq!( fields[5]["name"], "d20 distribution" );
+}
+
+#[tokio::test]
+async fn encounter_steps_through_turns_and_ends() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    client.send( in_guild(slash_command(GAME_MASTER, "encounter", json!([
+        { "name": "start", "type": 1, "options": [{ "name": "name", "type": 3, "value": "Ambush" }] }
+    ])))).await;
+    assert_eq!( embed_title(&client.last_response()), "Ambush" );
+
+    client.send( in_guild(slash_command(PLAYER, "encounter", json!([
+        { "name": "join", "type": 1, "options": [{ "name": "character", "type": 4, "value": character_id }] }
+    ])))).await;
+    client.send( in_guild(slash_command(GAME_MASTER, "encounter", json!([
+        { "name": "npc", "type": 1, "options": [
+            { "name": "name", "type": 3, "value": "Ogre" },
+            { "name": "initiative", "type": 4, "value": 99 }
+        ]}
+    ])))).await;
+
+    let response = client.last_response();
+    assert_eq!( response["data"]["embeds"][1]["title"], "Ogre joined the fight" );
+    let next_id = response["data"]["components"][0]["components"][1]["custom_id"].as_str().unwrap().to_owned();
+
+    // Only the game master can begin the fight
+    client.send( button_click(PLAYER, &next_id) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Only the game master can do that" );
+
+    client.send( button_click(GAME_MASTER, &next_id) ).await;
+    let response = client.last_response();
+    assert_eq!( response["type"], UPDATE_MESSAGE );
+    assert_eq!( response["data"]["embeds"][0]["description"], format!("Round 1, it's **Ogre**'s turn\nRun by <@{GAME_MASTER}>") );
+
+    // The Ogre goes first, then it's Merlin's turn, which his player may end
+    client.send( button_click(GAME_MASTER, &next_id) ).await;
+    client.send( button_click(PLAYER, &next_id) ).await;
+    let description = client.last_response()["data"]["embeds"][0]["description"].as_str().unwrap().to_owned();
+    assert!( description.starts_with("Round 2, it's **Ogre**'s turn"), "Got '{description}'" );
+
+    let end_id = next_id.replace( "next", "end" );
+    client.send( button_click(GAME_MASTER, &end_id) ).await;
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Ambush is over" );
+    assert_eq!( response["data"]["components"], json!([]) );
+
+    // Old turn orders don't bring it back
+    client.send( button_click(GAME_MASTER, &next_id) ).await;
+    assert_eq!( embed_title(&client.last_response()), "This fight is over" );
+}
+
+#[tokio::test]
+async fn joining_without_an_encounter_is_rejected() {
+    let client = TestClient::new().await;
+    build_through_interactions( &client, PLAYER, "Merlin" ).await;
+
+    client.send( slash_command(PLAYER, "encounter", json!([
+        { "name": "join", "type": 1, "options": [] }
+    ]))).await;
+
+    assert_eq!( embed_title(&client.last_response()), "There's no fight going on here" );
+}
+
+#[tokio::test]
+async fn damage_knocks_out_and_heal_brings_round() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    // Whoever runs the fight in the channel can deal the damage, by picking the player's character
+    client.send( in_guild(slash_command(GAME_MASTER, "encounter", json!([
+        { "name": "start", "type": 1, "options": [] }
+    ])))).await;
+    client.send( in_guild(slash_command(PLAYER, "encounter", json!([
+        { "name": "join", "type": 1, "options": [{ "name": "character", "type": 4, "value": character_id }] }
+    ])))).await;
+    client.send( in_guild(slash_command(GAME_MASTER, "damage", json!([
+        { "name": "amount", "type": 4, "value": 1000 },
+        { "name": "user", "type": 6, "value": PLAYER.to_string() },
+        { "name": "character", "type": 4, "value": character_id }
+    ])))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin took 1000 damage" );
+    assert_eq!( response["data"]["embeds"][0]["description"], "**Merlin falls unconscious**" );
+
+    client.send( slash_command(PLAYER, "heal", json!([
+        { "name": "amount", "type": 4, "value": 3 },
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin healed 3 HP" );
+    assert_eq!( response["data"]["embeds"][0]["description"], "**Merlin comes round**" );
+}
+
+#[tokio::test]
+async fn only_game_masters_change_other_players_characters() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    let damage = json!([
+        { "name": "amount", "type": 4, "value": 1 },
+        { "name": "user", "type": 6, "value": PLAYER.to_string() },
+        { "name": "character", "type": 4, "value": character_id }
+    ]);
+
+    client.send( slash_command(GAME_MASTER, "damage", damage.clone()) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Only a game master can do that to someone else's character" );
+
+    client.send( as_server_manager(slash_command(GAME_MASTER, "damage", damage)) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Merlin took 1 damage" );
+}
+
+#[tokio::test]
+async fn starting_an_encounter_only_rules_over_its_combatants() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    let start = || slash_command(GAME_MASTER, "encounter", json!([{ "name": "start", "type": 1, "options": [] }]));
+    let damage = || slash_command(GAME_MASTER, "damage", json!([
+        { "name": "amount", "type": 4, "value": 1 },
+        { "name": "user", "type": 6, "value": PLAYER.to_string() },
+        { "name": "character", "type": 4, "value": character_id }
+    ]));
+
+    // Anyone could start a fight in a DM with the bot, so they can't
+    client.send( start() ).await;
+    assert_eq!( embed_title(&client.last_response()), "Fights happen in a server" );
+    client.send( damage() ).await;
+    assert_eq!( embed_title(&client.last_response()), "Only a game master can do that to someone else's character" );
+
+    // Nor does starting one in a server hand over characters that aren't in it
+    client.send( in_guild(start()) ).await;
+    client.send( in_guild(damage()) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Only a game master can do that to someone else's character" );
+
+    assert!( db::resources::get(&client.harness.database_connection, character_id).await.unwrap().is_none() );
+}
+
+#[tokio::test]
+async fn spending_more_mana_than_there_is_depletes_it() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([1; 6])) ).await.unwrap();
+
+    client.send( slash_command(PLAYER, "spend_mana", json!([
+        { "name": "amount", "type": 4, "value": 500 },
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+
+    let response = client.last_response();
+    let description = response["data"]["embeds"][0]["description"].as_str().unwrap();
+    assert!( description.starts_with("**Merlin's mana is depleted**"), "Got '{description}'" );
+}
+
+#[tokio::test]
+async fn game_master_authors_and_teaches_a_spell_that_gets_cast() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    client.send( in_guild(slash_command(GAME_MASTER, "spell", json!([{ "name": "create", "type": 1, "options": [] }])))).await;
+    assert_eq!( client.last_response()["type"], MODAL );
+
+    client.send( in_guild(modal_submit(GAME_MASTER, "spell:create", &[
+        ("name", "Light"), ("school", "Illusion"), ("mana_cost", "4"), ("difficulty", "1"), ("effect", "The room lights up")
+    ]))).await;
+    assert_eq!( embed_title(&client.last_response()), "Saved Light" );
+
+    let spell_id = db::spells::get_catalogue( &client.harness.database_connection, GUILD ).await.unwrap()[0].spell_id;
+    client.send( in_guild(slash_command(GAME_MASTER, "spell", json!([
+        { "name": "teach", "type": 1, "options": [
+            { "name": "spell", "type": 4, "value": spell_id },
+            { "name": "user", "type": 6, "value": PLAYER.to_string() },
+            { "name": "character", "type": 4, "value": character_id }
+        ]}
+    ])))).await;
+    assert_eq!( embed_title(&client.last_response()), "Merlin learnt Light" );
+
+    client.send( slash_command(PLAYER, "cast", json!([
+        { "name": "spell", "type": 4, "value": spell_id },
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin casts Light" );
+    assert_eq!( response["data"]["embeds"][0]["description"], "**Success**\nThe room lights up" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][2]["value"], "21/25" );
+}
+
+#[tokio::test]
+async fn casting_an_unknown_spell_is_rejected() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    client.send( in_guild(modal_submit(GAME_MASTER, "spell:create", &[
+        ("name", "Light"), ("school", "Illusion"), ("mana_cost", "4"), ("difficulty", "1"), ("effect", "The room lights up")
+    ]))).await;
+    let spell_id = db::spells::get_catalogue( &client.harness.database_connection, GUILD ).await.unwrap()[0].spell_id;
+
+    client.send( slash_command(PLAYER, "cast", json!([
+        { "name": "spell", "type": 4, "value": spell_id },
+        { "name": "character", "type": 4, "value": character_id }
+    ]))).await;
+    assert_eq!( embed_title(&client.last_response()), "That character doesn't know that spell" );
+    assert_eq!( client.harness.count_rows("Rolls", "fk_characterId", character_id).await, 0 );
+}
+
+#[tokio::test]
+async fn only_server_managers_award_experience() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    client.send( slash_command(PLAYER, "switch_character", json!([{ "name": "character", "type": 4, "value": character_id }]))).await;
+
+    let award = || slash_command(PLAYER, "award_xp", json!([
+        { "name": "amount", "type": 4, "value": 5000 },
+        { "name": "players", "type": 3, "value": format!("<@{PLAYER}>") }
+    ]));
+
+    // Discord hides the command from other members, but not in DMs
+    client.send( award() ).await;
+    assert_eq!( embed_title(&client.last_response()), "Experience is awarded in a server" );
+
+    client.send( in_guild(award()) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Only a server manager can do that" );
+
+    let progression = db::progression::get( &client.harness.database_connection, character_id ).await.unwrap();
+    assert_eq!( progression.experience, 0 );
+}
+
+#[tokio::test]
+async fn awarded_levels_are_spent_through_the_allocation_message() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([3; 6])) ).await.unwrap();
+    client.send( slash_command(PLAYER, "switch_character", json!([{ "name": "character", "type": 4, "value": character_id }]))).await;
+
+    // The game master isn't playing as anyone, so they're skipped
+    client.send( as_server_manager(slash_command(GAME_MASTER, "award_xp", json!([
+        { "name": "amount", "type": 4, "value": 150 },
+        { "name": "players", "type": 3, "value": format!("<@{PLAYER}> <@{GAME_MASTER}>") }
+    ])))).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Awarded 150 XP" );
+    let fields = &response["data"]["embeds"][0]["fields"];
+    assert_eq!( fields[0]["name"], "Merlin" );
+    assert!( fields[0]["value"].as_str().unwrap().contains("**Reached level 2!** 2 attribute points"), "Got {}", fields[0]["value"] );
+    assert_eq!( fields[1]["value"], format!("<@{GAME_MASTER}>") );
+
+    client.send( slash_command(PLAYER, "level_up", json!([]))).await;
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Level up Merlin" );
+    assert_eq!( response["data"]["components"].as_array().unwrap().len(), 2 );
+
+    // Strength is selected to begin with, raise it twice and confirm
+    let spread = "3-3-3-3-3-3";
+    client.send( button_click(PLAYER, &format!("level_up:attr_inc:{character_id}:0:{spread}")) ).await;
+    assert_eq!( client.last_response()["type"], UPDATE_MESSAGE );
+    client.send( button_click(PLAYER, &format!("level_up:attr_confirm:{character_id}:0:5-3-3-3-3-3")) ).await;
+
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin spent 2 attribute points" );
+    let stored = db::attributes::get( &client.harness.database_connection, character_id ).await.unwrap().unwrap();
+    assert_eq!( stored.spread(), AttributeSpread([5, 3, 3, 3, 3, 3]) );
+
+    // Pressing confirm on the old message again can't spend the points twice
+    client.send( button_click(PLAYER, &format!("level_up:attr_confirm:{character_id}:0:3-5-3-3-3-3")) ).await;
+    assert_eq!( embed_title(&client.last_response()), "These points were already spent" );
+}
+
+#[tokio::test]
+async fn items_from_the_catalogue_are_picked_up_and_given_away() {
+    const OTHER_PLAYER: u64 = 200;
+
+    let client = TestClient::new().await;
+    let giver_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    let receiver_id = build_through_interactions( &client, OTHER_PLAYER, "Morgana" ).await;
+    for character_id in [giver_id, receiver_id] {
+        db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([3; 6])) ).await.unwrap();
+    }
+    client.send( slash_command(OTHER_PLAYER, "switch_character", json!([{ "name": "character", "type": 4, "value": receiver_id }]))).await;
+
+    client.send( in_guild(modal_submit(GAME_MASTER, "item:create", &[
+        ("name", "Anvil"), ("weight", "15"), ("value", "40"), ("tags", "Heavy"), ("description", "Mostly used for dropping")
+    ]))).await;
+    assert_eq!( embed_title(&client.last_response()), "Saved Anvil" );
+    let anvil_id = db::items::get_catalogue( &client.harness.database_connection, GUILD ).await.unwrap()[0].item_id;
+
+    // Items only exist in servers
+    let add = |quantity: i64| slash_command(PLAYER, "inventory", json!([
+        { "name": "add", "type": 1, "options": [
+            { "name": "item", "type": 4, "value": anvil_id },
+            { "name": "quantity", "type": 4, "value": quantity },
+            { "name": "character", "type": 4, "value": giver_id }
+        ]}
+    ]));
+    client.send( add(2) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Items belong to a server" );
+
+    client.send( in_guild(add(2)) ).await;
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin picked up 2 × Anvil" );
+    assert_eq!( response["data"]["embeds"][0]["description"], "Carrying 30/40" );
+
+    client.send( in_guild(add(1)) ).await;
+    assert_eq!( embed_title(&client.last_response()), "That's too heavy" );
+
+    client.send( in_guild(slash_command(PLAYER, "inventory", json!([
+        { "name": "give", "type": 1, "options": [
+            { "name": "item", "type": 4, "value": anvil_id },
+            { "name": "user", "type": 6, "value": OTHER_PLAYER.to_string() },
+            { "name": "character", "type": 4, "value": giver_id }
+        ]}
+    ])))).await;
+    assert_eq!( embed_title(&client.last_response()), "Merlin gave 1 × Anvil to Morgana" );
+
+    client.send( slash_command(OTHER_PLAYER, "inventory", json!([{ "name": "view", "type": 1, "options": [] }]))).await;
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Morgana's inventory" );
+    assert_eq!( response["data"]["embeds"][0]["description"], "Carrying 15/40" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][0]["name"], "Anvil ×1" );
+}
+
+#[tokio::test]
+async fn gear_is_equipped_from_the_character_sheet() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([3; 6])) ).await.unwrap();
+
+    client.send( in_guild(modal_submit(GAME_MASTER, "item:create", &[
+        ("name", "Sword"), ("weight", "3"), ("value", "20"), ("tags", ""), ("description", "Pointy")
+    ]))).await;
+    let sword_id = db::items::get_catalogue( &client.harness.database_connection, GUILD ).await.unwrap()[0].item_id;
+
+    let gear = |strength: i64| in_guild(slash_command(GAME_MASTER, "item", json!([
+        { "name": "gear", "type": 1, "options": [
+            { "name": "item", "type": 4, "value": sword_id },
+            { "name": "slot", "type": 3, "value": "main_hand" },
+            { "name": "strength", "type": 4, "value": strength }
+        ]}
+    ])));
+    client.send( gear(11) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Couldn't save that gear" );
+
+    client.send( gear(2) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Saved Sword" );
+
+    client.send( in_guild(slash_command(PLAYER, "inventory", json!([
+        { "name": "add", "type": 1, "options": [
+            { "name": "item", "type": 4, "value": sword_id },
+            { "name": "character", "type": 4, "value": character_id }
+        ]}
+    ])))).await;
+    assert_eq!( embed_title(&client.last_response()), "Merlin picked up 1 × Sword" );
+
+    // Picking the sword from the select menu under the sheet equips it
+    let mut select = interaction_base( 3, PLAYER, json!({
+        "custom_id":      format!("character:equip:{character_id}:0"),
+        "component_type": 3,
+        "values":         [sword_id.to_string()]
+    }));
+    select["message"] = message_json( &json!({}) );
+    client.send( select ).await;
+
+    let response = client.last_response();
+    assert_eq!( response["type"], UPDATE_MESSAGE );
+    let fields = response["data"]["embeds"][0]["fields"].as_array().unwrap();
+    let field = |name: &str| fields.iter().find( |field| field["name"] == name ).map( |field| field["value"].clone() ).unwrap();
+    assert_eq!( field("Strength"), "5 (base 3)" );
+    assert_eq!( field("Equipment"), "**Main hand:** Sword · +2 Strength" );
+
+    // Only their player can take it off again
+    let unequip_id = format!("character:unequip:{character_id}:0:main_hand");
+    client.send( button_click(GAME_MASTER, &unequip_id) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Selected character doesn't belong to you" );
+
+    client.send( button_click(PLAYER, &unequip_id) ).await;
+    assert_eq!( client.last_response()["type"], UPDATE_MESSAGE );
+    assert!( db::equipment::get_equipped( &client.harness.database_connection, character_id ).await.unwrap().is_empty() );
+
+    client.send( button_click(PLAYER, &unequip_id) ).await;
+    assert_eq!( embed_title(&client.last_response()), "That's no longer there" );
+}
+
+#[tokio::test]
+async fn long_ability_lists_are_split_into_pages() {
+    const OTHER_PLAYER: u64 = 200;
+
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    for index in 0..8 {
+        db::abilities::add( &client.harness.database_connection, character_id, &format!("Spell {index}"), &"a".repeat(1000) ).await.unwrap();
+    }
+
+    client.send( slash_command(PLAYER, "ability", json!([
+        { "name": "list", "type": 1, "options": [{ "name": "character", "type": 4, "value": character_id }] }
+    ]))).await;
+
+    // Eight full descriptions would break the 6000 character limit of a single embed
+    let response = client.last_response();
+    assert_eq!( embed_title(&response), "Merlin's abilities" );
+    assert_eq!( response["data"]["embeds"][0]["description"], "Page 1/2" );
+    assert_eq!( response["data"]["embeds"][0]["fields"].as_array().unwrap().len(), 4 );
+    let next_id = response["data"]["components"][0]["components"][1]["custom_id"].as_str().unwrap().to_owned();
+
+    client.send( button_click(OTHER_PLAYER, &next_id) ).await;
+    assert_eq!( embed_title(&client.last_response()), "Selected character doesn't belong to you" );
+
+    client.send( button_click(PLAYER, &next_id) ).await;
+    let response = client.last_response();
+    assert_eq!( response["type"], UPDATE_MESSAGE );
+    assert_eq!( response["data"]["embeds"][0]["description"], "Page 2/2" );
+    assert_eq!( response["data"]["embeds"][0]["fields"][0]["name"], "Spell 4" );
+}
+
+#[tokio::test]
+async fn commands_without_a_character_ask_for_one() {
+    let client = TestClient::new().await;
+    build_through_interactions( &client, PLAYER, "Merlin" ).await;
+
+    // Building a character doesn't make it the active one
+    for command in ["check", "spellbook", "inventory"] {
+        let options = match command {
+            "check"     => json!([{ "name": "attribute", "type": 3, "value": "Strength" }]),
+            "inventory" => json!([{ "name": "view", "type": 1, "options": [] }]),
+            _           => json!([])
+        };
+        client.send( slash_command(PLAYER, command, options) ).await;
+        assert_eq!( embed_title(&client.last_response()), "No character selected", "/{command}" );
+    }
+}
+
+#[tokio::test]
+async fn responses_name_the_active_character() {
+    let client = TestClient::new().await;
+    let character_id = build_through_interactions( &client, PLAYER, "Merlin" ).await;
+    db::attributes::insert( &client.harness.database_connection, &Attributes::from_spread(character_id, &AttributeSpread([5; 6])) ).await.unwrap();
+
+    client.send( slash_command(PLAYER, "switch_character", json!([{ "name": "character", "type": 4, "value": character_id }]))).await;
+    assert_eq!( client.last_response()["data"]["embeds"][0]["footer"]["text"], "Playing as Merlin" );
+
+    client.send( slash_command(PLAYER, "heal", json!([{ "name": "amount", "type": 4, "value": 1 }]))).await;
+    assert_eq!( client.last_response()["data"]["embeds"][0]["footer"]["text"], "Playing as Merlin", "/heal" );
+
+    client.send( slash_command(PLAYER, "rolls", json!([{ "name": "history", "type": 1, "options": [] }]))).await;
+    assert_eq!( client.last_response()["data"]["embeds"][0]["footer"]["text"], "Playing as Merlin", "/rolls history" );
+}
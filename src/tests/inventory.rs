@@ -0,0 +1,176 @@
+// The item catalogue, what characters carry and handing items between them
+
+use crate::{
+    attributes::AttributeSpread,
+    commands::{inventory, item},
+    db::{self, DbError, Item},
+    error::BotError,
+    utils::unix_now
+};
+use super::{TestHarness, GUILD, OTHER_PLAYER, PLAYER};
+
+
+/// How many of an item a character is carrying
+async fn quantity( harness: &TestHarness, character_id: i64, item_id: i64 ) -> i64 {
+    db::items::get_inventory( &harness.database_connection, character_id )
+        .await
+        .unwrap()
+        .into_iter()
+        .find( |entry| entry.item.item_id == item_id )
+        .map_or( 0, |entry| entry.quantity )
+}
+
+
+// --== CATALOGUE ==-- //
+
+    #[test]
+    fn tags_are_trimmed_lowercased_and_kept_once() {
+        let inputs = [" Rope ", "2", "10", "Tools,  CLIMBING,, tools ", "Fifty feet of it"].map( str::to_owned );
+
+        let rope = item::item_from_inputs( 3, GUILD, &inputs ).unwrap();
+        assert_eq!( (rope.item_id, rope.name.as_str()), (3, "Rope") );
+        assert_eq!( rope.tags, "tools, climbing" );
+    }
+
+    #[test]
+    fn weight_and_value_are_whole_numbers_of_at_least_0() {
+        let inputs = |weight: &str, value: &str| [
+            "Rope", weight, value, "", "Fifty feet of it"
+        ].map( str::to_owned );
+
+        let rope = item::item_from_inputs( 3, GUILD, &inputs(" 2", "10 ") ).unwrap();
+        assert_eq!( (rope.weight, rope.value), (2, 10) );
+        assert!( item::item_from_inputs( 3, GUILD, &inputs("0", "0") ).is_some() );
+
+        assert!( item::item_from_inputs( 3, GUILD, &inputs("-1", "10") ).is_none() );
+        assert!( item::item_from_inputs( 3, GUILD, &inputs("2", "-5") ).is_none() );
+        assert!( item::item_from_inputs( 3, GUILD, &inputs("2", "lots") ).is_none() );
+        assert!( item::item_from_inputs( 3, GUILD, &inputs("1.5", "10") ).is_none() );
+    }
+
+    #[tokio::test]
+    async fn items_are_only_seen_by_the_server_that_wrote_them() {
+        let harness = TestHarness::new().await;
+
+        let rope_id = db::items::add( &harness.database_connection, &TestHarness::item("Rope", 2) ).await.unwrap();
+        let duplicate = db::items::add( &harness.database_connection, &TestHarness::item("ROPE", 1) ).await;
+        assert!( matches!(duplicate, Err(DbError::DuplicateItem)) );
+
+        // Another server can have an item of the same name, and can't see this one
+        let elsewhere = Item { guild_id: GUILD as i64 + 1, ..TestHarness::item("Rope", 2) };
+        db::items::add( &harness.database_connection, &elsewhere ).await.unwrap();
+
+        assert!( db::items::get( &harness.database_connection, GUILD + 1, rope_id ).await.unwrap().is_none() );
+        assert_eq!( db::items::get_catalogue( &harness.database_connection, GUILD ).await.unwrap().len(), 1 );
+        assert!( !db::items::remove( &harness.database_connection, GUILD + 1, rope_id ).await.unwrap() );
+    }
+
+    #[tokio::test]
+    async fn removed_items_leave_every_inventory() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let rope_id = db::items::add( &harness.database_connection, &TestHarness::item("Rope", 2) ).await.unwrap();
+        inventory::add_items( &harness.database_connection, character_id, rope_id, 3 ).await.unwrap();
+
+        assert!( db::items::remove( &harness.database_connection, GUILD, rope_id ).await.unwrap() );
+        assert_eq!( harness.count_rows("InventoryItems", "fk_itemId", rope_id).await, 0 );
+        assert!( db::items::get_catalogue( &harness.database_connection, GUILD ).await.unwrap().is_empty() );
+    }
+// ==--
+
+
+// --== CARRYING ==-- //
+
+    #[tokio::test]
+    async fn characters_carry_up_to_their_capacity() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let anvil_id = db::items::add( &harness.database_connection, &TestHarness::item("Anvil", 10) ).await.unwrap();
+        let feather_id = db::items::add( &harness.database_connection, &TestHarness::item("Feather", 0) ).await.unwrap();
+
+        assert_eq!( inventory::add_items( &harness.database_connection, character_id, anvil_id, 4 ).await.unwrap(), (40, 40) );
+
+        let too_heavy = inventory::add_items( &harness.database_connection, character_id, anvil_id, 1 ).await;
+        assert!( matches!(too_heavy, Err(BotError::Overburdened)) );
+        assert_eq!( quantity(&harness, character_id, anvil_id).await, 4 );
+
+        // Weightless items can always be picked up
+        inventory::add_items( &harness.database_connection, character_id, feather_id, 3 ).await.unwrap();
+        assert_eq!( db::items::carried_weight( &harness.database_connection, character_id ).await.unwrap(), 40 );
+    }
+
+    #[tokio::test]
+    async fn characters_without_attributes_cant_carry_anything() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character( PLAYER, "Merlin" ).await;
+        let rope_id = db::items::add( &harness.database_connection, &TestHarness::item("Rope", 2) ).await.unwrap();
+
+        let added = inventory::add_items( &harness.database_connection, character_id, rope_id, 1 ).await;
+        assert!( matches!(added, Err(BotError::NoAttributes)) );
+    }
+
+    #[tokio::test]
+    async fn items_can_only_be_dropped_when_carried() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let rope_id = db::items::add( &harness.database_connection, &TestHarness::item("Rope", 2) ).await.unwrap();
+        inventory::add_items( &harness.database_connection, character_id, rope_id, 2 ).await.unwrap();
+
+        let too_many = db::items::take_from_inventory( &harness.database_connection, character_id, rope_id, 3 ).await;
+        assert!( matches!(too_many, Err(DbError::NotEnoughItems)) );
+        assert_eq!( quantity(&harness, character_id, rope_id).await, 2 );
+
+        // Dropping the last of them takes the item out of the inventory entirely
+        db::items::take_from_inventory( &harness.database_connection, character_id, rope_id, 2 ).await.unwrap();
+        assert_eq!( harness.count_rows("InventoryItems", "fk_characterId", character_id).await, 0 );
+    }
+
+    #[tokio::test]
+    async fn removed_characters_lose_their_inventory() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let rope_id = db::items::add( &harness.database_connection, &TestHarness::item("Rope", 2) ).await.unwrap();
+        inventory::add_items( &harness.database_connection, character_id, rope_id, 2 ).await.unwrap();
+
+        db::characters::archive( &harness.database_connection, character_id, unix_now() - 10 ).await.unwrap();
+        db::characters::remove_archived_before( &harness.database_connection, unix_now() ).await.unwrap();
+
+        assert_eq!( harness.count_rows("InventoryItems", "fk_characterId", character_id).await, 0 );
+    }
+// ==--
+
+
+// --== GIVING ==-- //
+
+    #[tokio::test]
+    async fn giving_moves_items_between_characters() {
+        let harness = TestHarness::new().await;
+        let giver_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        let receiver_id = harness.character_with_attributes( OTHER_PLAYER, "Morgana", AttributeSpread([3; 6]) ).await;
+        let rope_id = db::items::add( &harness.database_connection, &TestHarness::item("Rope", 2) ).await.unwrap();
+        inventory::add_items( &harness.database_connection, giver_id, rope_id, 5 ).await.unwrap();
+
+        assert_eq!( inventory::give_items( &harness.database_connection, giver_id, receiver_id, rope_id, 2 ).await.unwrap(), (4, 40) );
+        assert_eq!( quantity(&harness, giver_id, rope_id).await, 3 );
+        assert_eq!( quantity(&harness, receiver_id, rope_id).await, 2 );
+    }
+
+    #[tokio::test]
+    async fn failed_gifts_change_nothing() {
+        let harness = TestHarness::new().await;
+        let giver_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([5; 6]) ).await;
+        let receiver_id = harness.character_with_attributes( OTHER_PLAYER, "Morgana", AttributeSpread([1; 6]) ).await;
+        let anvil_id = db::items::add( &harness.database_connection, &TestHarness::item("Anvil", 10) ).await.unwrap();
+        inventory::add_items( &harness.database_connection, giver_id, anvil_id, 3 ).await.unwrap();
+
+        // Morgana can carry 20, so three anvils are too heavy for her
+        let too_heavy = inventory::give_items( &harness.database_connection, giver_id, receiver_id, anvil_id, 3 ).await;
+        assert!( matches!(too_heavy, Err(BotError::Overburdened)) );
+
+        let too_many = inventory::give_items( &harness.database_connection, giver_id, receiver_id, anvil_id, 4 ).await;
+        assert!( matches!(too_many, Err(BotError::NotEnoughItems)) );
+
+        assert_eq!( quantity(&harness, giver_id, anvil_id).await, 3 );
+        assert_eq!( quantity(&harness, receiver_id, anvil_id).await, 0 );
+    }
+// ==--
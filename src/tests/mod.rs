@@ -0,0 +1,180 @@
+// Shared setup for the test suite
+//
+// - Every test gets it's own in-memory SQLite database, with the same migrations run on it that
+//     the bot runs at startup, so tests can't see each other's rows
+// - The caches are created empty, the same as they would be when syncing them to a fresh database
+// - Flows are driven through the functions the command handlers call, which take the database
+//     and caches directly, so no connection to Discord is needed
+// - Whole interactions can be fed to the bot as JSON, with it's responses sent to `FakeDiscord`
+
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex}
+};
+
+use serde_json::Value;
+use serenity::{
+    all::Interaction,
+    prelude::{RwLock, TypeMap}
+};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool
+};
+
+use crate::{
+    attributes::AttributeSpread,
+    commands::{self, build_character, register},
+    db::{self, Attributes, Item},
+    error::BotError,
+    event_handler::DiscordBot,
+    responses::{BotContext, ResponseSink},
+    utils::{ActiveCharactersCache, CharacterId, CharacterMap, DatabaseCharactersCache}
+};
+
+mod fake_discord;
+mod character_flows;
+mod interactions;
+mod dice;
+mod checks;
+mod rolls;
+mod encounters;
+mod vitals;
+mod spells;
+mod progression;
+mod inventory;
+mod equipment;
+
+
+/// Discord IDs the tests play with. Nothing checks them against Discord, they only have to differ
+pub const PLAYER: u64 = 100;
+pub const OTHER_PLAYER: u64 = 200;
+pub const GAME_MASTER: u64 = 500;
+pub const GUILD: u64 = 300;
+
+
+/// A fresh database along with the caches the bot keeps next to it
+pub struct TestHarness {
+    pub database_connection:     SqlitePool,
+    pub characters_cache:        Arc<Mutex<CharacterMap>>,
+    pub active_characters_cache: Arc<Mutex<HashMap<u64, CharacterId>>>
+}
+impl TestHarness {
+
+    pub async fn new() -> TestHarness {
+        TestHarness {
+            database_connection:     test_database().await,
+            characters_cache:        Arc::default(),
+            active_characters_cache: Arc::default()
+        }
+    }
+
+    /// The bot, handling interactions against this harness' database
+    pub fn bot( &self ) -> DiscordBot {
+        DiscordBot {
+            database_connection: self.database_connection.clone(),
+            commands:            commands::registry()
+        }
+    }
+
+    /// Context with this harness' caches in it's data, sending responses to the given sink
+    pub async fn context( &self, responses: Arc<dyn ResponseSink> ) -> BotContext {
+        let data = Arc::new( RwLock::new(TypeMap::new()) );
+        {
+            let mut data_write = data.write().await;
+            data_write.insert::<DatabaseCharactersCache>( self.characters_cache.clone() );
+            data_write.insert::<ActiveCharactersCache>( self.active_characters_cache.clone() );
+        }
+
+        BotContext { data, responses }
+    }
+
+    /// Clone of the characters cache's entry for a user, empty if they have none
+    pub fn cached_characters( &self, user_id: u64 ) -> Vec<(CharacterId, String)> {
+        self.characters_cache
+            .lock()
+            .expect("Tests don't panic while holding the lock")
+            .get( &user_id )
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Register the user, unless they already are, and build them a character without any
+    /// attributes, returning it's ID
+    pub async fn character( &self, user_id: u64, name: &str ) -> CharacterId {
+        match register::register_profile( &self.database_connection, user_id ).await {
+            Ok(()) | Err( BotError::AlreadyRegistered ) => {},
+            Err( why ) => panic!("Registering a user shouldn't fail: {why}")
+        }
+
+        build_character::create_character(
+            &self.database_connection, &self.characters_cache,
+            user_id, name, "Human", "Born yesterday"
+        ).await
+            .expect("Building a character for a registered user succeeds")
+    }
+
+    /// Same as `character`, with the given attributes allocated to it
+    pub async fn character_with_attributes( &self, user_id: u64, name: &str, spread: AttributeSpread ) -> CharacterId {
+        let character_id = self.character( user_id, name ).await;
+
+        db::attributes::insert( &self.database_connection, &Attributes::from_spread(character_id, &spread) )
+            .await
+            .expect("Attributes of a new character can be inserted");
+        character_id
+    }
+
+    /// An item for the catalogue of `GUILD`, ready to be added
+    pub fn item( name: &str, weight: i64 ) -> Item {
+        Item {
+            guild_id:    GUILD as i64,
+            name:        name.to_owned(),
+            weight,
+            value:       5,
+            description: "Nothing special".to_owned(),
+            ..Item::default()
+        }
+    }
+
+    /// Number of rows in a table whose column holds the given value
+    pub async fn count_rows( &self, table: &str, column: &str, value: i64 ) -> i64 {
+        sqlx::query_scalar( &format!("SELECT COUNT(*) FROM {table} WHERE {column} = ?") )
+            .bind( value )
+            .fetch_one( &self.database_connection )
+            .await
+            .expect("Counting rows shouldn't fail")
+    }
+}
+
+
+/// Turn an interaction's JSON, as Discord would send it over the gateway, into an `Interaction`
+pub fn interaction( interaction_json: Value ) -> Interaction {
+    serde_json::from_value( interaction_json )
+        .expect("Synthetic interaction JSON must match Discord's layout")
+}
+
+
+/// Open an in-memory database and run the migrations on it
+///
+/// Every connection to `sqlite::memory:` gets a database of it's own, so the pool is kept to a
+/// single connection that never gets closed
+pub async fn test_database() -> SqlitePool {
+    let connect_options = SqliteConnectOptions::from_str("sqlite::memory:")
+        .expect("In-memory database URL is valid");
+
+    let database_connection = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with( connect_options )
+        .await
+        .expect("Opening an in-memory database shouldn't fail");
+
+    sqlx::migrate!("./migrations")
+        .run( &database_connection )
+        .await
+        .expect("Migrations must run on an empty database");
+
+    database_connection
+}
@@ -0,0 +1,160 @@
+// Experience, levelling up along the curve and spending the points it grants
+
+use crate::{
+    attributes::{AllocationRules, AllocationState, Attribute, AttributeSpread},
+    commands::{award_xp::mentioned_user_ids, level_up::level_up_rules},
+    db::{self, Attributes, Progression},
+    progression::{self, LevelCurve},
+    utils::unix_now
+};
+use super::{TestHarness, GAME_MASTER, PLAYER};
+
+
+// --== LEVEL CURVE ==-- //
+
+    #[test]
+    fn levels_follow_the_curve() {
+        let curve = LevelCurve::new( vec![100, 300, 600] ).unwrap();
+
+        assert_eq!( curve.level_for(0), 1 );
+        assert_eq!( curve.level_for(99), 1 );
+        assert_eq!( curve.level_for(100), 2 );
+        assert_eq!( curve.level_for(599), 3 );
+        // There's nothing past the end of the curve
+        assert_eq!( curve.level_for(1_000_000), 4 );
+
+        assert_eq!( curve.experience_for(1), Some(0) );
+        assert_eq!( curve.experience_for(3), Some(300) );
+        assert_eq!( curve.experience_for(5), None );
+    }
+
+    #[test]
+    fn curves_have_to_keep_rising() {
+        assert!( LevelCurve::new( vec![] ).is_some() );
+        assert!( LevelCurve::new( vec![0, 100] ).is_none() );
+        assert!( LevelCurve::new( vec![100, 100] ).is_none() );
+        assert!( LevelCurve::new( vec![300, 100] ).is_none() );
+    }
+
+    #[test]
+    fn experience_can_skip_several_levels() {
+        // The default curve needs 100 for level 2 and 300 for level 3, granting 2 points a level
+        let ( progression, level_ups ) = progression::apply_experience( &Progression::starting(1), 350, GAME_MASTER, 1000 );
+
+        assert_eq!( (progression.experience, progression.level, progression.unspent_points), (350, 3, 4) );
+        assert_eq!( level_ups.iter().map( |level_up| level_up.level ).collect::<Vec<i64>>(), vec![2, 3] );
+        assert!( level_ups.iter().all( |level_up| level_up.points_granted == 2 && level_up.awarded_by == GAME_MASTER as i64 ) );
+    }
+
+    #[test]
+    fn levels_are_never_lost() {
+        // As if the curve was changed to need more experience since they reached level 5
+        let stored = Progression { character_id: 1, experience: 150, level: 5, unspent_points: 0 };
+        let ( progression, level_ups ) = progression::apply_experience( &stored, 10, GAME_MASTER, 1000 );
+
+        assert_eq!( progression.level, 5 );
+        assert!( level_ups.is_empty() );
+    }
+
+    #[test]
+    fn mentions_are_read_in_order_without_repeats() {
+        assert_eq!( mentioned_user_ids("<@100> and <@!500>, <@100>"), vec![100, 500] );
+        assert_eq!( mentioned_user_ids("<@not_a_user> <@ 100> @everyone"), Vec::<u64>::new() );
+    }
+// ==--
+
+
+// --== AWARDING ==-- //
+
+    #[tokio::test]
+    async fn awards_are_stored_and_level_ups_logged() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+
+        let ( before, after ) = progression::award_experience( &harness.database_connection, character_id, 120, GAME_MASTER ).await.unwrap();
+        assert_eq!( before, Progression::starting(character_id) );
+        assert_eq!( (after.level, after.unspent_points), (2, 2) );
+
+        let ( _, after ) = progression::award_experience( &harness.database_connection, character_id, 500, GAME_MASTER ).await.unwrap();
+        assert_eq!( (after.experience, after.level, after.unspent_points), (620, 4, 6) );
+        assert_eq!( db::progression::get(&harness.database_connection, character_id).await.unwrap(), after );
+
+        let level_ups = db::progression::get_level_ups( &harness.database_connection, character_id ).await.unwrap();
+        assert_eq!( level_ups.iter().map( |level_up| (level_up.level, level_up.experience) ).collect::<Vec<_>>(), vec![(2, 120), (3, 620), (4, 620)] );
+    }
+
+    #[tokio::test]
+    async fn removed_characters_lose_their_progression() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        progression::award_experience( &harness.database_connection, character_id, 1000, GAME_MASTER ).await.unwrap();
+
+        db::characters::archive( &harness.database_connection, character_id, unix_now() - 10 ).await.unwrap();
+        db::characters::remove_archived_before( &harness.database_connection, unix_now() ).await.unwrap();
+
+        assert_eq!( harness.count_rows("CharacterProgression", "fk_characterId", character_id).await, 0 );
+        assert_eq!( harness.count_rows("LevelUps", "fk_characterId", character_id).await, 0 );
+    }
+// ==--
+
+
+// --== SPENDING POINTS ==-- //
+
+    #[test]
+    fn level_up_allocations_only_raise_attributes() {
+        let current = AttributeSpread([5, 3, 1, 1, 4, 2]);
+        let rules = AllocationRules::level_up( current, 2, 20 );
+        let mut allocation_state = AllocationState::new( 1, &rules );
+        assert_eq!( allocation_state.spread, current );
+
+        // Lowering below where they are now does nothing
+        allocation_state.adjust( &rules, -1 );
+        assert_eq!( allocation_state.spread, current );
+
+        allocation_state.adjust( &rules, 1 );
+        allocation_state.selected = Attribute::Casting;
+        allocation_state.adjust( &rules, 1 );
+        allocation_state.adjust( &rules, 1 );
+        assert_eq!( allocation_state.spread, AttributeSpread([6, 3, 1, 1, 4, 3]) );
+        assert_eq!( rules.remaining_points(&allocation_state.spread), 0 );
+
+        // Attributes can't go above the maximum either
+        let rules = AllocationRules::level_up( AttributeSpread([20; 6]), 2, 20 );
+        assert!( !rules.is_valid( &AttributeSpread([21, 20, 20, 20, 20, 20]) ) );
+    }
+
+    #[tokio::test]
+    async fn points_can_only_be_spent_once() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+        progression::award_experience( &harness.database_connection, character_id, 100, GAME_MASTER ).await.unwrap();
+
+        let ( rules, progression ) = level_up_rules( &harness.database_connection, character_id ).await.unwrap();
+        assert_eq!( (rules.budget, rules.floor), (2, AttributeSpread([3; 6])) );
+
+        let raised = Attributes::from_spread( character_id, &AttributeSpread([5, 3, 3, 3, 3, 3]) );
+        assert!( db::progression::spend_points(&harness.database_connection, &raised, progression.unspent_points, 2).await.unwrap() );
+        assert_eq!( db::progression::get(&harness.database_connection, character_id).await.unwrap().unspent_points, 0 );
+
+        // A second message, sent before the points were spent, can't spend them again
+        let also_raised = Attributes::from_spread( character_id, &AttributeSpread([3, 5, 3, 3, 3, 3]) );
+        assert!( !db::progression::spend_points(&harness.database_connection, &also_raised, progression.unspent_points, 2).await.unwrap() );
+
+        let stored = db::attributes::get( &harness.database_connection, character_id ).await.unwrap().unwrap();
+        assert_eq!( stored.spread(), AttributeSpread([5, 3, 3, 3, 3, 3]) );
+    }
+
+    #[tokio::test]
+    async fn points_are_only_spent_on_allocated_attributes() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character( PLAYER, "Merlin" ).await;
+        progression::award_experience( &harness.database_connection, character_id, 100, GAME_MASTER ).await.unwrap();
+
+        // Levelling up doesn't get to allocate their first attributes for them
+        let raised = Attributes::from_spread( character_id, &AttributeSpread([5, 3, 3, 3, 3, 3]) );
+        assert!( !db::progression::spend_points(&harness.database_connection, &raised, 2, 2).await.unwrap() );
+
+        assert!( db::attributes::get(&harness.database_connection, character_id).await.unwrap().is_none() );
+        assert_eq!( db::progression::get(&harness.database_connection, character_id).await.unwrap().unspent_points, 2 );
+    }
+// ==--
@@ -0,0 +1,113 @@
+// The roll history, checked against the database
+
+use crate::{
+    commands::delete_character,
+    config,
+    db::{self, RollRecord},
+    trash,
+    utils::unix_now
+};
+use super::{TestHarness, GUILD, PLAYER};
+
+
+/// A roll made by `PLAYER` in `GUILD`
+fn roll_record( character: Option<(i64, &str)>, total: i64, natural_d20: Option<i64>, rolled_at: i64 ) -> RollRecord {
+    RollRecord {
+        roll_id:        0,
+        discord_id:     PLAYER as i64,
+        character_id:   character.map( |(character_id, _)| character_id ),
+        character_name: character.map( |(_, character_name)| character_name.to_owned() ),
+        guild_id:       Some( GUILD as i64 ),
+        channel_id:     400,
+        label:          "Roll".to_owned(),
+        expression:     "1d20".to_owned(),
+        total,
+        natural_d20,
+        rolled_at
+    }
+}
+
+
+#[tokio::test]
+async fn history_is_paged_newest_first() {
+    let harness = TestHarness::new().await;
+
+    for rolled_at in 1..=5 {
+        db::rolls::add( &harness.database_connection, &roll_record(None, rolled_at, None, rolled_at) ).await.unwrap();
+    }
+
+    let first_page = db::rolls::get_page( &harness.database_connection, PLAYER, Some(GUILD), None, 0, 2 ).await.unwrap();
+    let last_page  = db::rolls::get_page( &harness.database_connection, PLAYER, Some(GUILD), None, 2, 2 ).await.unwrap();
+
+    assert_eq!( first_page.iter().map( |roll| roll.total ).collect::<Vec<_>>(), vec![5, 4] );
+    assert_eq!( last_page.iter().map( |roll| roll.total ).collect::<Vec<_>>(), vec![1] );
+    assert_eq!( db::rolls::count(&harness.database_connection, PLAYER, Some(GUILD), None).await.unwrap(), 5 );
+}
+
+#[tokio::test]
+async fn history_is_kept_per_guild_and_character() {
+    let harness = TestHarness::new().await;
+    let character_id = harness.character( PLAYER, "Merlin" ).await;
+
+    db::rolls::add( &harness.database_connection, &roll_record(Some((character_id, "Merlin")), 10, None, 1) ).await.unwrap();
+    db::rolls::add( &harness.database_connection, &roll_record(None, 20, None, 2) ).await.unwrap();
+    db::rolls::add( &harness.database_connection, &RollRecord { guild_id: None, ..roll_record(None, 30, None, 3) } ).await.unwrap();
+
+    let count = |guild_id, character_id| db::rolls::count( &harness.database_connection, PLAYER, guild_id, character_id );
+    assert_eq!( count(Some(GUILD), None).await.unwrap(), 2 );
+    assert_eq!( count(Some(GUILD), Some(character_id)).await.unwrap(), 1 );
+    assert_eq!( count(None, None).await.unwrap(), 1 );
+    assert_eq!( count(Some(GUILD + 1), None).await.unwrap(), 0 );
+}
+
+#[tokio::test]
+async fn stats_sum_up_each_character() {
+    let harness = TestHarness::new().await;
+    let character_id = harness.character( PLAYER, "Merlin" ).await;
+    let merlin = Some(( character_id, "Merlin" ));
+
+    for ( total, natural ) in [ (25, 20), (21, 20), (6, 1), (15, 10) ] {
+        db::rolls::add( &harness.database_connection, &roll_record(merlin, total, Some(natural), 1) ).await.unwrap();
+    }
+    db::rolls::add( &harness.database_connection, &roll_record(None, 8, None, 1) ).await.unwrap();
+
+    let character_stats = db::rolls::stats( &harness.database_connection, PLAYER, Some(GUILD) ).await.unwrap();
+    assert_eq!( character_stats.len(), 2 );
+
+    // Rolls without a character come last
+    let merlin_stats = &character_stats[0];
+    assert_eq!( merlin_stats.character_name.as_deref(), Some("Merlin") );
+    assert_eq!( merlin_stats.roll_count, 4 );
+    assert_eq!( merlin_stats.average_total, 16.75 );
+    assert_eq!( merlin_stats.average_natural, Some(12.75) );
+    assert_eq!( merlin_stats.natural_twenties, 2 );
+    assert_eq!( merlin_stats.natural_ones, 1 );
+
+    assert_eq!( character_stats[1].character_name, None );
+    assert_eq!( character_stats[1].average_natural, None );
+
+    let distribution = db::rolls::natural_distribution( &harness.database_connection, PLAYER, Some(GUILD), merlin_stats ).await.unwrap();
+    assert_eq!( distribution[0], 1 );
+    assert_eq!( distribution[9], 1 );
+    assert_eq!( distribution[19], 2 );
+    assert_eq!( distribution.iter().sum::<i64>(), 4 );
+}
+
+#[tokio::test]
+async fn rolls_outlive_removed_characters() {
+    let harness = TestHarness::new().await;
+    let character_id = harness.character( PLAYER, "Merlin" ).await;
+    db::rolls::add( &harness.database_connection, &roll_record(Some((character_id, "Merlin")), 12, Some(12), 1) ).await.unwrap();
+
+    delete_character::delete_character(
+        &harness.database_connection, &harness.characters_cache, &harness.active_characters_cache,
+        PLAYER, character_id, "Merlin"
+    ).await.unwrap();
+    let after_retention = unix_now() + config::get().trash_retention_days as i64 * 24 * 60 * 60 + 1;
+    trash::empty( &harness.database_connection, after_retention ).await.unwrap();
+
+    let history = db::rolls::get_page( &harness.database_connection, PLAYER, Some(GUILD), None, 0, 10 ).await.unwrap();
+    assert_eq!( history.len(), 1 );
+    assert_eq!( history[0].character_id, None );
+    assert_eq!( history[0].character_name.as_deref(), Some("Merlin") );
+}
@@ -0,0 +1,173 @@
+// The spell catalogue, the spells characters know and casting them
+
+use crate::{
+    attributes::AttributeSpread,
+    commands::{cast, spell},
+    db::{self, DbError, Spell},
+    derived_stats,
+    error::BotError,
+    utils::unix_now
+};
+use super::{TestHarness, GUILD, PLAYER};
+
+
+fn fireball( mana_cost: i64, difficulty: i64 ) -> Spell {
+    Spell {
+        spell_id:   0,
+        guild_id:   GUILD as i64,
+        name:       "Fireball".to_owned(),
+        school:     "Evocation".to_owned(),
+        mana_cost,
+        difficulty,
+        effect:     "Everything nearby is on fire".to_owned()
+    }
+}
+
+
+// --== CATALOGUE ==-- //
+
+    #[test]
+    fn modal_inputs_need_sensible_numbers() {
+        let inputs = |mana_cost: &str, difficulty: &str| [
+            " Fireball ", "Evocation", mana_cost, difficulty, "Boom"
+        ].map( str::to_owned );
+
+        let spell = spell::spell_from_inputs( 3, GUILD, &inputs(" 5", "12 ") ).unwrap();
+        assert_eq!( (spell.spell_id, spell.name.as_str(), spell.mana_cost, spell.difficulty), (3, "Fireball", 5, 12) );
+
+        assert!( spell::spell_from_inputs( 0, GUILD, &inputs("0", "1") ).is_some() );
+        assert!( spell::spell_from_inputs( 0, GUILD, &inputs("-1", "12") ).is_none() );
+        assert!( spell::spell_from_inputs( 0, GUILD, &inputs("5", "0") ).is_none() );
+        assert!( spell::spell_from_inputs( 0, GUILD, &inputs("five", "12") ).is_none() );
+    }
+
+    #[tokio::test]
+    async fn spell_names_are_unique_regardless_of_case() {
+        let harness = TestHarness::new().await;
+
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(5, 12) ).await.unwrap();
+        let mut duplicate = fireball( 1, 1 );
+        duplicate.name = "FIREBALL".to_owned();
+        assert!( matches!( db::spells::add(&harness.database_connection, &duplicate).await, Err(DbError::DuplicateSpell) ) );
+
+        // Renaming onto another spell is caught too
+        let mut frostbolt = fireball( 3, 10 );
+        frostbolt.name = "Frostbolt".to_owned();
+        frostbolt.spell_id = db::spells::add( &harness.database_connection, &frostbolt ).await.unwrap();
+        frostbolt.name = "fireball".to_owned();
+        assert!( matches!( db::spells::update(&harness.database_connection, &frostbolt).await, Err(DbError::DuplicateSpell) ) );
+
+        let stored = db::spells::get( &harness.database_connection, GUILD, spell_id ).await.unwrap().unwrap();
+        assert_eq!( stored.mana_cost, 5 );
+        assert_eq!( db::spells::get_catalogue(&harness.database_connection, GUILD).await.unwrap().len(), 2 );
+    }
+
+    #[tokio::test]
+    async fn every_server_keeps_its_own_catalogue() {
+        let harness = TestHarness::new().await;
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(5, 12) ).await.unwrap();
+
+        // Another server can have a spell of the same name, and can't see, change or remove this one
+        let mut elsewhere = Spell { guild_id: GUILD as i64 + 1, ..fireball(1, 1) };
+        elsewhere.spell_id = db::spells::add( &harness.database_connection, &elsewhere ).await.unwrap();
+
+        assert!( db::spells::get(&harness.database_connection, GUILD + 1, spell_id).await.unwrap().is_none() );
+        assert_eq!( db::spells::get_catalogue(&harness.database_connection, GUILD).await.unwrap().len(), 1 );
+        assert!( !db::spells::remove(&harness.database_connection, GUILD + 1, spell_id).await.unwrap() );
+
+        let stolen = Spell { spell_id, ..elsewhere };
+        db::spells::update( &harness.database_connection, &stolen ).await.unwrap();
+        let stored = db::spells::get( &harness.database_connection, GUILD, spell_id ).await.unwrap().unwrap();
+        assert_eq!( stored.mana_cost, 5 );
+    }
+
+    #[tokio::test]
+    async fn removed_spells_are_forgotten() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(5, 12) ).await.unwrap();
+        assert!( db::spells::learn(&harness.database_connection, character_id, spell_id).await.unwrap() );
+        assert!( !db::spells::learn(&harness.database_connection, character_id, spell_id).await.unwrap() );
+
+        assert!( db::spells::remove(&harness.database_connection, GUILD, spell_id).await.unwrap() );
+        assert!( db::spells::get_known(&harness.database_connection, character_id).await.unwrap().is_empty() );
+        assert_eq!( harness.count_rows("KnownSpells", "fk_spellId", spell_id).await, 0 );
+    }
+
+    #[tokio::test]
+    async fn removed_characters_forget_their_spells() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([3; 6]) ).await;
+
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(5, 12) ).await.unwrap();
+        db::spells::learn( &harness.database_connection, character_id, spell_id ).await.unwrap();
+
+        db::characters::archive( &harness.database_connection, character_id, unix_now() - 10 ).await.unwrap();
+        db::characters::remove_archived_before( &harness.database_connection, unix_now() ).await.unwrap();
+
+        assert_eq!( harness.count_rows("KnownSpells", "fk_characterId", character_id).await, 0 );
+        // The spell itself stays in the catalogue
+        assert!( db::spells::get(&harness.database_connection, GUILD, spell_id).await.unwrap().is_some() );
+    }
+// ==--
+
+
+// --== CASTING ==-- //
+
+    #[tokio::test]
+    async fn casting_pays_the_mana_cost() {
+        let harness = TestHarness::new().await;
+        // 4 Casting is 20 mana with the default formula
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([4; 6]) ).await;
+
+        // A difficulty of 1 can't be missed
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(8, 1) ).await.unwrap();
+        db::spells::learn( &harness.database_connection, character_id, spell_id ).await.unwrap();
+
+        let cast = cast::cast_spell( &harness.database_connection, character_id, "Merlin", spell_id ).await.unwrap();
+        assert!( cast.success );
+        assert_eq!( (cast.vitals.mana, cast.vitals.max_mana), (12, 20) );
+        assert_eq!( cast.check.expression.to_string(), "1d20 + 4" );
+
+        let cast = cast::cast_spell( &harness.database_connection, character_id, "Merlin", spell_id ).await.unwrap();
+        assert_eq!( cast.vitals.mana, 4 );
+
+        // Without enough mana the cast fails before anything is paid
+        let result = cast::cast_spell( &harness.database_connection, character_id, "Merlin", spell_id ).await;
+        assert!( matches!( result, Err(BotError::NotEnoughMana) ) );
+        let ( _, vitals ) = derived_stats::adjust_vitals( &harness.database_connection, character_id, 0, 0 ).await.unwrap();
+        assert_eq!( vitals.mana, 4 );
+    }
+
+    #[tokio::test]
+    async fn only_known_spells_can_be_cast() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([4; 6]) ).await;
+
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(5, 12) ).await.unwrap();
+        let result = cast::cast_spell( &harness.database_connection, character_id, "Merlin", spell_id ).await;
+        assert!( matches!( result, Err(BotError::UnknownSpell) ) );
+
+        db::spells::learn( &harness.database_connection, character_id, spell_id ).await.unwrap();
+        assert!( db::spells::forget(&harness.database_connection, character_id, spell_id).await.unwrap() );
+        let result = cast::cast_spell( &harness.database_connection, character_id, "Merlin", spell_id ).await;
+        assert!( matches!( result, Err(BotError::UnknownSpell) ) );
+
+        // Nothing was spent on the failed attempts
+        assert_eq!( harness.count_rows("CharacterResources", "fk_characterId", character_id).await, 0 );
+    }
+
+    #[tokio::test]
+    async fn unreachable_difficulties_always_fizzle() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([4; 6]) ).await;
+
+        let spell_id = db::spells::add( &harness.database_connection, &fireball(0, 100) ).await.unwrap();
+        db::spells::learn( &harness.database_connection, character_id, spell_id ).await.unwrap();
+
+        let cast = cast::cast_spell( &harness.database_connection, character_id, "Merlin", spell_id ).await.unwrap();
+        assert!( !cast.success );
+        assert_eq!( cast.vitals.mana, 20 );
+    }
+// ==--
@@ -0,0 +1,101 @@
+// Derived stats worked out from attributes, and the HP and mana characters use up
+
+use crate::{
+    attributes::{Attribute, AttributeSpread},
+    db::{self, Resources},
+    derived_stats::{self, DerivedStats, StatFormula, Vitals},
+    error::BotError,
+    utils::unix_now
+};
+use super::{TestHarness, PLAYER};
+
+
+// --== FORMULAS ==-- //
+
+    #[test]
+    fn formulas_scale_with_their_attribute() {
+        let formula = StatFormula { attribute: Attribute::Constitution, base: 10, per_point: 5 };
+        let mut spread = AttributeSpread([1; 6]);
+
+        assert_eq!( formula.evaluate(&spread), 15 );
+        spread.set( Attribute::Constitution, 4 );
+        assert_eq!( formula.evaluate(&spread), 30 );
+        spread.set( Attribute::Strength, 10 );
+        assert_eq!( formula.evaluate(&spread), 30 );
+
+        assert_eq!( formula.to_string(), "10 + 5 × Constitution" );
+    }
+
+    #[test]
+    fn formulas_dont_go_below_zero() {
+        let formula = StatFormula { attribute: Attribute::Casting, base: -20, per_point: 2 };
+        assert_eq!( formula.evaluate(&AttributeSpread([1; 6])), 0 );
+    }
+
+    #[test]
+    fn stored_resources_are_clamped_to_the_maximums() {
+        let stats = DerivedStats { max_hp: 20, max_mana: 10, carry_capacity: 50 };
+
+        let full = Vitals::new( &stats, None );
+        assert_eq!( (full.hp, full.mana), (20, 10) );
+
+        let above_max = Resources { character_id: 1, current_hp: 35, current_mana: -3 };
+        let clamped = Vitals::new( &stats, Some(&above_max) );
+        assert_eq!( (clamped.hp, clamped.mana), (20, 0) );
+        assert!( clamped.is_depleted() );
+    }
+
+    #[test]
+    fn adjustments_stay_between_zero_and_the_maximums() {
+        let vitals = Vitals { hp: 5, max_hp: 20, mana: 4, max_mana: 10 };
+
+        assert_eq!( vitals.adjusted(-8, 0).hp, 0 );
+        assert!( vitals.adjusted(-8, 0).is_unconscious() );
+        assert_eq!( vitals.adjusted(100, 0).hp, 20 );
+        assert_eq!( vitals.adjusted(0, -5).mana, 0 );
+        assert_eq!( vitals.adjusted(0, i64::MAX).mana, 10 );
+    }
+// ==--
+
+// --== DATABASE ==-- //
+
+    #[tokio::test]
+    async fn changes_are_kept_between_adjustments() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([5; 6]) ).await;
+        let stats = DerivedStats::from_spread( &AttributeSpread([5; 6]) );
+
+        let ( before, after ) = derived_stats::adjust_vitals( &harness.database_connection, character_id, -7, 0 ).await.unwrap();
+        assert_eq!( before.hp, stats.max_hp );
+        assert_eq!( after.hp, stats.max_hp - 7 );
+
+        let ( before, after ) = derived_stats::adjust_vitals( &harness.database_connection, character_id, -1000, -3 ).await.unwrap();
+        assert_eq!( before.hp, stats.max_hp - 7 );
+        assert!( after.is_unconscious() );
+        assert_eq!( after.mana, stats.max_mana - 3 );
+
+        let ( _, after ) = derived_stats::adjust_vitals( &harness.database_connection, character_id, 1000, 0 ).await.unwrap();
+        assert_eq!( after.hp, stats.max_hp );
+    }
+
+    #[tokio::test]
+    async fn characters_without_attributes_have_no_vitals() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character( PLAYER, "Merlin" ).await;
+
+        let adjusted = derived_stats::adjust_vitals( &harness.database_connection, character_id, -1, 0 ).await;
+        assert!( matches!(adjusted, Err(BotError::NoAttributes)) );
+    }
+
+    #[tokio::test]
+    async fn resources_are_removed_with_the_character() {
+        let harness = TestHarness::new().await;
+        let character_id = harness.character_with_attributes( PLAYER, "Merlin", AttributeSpread([5; 6]) ).await;
+        derived_stats::adjust_vitals( &harness.database_connection, character_id, -1, 0 ).await.unwrap();
+
+        db::characters::archive( &harness.database_connection, character_id, unix_now() ).await.unwrap();
+        db::characters::remove_archived_before( &harness.database_connection, unix_now() + 1 ).await.unwrap();
+
+        assert_eq!( harness.count_rows("CharacterResources", "fk_characterId", character_id).await, 0 );
+    }
+// ==--
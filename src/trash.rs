@@ -0,0 +1,63 @@
+// Where deleted characters go
+//
+// - Deleting a character only archives it. It disappears from the caches, and so from every
+//     command and autocomplete, but stays in the database so it's owner can restore it
+// - Characters that have been in the trash for longer than the configured retention period are
+//     removed for good, along with their attributes and abilities, by a task running in the
+//     background
+
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    config,
+    db::{self, DbError},
+    utils::unix_now
+};
+
+
+/// How often the trash is checked for characters to remove
+const EMPTYING_INTERVAL: Duration = Duration::from_secs( 60 * 60 );
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+
+/// How long characters stay in the trash, in seconds. The config keeps the days small enough to
+/// fit, this only makes sure a mistake there can't wrap around into emptying the whole trash
+fn retention_seconds() -> i64 {
+    i64::try_from( config::get().trash_retention_days )
+        .unwrap_or( i64::MAX )
+        .saturating_mul( SECONDS_PER_DAY )
+}
+
+/// When a character archived at the given time gets removed for good
+pub fn removal_time( archived_at: i64 ) -> i64 {
+    archived_at.saturating_add( retention_seconds() )
+}
+
+/// Remove every character that has been in the trash for longer than the retention period, as of
+/// the given time. Returns how many were removed
+pub async fn empty( database_connection: &SqlitePool, now: i64 ) -> Result<usize, DbError> {
+    let archived_before = now.saturating_sub( retention_seconds() );
+
+    db::characters::remove_archived_before( database_connection, archived_before ).await
+}
+
+/// Start emptying the trash in the background, once right away and then every hour
+pub fn spawn_emptying_task( database_connection: SqlitePool ) {
+    tokio::spawn( async move {
+        let mut interval = tokio::time::interval( EMPTYING_INTERVAL );
+
+        loop {
+            interval.tick().await;
+
+            match empty( &database_connection, unix_now() ).await {
+                Ok(0) => { /* Nothing was old enough */ },
+                Ok( removed_count ) => info!("Removed {removed_count} character(s) from the trash for good"),
+                Err( why ) => warn!(error = %why, "Failed to empty the trash")
+            }
+        }
+    });
+}
@@ -1,11 +1,21 @@
-use serenity::{futures::lock::MutexGuard, model::Colour, prelude::TypeMapKey};
-use sqlx::Row;
+use serenity::{
+    all::{
+        ActionRowComponent, CommandInteraction, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, ModalInteraction, ResolvedOption, ResolvedValue, Unresolved
+    },
+    model::Colour,
+    prelude::TypeMapKey
+};
 use std::{
     collections::HashMap,
     sync::{Mutex, Arc},
+    time::{SystemTime, UNIX_EPOCH}
 };
 
-use crate::sql_scripts;
+use crate::{
+    config,
+    responses::BotContext
+};
 
 /// Header that apppears at the top during runtime
 pub const TITLE: &str = "
@@ -14,36 +24,33 @@ pub const TITLE: &str = "
   // xxxxxxxxxxxxxxxxxxxxxxxx //
 ";
 
-/// Severity of a message sent to the terminal
-pub enum LogLevel {
-    Fatal,
-    Error,
-    Warning,
-    Info
+/// Colours of embeds, as set in the config
+pub struct EmbedColours;
+impl EmbedColours {
+    pub fn info()  -> Colour { config::get().colour_info }
+    pub fn good()  -> Colour { config::get().colour_good }
+    pub fn error() -> Colour { config::get().colour_error }
 }
 
-/// Create a string containing a formated log message of give severity, message, and timestamp
-pub fn create_log_message( message: impl ToString, severity: LogLevel ) -> String {
-
-    let current_time = chrono::offset::Local::now();
-    let timestamp = current_time.format("%Y-%m-%d | %H:%M:%S").to_string();
+/// Most fields an embed can hold
+pub const MAX_FIELDS_PER_PAGE: usize = 25;
+/// An embed can hold 6000 characters in total. We leave some room for the title, description,
+/// footer and the field names
+pub const MAX_CHARACTERS_PER_PAGE: usize = 5000;
 
-    let log_level_message = match severity {
-        LogLevel::Fatal   => "FATAL",
-        LogLevel::Error   => "ERROR",
-        LogLevel::Warning => " WARN",
-        LogLevel::Info    => " INFO",
-    };
-
-    format!("[ {} ]  => {}:  {}", timestamp, log_level_message, message.to_string())
+/// Seconds since the unix epoch, as timestamps are stored in the database
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since( UNIX_EPOCH )
+        .map( |since_epoch| since_epoch.as_secs() as i64 )
+        .unwrap_or_default()
 }
 
-pub struct EmbedColours;
-impl EmbedColours {
-    pub const INFO:  Colour = Colour::from_rgb(0, 127, 255);
-    pub const GOOD:  Colour = Colour::from_rgb(0, 255, 127);
-    pub const ERROR: Colour = Colour::from_rgb(255, 127, 0);
-}
+/// The ID of a character, matching the `INTEGER PRIMARY KEY` of the Characters table
+pub type CharacterId = i64;
+
+/// Discord User IDs mapped to the characters they own, as (character_id, character_name)
+pub type CharacterMap = HashMap<u64, Vec<(CharacterId, String)>>;
 
 /// A TypeMapKey used to access cached character information storred in a HashMap
 ///
@@ -51,16 +58,23 @@ impl EmbedColours {
 /// as follows:  (character_id, character_name)
 pub struct DatabaseCharactersCache;
 impl TypeMapKey for DatabaseCharactersCache {
-    type Value = Arc<Mutex<
-        HashMap<u64, Vec<(u16, String)>>
-    >>;
+    type Value = Arc<Mutex<CharacterMap>>;
+}
+
+/// A TypeMapKey used to access the character each user is currently playing as
+///
+/// Mirrors `DiscordUsers.fk_currentCharacter`, with a key of Discord User IDs pointing to the ID
+/// of their active character. Users without an active character aren't in the map
+pub struct ActiveCharactersCache;
+impl TypeMapKey for ActiveCharactersCache {
+    type Value = Arc<Mutex<HashMap<u64, CharacterId>>>;
 }
 
 /// Blocks the current thread until a clone of the given user's character can be given
 pub fn clone_user_characters(
-    character_map: Arc<Mutex<HashMap<u64, Vec<(u16, String)>>>>,
+    character_map: Arc<Mutex<CharacterMap>>,
     user_id: &u64
-    ) -> Result< Vec<(u16, String)>, String> {
+    ) -> Result< Vec<(CharacterId, String)>, String> {
 
     let map = match character_map.lock() {
         Ok( map ) => map,
@@ -76,3 +90,216 @@ pub fn clone_user_characters(
 
 }
 
+
+/// Search the cache for the given user's characters whose name matches the query, as done by
+/// `filter_characters`. Used to fill autocomplete choices
+pub async fn search_user_characters( ctx: &BotContext, user_id: &u64, query: &str ) -> Vec<(CharacterId, String)> {
+
+    let users_characters = {
+        let data_read = ctx.data.read().await;
+        let users_character_map_mutex = match data_read.get::<DatabaseCharactersCache>() {
+            Some(mutex) => mutex,
+            None => {
+                // This shouldn't ever occur as the TypeMapKey should be inserted in main.rs
+                return vec![];
+            }
+        };
+
+        // If the lock is poisoned we can't do much more than give back no characters. Commands
+        // which aren't autocomplete will log it for us
+        clone_user_characters( users_character_map_mutex.clone(), user_id )
+            .unwrap_or(vec![])
+    };
+
+    filter_characters( users_characters, query )
+}
+
+/// Keep the characters whose name matches the query, case insensitively. Characters whose name
+/// begins with the query come first, followed by those that only contain it
+pub fn filter_characters( characters: Vec<(CharacterId, String)>, query: &str ) -> Vec<(CharacterId, String)> {
+
+    let query = query.to_lowercase();
+
+    let mut begins_with_choices = vec![];
+    let mut contains_choices    = vec![];
+
+    for character_data in characters.into_iter() {
+
+        let character_name = character_data.1.to_lowercase();
+
+        match (character_name.starts_with(&query), character_name.contains(&query)) {
+            (true, _) => begins_with_choices.push(character_data),
+            (false, true) => contains_choices.push(character_data),
+            _ => { /* Do Nothing */ }
+        }
+    }
+
+    [ begins_with_choices, contains_choices ].concat()
+}
+
+/// Collect the values of every InputText inside of a submitted modal, in the order they were
+/// added when creating it. Returns `None` if the modal contains anything else
+pub fn modal_input_values( interaction_data: &ModalInteraction ) -> Option<Vec<String>> {
+    let mut values = vec![];
+
+    for action_row in interaction_data.data.components.iter() {
+        match action_row.components.first()? {
+            ActionRowComponent::InputText( input ) => values.push( input.value.clone()? ),
+            _ => return None
+        }
+    }
+
+    Some( values )
+}
+
+/// Get a command option's value, whichever type it is
+pub fn find_option<'a>( options: &'a [ResolvedOption<'a>], name: &str ) -> Option<&'a ResolvedValue<'a>> {
+    options
+        .iter()
+        .find( |option| option.name == name )
+        .map( |option| &option.value )
+}
+
+/// Get the value of an integer command option. Characters, items and spells are all picked by
+/// their ID through one of these
+pub fn integer_option( options: &[ResolvedOption], name: &str ) -> Option<i64> {
+    match find_option( options, name )? {
+        ResolvedValue::Integer( value ) => Some( *value ),
+        _ => None
+    }
+}
+
+/// Get the value of a string command option
+pub fn string_option<'a>( options: &'a [ResolvedOption<'a>], name: &str ) -> Option<&'a str> {
+    match find_option( options, name )? {
+        ResolvedValue::String( value ) => Some( value ),
+        _ => None
+    }
+}
+
+/// Get the ID of the user picked in a user command option. In autocomplete interactions the user
+/// might not be resolved, so we accept just their ID too
+pub fn user_option( options: &[ResolvedOption], name: &str ) -> Option<u64> {
+    match find_option( options, name )? {
+        ResolvedValue::User( user, _ ) => Some( user.id.get() ),
+        ResolvedValue::Unresolved( Unresolved::User(user_id) ) => Some( user_id.get() ),
+        _ => None
+    }
+}
+
+/// Whether the command was used in a server by a member who can manage it. Outside of servers
+/// there's no member, so nobody can
+pub fn manages_guild( interaction_data: &CommandInteraction ) -> bool {
+    interaction_data.member
+        .as_ref()
+        .and_then( |member| member.permissions )
+        .is_some_and( |permissions| permissions.manage_guild() )
+}
+
+/// An embed explaining a mistake that doesn't come from a `BotError`, such as a blank name
+pub fn error_embed( title: impl Into<String>, description: impl Into<String> ) -> CreateEmbed {
+    CreateEmbed::new()
+        .title( title )
+        .description( description )
+        .colour( EmbedColours::error() )
+}
+
+/// Shown when a catalogue command is used outside of a server. `kind` is what the catalogue holds,
+/// like "Items"
+pub fn no_catalogue_embed( kind: &str ) -> CreateEmbed {
+    error_embed( format!("{kind} belong to a server"), "Every server keeps it's own catalogue, please use this in one" )
+}
+
+/// Send an embed back as a plain message
+pub fn message_response( embed: CreateEmbed ) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed( embed )
+    )
+}
+
+/// Get the name of one of the user's characters from the cache. Returns `None` if the character
+/// doesn't belong to the user
+pub async fn get_user_character_name( ctx: &BotContext, user_id: &u64, character_id: CharacterId ) -> Option<String> {
+    let user_owned_characters = {
+        let data_read = ctx.data.read().await;
+        let character_map_mutex = data_read
+            .get::<DatabaseCharactersCache>()
+            .expect("Key 'DatabaseCharactersCache' must be in map, as it get's inserted in main.rs");
+
+        clone_user_characters( character_map_mutex.clone(), user_id ).unwrap_or(vec![])
+    };
+
+    user_owned_characters
+        .into_iter()
+        .find( |character| character.0 == character_id )
+        .map( |character| character.1 )
+}
+
+/// Get the ID and name of the character the user is currently playing as, if they've got one
+pub async fn get_active_character( ctx: &BotContext, user_id: &u64 ) -> Option<(CharacterId, String)> {
+    let active_character_id = {
+        let data_read = ctx.data.read().await;
+        let active_map_mutex = data_read
+            .get::<ActiveCharactersCache>()
+            .expect("Key 'ActiveCharactersCache' must be in map, as it get's inserted in main.rs");
+
+        // Just like with the characters cache, a poisoned lock is treated as if there was no data
+        let active_map = active_map_mutex.lock().ok()?;
+        *active_map.get(user_id)?
+    };
+
+    let character_name = get_user_character_name( ctx, user_id, active_character_id ).await?;
+    Some(( active_character_id, character_name ))
+}
+
+/// Use the character picked in an option, or fall back onto the user's active character
+pub async fn selected_character_id( ctx: &BotContext, user_id: &u64, character_option: Option<CharacterId> ) -> Option<CharacterId> {
+    match character_option {
+        Some( character_id ) => Some( character_id ),
+        None => get_active_character( ctx, user_id )
+            .await
+            .map( |(character_id, _)| character_id )
+    }
+}
+
+/// A footer naming the user's active character, if they've got one
+pub async fn active_character_footer( ctx: &BotContext, user_id: &u64 ) -> Option<CreateEmbedFooter> {
+    get_active_character( ctx, user_id )
+        .await
+        .map( |(_, character_name)| CreateEmbedFooter::new( format!("Playing as {character_name}") ) )
+}
+
+/// Add a footer naming the user's active character to an embed. If they don't have one, the
+/// embed is given back untouched
+pub async fn add_active_character_footer( ctx: &BotContext, user_id: &u64, embed: CreateEmbed ) -> CreateEmbed {
+    match active_character_footer( ctx, user_id ).await {
+        Some( footer ) => embed.footer( footer ),
+        None => embed
+    }
+}
+
+/// Split embed fields into pages, each of which fits into a single embed. Every page is filled up
+/// for as long as it stays within the limits, then a new one is started. There's always at least
+/// one page, even if it's empty
+pub fn paginate_fields( fields: Vec<(String, String, bool)> ) -> Vec<Vec<(String, String, bool)>> {
+    let mut pages = vec![];
+    let mut current_page: Vec<(String, String, bool)> = vec![];
+    let mut current_length = 0;
+
+    for field in fields.into_iter() {
+        let field_length = field.0.chars().count() + field.1.chars().count();
+
+        if !current_page.is_empty()
+            && ( current_page.len() >= MAX_FIELDS_PER_PAGE || current_length + field_length > MAX_CHARACTERS_PER_PAGE )
+        {
+            pages.push( std::mem::take(&mut current_page) );
+            current_length = 0;
+        }
+
+        current_length += field_length;
+        current_page.push( field );
+    }
+    pages.push( current_page );
+
+    pages
+}